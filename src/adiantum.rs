@@ -0,0 +1,374 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+An implementation of Adiantum, a length-preserving tweakable wide-block cipher [1].
+
+Adiantum encrypts a whole message of at least 16 bytes at once, producing a ciphertext of exactly
+the same length. Changing any bit of the plaintext or the tweak changes the entire ciphertext, which
+makes it suitable for encrypting filenames and disk sectors where there is no room to store a nonce
+or an authentication tag. It was designed for devices without AES instructions: almost all of the
+work is done by the XChaCha12 stream cipher and the NH and Poly1305 hash functions, and AES-256 is
+applied to a single 16 byte block per message, using the constant time `aessafe` implementation.
+
+This is the Adiantum-XChaCha12-AES construction from the paper, as used by Linux for storage
+encryption. The key is 32 bytes and the tweak may be any length, although Linux always uses a 32
+byte tweak.
+
+Adiantum provides confidentiality only. It does not detect modification of the ciphertext, although
+any modification will garble the entire message.
+
+# Usage
+
+```
+use crypto::adiantum::Adiantum;
+
+let key = [7u8; 32];
+let tweak = [1u8; 32];
+let sector = [0u8; 4096];
+
+let adiantum = Adiantum::new(&key);
+let mut encrypted = [0u8; 4096];
+adiantum.encrypt(&tweak, &sector, &mut encrypted);
+
+let mut decrypted = [0u8; 4096];
+adiantum.decrypt(&tweak, &encrypted, &mut decrypted);
+assert!(sector[..] == decrypted[..]);
+```
+
+[1] - "Adiantum: length-preserving encryption for entry-level processors" - Paul Crowley and Eric
+      Biggers <https://eprint.iacr.org/2018/720>
+*/
+
+use aessafe::{AesSafe256Encryptor, AesSafe256Decryptor};
+use chacha20::ChaCha20;
+use cryptoutil::{copy_memory, read_u32_le, read_u32v_le, write_u64_le};
+use mac::Mac;
+use poly1305::Poly1305;
+use symmetriccipher::{BlockEncryptor, BlockDecryptor, SynchronousStreamCipher};
+
+const BLOCK_SIZE: usize = 16;
+
+// NH processes the message in chunks of up to 1024 bytes, each of which is hashed to 32 bytes by
+// 4 passes. Each pass uses the key offset by 4 more words than the last.
+const NH_MESSAGE_BYTES: usize = 1024;
+const NH_KEY_WORDS: usize = NH_MESSAGE_BYTES / 4 + 3 * 4;
+
+// The AES-256 key, the two Poly1305 keys and the NH key, in that order
+const DERIVED_KEY_BYTES: usize = 32 + 16 + 16 + NH_KEY_WORDS * 4;
+
+// Hash a chunk of at most NH_MESSAGE_BYTES bytes. A final partial 16 byte unit is padded with
+// zeros.
+fn nh(key: &[u32; NH_KEY_WORDS], message: &[u8], output: &mut [u8; 32]) {
+    let mut sums = [0u64; 4];
+    for (j, unit) in message.chunks(16).enumerate() {
+        let mut block = [0u8; 16];
+        copy_memory(unit, &mut block[..unit.len()]);
+        let m0 = read_u32_le(&block[0..4]);
+        let m1 = read_u32_le(&block[4..8]);
+        let m2 = read_u32_le(&block[8..12]);
+        let m3 = read_u32_le(&block[12..16]);
+        let k = &key[4 * j..];
+        for (i, sum) in sums.iter_mut().enumerate() {
+            *sum = sum
+                .wrapping_add(m0.wrapping_add(k[4 * i]) as u64 *
+                    m2.wrapping_add(k[4 * i + 2]) as u64)
+                .wrapping_add(m1.wrapping_add(k[4 * i + 1]) as u64 *
+                    m3.wrapping_add(k[4 * i + 3]) as u64);
+        }
+    }
+    for (i, &sum) in sums.iter().enumerate() {
+        write_u64_le(&mut output[i * 8..(i + 1) * 8], sum);
+    }
+}
+
+// Add b to a, treating both as 128 bit little endian values
+fn add_le128(a: &mut [u8; BLOCK_SIZE], b: &[u8; BLOCK_SIZE]) {
+    let mut carry = 0u16;
+    for (x, &y) in a.iter_mut().zip(b.iter()) {
+        let sum = *x as u16 + y as u16 + carry;
+        *x = sum as u8;
+        carry = sum >> 8;
+    }
+}
+
+// Subtract b from a, treating both as 128 bit little endian values
+fn sub_le128(a: &mut [u8; BLOCK_SIZE], b: &[u8; BLOCK_SIZE]) {
+    let mut borrow = 0u16;
+    for (x, &y) in a.iter_mut().zip(b.iter()) {
+        let diff = (*x as u16).wrapping_sub(y as u16).wrapping_sub(borrow);
+        *x = diff as u8;
+        borrow = (diff >> 8) & 1;
+    }
+}
+
+/// The Adiantum-XChaCha12-AES tweakable wide-block cipher
+pub struct Adiantum {
+    key: [u8; 32],
+    block_encryptor: AesSafe256Encryptor,
+    block_decryptor: AesSafe256Decryptor,
+    header_poly: Poly1305,
+    message_poly: Poly1305,
+    nh_key: [u32; NH_KEY_WORDS]
+}
+
+impl Adiantum {
+    /// Create a new Adiantum instance from a 32 byte key
+    pub fn new(key: &[u8]) -> Adiantum {
+        assert!(key.len() == 32);
+
+        // The subkeys are taken from the XChaCha12 keystream for the nonce 1
+        let mut nonce = [0u8; 24];
+        nonce[0] = 1;
+        let mut derived = [0u8; DERIVED_KEY_BYTES];
+        ChaCha20::new_xchacha12(key, &nonce).process(&[0u8; DERIVED_KEY_BYTES], &mut derived);
+
+        // Adiantum uses Poly1305 without the final addition, which is the same as using an s of 0
+        let mut header_key = [0u8; 32];
+        copy_memory(&derived[32..48], &mut header_key[..16]);
+        let mut message_key = [0u8; 32];
+        copy_memory(&derived[48..64], &mut message_key[..16]);
+        let mut nh_key = [0u32; NH_KEY_WORDS];
+        read_u32v_le(&mut nh_key, &derived[64..]);
+
+        let mut stream_key = [0u8; 32];
+        copy_memory(key, &mut stream_key);
+
+        Adiantum {
+            key: stream_key,
+            block_encryptor: AesSafe256Encryptor::new(&derived[..32]),
+            block_decryptor: AesSafe256Decryptor::new(&derived[..32]),
+            header_poly: Poly1305::new(&header_key),
+            message_poly: Poly1305::new(&message_key),
+            nh_key: nh_key
+        }
+    }
+
+    // Hash the tweak and the bulk of the message. The tweak, preceded by the message length in
+    // bits, is hashed with Poly1305, while the message is hashed with NH and the NH output is then
+    // hashed with Poly1305. The result is the sum of the two hashes.
+    fn hash(&self, tweak: &[u8], message: &[u8]) -> [u8; BLOCK_SIZE] {
+        let mut header = [0u8; BLOCK_SIZE];
+        write_u64_le(&mut header[0..8], message.len() as u64 * 8);
+        let mut header_poly = self.header_poly;
+        header_poly.input(&header);
+        header_poly.input(tweak);
+        let mut result = [0u8; BLOCK_SIZE];
+        header_poly.raw_result(&mut result);
+
+        let mut message_poly = self.message_poly;
+        let mut nh_output = [0u8; 32];
+        for chunk in message.chunks(NH_MESSAGE_BYTES) {
+            nh(&self.nh_key, chunk, &mut nh_output);
+            message_poly.input(&nh_output);
+        }
+        let mut message_hash = [0u8; BLOCK_SIZE];
+        message_poly.raw_result(&mut message_hash);
+
+        add_le128(&mut result, &message_hash);
+        result
+    }
+
+    // XOR the bulk of the message with the XChaCha12 keystream for the nonce made up of the
+    // enciphered block followed by 1
+    fn stream(&self, block: &[u8; BLOCK_SIZE], input: &[u8], output: &mut [u8]) {
+        let mut nonce = [0u8; 24];
+        copy_memory(block, &mut nonce[..BLOCK_SIZE]);
+        nonce[BLOCK_SIZE] = 1;
+        ChaCha20::new_xchacha12(&self.key, &nonce).process(input, output);
+    }
+
+    /// Encrypt a message of at least 16 bytes under the given tweak. The output must be the same
+    /// length as the input.
+    pub fn encrypt(&self, tweak: &[u8], input: &[u8], output: &mut [u8]) {
+        assert!(input.len() == output.len());
+        assert!(input.len() >= BLOCK_SIZE);
+
+        let bulk_len = input.len() - BLOCK_SIZE;
+        let (p_l, p_r) = input.split_at(bulk_len);
+        let (c_l, c_r) = output.split_at_mut(bulk_len);
+
+        let mut p_m = [0u8; BLOCK_SIZE];
+        copy_memory(p_r, &mut p_m);
+        add_le128(&mut p_m, &self.hash(tweak, p_l));
+        let mut c_m = [0u8; BLOCK_SIZE];
+        self.block_encryptor.encrypt_block(&p_m, &mut c_m);
+
+        self.stream(&c_m, p_l, c_l);
+
+        sub_le128(&mut c_m, &self.hash(tweak, c_l));
+        copy_memory(&c_m, c_r);
+    }
+
+    /// Decrypt a message of at least 16 bytes under the given tweak. The output must be the same
+    /// length as the input.
+    pub fn decrypt(&self, tweak: &[u8], input: &[u8], output: &mut [u8]) {
+        assert!(input.len() == output.len());
+        assert!(input.len() >= BLOCK_SIZE);
+
+        let bulk_len = input.len() - BLOCK_SIZE;
+        let (c_l, c_r) = input.split_at(bulk_len);
+        let (p_l, p_r) = output.split_at_mut(bulk_len);
+
+        let mut c_m = [0u8; BLOCK_SIZE];
+        copy_memory(c_r, &mut c_m);
+        add_le128(&mut c_m, &self.hash(tweak, c_l));
+
+        self.stream(&c_m, c_l, p_l);
+
+        let mut p_m = [0u8; BLOCK_SIZE];
+        self.block_decryptor.decrypt_block(&c_m, &mut p_m);
+        sub_le128(&mut p_m, &self.hash(tweak, p_l));
+        copy_memory(&p_m, p_r);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use adiantum::Adiantum;
+    use digest::Digest;
+    use serialize::hex::FromHex;
+    use sha2::Sha256;
+
+    struct Test {
+        tweak: &'static str,
+        plaintext: &'static str,
+        ciphertext: &'static str
+    }
+
+    static KEY: &'static str = "010c17222d38434e59646f7a85909ba6b1bcc7d2dde8f3fe09141f2a35404b56";
+
+    // Generated with an independent implementation of Adiantum written from the paper
+    fn tests() -> Vec<Test> {
+        vec![
+            Test {
+                tweak: "05121f2c394653606d7a8794a1aebbc8d5e2effc091623303d4a5764717e8b98",
+                plaintext: "030a11181f262d343b424950575e656c",
+                ciphertext: "df952ec6108ab15d102797fbe5568477"
+            },
+            Test {
+                tweak: "05121f2c394653606d7a8794a1aebbc8d5e2effc091623303d4a5764717e8b98",
+                plaintext: "030a11181f262d343b424950575e656c73",
+                ciphertext: "50df42a90e7dcc721b5614d0c77c2b1f30"
+            },
+            Test {
+                tweak: "05121f2c394653606d7a8794a1aebbc8d5e2effc091623303d4a5764717e8b98",
+                plaintext: "030a11181f262d343b424950575e656c737a81888f969da4abb2b9c0c7ced5dc\
+                    e3eaf1f8ff060d141b222930373e454c535a61686f767d848b9299a0a7aeb5bc\
+                    c3cad1d8dfe6edf4fb020910171e252c333a41484f565d646b727980878e959c\
+                    a3aab1b8",
+                ciphertext: "add251a4d45115a9d9bf67269ba88a1ffde7bfdaa4cc7f3ba062fddbfd1ca635\
+                    e98f3d04aee9d29ad9f89be990275d761f9e7600ce0f0b8aa37c331217c0265f\
+                    413fe998505045a5e092227f0598f40b4ab1ab28ebe89865f4bad3c59e7aed7f\
+                    c66725e8"
+            },
+            Test {
+                tweak: "",
+                plaintext: "030a11181f262d343b424950575e656c737a81888f969da4abb2b9c0c7ced5dc\
+                    e3eaf1f8ff060d141b222930373e454c",
+                ciphertext: "fe169bd22bad19189951d553478c8267e5055f704fd239e7f859b8a964fc715a\
+                    82471b8db41dfa34ac488d9c264e5ce0"
+            }
+        ]
+    }
+
+    #[test]
+    fn adiantum() {
+        let adiantum = Adiantum::new(&KEY.from_hex().unwrap());
+        for test in tests().iter() {
+            let tweak = test.tweak.from_hex().unwrap();
+            let plaintext = test.plaintext.from_hex().unwrap();
+            let ciphertext = test.ciphertext.from_hex().unwrap();
+
+            let mut output: Vec<u8> = vec![0; plaintext.len()];
+            adiantum.encrypt(&tweak, &plaintext, &mut output);
+            assert!(output == ciphertext);
+
+            adiantum.decrypt(&tweak, &ciphertext, &mut output);
+            assert!(output == plaintext);
+        }
+    }
+
+    // Test vectors from the Linux kernel's crypto/testmgr.h (adiantum_xchacha12_aes_tv_template)
+    #[test]
+    fn adiantum_testmgr() {
+        let tests = [
+            ("9eebb2493c1cf5f46a99c2c4dfb1f4dd752057ea2c4fcdb2a53d7b491eabfd0f",
+                "df63d4abd249f3d8338137607dfa7308d8496d80e82f6254eb0ea9395b457f8a",
+                "67c9f23084418e43fbf3b33e79367fe8",
+                "6d32861867860f3f967c9d280d53ec9f"),
+            ("362b5797f85dcd995f1a5a441d920f27cc16d72b856399d3ba96a1dbd26068da",
+                "ef5869b12c5e9a4724c1b169e112938f433d6d00db5ed8d9129afed9ff2daac4",
+                "5ea8681985981223260accdb0a04b9df4db3487bb0e3c819435a4606942df2",
+                "c7c6f1738fc4ff4a39be78be8d28c8894663e70c7d87e84ec9187bbe186050")
+        ];
+        for &(key, tweak, plaintext, ciphertext) in tests.iter() {
+            let adiantum = Adiantum::new(&key.from_hex().unwrap());
+            let tweak = tweak.from_hex().unwrap();
+            let plaintext = plaintext.from_hex().unwrap();
+            let ciphertext = ciphertext.from_hex().unwrap();
+
+            let mut output: Vec<u8> = vec![0; plaintext.len()];
+            adiantum.encrypt(&tweak, &plaintext, &mut output);
+            assert!(output == ciphertext);
+
+            adiantum.decrypt(&tweak, &ciphertext, &mut output);
+            assert!(output == plaintext);
+        }
+    }
+
+    // Messages long enough that NH processes more than one chunk, including a partial final chunk
+    // and a partial final 16 byte unit. The ciphertexts are checked by their SHA-256 digests.
+    #[test]
+    fn adiantum_long() {
+        let tests = [
+            ("05121f2c394653606d7a8794a1", 1023,
+                "ac8fbcd74936258d0af03d6007a324a8d50ee34bd37b396007affbff78811140"),
+            ("0000000000000000000000000000000000000000000000000000000000000000", 4096,
+                "0b99ced0e05bdf55ff34f2cdea7b58031c4b82a1671380bcbfd04dfb1f62bf2f")
+        ];
+        let adiantum = Adiantum::new(&KEY.from_hex().unwrap());
+        for &(tweak, len, digest) in tests.iter() {
+            let tweak = tweak.from_hex().unwrap();
+            let plaintext: Vec<u8> = (0..len).map(|i| (i * 7 + 3) as u8).collect();
+
+            let mut ciphertext: Vec<u8> = vec![0; len];
+            adiantum.encrypt(&tweak, &plaintext, &mut ciphertext);
+            let mut sha = Sha256::new();
+            sha.input(&ciphertext);
+            assert_eq!(sha.result_str(), digest);
+
+            let mut output: Vec<u8> = vec![0; len];
+            adiantum.decrypt(&tweak, &ciphertext, &mut output);
+            assert!(output == plaintext);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn adiantum_short_input() {
+        let adiantum = Adiantum::new(&[0u8; 32]);
+        let mut output = [0u8; 15];
+        adiantum.encrypt(&[], &[0u8; 15], &mut output);
+    }
+}
+
+#[cfg(all(test, feature = "with-bench"))]
+mod bench {
+    use test::Bencher;
+    use adiantum::Adiantum;
+
+    #[bench]
+    pub fn adiantum_4k(bh: & mut Bencher) {
+        let adiantum = Adiantum::new(&[1u8; 32]);
+        let input = [2u8; 4096];
+        let mut output = [0u8; 4096];
+        bh.iter( || {
+            adiantum.encrypt(&[3u8; 32], &input, &mut output);
+        });
+        bh.bytes = input.len() as u64;
+    }
+}
@@ -12,4 +12,31 @@ pub trait AeadEncryptor {
 pub trait AeadDecryptor {
 
 	fn decrypt(&mut self, input: &[u8], output: &mut [u8], tag: &[u8]) -> bool;
-}
\ No newline at end of file
+}
+
+/// An AEAD encryptor that processes the associated data and the message incrementally, so that
+/// they don't need to be held in memory at once. All of the associated data must be supplied
+/// before the first call to `encrypt_update`. The result is the same as with the one-shot
+/// `AeadEncryptor` when the pieces are concatenated.
+pub trait AeadStreamEncryptor {
+
+	fn encrypt_aad(&mut self, aad: &[u8]);
+
+	fn encrypt_update(&mut self, input: &[u8], output: &mut [u8]);
+
+	fn encrypt_finish(&mut self, tag: &mut [u8]);
+}
+
+/// An AEAD decryptor that processes the associated data and the message incrementally. All of the
+/// associated data must be supplied before the first call to `decrypt_update`.
+///
+/// The output of `decrypt_update` has not been authenticated yet: it must not be used, and should
+/// be discarded, unless `decrypt_finish` returns true.
+pub trait AeadStreamDecryptor {
+
+	fn decrypt_aad(&mut self, aad: &[u8]);
+
+	fn decrypt_update(&mut self, input: &[u8], output: &mut [u8]);
+
+	fn decrypt_finish(&mut self, tag: &[u8]) -> bool;
+}
@@ -8,11 +8,28 @@
 use aesni;
 
 use aessafe;
-use blockmodes::{PaddingProcessor, EcbEncryptor, EcbDecryptor, CbcEncryptor, CbcDecryptor, CtrMode,
-    CtrModeX8};
-use symmetriccipher::{Encryptor, Decryptor, SynchronousStreamCipher};
+use blockmodes::{PaddingProcessor, EcbEncryptor, EcbDecryptor, EcbEncryptorX8, EcbDecryptorX8,
+    CbcEncryptor, CbcDecryptorX8, CtrModeX8, CfbEncryptor, CfbDecryptor, CfbSegmentSize, OfbMode};
+use symmetriccipher::{BlockEncryptor, Encryptor, Decryptor, SynchronousStreamCipher};
 use util;
 
+// The bitsliced implementations that process several blocks at once. The X4 variants work on u64
+// values, so they are only picked where those are native.
+#[cfg(target_pointer_width = "64")]
+use aessafe::{AesSafe128EncryptorX4 as AesSafe128EncryptorMulti,
+    AesSafe192EncryptorX4 as AesSafe192EncryptorMulti,
+    AesSafe256EncryptorX4 as AesSafe256EncryptorMulti,
+    AesSafe128DecryptorX4 as AesSafe128DecryptorMulti,
+    AesSafe192DecryptorX4 as AesSafe192DecryptorMulti,
+    AesSafe256DecryptorX4 as AesSafe256DecryptorMulti};
+#[cfg(not(target_pointer_width = "64"))]
+use aessafe::{AesSafe128EncryptorX8 as AesSafe128EncryptorMulti,
+    AesSafe192EncryptorX8 as AesSafe192EncryptorMulti,
+    AesSafe256EncryptorX8 as AesSafe256EncryptorMulti,
+    AesSafe128DecryptorX8 as AesSafe128DecryptorMulti,
+    AesSafe192DecryptorX8 as AesSafe192DecryptorMulti,
+    AesSafe256DecryptorX8 as AesSafe256DecryptorMulti};
+
 /// AES key size
 #[derive(Clone, Copy)]
 pub enum KeySize {
@@ -29,7 +46,7 @@ pub fn ecb_encryptor<X: PaddingProcessor + Send + 'static>(
         padding: X) -> Box<Encryptor> {
     if util::supports_aesni() {
         let aes_enc = aesni::AesNiEncryptor::new(key_size, key);
-        let enc = Box::new(EcbEncryptor::new(aes_enc, padding));
+        let enc = Box::new(EcbEncryptorX8::new(aes_enc, padding));
         enc
     } else {
         match key_size {
@@ -85,7 +102,7 @@ pub fn ecb_decryptor<X: PaddingProcessor + Send + 'static>(
         padding: X) -> Box<Decryptor> {
     if util::supports_aesni() {
         let aes_dec = aesni::AesNiDecryptor::new(key_size, key);
-        let dec = Box::new(EcbDecryptor::new(aes_dec, padding));
+        let dec = Box::new(EcbDecryptorX8::new(aes_dec, padding));
         dec
     } else {
         match key_size {
@@ -200,23 +217,23 @@ pub fn cbc_decryptor<X: PaddingProcessor + Send + 'static>(
         padding: X) -> Box<Decryptor + 'static> {
     if util::supports_aesni() {
         let aes_dec = aesni::AesNiDecryptor::new(key_size, key);
-        let dec = Box::new(CbcDecryptor::new(aes_dec, padding, iv.to_vec()));
+        let dec = Box::new(CbcDecryptorX8::new(aes_dec, padding, iv.to_vec()));
         dec
     } else {
         match key_size {
             KeySize::KeySize128 => {
-                let aes_dec = aessafe::AesSafe128Decryptor::new(key);
-                let dec = Box::new(CbcDecryptor::new(aes_dec, padding, iv.to_vec()));
+                let aes_dec = AesSafe128DecryptorMulti::new(key);
+                let dec = Box::new(CbcDecryptorX8::new(aes_dec, padding, iv.to_vec()));
                 dec
             }
             KeySize::KeySize192 => {
-                let aes_dec = aessafe::AesSafe192Decryptor::new(key);
-                let dec = Box::new(CbcDecryptor::new(aes_dec, padding, iv.to_vec()));
+                let aes_dec = AesSafe192DecryptorMulti::new(key);
+                let dec = Box::new(CbcDecryptorX8::new(aes_dec, padding, iv.to_vec()));
                 dec
             }
             KeySize::KeySize256 => {
-                let aes_dec = aessafe::AesSafe256Decryptor::new(key);
-                let dec = Box::new(CbcDecryptor::new(aes_dec, padding, iv.to_vec()));
+                let aes_dec = AesSafe256DecryptorMulti::new(key);
+                let dec = Box::new(CbcDecryptorX8::new(aes_dec, padding, iv.to_vec()));
                 dec
             }
         }
@@ -232,79 +249,291 @@ pub fn cbc_decryptor<X: PaddingProcessor + Send + 'static>(
         padding: X) -> Box<Decryptor + 'static> {
     match key_size {
         KeySize::KeySize128 => {
-            let aes_dec = aessafe::AesSafe128Decryptor::new(key);
-            let dec = Box::new(CbcDecryptor::new(aes_dec, padding, iv.to_vec()));
+            let aes_dec = AesSafe128DecryptorMulti::new(key);
+            let dec = Box::new(CbcDecryptorX8::new(aes_dec, padding, iv.to_vec()));
             dec as Box<Decryptor + 'static>
         }
         KeySize::KeySize192 => {
-            let aes_dec = aessafe::AesSafe192Decryptor::new(key);
-            let dec = Box::new(CbcDecryptor::new(aes_dec, padding, iv.to_vec()));
+            let aes_dec = AesSafe192DecryptorMulti::new(key);
+            let dec = Box::new(CbcDecryptorX8::new(aes_dec, padding, iv.to_vec()));
             dec as Box<Decryptor + 'static>
         }
         KeySize::KeySize256 => {
-            let aes_dec = aessafe::AesSafe256Decryptor::new(key);
-            let dec = Box::new(CbcDecryptor::new(aes_dec, padding, iv.to_vec()));
+            let aes_dec = AesSafe256DecryptorMulti::new(key);
+            let dec = Box::new(CbcDecryptorX8::new(aes_dec, padding, iv.to_vec()));
             dec as Box<Decryptor + 'static>
         }
     }
 }
 
 /// Get the best implementation of a Ctr
-#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
 pub fn ctr(
         key_size: KeySize,
         key: &[u8],
         iv: &[u8]) -> Box<SynchronousStreamCipher + 'static> {
+    ctr_with_counter_size(key_size, key, iv, 16)
+}
+
+/// Get the best implementation of a Ctr where only the last counter_size bytes of the counter
+/// block are incremented, such as the 32 bit counter used by GCM
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub fn ctr_with_counter_size(
+        key_size: KeySize,
+        key: &[u8],
+        iv: &[u8],
+        counter_size: usize) -> Box<SynchronousStreamCipher + 'static> {
     if util::supports_aesni() {
         let aes_dec = aesni::AesNiEncryptor::new(key_size, key);
-        let dec = Box::new(CtrMode::new(aes_dec, iv.to_vec()));
+        let dec = Box::new(CtrModeX8::new_with_counter_size(aes_dec, iv, counter_size));
         dec
     } else {
         match key_size {
             KeySize::KeySize128 => {
-                let aes_dec = aessafe::AesSafe128EncryptorX8::new(key);
-                let dec = Box::new(CtrModeX8::new(aes_dec, iv));
+                let aes_dec = AesSafe128EncryptorMulti::new(key);
+                let dec = Box::new(CtrModeX8::new_with_counter_size(aes_dec, iv, counter_size));
                 dec
             }
             KeySize::KeySize192 => {
-                let aes_dec = aessafe::AesSafe192EncryptorX8::new(key);
-                let dec = Box::new(CtrModeX8::new(aes_dec, iv));
+                let aes_dec = AesSafe192EncryptorMulti::new(key);
+                let dec = Box::new(CtrModeX8::new_with_counter_size(aes_dec, iv, counter_size));
                 dec
             }
             KeySize::KeySize256 => {
-                let aes_dec = aessafe::AesSafe256EncryptorX8::new(key);
-                let dec = Box::new(CtrModeX8::new(aes_dec, iv));
+                let aes_dec = AesSafe256EncryptorMulti::new(key);
+                let dec = Box::new(CtrModeX8::new_with_counter_size(aes_dec, iv, counter_size));
                 dec
             }
         }
     }
 }
 
-/// Get the best implementation of a Ctr
+/// Get the best implementation of a Ctr where only the last counter_size bytes of the counter
+/// block are incremented, such as the 32 bit counter used by GCM
 #[cfg(all(not(target_arch = "x86"), not(target_arch = "x86_64")))]
-pub fn ctr(
+pub fn ctr_with_counter_size(
         key_size: KeySize,
         key: &[u8],
-        iv: &[u8]) -> Box<SynchronousStreamCipher + 'static> {
+        iv: &[u8],
+        counter_size: usize) -> Box<SynchronousStreamCipher + 'static> {
     match key_size {
         KeySize::KeySize128 => {
-            let aes_dec = aessafe::AesSafe128EncryptorX8::new(key);
-            let dec = Box::new(CtrModeX8::new(aes_dec, iv));
+            let aes_dec = AesSafe128EncryptorMulti::new(key);
+            let dec = Box::new(CtrModeX8::new_with_counter_size(aes_dec, iv, counter_size));
             dec as Box<SynchronousStreamCipher>
         }
         KeySize::KeySize192 => {
-            let aes_dec = aessafe::AesSafe192EncryptorX8::new(key);
-            let dec = Box::new(CtrModeX8::new(aes_dec, iv));
+            let aes_dec = AesSafe192EncryptorMulti::new(key);
+            let dec = Box::new(CtrModeX8::new_with_counter_size(aes_dec, iv, counter_size));
             dec as Box<SynchronousStreamCipher>
         }
         KeySize::KeySize256 => {
-            let aes_dec = aessafe::AesSafe256EncryptorX8::new(key);
-            let dec = Box::new(CtrModeX8::new(aes_dec, iv));
+            let aes_dec = AesSafe256EncryptorMulti::new(key);
+            let dec = Box::new(CtrModeX8::new_with_counter_size(aes_dec, iv, counter_size));
             dec as Box<SynchronousStreamCipher>
         }
     }
 }
 
+/// Get the best implementation of a CfbEncryptor
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub fn cfb_encryptor(
+        key_size: KeySize,
+        key: &[u8],
+        iv: &[u8],
+        segment_size: CfbSegmentSize) -> Box<Encryptor + 'static> {
+    if util::supports_aesni() {
+        let aes_enc = aesni::AesNiEncryptor::new(key_size, key);
+        let enc = Box::new(CfbEncryptor::new(aes_enc, segment_size, iv.to_vec()));
+        enc
+    } else {
+        match key_size {
+            KeySize::KeySize128 => {
+                let aes_enc = aessafe::AesSafe128Encryptor::new(key);
+                let enc = Box::new(CfbEncryptor::new(aes_enc, segment_size, iv.to_vec()));
+                enc
+            }
+            KeySize::KeySize192 => {
+                let aes_enc = aessafe::AesSafe192Encryptor::new(key);
+                let enc = Box::new(CfbEncryptor::new(aes_enc, segment_size, iv.to_vec()));
+                enc
+            }
+            KeySize::KeySize256 => {
+                let aes_enc = aessafe::AesSafe256Encryptor::new(key);
+                let enc = Box::new(CfbEncryptor::new(aes_enc, segment_size, iv.to_vec()));
+                enc
+            }
+        }
+    }
+}
+
+/// Get the best implementation of a CfbEncryptor
+#[cfg(all(not(target_arch = "x86"), not(target_arch = "x86_64")))]
+pub fn cfb_encryptor(
+        key_size: KeySize,
+        key: &[u8],
+        iv: &[u8],
+        segment_size: CfbSegmentSize) -> Box<Encryptor + 'static> {
+    match key_size {
+        KeySize::KeySize128 => {
+            let aes_enc = aessafe::AesSafe128Encryptor::new(key);
+            let enc = Box::new(CfbEncryptor::new(aes_enc, segment_size, iv.to_vec()));
+            enc as Box<Encryptor + 'static>
+        }
+        KeySize::KeySize192 => {
+            let aes_enc = aessafe::AesSafe192Encryptor::new(key);
+            let enc = Box::new(CfbEncryptor::new(aes_enc, segment_size, iv.to_vec()));
+            enc as Box<Encryptor + 'static>
+        }
+        KeySize::KeySize256 => {
+            let aes_enc = aessafe::AesSafe256Encryptor::new(key);
+            let enc = Box::new(CfbEncryptor::new(aes_enc, segment_size, iv.to_vec()));
+            enc as Box<Encryptor + 'static>
+        }
+    }
+}
+
+/// Get the best implementation of a CfbDecryptor
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub fn cfb_decryptor(
+        key_size: KeySize,
+        key: &[u8],
+        iv: &[u8],
+        segment_size: CfbSegmentSize) -> Box<Decryptor + 'static> {
+    if util::supports_aesni() {
+        let aes_enc = aesni::AesNiEncryptor::new(key_size, key);
+        let dec = Box::new(CfbDecryptor::new(aes_enc, segment_size, iv.to_vec()));
+        dec
+    } else {
+        match key_size {
+            KeySize::KeySize128 => {
+                let aes_enc = aessafe::AesSafe128Encryptor::new(key);
+                let dec = Box::new(CfbDecryptor::new(aes_enc, segment_size, iv.to_vec()));
+                dec
+            }
+            KeySize::KeySize192 => {
+                let aes_enc = aessafe::AesSafe192Encryptor::new(key);
+                let dec = Box::new(CfbDecryptor::new(aes_enc, segment_size, iv.to_vec()));
+                dec
+            }
+            KeySize::KeySize256 => {
+                let aes_enc = aessafe::AesSafe256Encryptor::new(key);
+                let dec = Box::new(CfbDecryptor::new(aes_enc, segment_size, iv.to_vec()));
+                dec
+            }
+        }
+    }
+}
+
+/// Get the best implementation of a CfbDecryptor
+#[cfg(all(not(target_arch = "x86"), not(target_arch = "x86_64")))]
+pub fn cfb_decryptor(
+        key_size: KeySize,
+        key: &[u8],
+        iv: &[u8],
+        segment_size: CfbSegmentSize) -> Box<Decryptor + 'static> {
+    match key_size {
+        KeySize::KeySize128 => {
+            let aes_enc = aessafe::AesSafe128Encryptor::new(key);
+            let dec = Box::new(CfbDecryptor::new(aes_enc, segment_size, iv.to_vec()));
+            dec as Box<Decryptor + 'static>
+        }
+        KeySize::KeySize192 => {
+            let aes_enc = aessafe::AesSafe192Encryptor::new(key);
+            let dec = Box::new(CfbDecryptor::new(aes_enc, segment_size, iv.to_vec()));
+            dec as Box<Decryptor + 'static>
+        }
+        KeySize::KeySize256 => {
+            let aes_enc = aessafe::AesSafe256Encryptor::new(key);
+            let dec = Box::new(CfbDecryptor::new(aes_enc, segment_size, iv.to_vec()));
+            dec as Box<Decryptor + 'static>
+        }
+    }
+}
+
+/// Get the best implementation of an OfbMode
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub fn ofb(
+        key_size: KeySize,
+        key: &[u8],
+        iv: &[u8]) -> Box<SynchronousStreamCipher + 'static> {
+    if util::supports_aesni() {
+        let aes_enc = aesni::AesNiEncryptor::new(key_size, key);
+        let ofb = Box::new(OfbMode::new(aes_enc, iv.to_vec()));
+        ofb
+    } else {
+        match key_size {
+            KeySize::KeySize128 => {
+                let aes_enc = aessafe::AesSafe128Encryptor::new(key);
+                let ofb = Box::new(OfbMode::new(aes_enc, iv.to_vec()));
+                ofb
+            }
+            KeySize::KeySize192 => {
+                let aes_enc = aessafe::AesSafe192Encryptor::new(key);
+                let ofb = Box::new(OfbMode::new(aes_enc, iv.to_vec()));
+                ofb
+            }
+            KeySize::KeySize256 => {
+                let aes_enc = aessafe::AesSafe256Encryptor::new(key);
+                let ofb = Box::new(OfbMode::new(aes_enc, iv.to_vec()));
+                ofb
+            }
+        }
+    }
+}
+
+/// Get the best implementation of an OfbMode
+#[cfg(all(not(target_arch = "x86"), not(target_arch = "x86_64")))]
+pub fn ofb(
+        key_size: KeySize,
+        key: &[u8],
+        iv: &[u8]) -> Box<SynchronousStreamCipher + 'static> {
+    match key_size {
+        KeySize::KeySize128 => {
+            let aes_enc = aessafe::AesSafe128Encryptor::new(key);
+            let ofb = Box::new(OfbMode::new(aes_enc, iv.to_vec()));
+            ofb as Box<SynchronousStreamCipher>
+        }
+        KeySize::KeySize192 => {
+            let aes_enc = aessafe::AesSafe192Encryptor::new(key);
+            let ofb = Box::new(OfbMode::new(aes_enc, iv.to_vec()));
+            ofb as Box<SynchronousStreamCipher>
+        }
+        KeySize::KeySize256 => {
+            let aes_enc = aessafe::AesSafe256Encryptor::new(key);
+            let ofb = Box::new(OfbMode::new(aes_enc, iv.to_vec()));
+            ofb as Box<SynchronousStreamCipher>
+        }
+    }
+}
+
+/// Get the best implementation of a BlockEncryptor
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub fn block_encryptor(
+        key_size: KeySize,
+        key: &[u8]) -> Box<BlockEncryptor + 'static> {
+    if util::supports_aesni() {
+        Box::new(aesni::AesNiEncryptor::new(key_size, key))
+    } else {
+        match key_size {
+            KeySize::KeySize128 => Box::new(aessafe::AesSafe128Encryptor::new(key)),
+            KeySize::KeySize192 => Box::new(aessafe::AesSafe192Encryptor::new(key)),
+            KeySize::KeySize256 => Box::new(aessafe::AesSafe256Encryptor::new(key))
+        }
+    }
+}
+
+/// Get the best implementation of a BlockEncryptor
+#[cfg(all(not(target_arch = "x86"), not(target_arch = "x86_64")))]
+pub fn block_encryptor(
+        key_size: KeySize,
+        key: &[u8]) -> Box<BlockEncryptor + 'static> {
+    match key_size {
+        KeySize::KeySize128 => Box::new(aessafe::AesSafe128Encryptor::new(key)),
+        KeySize::KeySize192 => Box::new(aessafe::AesSafe192Encryptor::new(key)),
+        KeySize::KeySize256 => Box::new(aessafe::AesSafe256Encryptor::new(key))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::iter::repeat;
@@ -313,11 +542,14 @@ mod test {
     use aesni;
 
     use aessafe;
+    use blockmodes::{CfbSegmentSize, NoPadding};
+    use buffer::{RefReadBuffer, RefWriteBuffer};
     use symmetriccipher::{BlockEncryptor, BlockDecryptor, BlockEncryptorX8, BlockDecryptorX8,
-            SynchronousStreamCipher};
+            Encryptor, Decryptor, SynchronousStreamCipher};
     use util;
     use aes;
     use aes::KeySize::{KeySize128, KeySize192, KeySize256};
+    use serialize::hex::FromHex;
 
     // Test vectors from:
     // http://www.inconteam.com/software-development/41-encryption/55-aes-test-vectors
@@ -511,6 +743,33 @@ mod test {
         }
     }
 
+    // The 8 block functions must give the same results as processing each block separately
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[test]
+    fn test_aesni_x8() {
+        if util::supports_aesni() {
+            let key: Vec<u8> = (0..32).collect();
+            let plain: Vec<u8> = (0..128).map(|i| (i * 7) as u8).collect();
+            let key_sizes = [(KeySize128, 16), (KeySize192, 24), (KeySize256, 32)];
+            for &(key_size, key_len) in key_sizes.iter() {
+                let enc = aesni::AesNiEncryptor::new(key_size, &key[..key_len]);
+                let dec = aesni::AesNiDecryptor::new(key_size, &key[..key_len]);
+
+                let mut expected = [0u8; 128];
+                for (p, c) in plain.chunks(16).zip(expected.chunks_mut(16)) {
+                    enc.encrypt_block(p, c);
+                }
+                let mut cipher = [0u8; 128];
+                enc.encrypt_block_x8(&plain[..], &mut cipher);
+                assert!(cipher[..] == expected[..]);
+
+                let mut tmp = [0u8; 128];
+                dec.decrypt_block_x8(&cipher, &mut tmp);
+                assert!(tmp[..] == plain[..]);
+            }
+        }
+    }
+
     #[test]
     fn test_aessafe_128() {
         let tests = tests128();
@@ -703,6 +962,227 @@ mod test {
             assert!(res == &test.cipher[..]);
         }
     }
+
+    // AES-256 vectors from NIST SP 800-38A, Appendix F
+    fn nist_256_key() -> Vec<u8> {
+        "603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4".from_hex().unwrap()
+    }
+
+    fn nist_iv() -> Vec<u8> {
+        "000102030405060708090a0b0c0d0e0f".from_hex().unwrap()
+    }
+
+    fn nist_plain() -> Vec<u8> {
+        "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51".from_hex().unwrap()
+    }
+
+    #[test]
+    fn aes_cfb_box() {
+        let tests = [
+            (CfbSegmentSize::Bits8,
+                "dc1f1a8520a64db55fcc8ac554844e889700adc6e10c63cf2d8cd2d8ce668f3e"),
+            (CfbSegmentSize::Block,
+                "dc7e84bfda79164b7ecd8486985d386039ffed143b28b1c832113c6331e5407b")];
+        for &(segment_size, cipher) in tests.iter() {
+            let plain = nist_plain();
+            let cipher = cipher.from_hex().unwrap();
+
+            let key = nist_256_key();
+            let iv = nist_iv();
+            let mut result = [0u8; 32];
+
+            let mut enc = aes::cfb_encryptor(KeySize256, &key, &iv, segment_size);
+            {
+                let mut buff_in = RefReadBuffer::new(&plain);
+                let mut buff_out = RefWriteBuffer::new(&mut result);
+                enc.encrypt(&mut buff_in, &mut buff_out, true).unwrap();
+            }
+            assert!(result[..] == cipher[..]);
+
+            let mut dec = aes::cfb_decryptor(KeySize256, &key, &iv, segment_size);
+            {
+                let mut buff_in = RefReadBuffer::new(&cipher);
+                let mut buff_out = RefWriteBuffer::new(&mut result);
+                dec.decrypt(&mut buff_in, &mut buff_out, true).unwrap();
+            }
+            assert!(result[..] == plain[..]);
+        }
+    }
+
+    #[test]
+    fn aes_ofb_box() {
+        let cipher = "dc7e84bfda79164b7ecd8486985d38604febdc6740d20b3ac88f6ad82a4fb08d"
+            .from_hex().unwrap();
+        let mut ofb = aes::ofb(KeySize256, &nist_256_key(), &nist_iv());
+        let mut result = [0u8; 32];
+        ofb.process(&nist_plain(), &mut result);
+        assert!(result[..] == cipher[..]);
+    }
+
+    // The four NIST SP 800-38A blocks repeated three times, so that a complete group of 8 blocks
+    // followed by a partial group is processed. Expected values were generated with OpenSSL.
+    fn long_plain() -> Vec<u8> {
+        let plain = "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51\
+            30c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710".from_hex().unwrap();
+        let mut result = Vec::new();
+        for _ in 0..3 {
+            result.extend_from_slice(&plain[..]);
+        }
+        result
+    }
+
+    fn run_box_test(mut enc: Box<Encryptor>, mut dec: Box<Decryptor>, plain: &[u8], cipher: &[u8]) {
+        let mut result: Vec<u8> = repeat(0).take(plain.len()).collect();
+        {
+            let mut buff_in = RefReadBuffer::new(plain);
+            let mut buff_out = RefWriteBuffer::new(&mut result);
+            enc.encrypt(&mut buff_in, &mut buff_out, true).unwrap();
+        }
+        assert!(&result[..] == cipher);
+        {
+            let mut buff_in = RefReadBuffer::new(cipher);
+            let mut buff_out = RefWriteBuffer::new(&mut result);
+            dec.decrypt(&mut buff_in, &mut buff_out, true).unwrap();
+        }
+        assert!(&result[..] == plain);
+    }
+
+    #[test]
+    fn aes_ecb_long_box() {
+        let block = "f3eed1bdb5d2a03c064b5a7e3db181f8591ccb10d410ed26dc5ba74a31362870\
+            b6ed21b99ca6f4f9f153e7b1beafed1d23304b7a39f9f3ff067d8d8f9e24ecc7".from_hex().unwrap();
+        let mut cipher = Vec::new();
+        for _ in 0..3 {
+            cipher.extend_from_slice(&block[..]);
+        }
+        let key = nist_256_key();
+        run_box_test(
+            aes::ecb_encryptor(KeySize256, &key, NoPadding),
+            aes::ecb_decryptor(KeySize256, &key, NoPadding),
+            &long_plain(),
+            &cipher);
+    }
+
+    #[test]
+    fn aes_cbc_long_box() {
+        let cipher = "f58c4c04d6e5f1ba779eabfb5f7bfbd69cfc4e967edb808d679f777bc6702c7d\
+            39f23369a9d9bacfa530e26304231461b2eb05e2c39be9fcda6c19078c6a9d1b\
+            b34e62fc55e1ccf6674cf57f3ac8f2829e44a898a93f73592513986a0d5a2a71\
+            eb440582fff1c1a5f695697f53d9bab497db25863aef3a5801dbf1e1d43b6a6b\
+            b8d11b12f41acbc0d2145f7aebdeeee281ebf423c342318688ff61879e701eb9\
+            3792fdd7fb93decaa6b2e5d789afe237b049f9131e450ca74e778ea57ab7d8fb".from_hex().unwrap();
+        let key = nist_256_key();
+        let iv = nist_iv();
+        run_box_test(
+            aes::cbc_encryptor(KeySize256, &key, &iv, NoPadding),
+            aes::cbc_decryptor(KeySize256, &key, &iv, NoPadding),
+            &long_plain(),
+            &cipher);
+    }
+
+    #[test]
+    fn aes_ctr_long_box() {
+        let cipher = "601ec313775789a5b7a7f504bbf3d228f443e3ca4d62b59aca84e990cacaf5c5\
+            2b0930daa23de94ce87017ba2d84988ddfc9c58db67aada613c2dd08457941a6\
+            e0b64102f73c96043eca700d9a5cd49de2c6edd57e05a41ff215a4e960350bfc\
+            29064fba9349656330acdd59f44b0b87c2f00d4c6182fa14c90c9de0cdbb5af6\
+            b57c2d9f642e729f3f728d385852995a34e1a3ff4fe0dd91555892d957419066\
+            fdc486966588343c700c291c0eda9d935bb9412c3a0fa486f8d78e483fb88f47".from_hex().unwrap();
+        let ctr = "f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff".from_hex().unwrap();
+        let mut ctr_mode = aes::ctr(KeySize256, &nist_256_key(), &ctr);
+        let mut result = [0u8; 192];
+        ctr_mode.process(&long_plain(), &mut result);
+        assert!(result[..] == cipher[..]);
+    }
+
+    #[test]
+    fn test_aessafe_128_x4() {
+        let tests = tests128();
+        for t in tests.iter() {
+            let mut enc = aessafe::AesSafe128EncryptorX4::new(&t.key[..]);
+            let mut dec = aessafe::AesSafe128DecryptorX4::new(&t.key[..]);
+            run_test(&mut enc, &mut dec, t);
+        }
+    }
+
+    #[test]
+    fn test_aessafe_192_x4() {
+        let tests = tests192();
+        for t in tests.iter() {
+            let mut enc = aessafe::AesSafe192EncryptorX4::new(&t.key[..]);
+            let mut dec = aessafe::AesSafe192DecryptorX4::new(&t.key[..]);
+            run_test(&mut enc, &mut dec, t);
+        }
+    }
+
+    #[test]
+    fn test_aessafe_256_x4() {
+        let tests = tests256();
+        for t in tests.iter() {
+            let mut enc = aessafe::AesSafe256EncryptorX4::new(&t.key[..]);
+            let mut dec = aessafe::AesSafe256DecryptorX4::new(&t.key[..]);
+            run_test(&mut enc, &mut dec, t);
+        }
+    }
+
+    #[test]
+    fn test_aessafe_x8_single_block() {
+        for t in tests128().iter() {
+            let mut enc = aessafe::AesSafe128EncryptorX8::new(&t.key[..]);
+            let mut dec = aessafe::AesSafe128DecryptorX8::new(&t.key[..]);
+            run_test(&mut enc, &mut dec, t);
+        }
+        for t in tests192().iter() {
+            let mut enc = aessafe::AesSafe192EncryptorX8::new(&t.key[..]);
+            let mut dec = aessafe::AesSafe192DecryptorX8::new(&t.key[..]);
+            run_test(&mut enc, &mut dec, t);
+        }
+        for t in tests256().iter() {
+            let mut enc = aessafe::AesSafe256EncryptorX8::new(&t.key[..]);
+            let mut dec = aessafe::AesSafe256DecryptorX8::new(&t.key[..]);
+            run_test(&mut enc, &mut dec, t);
+        }
+    }
+
+    // The 4 block implementation must give the same results as the 8 block one
+    #[test]
+    fn test_aessafe_x4_x8() {
+        fn check<E4, D4, E8, D8>(enc4: E4, dec4: D4, enc8: E8, dec8: D8)
+                where E4: BlockEncryptorX8, D4: BlockDecryptorX8,
+                      E8: BlockEncryptorX8, D8: BlockDecryptorX8 {
+            let plain: Vec<u8> = (0..128).map(|i| (i * 7) as u8).collect();
+
+            let mut expected = [0u8; 128];
+            enc8.encrypt_block_x8(&plain[..], &mut expected);
+            let mut cipher = [0u8; 128];
+            enc4.encrypt_block_x8(&plain[..], &mut cipher);
+            assert!(cipher[..] == expected[..]);
+
+            dec8.decrypt_block_x8(&cipher, &mut expected);
+            assert!(expected[..] == plain[..]);
+            let mut tmp = [0u8; 128];
+            dec4.decrypt_block_x8(&cipher, &mut tmp);
+            assert!(tmp[..] == plain[..]);
+        }
+
+        let key: Vec<u8> = (0..32).collect();
+        check(
+            aessafe::AesSafe128EncryptorX4::new(&key[..16]),
+            aessafe::AesSafe128DecryptorX4::new(&key[..16]),
+            aessafe::AesSafe128EncryptorX8::new(&key[..16]),
+            aessafe::AesSafe128DecryptorX8::new(&key[..16]));
+        check(
+            aessafe::AesSafe192EncryptorX4::new(&key[..24]),
+            aessafe::AesSafe192DecryptorX4::new(&key[..24]),
+            aessafe::AesSafe192EncryptorX8::new(&key[..24]),
+            aessafe::AesSafe192DecryptorX8::new(&key[..24]));
+        check(
+            aessafe::AesSafe256EncryptorX4::new(&key),
+            aessafe::AesSafe256DecryptorX4::new(&key),
+            aessafe::AesSafe256EncryptorX8::new(&key),
+            aessafe::AesSafe256DecryptorX8::new(&key));
+    }
+
 }
 
 #[cfg(all(test, feature = "with-bench"))]
@@ -753,6 +1233,25 @@ mod bench {
         }
     }
 
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[bench]
+    pub fn aesni_x8_bench(bh: &mut Bencher) {
+        if util::supports_aesni() {
+            let key: [u8; 16] = [1u8; 16];
+            let plain: [u8; 128] = [2u8; 128];
+
+            let a = aesni::AesNiEncryptor::new(KeySize128, &key);
+
+            let mut tmp = [0u8; 128];
+
+            bh.iter( || {
+                a.encrypt_block_x8(&plain, &mut tmp);
+            });
+
+            bh.bytes = (plain.len()) as u64;
+        }
+    }
+
     #[bench]
     pub fn aes_safe_bench(bh: &mut Bencher) {
         let key: [u8; 16] = [1u8; 16];
@@ -784,4 +1283,20 @@ mod bench {
 
         bh.bytes = (plain.len()) as u64;
     }
+
+    #[bench]
+    pub fn aes_safe_x4_bench(bh: &mut Bencher) {
+        let key: [u8; 16] = [1u8; 16];
+        let plain: [u8; 64] = [2u8; 64];
+
+        let a = aessafe::AesSafe128EncryptorX4::new(&key);
+
+        let mut tmp = [0u8; 64];
+
+        bh.iter( || {
+            a.encrypt_block_x4(&plain, &mut tmp);
+        });
+
+        bh.bytes = (plain.len()) as u64;
+    }
 }
@@ -0,0 +1,375 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+ * An implementation of AES in CCM (Counter with CBC-MAC) mode, as defined in RFC 3610 [1] and NIST
+ * SP 800-38C [2].
+ *
+ * The nonce length may be between 7 and 13 bytes, and the tag length may be any even number of
+ * bytes between 4 and 16. A shorter nonce leaves more room for the message length: with a nonce of
+ * n bytes, messages must be shorter than 2^(8 * (15 - n)) bytes.
+ *
+ * [1] - "Counter with CBC-MAC (CCM)" - D. Whiting, R. Housley and N. Ferguson
+ *       <https://tools.ietf.org/html/rfc3610>
+ * [2] - "Recommendation for Block Cipher Modes of Operation: The CCM Mode for Authentication and
+ *       Confidentiality" <http://csrc.nist.gov/publications/nistpubs/800-38C/SP800-38C.pdf>
+ */
+
+use std::cmp;
+
+use aes::{block_encryptor, KeySize};
+use aead::{AeadEncryptor, AeadDecryptor};
+use cryptoutil::{copy_memory, write_u32_be, write_u64_be, zero};
+use symmetriccipher::BlockEncryptor;
+use util::fixed_time_eq;
+
+pub struct AesCcm {
+    cipher: Box<BlockEncryptor>,
+    nonce: Vec<u8>,
+    aad: Vec<u8>,
+    tag_len: usize,
+    finished: bool
+}
+
+// Xor data into the CBC-MAC state one block at a time, zero padding the final block
+fn cbc_mac(cipher: &BlockEncryptor, state: &mut [u8; 16], data: &[u8]) {
+    for chunk in data.chunks(16) {
+        for (s, &d) in state.iter_mut().zip(chunk.iter()) {
+            *s ^= d;
+        }
+        let tmp = *state;
+        cipher.encrypt_block(&tmp, state);
+    }
+}
+
+impl AesCcm {
+    /**
+     * Create a new AesCcm instance.
+     *
+     * # Arguments
+     * * key_size - The AES key size.
+     * * key - The AES key.
+     * * nonce - The nonce, between 7 and 13 bytes long.
+     * * aad - The associated data to authenticate.
+     * * tag_len - The length of the tag in bytes: 4, 6, 8, 10, 12, 14 or 16.
+     *
+     */
+    pub fn new(key_size: KeySize, key: &[u8], nonce: &[u8], aad: &[u8], tag_len: usize) -> AesCcm {
+        assert!(key.len() == 16 || key.len() == 24 || key.len() == 32);
+        assert!((7..14).contains(&nonce.len()));
+        assert!((4..17).contains(&tag_len) && tag_len % 2 == 0);
+
+        AesCcm {
+            cipher: block_encryptor(key_size, key),
+            nonce: nonce.to_vec(),
+            aad: aad.to_vec(),
+            tag_len: tag_len,
+            finished: false
+        }
+    }
+
+    // The size of the length field (L in RFC 3610) in bytes
+    fn length_size(&self) -> usize {
+        15 - self.nonce.len()
+    }
+
+    // Computes the block A_i of the key stream, which is also used to encrypt the tag when i = 0
+    fn counter_block(&self, i: u64) -> [u8; 16] {
+        let mut block = [0u8; 16];
+        block[0] = (self.length_size() - 1) as u8;
+        copy_memory(&self.nonce, &mut block[1..]);
+        let mut counter = [0u8; 8];
+        write_u64_be(&mut counter, i);
+        let l = cmp::min(self.length_size(), 8);
+        copy_memory(&counter[8 - l..], &mut block[16 - l..]);
+        block
+    }
+
+    fn process(&self, input: &[u8], output: &mut [u8]) {
+        let mut key_stream = [0u8; 16];
+        for (i, (in_chunk, out_chunk)) in input.chunks(16).zip(output.chunks_mut(16)).enumerate() {
+            self.cipher.encrypt_block(&self.counter_block(i as u64 + 1), &mut key_stream);
+            for ((o, &x), &k) in out_chunk.iter_mut().zip(in_chunk.iter()).zip(key_stream.iter()) {
+                *o = x ^ k;
+            }
+        }
+    }
+
+    // Computes the CBC-MAC of the nonce, associated data and plain text and encrypts it
+    fn compute_tag(&self, plain_text: &[u8]) -> [u8; 16] {
+        let flags = (if self.aad.is_empty() { 0 } else { 0x40 }) |
+            (((self.tag_len - 2) / 2) << 3) as u8 |
+            (self.length_size() - 1) as u8;
+        let mut b0 = [0u8; 16];
+        b0[0] = flags;
+        copy_memory(&self.nonce, &mut b0[1..]);
+        let mut msg_len = [0u8; 8];
+        write_u64_be(&mut msg_len, plain_text.len() as u64);
+        let l = cmp::min(self.length_size(), 8);
+        copy_memory(&msg_len[8 - l..], &mut b0[16 - l..]);
+
+        let mut state = [0u8; 16];
+        self.cipher.encrypt_block(&b0, &mut state);
+
+        if !self.aad.is_empty() {
+            // The length of the associated data is prepended using a 2, 6 or 10 byte encoding
+            let aad_len = self.aad.len() as u64;
+            let mut header = Vec::with_capacity(10 + self.aad.len());
+            if aad_len < 0xff00 {
+                header.push((aad_len >> 8) as u8);
+                header.push(aad_len as u8);
+            } else if aad_len <= 0xffff_ffff {
+                let mut len = [0u8; 4];
+                write_u32_be(&mut len, aad_len as u32);
+                header.extend_from_slice(&[0xff, 0xfe]);
+                header.extend_from_slice(&len);
+            } else {
+                let mut len = [0u8; 8];
+                write_u64_be(&mut len, aad_len);
+                header.extend_from_slice(&[0xff, 0xff]);
+                header.extend_from_slice(&len);
+            }
+            header.extend_from_slice(&self.aad);
+            cbc_mac(&*self.cipher, &mut state, &header);
+        }
+        cbc_mac(&*self.cipher, &mut state, plain_text);
+
+        let mut s0 = [0u8; 16];
+        self.cipher.encrypt_block(&self.counter_block(0), &mut s0);
+        for (t, &s) in state.iter_mut().zip(s0.iter()) {
+            *t ^= s;
+        }
+        state
+    }
+
+    fn check_length(&self, len: usize) {
+        let l = self.length_size();
+        assert!(l >= 8 || (len as u64) < 1u64 << (8 * l), "Message too long for the nonce size");
+    }
+}
+
+impl AeadEncryptor for AesCcm {
+    fn encrypt(&mut self, input: &[u8], output: &mut [u8], tag: &mut [u8]) {
+        assert!(input.len() == output.len());
+        assert!(tag.len() == self.tag_len);
+        assert!(!self.finished);
+        self.check_length(input.len());
+        self.finished = true;
+        let full_tag = self.compute_tag(input);
+        copy_memory(&full_tag[..self.tag_len], tag);
+        self.process(input, output);
+    }
+}
+
+impl AeadDecryptor for AesCcm {
+    fn decrypt(&mut self, input: &[u8], output: &mut [u8], tag: &[u8]) -> bool {
+        assert!(input.len() == output.len());
+        assert!(tag.len() == self.tag_len);
+        assert!(!self.finished);
+        self.check_length(input.len());
+        self.finished = true;
+        // The tag is computed over the plain text, so it must be decrypted before it can be checked
+        self.process(input, output);
+        let calc_tag = self.compute_tag(output);
+        if fixed_time_eq(&calc_tag[..self.tag_len], tag) {
+            true
+        } else {
+            zero(output);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use aes::KeySize;
+    use aes_ccm::AesCcm;
+    use aead::{AeadEncryptor, AeadDecryptor};
+    use serialize::hex::FromHex;
+    use std::iter::repeat;
+    fn hex_to_bytes(raw_hex: &str) -> Vec<u8> {
+        raw_hex.from_hex().ok().unwrap()
+    }
+    fn key_size(key: &[u8]) -> KeySize {
+        match key.len() {
+            16 => KeySize::KeySize128,
+            24 => KeySize::KeySize192,
+            32 => KeySize::KeySize256,
+            _ => unreachable!()
+        }
+    }
+    struct TestVector {
+        key: Vec<u8>,
+        nonce: Vec<u8>,
+        plain_text: Vec<u8>,
+        cipher_text: Vec<u8>,
+        aad: Vec<u8>,
+        tag: Vec<u8>,
+    }
+
+    // The first vector is packet vector #1 from RFC 3610 and the next three are examples 1 to 3
+    // from NIST SP 800-38C Appendix C. The remaining vectors were generated with the AESCCM
+    // implementation of the Python cryptography package.
+    fn get_test_vectors() -> [TestVector; 7] {
+        [
+            TestVector {
+                key: hex_to_bytes("c0c1c2c3c4c5c6c7c8c9cacbcccdcecf"),
+                nonce: hex_to_bytes("00000003020100a0a1a2a3a4a5"),
+                plain_text: hex_to_bytes("08090a0b0c0d0e0f101112131415161718191a1b1c1d1e"),
+                cipher_text: hex_to_bytes("588c979a61c663d2f066d0c2c0f989806d5f6b61dac384"),
+                aad: hex_to_bytes("0001020304050607"),
+                tag: hex_to_bytes("17e8d12cfdf926e0")
+            },
+            TestVector {
+                key: hex_to_bytes("404142434445464748494a4b4c4d4e4f"),
+                nonce: hex_to_bytes("10111213141516"),
+                plain_text: hex_to_bytes("20212223"),
+                cipher_text: hex_to_bytes("7162015b"),
+                aad: hex_to_bytes("0001020304050607"),
+                tag: hex_to_bytes("4dac255d")
+            },
+            TestVector {
+                key: hex_to_bytes("404142434445464748494a4b4c4d4e4f"),
+                nonce: hex_to_bytes("1011121314151617"),
+                plain_text: hex_to_bytes("202122232425262728292a2b2c2d2e2f"),
+                cipher_text: hex_to_bytes("d2a1f0e051ea5f62081a7792073d593d"),
+                aad: hex_to_bytes("000102030405060708090a0b0c0d0e0f"),
+                tag: hex_to_bytes("1fc64fbfaccd")
+            },
+            TestVector {
+                key: hex_to_bytes("404142434445464748494a4b4c4d4e4f"),
+                nonce: hex_to_bytes("101112131415161718191a1b"),
+                plain_text: hex_to_bytes("202122232425262728292a2b2c2d2e2f3031323334353637"),
+                cipher_text: hex_to_bytes("e3b201a9f5b71a7a9b1ceaeccd97e70b6176aad9a4428aa5"),
+                aad: hex_to_bytes("000102030405060708090a0b0c0d0e0f10111213"),
+                tag: hex_to_bytes("484392fbc1b09951")
+            },
+            TestVector {
+                key: hex_to_bytes("98be0d89e338f5c702c60b4a8ee1818d0475259ded90c17e"),
+                nonce: hex_to_bytes("84dfd68a882f07aaef5e8c300d"),
+                plain_text: hex_to_bytes("c57ec2e8ef8f35d8a015fa6d6df3e33a68f286218d0a929cb2d58bea1d7e1331\
+                                          3012b0344b15a540"),
+                cipher_text: hex_to_bytes("eb6da0f974d19a721d856012e860110450a1fa51c40364b9efe1f061e4bb7ba8\
+                                           ed067b9a3e39c17f"),
+                aad: hex_to_bytes(""),
+                tag: hex_to_bytes("562b2e2124b6c44e0e4fb1c75c7fe080")
+            },
+            TestVector {
+                key: hex_to_bytes("71a9fbc9a6ba624bffe9125ecd4b8b9af55b995df6bca72f87c89d4ca6ee164e"),
+                nonce: hex_to_bytes("a7a591bfadd0f66b377d"),
+                plain_text: hex_to_bytes("5dbd8e2ec967e427f39b36a6e3d324d5f517cc58dca838b125c0545c52516d93\
+                                          c35eac7debf91f5458a088f553e620e82d59f0e6b8189d1ebf71461c37d4fee8\
+                                          5dbd8e2ec967"),
+                cipher_text: hex_to_bytes("6bb7c6f8eb0837f110269e6244610fd5f0db799ec49d95eaed8e8adc5bb87c35\
+                                           8f0eae9c0cb67513aade306af26cab834d5f458f822df55f59330bac7ac8bc8b\
+                                           8daaddd69020"),
+                aad: hex_to_bytes("06732a276adb7570b3ca86ba231e307a30bc9af4"),
+                tag: hex_to_bytes("cef923107ea25510dd8802db")
+            },
+            TestVector {
+                key: hex_to_bytes("dfe174a5f0a0f715804fb483e5d89546d7db847f09e927de3e5aa1562b60e764"),
+                nonce: hex_to_bytes("e44ed67a087f77"),
+                plain_text: hex_to_bytes(""),
+                cipher_text: hex_to_bytes(""),
+                aad: hex_to_bytes(""),
+                tag: hex_to_bytes("32c55e29e4010946f7d6")
+            },
+        ]
+    }
+
+    #[test]
+    fn aes_ccm_test() {
+        for item in get_test_vectors().iter() {
+            let mut cipher = AesCcm::new(key_size(&item.key), &item.key[..], &item.nonce[..],
+                                         &item.aad[..], item.tag.len());
+            let mut out: Vec<u8> = repeat(0).take(item.plain_text.len()).collect();
+            let mut out_tag: Vec<u8> = repeat(0).take(item.tag.len()).collect();
+            cipher.encrypt(&item.plain_text[..], &mut out[..], &mut out_tag[..]);
+            assert_eq!(out, item.cipher_text);
+            assert_eq!(out_tag, item.tag);
+        }
+    }
+
+    #[test]
+    fn aes_ccm_decrypt_test() {
+        for item in get_test_vectors().iter() {
+            let mut decipher = AesCcm::new(key_size(&item.key), &item.key[..], &item.nonce[..],
+                                           &item.aad[..], item.tag.len());
+            let mut out: Vec<u8> = repeat(0).take(item.plain_text.len()).collect();
+            let result = decipher.decrypt(&item.cipher_text[..], &mut out[..], &item.tag[..]);
+            assert_eq!(out, item.plain_text);
+            assert!(result);
+        }
+    }
+
+    #[test]
+    fn aes_ccm_decrypt_fail_test() {
+        for item in get_test_vectors().iter() {
+            let mut decipher = AesCcm::new(key_size(&item.key), &item.key[..], &item.nonce[..],
+                                           &item.aad[..], item.tag.len());
+            let mut tag = item.tag.clone();
+            tag[0] ^= 1;
+            let mut out1: Vec<u8> = repeat(0).take(item.plain_text.len()).collect();
+            let out2: Vec<u8> = repeat(0).take(item.plain_text.len()).collect();
+            let result = decipher.decrypt(&item.cipher_text[..], &mut out1[..], &tag[..]);
+            assert_eq!(out1, out2);
+            assert!(!result);
+        }
+    }
+
+    #[test]
+    fn aes_ccm_long_aad_test() {
+        // Associated data of 0xff00 bytes or more uses the 6 byte length encoding
+        let key: Vec<u8> = (0..16).collect();
+        let nonce: Vec<u8> = (0..12).collect();
+        let aad: Vec<u8> = (0..70000).map(|i| i as u8).collect();
+        let plain_text = [0u8; 32];
+        let mut cipher = AesCcm::new(KeySize::KeySize128, &key, &nonce, &aad, 16);
+        let mut out = [0u8; 32];
+        let mut tag = [0u8; 16];
+        cipher.encrypt(&plain_text, &mut out, &mut tag);
+        assert_eq!(&out[..],
+            &hex_to_bytes("3315f367dc80c4b17113c9e002ea85806c561932cb04b7384e674c9df020abb1")[..]);
+        assert_eq!(&tag[..], &hex_to_bytes("b4f3df8a4f5a45f7e24f0b573c3b0737")[..]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn aes_ccm_message_too_long() {
+        // A 13 byte nonce only leaves 2 bytes for the message length
+        let mut cipher = AesCcm::new(KeySize::KeySize128, &[0u8; 16], &[0u8; 13], &[], 16);
+        let input: Vec<u8> = repeat(0).take(0x10000).collect();
+        let mut out: Vec<u8> = repeat(0).take(0x10000).collect();
+        let mut tag = [0u8; 16];
+        cipher.encrypt(&input, &mut out, &mut tag);
+    }
+}
+
+#[cfg(all(test, feature = "with-bench"))]
+mod bench {
+    use test::Bencher;
+    use aes::KeySize;
+    use aes_ccm::AesCcm;
+    use aead::{AeadEncryptor, AeadDecryptor};
+
+    #[bench]
+    pub fn ccm_1k(bh: & mut Bencher) {
+        let input = [1u8; 1024];
+        let aad = [3u8; 1024];
+        bh.iter( || {
+            let mut cipher = AesCcm::new(KeySize::KeySize256, &[0; 32], &[0; 12], &aad, 16);
+            let mut decipher = AesCcm::new(KeySize::KeySize256, &[0; 32], &[0; 12], &aad, 16);
+
+            let mut output = [0u8; 1024];
+            let mut tag = [0u8; 16];
+            let mut output2 = [0u8; 1024];
+
+            cipher.encrypt(&input, &mut output, &mut tag);
+            decipher.decrypt(&output, &mut output2, &tag);
+        });
+        bh.bytes = 1024u64;
+    }
+}
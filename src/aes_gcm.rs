@@ -4,76 +4,142 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use aes::{ctr, KeySize};
-use aead::{AeadEncryptor,AeadDecryptor};
+use aes::{block_encryptor, ctr_with_counter_size, KeySize};
+use aead::{AeadEncryptor, AeadDecryptor, AeadStreamEncryptor, AeadStreamDecryptor};
 use cryptoutil::copy_memory;
-use symmetriccipher::SynchronousStreamCipher;
-use ghash::{Ghash};
+use symmetriccipher::{BlockEncryptor, SynchronousStreamCipher};
+use ghash::{Ghash, GhashWithC};
 use util::fixed_time_eq;
 
+// The maximum length of the plain text for a single nonce, 2^39 - 256 bits, which is where the
+// 32 bit block counter would wrap around onto the block used to encrypt the tag
+const MAX_LEN: u64 = (1 << 36) - 32;
+
 pub struct AesGcm<'a> {
     cipher: Box<SynchronousStreamCipher + 'a>,
     mac: Ghash,
+    // The GHASH state once the first part of the cipher text has been processed
+    mac_c: Option<GhashWithC>,
+    data_len: u64,
     finished: bool,
     end_tag: [u8; 16]
 }
 
 impl<'a> AesGcm<'a> {
+    /// Create a new AesGcm instance. The nonce may have any non-zero length. 12 byte nonces are
+    /// used directly as the initial counter block and are recommended; nonces of any other length
+    /// are hashed with GHASH first, as specified in NIST SP 800-38D.
     pub fn new (key_size: KeySize, key: &[u8], nonce: &[u8], aad: &[u8]) -> AesGcm<'a> {
         assert!(key.len() == 16 || key.len() == 24 || key.len() == 32);
-        assert!(nonce.len() == 12);
-
-        // GCM technically differs from CTR mode in how role overs are handled
-        // GCM only touches the right most 4 bytes while CTR roles all 16 over
-        // when the iv is only 96 bits (12 bytes) then 4 bytes of zeros are
-        // appended to it meaning you have to encrypt 2^37 bytes (256 gigabytes)
-        // of data before a difference crops up.
-        // The GCM handles nonces of other lengths by hashing them once with ghash
-        // this would cause the roleover behavior to potentially be triggered much
-        // earlier preventing the use of generic CTR mode.
-
-        let mut iv = [0u8; 16];
-        copy_memory(nonce, &mut iv);
-        iv[15] = 1u8;
-        let mut cipher = ctr(key_size,key,&iv);
-        let temp_block = [0u8; 16];
+        assert!(!nonce.is_empty());
+
+        let block_cipher = block_encryptor(key_size, key);
+        let mut hash_key = [0u8; 16];
+        block_cipher.encrypt_block(&[0u8; 16], &mut hash_key);
+
+        let mut j0 = [0u8; 16];
+        if nonce.len() == 12 {
+            copy_memory(nonce, &mut j0);
+            j0[15] = 1u8;
+        } else {
+            // GHASH of the nonce padded to a multiple of 16 bytes followed by a block holding its
+            // length in bits, which is what Ghash produces for an empty aad and the nonce as the
+            // cipher text
+            j0 = Ghash::new(&hash_key).input_c(nonce).result();
+        }
         let mut final_block = [0u8; 16];
-        cipher.process(&temp_block, &mut final_block);
-        let mut hash_key =  [0u8; 16];
-        let mut encryptor = ctr(key_size,key,&temp_block);
-        encryptor.process(&temp_block, &mut hash_key);
+        block_cipher.encrypt_block(&j0, &mut final_block);
+
+        // GCM only increments the right most 4 bytes of the counter block, so a generic CTR mode
+        // can't be used - unlike with 12 byte nonces, the counter of a hashed nonce may start
+        // close enough to 2^32 to wrap around
+        let mut iv = j0;
+        inc32(&mut iv);
         AesGcm {
-            cipher: cipher,
+            cipher: ctr_with_counter_size(key_size, key, &iv, 4),
             mac:  Ghash::new(&hash_key).input_a(aad),
+            mac_c: None,
+            data_len: 0,
             finished: false,
             end_tag: final_block
         }
     }
-    
-}
 
-impl<'a> AeadEncryptor for AesGcm<'static> {
-    fn encrypt(&mut self, input: &[u8], output: &mut [u8], tag: &mut [u8]) {
-        assert!(input.len() == output.len());
+    fn update_aad(&mut self, aad: &[u8]) {
+        assert!(!self.finished);
+        assert!(self.mac_c.is_none(), "The aad must be supplied before the message");
+        self.mac = self.mac.input_a(aad);
+    }
+
+    // Whether another len bytes of message fit within the limit for a single nonce
+    fn fits(&self, len: usize) -> bool {
+        len as u64 <= MAX_LEN - self.data_len
+    }
+
+    // Counts len more bytes of message. This must be done before any of them are processed.
+    fn add_len(&mut self, len: usize) {
+        assert!(!self.finished);
+        assert!(self.fits(len), "Message too long for a single nonce");
+        self.data_len += len as u64;
+    }
+
+    fn update_mac(&mut self, cipher_text: &[u8]) {
+        assert!(!self.finished);
+        self.mac_c = Some(match self.mac_c {
+            Some(mac_c) => mac_c.input_c(cipher_text),
+            None => self.mac.input_c(cipher_text)
+        });
+    }
+
+    fn compute_tag(&mut self) -> [u8; 16] {
         assert!(!self.finished);
-        self.cipher.process(input, output);
-        let result = self.mac.input_c(output).result();
         self.finished = true;
+        let mut tag = match self.mac_c {
+            Some(mac_c) => mac_c.result(),
+            None => self.mac.input_c(&[]).result()
+        };
         for i in (0..16) {
-            tag[i] = result[i] ^ self.end_tag[i];
+            tag[i] ^= self.end_tag[i];
         }
+        tag
+    }
+}
+
+// Increment the right most 32 bits of a counter block, modulo 2^32
+fn inc32(block: &mut [u8; 16]) {
+    for b in block[12..].iter_mut().rev() {
+        *b = b.wrapping_add(1);
+        if *b != 0 {
+            break;
+        }
+    }
+}
+
+impl<'a> AeadEncryptor for AesGcm<'static> {
+    /// # Panics
+    ///
+    /// Panics if the message is longer than 2^36 - 32 bytes, the most that may be encrypted with a
+    /// single nonce.
+    fn encrypt(&mut self, input: &[u8], output: &mut [u8], tag: &mut [u8]) {
+        assert!(input.len() == output.len());
+        self.encrypt_update(input, output);
+        self.encrypt_finish(tag);
     }
 }
 
 impl<'a> AeadDecryptor for AesGcm<'static> {
+    /// Returns false if the message is longer than 2^36 - 32 bytes, since no message that long
+    /// can have been encrypted with a single nonce.
     fn decrypt(&mut self, input: &[u8], output: &mut [u8], tag: &[u8])  -> bool {
         assert!(input.len() == output.len());
         assert!(!self.finished);
-        self.finished = true;
-        let mut calc_tag = self.mac.input_c(input).result();
-        for i in (0..16) {
-            calc_tag[i] ^= self.end_tag[i];
+        if !self.fits(input.len()) {
+            self.finished = true;
+            return false;
         }
+        self.add_len(input.len());
+        self.update_mac(input);
+        let calc_tag = self.compute_tag();
         if fixed_time_eq(&calc_tag, tag) {
             self.cipher.process(input, output);
             true
@@ -83,11 +149,51 @@ impl<'a> AeadDecryptor for AesGcm<'static> {
     }
 }
 
+impl AeadStreamEncryptor for AesGcm<'static> {
+    fn encrypt_aad(&mut self, aad: &[u8]) {
+        self.update_aad(aad);
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the total length of the message grows beyond 2^36 - 32 bytes, the most that may
+    /// be encrypted with a single nonce.
+    fn encrypt_update(&mut self, input: &[u8], output: &mut [u8]) {
+        assert!(input.len() == output.len());
+        self.add_len(input.len());
+        self.cipher.process(input, output);
+        self.update_mac(output);
+    }
+
+    fn encrypt_finish(&mut self, tag: &mut [u8]) {
+        let calc_tag = self.compute_tag();
+        copy_memory(&calc_tag, tag);
+    }
+}
+
+impl AeadStreamDecryptor for AesGcm<'static> {
+    fn decrypt_aad(&mut self, aad: &[u8]) {
+        self.update_aad(aad);
+    }
+
+    fn decrypt_update(&mut self, input: &[u8], output: &mut [u8]) {
+        assert!(input.len() == output.len());
+        self.add_len(input.len());
+        self.update_mac(input);
+        self.cipher.process(input, output);
+    }
+
+    fn decrypt_finish(&mut self, tag: &[u8]) -> bool {
+        let calc_tag = self.compute_tag();
+        fixed_time_eq(&calc_tag, tag)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use aes::KeySize;
-    use aes_gcm::AesGcm;
-    use aead::{AeadEncryptor, AeadDecryptor};
+    use aes_gcm::{AesGcm, MAX_LEN};
+    use aead::{AeadEncryptor, AeadDecryptor, AeadStreamEncryptor, AeadStreamDecryptor};
     use serialize::hex::FromHex;
     use std::iter::repeat;
     fn hex_to_bytes(raw_hex: &str) -> Vec<u8> {
@@ -102,7 +208,7 @@ mod test {
                 tag:  Vec<u8>,
             }
 
-    fn get_test_vectors()-> [TestVector; 5]{
+    fn get_test_vectors()-> [TestVector; 8]{
       [
         TestVector {
                 key: hex_to_bytes("00000000000000000000000000000000"),
@@ -144,6 +250,35 @@ mod test {
                 aad: hex_to_bytes("feedfacedeadbeeffeedfacedeadbeefabaddad2"),
                 tag: hex_to_bytes("76fc6ece0f4e1768cddf8853bb2d551b")
             },
+            // Test case 5 from the GCM specification, with a 8 byte nonce
+            TestVector {
+                key: hex_to_bytes("feffe9928665731c6d6a8f9467308308"),
+                iv: hex_to_bytes("cafebabefacedbad"),
+                plain_text: hex_to_bytes("d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a721c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39"),
+                cipher_text: hex_to_bytes("61353b4c2806934a777ff51fa22a4755699b2a714fcdc6f83766e5f97b6c742373806900e49f24b22b097544d4896b424989b5e1ebac0f07c23f4598"),
+                aad: hex_to_bytes("feedfacedeadbeeffeedfacedeadbeefabaddad2"),
+                tag: hex_to_bytes("3612d2e79e3b0785561be14aaca2fccb")
+            },
+            // A 60 byte nonce, generated with the Python cryptography package
+            TestVector {
+                key: hex_to_bytes("feffe9928665731c6d6a8f9467308308"),
+                iv: hex_to_bytes("9313225df88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a721c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39"),
+                plain_text: hex_to_bytes("d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a721c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39"),
+                cipher_text: hex_to_bytes("2b4b26fb49f400296428f090cdb8671a60f2f674c7d2635c67c52763caccfb7afbde37c47ceaeaf102e38224d71d8e8c6a6ed055a28dcef35ee92cd9"),
+                aad: hex_to_bytes("feedfacedeadbeeffeedfacedeadbeefabaddad2"),
+                tag: hex_to_bytes("9a58d4b7c0030413d4cc72a5b67c11df")
+            },
+            // A 16 byte nonce chosen so that the initial counter block ends in fffffffe, which
+            // makes the 32 bit counter wrap around after the first block. Generated with the
+            // Python cryptography package.
+            TestVector {
+                key: hex_to_bytes("feffe9928665731c6d6a8f9467308308"),
+                iv: hex_to_bytes("a24a890d42959b1428a72b78e6baa1e9"),
+                plain_text: hex_to_bytes("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f202122232425262728292a2b2c2d2e2f303132333435363738393a3b3c3d3e3f"),
+                cipher_text: hex_to_bytes("2fb0e7ed7ff319236390b50a9cb66252e7927d7b69bddc6751b5c301b83bd93797404f5924acfb729c8efd0e854898ac8c1d7f504432ce57e04ae9a9c5414f3c"),
+                aad: hex_to_bytes("77726170"),
+                tag: hex_to_bytes("b5e2bbac6cdf047d9ad1ccf240ccb767")
+            },
     ]
 }
     #[test]
@@ -205,6 +340,99 @@ mod test {
         }
     }
 
+    fn key_size(key: &[u8]) -> KeySize {
+        match key.len() {
+            16 => KeySize::KeySize128,
+            24 => KeySize::KeySize192,
+            32 => KeySize::KeySize256,
+            _ => unreachable!()
+        }
+    }
+
+    #[test]
+    fn aes_gcm_stream_test() {
+        for item in get_test_vectors().iter() {
+            for &chunk_size in [1, 7, 16, 17, 64].iter() {
+                let mut cipher = AesGcm::new(key_size(&item.key), &item.key[..], &item.iv[..], &[]);
+                for aad in item.aad.chunks(chunk_size) {
+                    cipher.encrypt_aad(aad);
+                }
+                let mut out: Vec<u8> = repeat(0).take(item.plain_text.len()).collect();
+                for (input, output) in item.plain_text.chunks(chunk_size)
+                        .zip(out.chunks_mut(chunk_size)) {
+                    cipher.encrypt_update(input, output);
+                }
+                let mut out_tag: Vec<u8> = repeat(0).take(16).collect();
+                cipher.encrypt_finish(&mut out_tag[..]);
+                assert_eq!(out, item.cipher_text);
+                assert_eq!(out_tag, item.tag);
+            }
+        }
+    }
+
+    #[test]
+    fn aes_gcm_stream_decrypt_test() {
+        for item in get_test_vectors().iter() {
+            for &chunk_size in [1, 7, 16, 17, 64].iter() {
+                let mut decipher = AesGcm::new(key_size(&item.key), &item.key[..], &item.iv[..],
+                                               &[]);
+                for aad in item.aad.chunks(chunk_size) {
+                    decipher.decrypt_aad(aad);
+                }
+                let mut out: Vec<u8> = repeat(0).take(item.plain_text.len()).collect();
+                for (input, output) in item.cipher_text.chunks(chunk_size)
+                        .zip(out.chunks_mut(chunk_size)) {
+                    decipher.decrypt_update(input, output);
+                }
+                assert_eq!(out, item.plain_text);
+                assert!(decipher.decrypt_finish(&item.tag[..]));
+
+                let mut decipher = AesGcm::new(key_size(&item.key), &item.key[..], &item.iv[..],
+                                               &item.aad[..]);
+                decipher.decrypt_update(&item.cipher_text[..], &mut out[..]);
+                assert!(!decipher.decrypt_finish(&[0u8; 16]));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn aes_gcm_stream_aad_after_message_test() {
+        let mut cipher = AesGcm::new(KeySize::KeySize128, &[0; 16], &[0; 12], &[]);
+        let mut output = [0u8; 16];
+        cipher.encrypt_update(&[0; 16], &mut output);
+        cipher.encrypt_aad(&[0; 16]);
+    }
+
+    // The length limits are tested by starting from a message that is already close to the limit,
+    // rather than by processing 64 GiB of data
+    #[test]
+    fn aes_gcm_max_len_test() {
+        let mut cipher = AesGcm::new(KeySize::KeySize128, &[0; 16], &[0; 12], &[]);
+        cipher.data_len = MAX_LEN - 16;
+        let mut output = [0u8; 16];
+        let mut tag = [0u8; 16];
+        cipher.encrypt(&[0; 16], &mut output, &mut tag);
+    }
+
+    #[test]
+    #[should_panic(expected = "Message too long for a single nonce")]
+    fn aes_gcm_encrypt_too_long_test() {
+        let mut cipher = AesGcm::new(KeySize::KeySize128, &[0; 16], &[0; 12], &[]);
+        cipher.data_len = MAX_LEN - 15;
+        let mut output = [0u8; 16];
+        let mut tag = [0u8; 16];
+        cipher.encrypt(&[0; 16], &mut output, &mut tag);
+    }
+
+    #[test]
+    fn aes_gcm_decrypt_too_long_test() {
+        let mut decipher = AesGcm::new(KeySize::KeySize128, &[0; 16], &[0; 12], &[]);
+        decipher.data_len = MAX_LEN - 15;
+        let mut output = [1u8; 16];
+        assert!(!decipher.decrypt(&[0; 16], &mut output, &[0; 16]));
+        assert!(output == [1u8; 16]);
+    }
 }
 
 #[cfg(all(test, feature = "with-bench"))]
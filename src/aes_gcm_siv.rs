@@ -0,0 +1,377 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+ * An implementation of AES-GCM-SIV, the nonce misuse-resistant authenticated encryption scheme
+ * defined in RFC 8452 [1], for 128 and 256 bit keys.
+ *
+ * For every nonce, a message authentication key and a message encryption key are derived from the
+ * key generating key. The tag is computed with POLYVAL over the associated data and the plain text
+ * and is then used as the initial counter for encrypting the plain text in a CTR mode with a 32
+ * bit little endian counter. If a nonce is ever repeated, the only information leaked is whether
+ * the same message was encrypted twice with the same associated data.
+ *
+ * The nonce is 12 bytes long and the tag is 16 bytes long. The plain text and the associated data
+ * may each be at most 2^36 bytes long.
+ *
+ * [1] - "AES-GCM-SIV: Nonce Misuse-Resistant Authenticated Encryption" - S. Gueron, A. Langley and
+ *       Y. Lindell <https://tools.ietf.org/html/rfc8452>
+ */
+
+use aes::{block_encryptor, KeySize};
+use aead::{AeadEncryptor, AeadDecryptor};
+use cryptoutil::{copy_memory, read_u32_le, write_u32_le, write_u64_le, xor_keystream, zero};
+use mac::Mac;
+use polyval::Polyval;
+use symmetriccipher::BlockEncryptor;
+use util::fixed_time_eq;
+
+// The maximum length of the plain text and of the associated data, in bytes
+const MAX_LEN: u64 = 1 << 36;
+
+pub struct AesGcmSiv {
+    cipher: Box<BlockEncryptor>,
+    mac: Polyval,
+    nonce: [u8; 12],
+    aad_len: usize,
+    finished: bool
+}
+
+// Feed zeros to the mac to pad data of the given length to a multiple of 16 bytes
+fn pad(mac: &mut Polyval, len: usize) {
+    let remainder = len % 16;
+    if remainder != 0 {
+        mac.input(&[0u8; 16][remainder..]);
+    }
+}
+
+impl AesGcmSiv {
+    /**
+     * Create a new AesGcmSiv instance, deriving the keys for the nonce.
+     *
+     * # Arguments
+     * * key_size - The AES key size. Only 128 and 256 bit keys are supported.
+     * * key - The key generating key.
+     * * nonce - The nonce, which must be 12 bytes long.
+     * * aad - The associated data to authenticate.
+     *
+     */
+    pub fn new(key_size: KeySize, key: &[u8], nonce: &[u8], aad: &[u8]) -> AesGcmSiv {
+        let key_blocks = match key_size {
+            KeySize::KeySize128 => 2,
+            KeySize::KeySize256 => 4,
+            KeySize::KeySize192 => panic!("AES-GCM-SIV is only defined for 128 and 256 bit keys")
+        };
+        assert!(key.len() == 8 * key_blocks);
+        assert!(nonce.len() == 12);
+        assert!(aad.len() as u64 <= MAX_LEN);
+
+        // The derived keys are made up of the first 8 bytes of the encryptions of
+        // le32(i) || nonce, two blocks for the authentication key followed by the encryption key.
+        let key_generator = block_encryptor(key_size, key);
+        let mut derived = [0u8; 48];
+        let mut input = [0u8; 16];
+        let mut output = [0u8; 16];
+        copy_memory(nonce, &mut input[4..]);
+        for i in 0..2 + key_blocks {
+            write_u32_le(&mut input[..4], i as u32);
+            key_generator.encrypt_block(&input, &mut output);
+            copy_memory(&output[..8], &mut derived[8 * i..8 * i + 8]);
+        }
+
+        let mut mac = Polyval::new(&derived[..16]);
+        mac.input(aad);
+        pad(&mut mac, aad.len());
+
+        let mut n = [0u8; 12];
+        copy_memory(nonce, &mut n);
+        AesGcmSiv {
+            cipher: block_encryptor(key_size, &derived[16..16 + 8 * key_blocks]),
+            mac: mac,
+            nonce: n,
+            aad_len: aad.len(),
+            finished: false
+        }
+    }
+
+    fn compute_tag(&mut self, plain_text: &[u8]) -> [u8; 16] {
+        self.mac.input(plain_text);
+        pad(&mut self.mac, plain_text.len());
+        let mut lengths = [0u8; 16];
+        write_u64_le(&mut lengths[..8], self.aad_len as u64 * 8);
+        write_u64_le(&mut lengths[8..], plain_text.len() as u64 * 8);
+        self.mac.input(&lengths);
+
+        let mut s = [0u8; 16];
+        self.mac.raw_result(&mut s);
+        for (x, &n) in s.iter_mut().zip(self.nonce.iter()) {
+            *x ^= n;
+        }
+        s[15] &= 0x7f;
+        let mut tag = [0u8; 16];
+        self.cipher.encrypt_block(&s, &mut tag);
+        tag
+    }
+
+    // CTR mode starting from the tag with its top bit set, incrementing only the first 4 bytes as
+    // a little endian counter
+    fn process(&self, tag: &[u8], input: &[u8], output: &mut [u8]) {
+        let mut counter_block = [0u8; 16];
+        copy_memory(tag, &mut counter_block);
+        counter_block[15] |= 0x80;
+        let mut counter = read_u32_le(&counter_block[..4]);
+        let mut key_stream = [0u8; 16];
+        for (in_chunk, out_chunk) in input.chunks(16).zip(output.chunks_mut(16)) {
+            write_u32_le(&mut counter_block[..4], counter);
+            self.cipher.encrypt_block(&counter_block, &mut key_stream);
+            xor_keystream(out_chunk, in_chunk, &key_stream);
+            counter = counter.wrapping_add(1);
+        }
+    }
+}
+
+impl AeadEncryptor for AesGcmSiv {
+    fn encrypt(&mut self, input: &[u8], output: &mut [u8], tag: &mut [u8]) {
+        assert!(input.len() == output.len());
+        assert!(input.len() as u64 <= MAX_LEN);
+        assert!(tag.len() == 16);
+        assert!(!self.finished);
+        self.finished = true;
+        let calc_tag = self.compute_tag(input);
+        self.process(&calc_tag, input, output);
+        copy_memory(&calc_tag, tag);
+    }
+}
+
+impl AeadDecryptor for AesGcmSiv {
+    fn decrypt(&mut self, input: &[u8], output: &mut [u8], tag: &[u8]) -> bool {
+        assert!(input.len() == output.len());
+        assert!(input.len() as u64 <= MAX_LEN);
+        assert!(tag.len() == 16);
+        assert!(!self.finished);
+        self.finished = true;
+        // The tag is computed over the plain text, so it must be decrypted before it can be checked
+        self.process(tag, input, output);
+        let calc_tag = self.compute_tag(output);
+        if fixed_time_eq(&calc_tag, tag) {
+            true
+        } else {
+            zero(output);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use aes::KeySize;
+    use aes_gcm_siv::AesGcmSiv;
+    use aead::{AeadEncryptor, AeadDecryptor};
+    use serialize::hex::FromHex;
+    use std::iter::repeat;
+    fn hex_to_bytes(raw_hex: &str) -> Vec<u8> {
+        raw_hex.from_hex().ok().unwrap()
+    }
+    fn key_size(key: &[u8]) -> KeySize {
+        match key.len() {
+            16 => KeySize::KeySize128,
+            32 => KeySize::KeySize256,
+            _ => unreachable!()
+        }
+    }
+    struct TestVector {
+        key: Vec<u8>,
+        nonce: Vec<u8>,
+        plain_text: Vec<u8>,
+        cipher_text: Vec<u8>,
+        aad: Vec<u8>,
+        tag: Vec<u8>,
+    }
+
+    // The test vectors use the keys, nonces and messages of RFC 8452 Appendix C.1 and C.2, and the
+    // last two are the counter wrap tests from Appendix C.3. The results were checked against the
+    // AESGCMSIV implementation of the Python cryptography package.
+    fn get_test_vectors() -> Vec<TestVector> {
+        vec![
+            TestVector {
+                key: hex_to_bytes("01000000000000000000000000000000"),
+                nonce: hex_to_bytes("030000000000000000000000"),
+                plain_text: hex_to_bytes(""),
+                cipher_text: hex_to_bytes(""),
+                aad: hex_to_bytes(""),
+                tag: hex_to_bytes("dc20e2d83f25705bb49e439eca56de25")
+            },
+            TestVector {
+                key: hex_to_bytes("01000000000000000000000000000000"),
+                nonce: hex_to_bytes("030000000000000000000000"),
+                plain_text: hex_to_bytes("0100000000000000"),
+                cipher_text: hex_to_bytes("b5d839330ac7b786"),
+                aad: hex_to_bytes(""),
+                tag: hex_to_bytes("578782fff6013b815b287c22493a364c")
+            },
+            TestVector {
+                key: hex_to_bytes("01000000000000000000000000000000"),
+                nonce: hex_to_bytes("030000000000000000000000"),
+                plain_text: hex_to_bytes("0100000000000000000000000000000002000000000000000000000000000000\
+                                          03000000000000000000000000000000"),
+                cipher_text: hex_to_bytes("3fd24ce1f5a67b75bf2351f181a475c7b800a5b4d3dcf70106b1eea82fa1d64d\
+                                           f42bf7226122fa92e17a40eeaac1201b"),
+                aad: hex_to_bytes(""),
+                tag: hex_to_bytes("5e6e311dbf395d35b0fe39c2714388f8")
+            },
+            TestVector {
+                key: hex_to_bytes("01000000000000000000000000000000"),
+                nonce: hex_to_bytes("030000000000000000000000"),
+                plain_text: hex_to_bytes("0200000000000000"),
+                cipher_text: hex_to_bytes("1e6daba35669f427"),
+                aad: hex_to_bytes("01"),
+                tag: hex_to_bytes("3b0a1a2560969cdf790d99759abd1508")
+            },
+            TestVector {
+                key: hex_to_bytes("01000000000000000000000000000000"),
+                nonce: hex_to_bytes("030000000000000000000000"),
+                plain_text: hex_to_bytes("0200000000000000000000000000000003000000"),
+                cipher_text: hex_to_bytes("3fba97925b427e4f3ba39ab2749d9acd9a1d4a40"),
+                aad: hex_to_bytes("010000000000000000000000"),
+                tag: hex_to_bytes("c3483fdd93adbcdea1e03c4b23875be4")
+            },
+            TestVector {
+                key: hex_to_bytes("0100000000000000000000000000000000000000000000000000000000000000"),
+                nonce: hex_to_bytes("030000000000000000000000"),
+                plain_text: hex_to_bytes(""),
+                cipher_text: hex_to_bytes(""),
+                aad: hex_to_bytes(""),
+                tag: hex_to_bytes("07f5f4169bbf55a8400cd47ea6fd400f")
+            },
+            TestVector {
+                key: hex_to_bytes("0100000000000000000000000000000000000000000000000000000000000000"),
+                nonce: hex_to_bytes("030000000000000000000000"),
+                plain_text: hex_to_bytes("0100000000000000000000000000000002000000000000000000000000000000"),
+                cipher_text: hex_to_bytes("4a6a9db4c8c6549201b9edb53006cba821ec9cf850948a7c86c68ac7539d027f"),
+                aad: hex_to_bytes(""),
+                tag: hex_to_bytes("e819e63abcd020b006a976397632eb5d")
+            },
+            TestVector {
+                key: hex_to_bytes("0100000000000000000000000000000000000000000000000000000000000000"),
+                nonce: hex_to_bytes("030000000000000000000000"),
+                plain_text: hex_to_bytes("0200000000000000000000000000000003000000000000000000000000000000\
+                                          0400000000000000000000000000000005000000000000000000000000000000"),
+                cipher_text: hex_to_bytes("67fd45e126bfb9a79930c43aad2d36967d3f0e4d217c1e551f59727870beefc9\
+                                           8cb933a8fce9de887b1e40799988db1fc3f91880ed405b2dd298318858467c89"),
+                aad: hex_to_bytes("01"),
+                tag: hex_to_bytes("5bde0285037c5de81e5b570a049b62a0")
+            },
+            TestVector {
+                key: hex_to_bytes("0100000000000000000000000000000000000000000000000000000000000000"),
+                nonce: hex_to_bytes("030000000000000000000000"),
+                plain_text: hex_to_bytes("0200000000000000000000000000000003000000"),
+                cipher_text: hex_to_bytes("8932854141f6bbe652fddeee7d3f2f0995be8d63"),
+                aad: hex_to_bytes("010000000000000000000000"),
+                tag: hex_to_bytes("7ab44c86af13b3cd505d7db19160ac03")
+            },
+            TestVector {
+                key: hex_to_bytes("0000000000000000000000000000000000000000000000000000000000000000"),
+                nonce: hex_to_bytes("000000000000000000000000"),
+                plain_text: hex_to_bytes("000000000000000000000000000000004db923dc793ee6497c76dcc03a98e108"),
+                cipher_text: hex_to_bytes("f3f80f2cf0cb2dd9c5984fcda908456cc537703b5ba70324a6793a7bf218d3ea"),
+                aad: hex_to_bytes(""),
+                tag: hex_to_bytes("ffffffff000000000000000000000000")
+            },
+            TestVector {
+                key: hex_to_bytes("0000000000000000000000000000000000000000000000000000000000000000"),
+                nonce: hex_to_bytes("000000000000000000000000"),
+                plain_text: hex_to_bytes("eb3640277c7ffd1303c7a542d02d3e4c0000000000000000"),
+                cipher_text: hex_to_bytes("18ce4f0b8cb4d0cac65fea8f79257b20888e53e72299e56d"),
+                aad: hex_to_bytes(""),
+                tag: hex_to_bytes("ffffffff000000000000000000000000")
+            },
+        ]
+    }
+
+    #[test]
+    fn aes_gcm_siv_test() {
+        for item in get_test_vectors().iter() {
+            let mut cipher = AesGcmSiv::new(key_size(&item.key), &item.key[..], &item.nonce[..],
+                                            &item.aad[..]);
+            let mut out: Vec<u8> = repeat(0).take(item.plain_text.len()).collect();
+            let mut out_tag: Vec<u8> = repeat(0).take(16).collect();
+            cipher.encrypt(&item.plain_text[..], &mut out[..], &mut out_tag[..]);
+            assert_eq!(out, item.cipher_text);
+            assert_eq!(out_tag, item.tag);
+        }
+    }
+
+    #[test]
+    fn aes_gcm_siv_decrypt_test() {
+        for item in get_test_vectors().iter() {
+            let mut decipher = AesGcmSiv::new(key_size(&item.key), &item.key[..], &item.nonce[..],
+                                              &item.aad[..]);
+            let mut out: Vec<u8> = repeat(0).take(item.plain_text.len()).collect();
+            let result = decipher.decrypt(&item.cipher_text[..], &mut out[..], &item.tag[..]);
+            assert_eq!(out, item.plain_text);
+            assert!(result);
+        }
+    }
+
+    #[test]
+    fn aes_gcm_siv_decrypt_fail_test() {
+        for item in get_test_vectors().iter() {
+            let mut decipher = AesGcmSiv::new(key_size(&item.key), &item.key[..], &item.nonce[..],
+                                              &item.aad[..]);
+            let mut tag = item.tag.clone();
+            tag[15] ^= 1;
+            let mut out1: Vec<u8> = repeat(0).take(item.plain_text.len()).collect();
+            let out2: Vec<u8> = repeat(0).take(item.plain_text.len()).collect();
+            let result = decipher.decrypt(&item.cipher_text[..], &mut out1[..], &tag[..]);
+            assert_eq!(out1, out2);
+            assert!(!result);
+        }
+    }
+
+    #[test]
+    fn aes_gcm_siv_round_trip_test() {
+        let key: Vec<u8> = (0..32).collect();
+        let aad: Vec<u8> = (0..100).map(|i| i as u8).collect();
+        for len in 0..300 {
+            let plain_text: Vec<u8> = (0..len).map(|i| (i * 3) as u8).collect();
+            let mut cipher_text: Vec<u8> = repeat(0).take(len).collect();
+            let mut tag = [0u8; 16];
+            let mut cipher = AesGcmSiv::new(KeySize::KeySize256, &key, &key[..12], &aad);
+            cipher.encrypt(&plain_text, &mut cipher_text, &mut tag);
+
+            let mut out: Vec<u8> = repeat(0).take(len).collect();
+            let mut decipher = AesGcmSiv::new(KeySize::KeySize256, &key, &key[..12], &aad);
+            assert!(decipher.decrypt(&cipher_text, &mut out, &tag));
+            assert_eq!(out, plain_text);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "with-bench"))]
+mod bench {
+    use test::Bencher;
+    use aes::KeySize;
+    use aes_gcm_siv::AesGcmSiv;
+    use aead::{AeadEncryptor, AeadDecryptor};
+
+    #[bench]
+    pub fn gcm_siv_1k(bh: & mut Bencher) {
+        let input = [1u8; 1024];
+        let aad = [3u8; 1024];
+        bh.iter( || {
+            let mut cipher = AesGcmSiv::new(KeySize::KeySize256, &[0; 32], &[0; 12], &aad);
+            let mut decipher = AesGcmSiv::new(KeySize::KeySize256, &[0; 32], &[0; 12], &aad);
+
+            let mut output = [0u8; 1024];
+            let mut tag = [0u8; 16];
+            let mut output2 = [0u8; 1024];
+
+            cipher.encrypt(&input, &mut output, &mut tag);
+            decipher.decrypt(&output, &mut output2, &tag);
+        });
+        bh.bytes = 1024u64;
+    }
+}
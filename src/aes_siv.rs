@@ -0,0 +1,338 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+ * An implementation of AES-SIV, the deterministic authenticated encryption mode defined in RFC
+ * 5297 [1].
+ *
+ * The synthetic IV is computed with S2V, a CMAC based pseudo random function over a vector of
+ * strings: the associated data components followed by the plain text. It is used both as the tag
+ * and as the initial counter for encrypting the plain text in CTR mode. As a result, encryption is
+ * deterministic: equal plain texts with equal associated data give equal cipher texts, and
+ * nothing else is leaked. A nonce can be added for probabilistic encryption by passing it as the
+ * last associated data component.
+ *
+ * The key is twice as long as the AES key: the first half is used for S2V and the second half for
+ * CTR mode. The synthetic IV (the tag) is 16 bytes long.
+ *
+ * [1] - "Synthetic Initialization Vector (SIV) Authenticated Encryption Using the Advanced
+ *       Encryption Standard (AES)" - D. Harkins <https://tools.ietf.org/html/rfc5297>
+ */
+
+use aes::{block_encryptor, KeySize};
+use aead::{AeadEncryptor, AeadDecryptor};
+use blockmodes::CtrMode;
+use cmac::{Cmac, double};
+use cryptoutil::{copy_memory, zero};
+use mac::Mac;
+use symmetriccipher::{BlockEncryptor, SynchronousStreamCipher};
+use util::fixed_time_eq;
+
+// The maximum number of associated data components, including the nonce
+const MAX_COMPONENTS: usize = 126;
+
+pub struct AesSiv {
+    mac: Cmac<Box<BlockEncryptor>>,
+    cipher: CtrMode<Box<BlockEncryptor>>,
+    // The S2V state after processing the associated data components
+    d: [u8; 16],
+    finished: bool
+}
+
+fn cmac(mac: &mut Cmac<Box<BlockEncryptor>>, data: &[u8]) -> [u8; 16] {
+    let mut result = [0u8; 16];
+    mac.reset();
+    mac.input(data);
+    mac.raw_result(&mut result);
+    result
+}
+
+impl AesSiv {
+    /**
+     * Create a new AesSiv instance.
+     *
+     * # Arguments
+     * * key_size - The AES key size.
+     * * key - The key, which is twice as long as the AES key.
+     * * ad - The associated data components to authenticate, at most 126 of them. For nonce based
+     *   encryption, the nonce is the last component. An empty slice gives deterministic
+     *   encryption without associated data.
+     *
+     */
+    pub fn new(key_size: KeySize, key: &[u8], ad: &[&[u8]]) -> AesSiv {
+        assert!(key.len() == 32 || key.len() == 48 || key.len() == 64);
+        assert!(ad.len() <= MAX_COMPONENTS);
+        let half = key.len() / 2;
+
+        let mut mac = Cmac::new(block_encryptor(key_size, &key[..half]));
+        let mut d = cmac(&mut mac, &[0u8; 16]);
+        for component in ad.iter() {
+            let c = cmac(&mut mac, component);
+            double(&mut d);
+            for (x, &y) in d.iter_mut().zip(c.iter()) {
+                *x ^= y;
+            }
+        }
+
+        AesSiv {
+            mac: mac,
+            cipher: CtrMode::new(block_encryptor(key_size, &key[half..]), vec![0u8; 16]),
+            d: d,
+            finished: false
+        }
+    }
+
+    // Finishes S2V with the plain text as the last component
+    fn s2v(&mut self, plain_text: &[u8]) -> [u8; 16] {
+        self.mac.reset();
+        if plain_text.len() >= 16 {
+            // xor the state into the last 16 bytes of the plain text
+            let split = plain_text.len() - 16;
+            let mut last = [0u8; 16];
+            copy_memory(&plain_text[split..], &mut last);
+            for (x, &y) in last.iter_mut().zip(self.d.iter()) {
+                *x ^= y;
+            }
+            self.mac.input(&plain_text[..split]);
+            self.mac.input(&last);
+        } else {
+            let mut padded = [0u8; 16];
+            copy_memory(plain_text, &mut padded);
+            padded[plain_text.len()] = 0x80;
+            let mut d = self.d;
+            double(&mut d);
+            for (x, &y) in padded.iter_mut().zip(d.iter()) {
+                *x ^= y;
+            }
+            self.mac.input(&padded);
+        }
+        let mut v = [0u8; 16];
+        self.mac.raw_result(&mut v);
+        v
+    }
+
+    // CTR mode, starting from the synthetic IV with the 31st and 63rd bits cleared
+    fn process(&mut self, v: &[u8], input: &[u8], output: &mut [u8]) {
+        let mut q = [0u8; 16];
+        copy_memory(v, &mut q);
+        q[8] &= 0x7f;
+        q[12] &= 0x7f;
+        self.cipher.reset(&q);
+        self.cipher.process(input, output);
+    }
+}
+
+impl AeadEncryptor for AesSiv {
+    fn encrypt(&mut self, input: &[u8], output: &mut [u8], tag: &mut [u8]) {
+        assert!(input.len() == output.len());
+        assert!(tag.len() == 16);
+        assert!(!self.finished);
+        self.finished = true;
+        let v = self.s2v(input);
+        self.process(&v, input, output);
+        copy_memory(&v, tag);
+    }
+}
+
+impl AeadDecryptor for AesSiv {
+    fn decrypt(&mut self, input: &[u8], output: &mut [u8], tag: &[u8]) -> bool {
+        assert!(input.len() == output.len());
+        assert!(tag.len() == 16);
+        assert!(!self.finished);
+        self.finished = true;
+        // The tag is computed over the plain text, so it must be decrypted before it can be checked
+        self.process(tag, input, output);
+        let v = self.s2v(output);
+        if fixed_time_eq(&v, tag) {
+            true
+        } else {
+            zero(output);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use aes::KeySize;
+    use aes_siv::AesSiv;
+    use aead::{AeadEncryptor, AeadDecryptor};
+    use serialize::hex::FromHex;
+    use std::iter::repeat;
+    fn hex_to_bytes(raw_hex: &str) -> Vec<u8> {
+        raw_hex.from_hex().ok().unwrap()
+    }
+    fn key_size(key: &[u8]) -> KeySize {
+        match key.len() {
+            32 => KeySize::KeySize128,
+            48 => KeySize::KeySize192,
+            64 => KeySize::KeySize256,
+            _ => unreachable!()
+        }
+    }
+    struct TestVector {
+        key: Vec<u8>,
+        ad: Vec<Vec<u8>>,
+        plain_text: Vec<u8>,
+        cipher_text: Vec<u8>,
+        tag: Vec<u8>,
+    }
+
+    // The first two test vectors are from RFC 5297 Appendix A - the first one is deterministic and
+    // the second one uses a nonce as the last component. The remaining vectors were generated with
+    // the AESSIV implementation of the Python cryptography package.
+    fn get_test_vectors() -> Vec<TestVector> {
+        vec![
+            TestVector {
+                key: hex_to_bytes("fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff"),
+                ad: vec![hex_to_bytes("101112131415161718191a1b1c1d1e1f2021222324252627")],
+                plain_text: hex_to_bytes("112233445566778899aabbccddee"),
+                cipher_text: hex_to_bytes("40c02b9690c4dc04daef7f6afe5c"),
+                tag: hex_to_bytes("85632d07c6e8f37f950acd320a2ecc93")
+            },
+            TestVector {
+                key: hex_to_bytes("7f7e7d7c7b7a797877767574737271704041424344454647\
+                                   48494a4b4c4d4e4f"),
+                ad: vec![
+                    hex_to_bytes("00112233445566778899aabbccddeeffdeaddadadeaddadaffeeddccbbaa9988\
+                                  7766554433221100"),
+                    hex_to_bytes("102030405060708090a0"),
+                    hex_to_bytes("09f911029d74e35bd84156c5635688c0")],
+                plain_text: hex_to_bytes("7468697320697320736f6d6520706c61696e7465787420746f20656e63727970\
+                                          74207573696e67205349562d414553"),
+                cipher_text: hex_to_bytes("cb900f2fddbe404326601965c889bf17dba77ceb094fa663b7a3f748ba8af829\
+                                           ea64ad544a272e9c485b62a3fd5c0d"),
+                tag: hex_to_bytes("7bdb6e3b432667eb06f4d14bff2fbd0f")
+            },
+            TestVector {
+                key: hex_to_bytes("0638634b12610fa7cd10e572e436e162d6092b928f919be2a556cd51d9db5b56\
+                                   0d11bc8008336e319a4a61a83e5df55e"),
+                ad: vec![],
+                plain_text: hex_to_bytes(""),
+                cipher_text: hex_to_bytes(""),
+                tag: hex_to_bytes("6dcfe151a03e7699f03582f0d71d220f")
+            },
+            TestVector {
+                key: hex_to_bytes("56ac2d1871a27450b3439f2a0067a1c41b01e73c6a36841fb4bd5c422fe80567\
+                                   c2c8c0265e1999c1be036a66a8a19924c21e138bece961b060d5b94d9d82b551"),
+                ad: vec![hex_to_bytes("1f40fc92da")],
+                plain_text: hex_to_bytes("896c3841335984c738ffa4224a4ba20c"),
+                cipher_text: hex_to_bytes("917987e735d20585124fbf3a99ae4344"),
+                tag: hex_to_bytes("c9befb6e9b6e14ad59f267c93a4f5b59")
+            },
+            TestVector {
+                key: hex_to_bytes("56ac2d1871a27450b3439f2a0067a1c41b01e73c6a36841fb4bd5c422fe80567\
+                                   c2c8c0265e1999c1be036a66a8a19924c21e138bece961b060d5b94d9d82b551"),
+                ad: vec![
+                    hex_to_bytes(""),
+                    hex_to_bytes("acc28db2beb7b42baa1cb0243d401ccb4e"),
+                    hex_to_bytes("48fb10b15f3d44a09dc82d02b06581e0c0c69478c9fd2cf8f9093659019a1687")],
+                plain_text: hex_to_bytes("dd633e60cc670c3dcec6ae7238e97a3cfac08691962a2dd6e14c78b95dc97c23\
+                                          a1ae3bdb17bfaac0c6d464c2d72bcd926dc8154611ba8fd8f310294d3e383919\
+                                          dd633e60cc670c3dcec6ae7238e97a3cfac08691962a2dd6e14c78b95dc97c23\
+                                          a1ae3bdb"),
+                cipher_text: hex_to_bytes("3a7e22164521976f8d6e94187c36ed971b6f79b0e5ec7e3242f83052e68aa569\
+                                           05f8318e5b623febdf5e44d6ad365e232dccee246123cf9b3a8799cf4adf77b8\
+                                           2d73ec577a6bc9c4ed23495a7b2e47b8f23a367c3efb474228ad1412e5fd25e4\
+                                           8c1d3df0"),
+                tag: hex_to_bytes("cb04e174e723d8d38a65ca44216b7578")
+            },
+            TestVector {
+                key: hex_to_bytes("71a9fbc9a6ba624bffe9125ecd4b8b9af55b995df6bca72f87c89d4ca6ee164e"),
+                ad: vec![],
+                plain_text: hex_to_bytes("d71079b606f7a4ca8b6d54bce0864b"),
+                cipher_text: hex_to_bytes("ac018023762ef6c81d0f961eccdf84"),
+                tag: hex_to_bytes("8fad24459986bffb54f5e7ac08ede11f")
+            },
+        ]
+    }
+
+    fn new_siv(item: &TestVector) -> AesSiv {
+        let ad: Vec<&[u8]> = item.ad.iter().map(|a| &a[..]).collect();
+        AesSiv::new(key_size(&item.key), &item.key[..], &ad)
+    }
+
+    #[test]
+    fn aes_siv_test() {
+        for item in get_test_vectors().iter() {
+            let mut cipher = new_siv(item);
+            let mut out: Vec<u8> = repeat(0).take(item.plain_text.len()).collect();
+            let mut out_tag: Vec<u8> = repeat(0).take(16).collect();
+            cipher.encrypt(&item.plain_text[..], &mut out[..], &mut out_tag[..]);
+            assert_eq!(out, item.cipher_text);
+            assert_eq!(out_tag, item.tag);
+        }
+    }
+
+    #[test]
+    fn aes_siv_decrypt_test() {
+        for item in get_test_vectors().iter() {
+            let mut decipher = new_siv(item);
+            let mut out: Vec<u8> = repeat(0).take(item.plain_text.len()).collect();
+            let result = decipher.decrypt(&item.cipher_text[..], &mut out[..], &item.tag[..]);
+            assert_eq!(out, item.plain_text);
+            assert!(result);
+        }
+    }
+
+    #[test]
+    fn aes_siv_decrypt_fail_test() {
+        for item in get_test_vectors().iter() {
+            let mut decipher = new_siv(item);
+            let mut tag = item.tag.clone();
+            tag[0] ^= 1;
+            let mut out1: Vec<u8> = repeat(0).take(item.plain_text.len()).collect();
+            let out2: Vec<u8> = repeat(0).take(item.plain_text.len()).collect();
+            let result = decipher.decrypt(&item.cipher_text[..], &mut out1[..], &tag[..]);
+            assert_eq!(out1, out2);
+            assert!(!result);
+        }
+    }
+
+    #[test]
+    fn aes_siv_deterministic_test() {
+        // Encrypting the same message twice gives the same result, while changing the associated
+        // data changes it
+        let key: Vec<u8> = (0..32).collect();
+        let plain_text = [7u8; 40];
+        let encrypt = |ad: &[&[u8]]| {
+            let mut cipher = AesSiv::new(KeySize::KeySize128, &key, ad);
+            let mut out = [0u8; 40];
+            let mut tag = [0u8; 16];
+            cipher.encrypt(&plain_text, &mut out, &mut tag);
+            (out.to_vec(), tag)
+        };
+        assert!(encrypt(&[b"header"]) == encrypt(&[b"header"]));
+        assert!(encrypt(&[b"header"]) != encrypt(&[b"header", b""]));
+        assert!(encrypt(&[b"header"]) != encrypt(&[b"other"]));
+    }
+}
+
+#[cfg(all(test, feature = "with-bench"))]
+mod bench {
+    use test::Bencher;
+    use aes::KeySize;
+    use aes_siv::AesSiv;
+    use aead::{AeadEncryptor, AeadDecryptor};
+
+    #[bench]
+    pub fn siv_1k(bh: & mut Bencher) {
+        let input = [1u8; 1024];
+        let aad = [3u8; 1024];
+        bh.iter( || {
+            let mut cipher = AesSiv::new(KeySize::KeySize256, &[0; 64], &[&aad]);
+            let mut decipher = AesSiv::new(KeySize::KeySize256, &[0; 64], &[&aad]);
+
+            let mut output = [0u8; 1024];
+            let mut tag = [0u8; 16];
+            let mut output2 = [0u8; 1024];
+
+            cipher.encrypt(&input, &mut output, &mut tag);
+            decipher.decrypt(&output, &mut output2, &tag);
+        });
+        bh.bytes = 1024u64;
+    }
+}
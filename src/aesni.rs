@@ -6,7 +6,7 @@
 
 use aes::KeySize;
 use aes::KeySize::{KeySize128, KeySize192, KeySize256};
-use symmetriccipher::{BlockEncryptor, BlockDecryptor};
+use symmetriccipher::{BlockEncryptor, BlockEncryptorX8, BlockDecryptor, BlockDecryptorX8};
 
 #[derive(Copy)]
 pub struct AesNiEncryptor {
@@ -74,6 +74,20 @@ impl BlockDecryptor for AesNiDecryptor {
     }
 }
 
+impl BlockEncryptorX8 for AesNiEncryptor {
+    fn block_size(&self) -> usize { 16 }
+    fn encrypt_block_x8(&self, input: &[u8], output: &mut [u8]) {
+        encrypt_block_x8_aesni(self.rounds, input, &self.round_keys[0..size(self.rounds)], output);
+    }
+}
+
+impl BlockDecryptorX8 for AesNiDecryptor {
+    fn block_size(&self) -> usize { 16 }
+    fn decrypt_block_x8(&self, input: &[u8], output: &mut [u8]) {
+        decrypt_block_x8_aesni(self.rounds, input, &self.round_keys[0..size(self.rounds)], output);
+    }
+}
+
 enum KeyType {
     Encryption,
     Decryption
@@ -97,6 +111,16 @@ extern {
             input: *const u8,
             round_keys: *const u8,
             output: *mut u8);
+    fn rust_crypto_aesni_encrypt_block_x8(
+            rounds: u8,
+            input: *const u8,
+            round_keys: *const u8,
+            output: *mut u8);
+    fn rust_crypto_aesni_decrypt_block_x8(
+            rounds: u8,
+            input: *const u8,
+            round_keys: *const u8,
+            output: *mut u8);
 }
 
 fn setup_working_key_aesni_128(key: &[u8], key_type: KeyType, round_key: &mut [u8]) {
@@ -166,3 +190,25 @@ fn decrypt_block_aesni(rounds: u8, input: &[u8], round_keys: &[u8], output: &mut
                 output.as_mut_ptr());
     }
 }
+
+fn encrypt_block_x8_aesni(rounds: u8, input: &[u8], round_keys: &[u8], output: &mut [u8]) {
+    assert!(input.len() == 128 && output.len() == 128);
+    unsafe {
+        rust_crypto_aesni_encrypt_block_x8(
+                rounds,
+                input.as_ptr(),
+                round_keys.as_ptr(),
+                output.as_mut_ptr());
+    }
+}
+
+fn decrypt_block_x8_aesni(rounds: u8, input: &[u8], round_keys: &[u8], output: &mut [u8]) {
+    assert!(input.len() == 128 && output.len() == 128);
+    unsafe {
+        rust_crypto_aesni_decrypt_block_x8(
+                rounds,
+                input.as_ptr(),
+                round_keys.get_unchecked(round_keys.len() - 16),
+                output.as_mut_ptr());
+    }
+}
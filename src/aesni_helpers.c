@@ -9,7 +9,7 @@
 #if defined(__i386__) || defined(__x86_64__)
 
 void rust_crypto_aesni_aesimc(uint8_t* round_keys) {
-    asm(
+    asm volatile(
         " \
             movdqu (%0), %%xmm1; \
             aesimc %%xmm1, %%xmm1; \
@@ -24,7 +24,7 @@ void rust_crypto_aesni_aesimc(uint8_t* round_keys) {
 void rust_crypto_aesni_setup_working_key_128(
         uint8_t* key,
         uint8_t* round_key) {
-    asm(
+    asm volatile(
         " \
             movdqu (%1), %%xmm1; \
             movdqu %%xmm1, (%0); \
@@ -77,7 +77,7 @@ void rust_crypto_aesni_setup_working_key_128(
 void rust_crypto_aesni_setup_working_key_192(
         uint8_t* key,
         uint8_t* round_key) {
-    asm(
+    asm volatile(
         " \
             movdqu (%1), %%xmm1; \
             movdqu 16(%1), %%xmm3; \
@@ -165,7 +165,7 @@ void rust_crypto_aesni_setup_working_key_192(
 void rust_crypto_aesni_setup_working_key_256(
         uint8_t* key,
         uint8_t* round_key) {
-    asm(
+    asm volatile(
         " \
             movdqu (%1), %%xmm1; \
             movdqu 16(%1), %%xmm3; \
@@ -263,7 +263,7 @@ void rust_crypto_aesni_encrypt_block(
             uint8_t* input,
             uint8_t* round_keys,
             uint8_t* output) {
-    asm(
+    asm volatile(
     " \
         /* Copy the data to encrypt to xmm1 */ \
         movdqu (%2), %%xmm1; \
@@ -300,7 +300,7 @@ void rust_crypto_aesni_decrypt_block(
             uint8_t* input,
             uint8_t* round_keys,
             uint8_t* output) {
-    asm(
+    asm volatile(
         " \
             /* Copy the data to decrypt to xmm1 */ \
             movdqu (%2), %%xmm1; \
@@ -332,4 +332,281 @@ void rust_crypto_aesni_decrypt_block(
     );
 }
 
+// Encrypting or decrypting 8 independent blocks at once allows the AES instructions for the
+// different blocks to be pipelined. x86_64 has enough registers to hold all 8 blocks. Only 8
+// registers are available on x86, so the blocks are processed 4 at a time there.
+#if defined(__x86_64__)
+
+void rust_crypto_aesni_encrypt_block_x8(
+            uint8_t rounds,
+            uint8_t* input,
+            uint8_t* round_keys,
+            uint8_t* output) {
+    asm volatile(
+    " \
+        /* Copy the data to encrypt to xmm1-xmm8 */ \
+        movdqu (%2), %%xmm1; \
+        movdqu 16(%2), %%xmm2; \
+        movdqu 32(%2), %%xmm3; \
+        movdqu 48(%2), %%xmm4; \
+        movdqu 64(%2), %%xmm5; \
+        movdqu 80(%2), %%xmm6; \
+        movdqu 96(%2), %%xmm7; \
+        movdqu 112(%2), %%xmm8; \
+        \
+        /* Perform round 0 - the whitening step */ \
+        movdqu (%1), %%xmm0; \
+        add $0x10, %1; \
+        pxor %%xmm0, %%xmm1; \
+        pxor %%xmm0, %%xmm2; \
+        pxor %%xmm0, %%xmm3; \
+        pxor %%xmm0, %%xmm4; \
+        pxor %%xmm0, %%xmm5; \
+        pxor %%xmm0, %%xmm6; \
+        pxor %%xmm0, %%xmm7; \
+        pxor %%xmm0, %%xmm8; \
+        \
+        /* Perform all remaining rounds (except the final one), interleaving the blocks */ \
+        1: \
+        movdqu (%1), %%xmm0; \
+        add $0x10, %1; \
+        aesenc %%xmm0, %%xmm1; \
+        aesenc %%xmm0, %%xmm2; \
+        aesenc %%xmm0, %%xmm3; \
+        aesenc %%xmm0, %%xmm4; \
+        aesenc %%xmm0, %%xmm5; \
+        aesenc %%xmm0, %%xmm6; \
+        aesenc %%xmm0, %%xmm7; \
+        aesenc %%xmm0, %%xmm8; \
+        sub $0x01, %0; \
+        cmp $0x01, %0; \
+        jne 1b; \
+        \
+        /* Perform the last round */ \
+        movdqu (%1), %%xmm0; \
+        aesenclast %%xmm0, %%xmm1; \
+        aesenclast %%xmm0, %%xmm2; \
+        aesenclast %%xmm0, %%xmm3; \
+        aesenclast %%xmm0, %%xmm4; \
+        aesenclast %%xmm0, %%xmm5; \
+        aesenclast %%xmm0, %%xmm6; \
+        aesenclast %%xmm0, %%xmm7; \
+        aesenclast %%xmm0, %%xmm8; \
+        \
+        /* Finally, move the results to outp */ \
+        movdqu %%xmm1, (%3); \
+        movdqu %%xmm2, 16(%3); \
+        movdqu %%xmm3, 32(%3); \
+        movdqu %%xmm4, 48(%3); \
+        movdqu %%xmm5, 64(%3); \
+        movdqu %%xmm6, 80(%3); \
+        movdqu %%xmm7, 96(%3); \
+        movdqu %%xmm8, 112(%3); \
+    "
+    : "+&r" (rounds), "+&r" (round_keys) // outputs
+    : "r" (input), "r" (output) // inputs
+    : "xmm0", "xmm1", "xmm2", "xmm3", "xmm4", "xmm5", "xmm6", "xmm7", "xmm8",
+      "memory", "cc" // clobbers
+    );
+}
+
+void rust_crypto_aesni_decrypt_block_x8(
+            uint8_t rounds,
+            uint8_t* input,
+            uint8_t* round_keys,
+            uint8_t* output) {
+    asm volatile(
+    " \
+        /* Copy the data to decrypt to xmm1-xmm8 */ \
+        movdqu (%2), %%xmm1; \
+        movdqu 16(%2), %%xmm2; \
+        movdqu 32(%2), %%xmm3; \
+        movdqu 48(%2), %%xmm4; \
+        movdqu 64(%2), %%xmm5; \
+        movdqu 80(%2), %%xmm6; \
+        movdqu 96(%2), %%xmm7; \
+        movdqu 112(%2), %%xmm8; \
+        \
+        /* Perform round 0 - the whitening step */ \
+        movdqu (%1), %%xmm0; \
+        sub $0x10, %1; \
+        pxor %%xmm0, %%xmm1; \
+        pxor %%xmm0, %%xmm2; \
+        pxor %%xmm0, %%xmm3; \
+        pxor %%xmm0, %%xmm4; \
+        pxor %%xmm0, %%xmm5; \
+        pxor %%xmm0, %%xmm6; \
+        pxor %%xmm0, %%xmm7; \
+        pxor %%xmm0, %%xmm8; \
+        \
+        /* Perform all remaining rounds (except the final one), interleaving the blocks */ \
+        1: \
+        movdqu (%1), %%xmm0; \
+        sub $0x10, %1; \
+        aesdec %%xmm0, %%xmm1; \
+        aesdec %%xmm0, %%xmm2; \
+        aesdec %%xmm0, %%xmm3; \
+        aesdec %%xmm0, %%xmm4; \
+        aesdec %%xmm0, %%xmm5; \
+        aesdec %%xmm0, %%xmm6; \
+        aesdec %%xmm0, %%xmm7; \
+        aesdec %%xmm0, %%xmm8; \
+        sub $0x01, %0; \
+        cmp $0x01, %0; \
+        jne 1b; \
+        \
+        /* Perform the last round */ \
+        movdqu (%1), %%xmm0; \
+        aesdeclast %%xmm0, %%xmm1; \
+        aesdeclast %%xmm0, %%xmm2; \
+        aesdeclast %%xmm0, %%xmm3; \
+        aesdeclast %%xmm0, %%xmm4; \
+        aesdeclast %%xmm0, %%xmm5; \
+        aesdeclast %%xmm0, %%xmm6; \
+        aesdeclast %%xmm0, %%xmm7; \
+        aesdeclast %%xmm0, %%xmm8; \
+        \
+        /* Finally, move the results to outp */ \
+        movdqu %%xmm1, (%3); \
+        movdqu %%xmm2, 16(%3); \
+        movdqu %%xmm3, 32(%3); \
+        movdqu %%xmm4, 48(%3); \
+        movdqu %%xmm5, 64(%3); \
+        movdqu %%xmm6, 80(%3); \
+        movdqu %%xmm7, 96(%3); \
+        movdqu %%xmm8, 112(%3); \
+    "
+    : "+&r" (rounds), "+&r" (round_keys) // outputs
+    : "r" (input), "r" (output) // inputs
+    : "xmm0", "xmm1", "xmm2", "xmm3", "xmm4", "xmm5", "xmm6", "xmm7", "xmm8",
+      "memory", "cc" // clobbers
+    );
+}
+
+#else
+
+static void aesni_encrypt_block_x4(
+            uint8_t rounds,
+            uint8_t* input,
+            uint8_t* round_keys,
+            uint8_t* output) {
+    asm volatile(
+    " \
+        /* Copy the data to encrypt to xmm1-xmm4 */ \
+        movdqu (%2), %%xmm1; \
+        movdqu 16(%2), %%xmm2; \
+        movdqu 32(%2), %%xmm3; \
+        movdqu 48(%2), %%xmm4; \
+        \
+        /* Perform round 0 - the whitening step */ \
+        movdqu (%1), %%xmm0; \
+        add $0x10, %1; \
+        pxor %%xmm0, %%xmm1; \
+        pxor %%xmm0, %%xmm2; \
+        pxor %%xmm0, %%xmm3; \
+        pxor %%xmm0, %%xmm4; \
+        \
+        /* Perform all remaining rounds (except the final one), interleaving the blocks */ \
+        1: \
+        movdqu (%1), %%xmm0; \
+        add $0x10, %1; \
+        aesenc %%xmm0, %%xmm1; \
+        aesenc %%xmm0, %%xmm2; \
+        aesenc %%xmm0, %%xmm3; \
+        aesenc %%xmm0, %%xmm4; \
+        sub $0x01, %0; \
+        cmp $0x01, %0; \
+        jne 1b; \
+        \
+        /* Perform the last round */ \
+        movdqu (%1), %%xmm0; \
+        aesenclast %%xmm0, %%xmm1; \
+        aesenclast %%xmm0, %%xmm2; \
+        aesenclast %%xmm0, %%xmm3; \
+        aesenclast %%xmm0, %%xmm4; \
+        \
+        /* Finally, move the results to outp */ \
+        movdqu %%xmm1, (%3); \
+        movdqu %%xmm2, 16(%3); \
+        movdqu %%xmm3, 32(%3); \
+        movdqu %%xmm4, 48(%3); \
+    "
+    : "+&r" (rounds), "+&r" (round_keys) // outputs
+    : "r" (input), "r" (output) // inputs
+    : "xmm0", "xmm1", "xmm2", "xmm3", "xmm4", "memory", "cc" // clobbers
+    );
+}
+
+static void aesni_decrypt_block_x4(
+            uint8_t rounds,
+            uint8_t* input,
+            uint8_t* round_keys,
+            uint8_t* output) {
+    asm volatile(
+    " \
+        /* Copy the data to decrypt to xmm1-xmm4 */ \
+        movdqu (%2), %%xmm1; \
+        movdqu 16(%2), %%xmm2; \
+        movdqu 32(%2), %%xmm3; \
+        movdqu 48(%2), %%xmm4; \
+        \
+        /* Perform round 0 - the whitening step */ \
+        movdqu (%1), %%xmm0; \
+        sub $0x10, %1; \
+        pxor %%xmm0, %%xmm1; \
+        pxor %%xmm0, %%xmm2; \
+        pxor %%xmm0, %%xmm3; \
+        pxor %%xmm0, %%xmm4; \
+        \
+        /* Perform all remaining rounds (except the final one), interleaving the blocks */ \
+        1: \
+        movdqu (%1), %%xmm0; \
+        sub $0x10, %1; \
+        aesdec %%xmm0, %%xmm1; \
+        aesdec %%xmm0, %%xmm2; \
+        aesdec %%xmm0, %%xmm3; \
+        aesdec %%xmm0, %%xmm4; \
+        sub $0x01, %0; \
+        cmp $0x01, %0; \
+        jne 1b; \
+        \
+        /* Perform the last round */ \
+        movdqu (%1), %%xmm0; \
+        aesdeclast %%xmm0, %%xmm1; \
+        aesdeclast %%xmm0, %%xmm2; \
+        aesdeclast %%xmm0, %%xmm3; \
+        aesdeclast %%xmm0, %%xmm4; \
+        \
+        /* Finally, move the results to outp */ \
+        movdqu %%xmm1, (%3); \
+        movdqu %%xmm2, 16(%3); \
+        movdqu %%xmm3, 32(%3); \
+        movdqu %%xmm4, 48(%3); \
+    "
+    : "+&r" (rounds), "+&r" (round_keys) // outputs
+    : "r" (input), "r" (output) // inputs
+    : "xmm0", "xmm1", "xmm2", "xmm3", "xmm4", "memory", "cc" // clobbers
+    );
+}
+
+void rust_crypto_aesni_encrypt_block_x8(
+            uint8_t rounds,
+            uint8_t* input,
+            uint8_t* round_keys,
+            uint8_t* output) {
+    aesni_encrypt_block_x4(rounds, input, round_keys, output);
+    aesni_encrypt_block_x4(rounds, input + 64, round_keys, output + 64);
+}
+
+void rust_crypto_aesni_decrypt_block_x8(
+            uint8_t rounds,
+            uint8_t* input,
+            uint8_t* round_keys,
+            uint8_t* output) {
+    aesni_decrypt_block_x4(rounds, input, round_keys, output);
+    aesni_decrypt_block_x4(rounds, input + 64, round_keys, output + 64);
+}
+
+#endif
+
 #endif
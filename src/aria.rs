@@ -0,0 +1,369 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+An implementation of the ARIA block cipher as specified in RFC 5794.
+
+ARIA is a 128 bit block cipher with a substitution-permutation network structure. It accepts 128,
+192, or 256 bit keys, which use 12, 14, or 16 rounds respectively. Each round consists of a round
+key addition, one of two substitution layers, and an involutional diffusion layer.
+
+This implementation uses S-box lookups which are indexed by secret data and so it is not
+protected against cache timing attacks.
+*/
+
+use step_by::RangeExt;
+use symmetriccipher::{BlockEncryptor, BlockDecryptor};
+
+// SB1 is the AES S-box and SB3 is its inverse. SB4 is the inverse of SB2.
+static SB1: [u8; 256] = [
+    0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7, 0xab, 0x76,
+    0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf, 0x9c, 0xa4, 0x72, 0xc0,
+    0xb7, 0xfd, 0x93, 0x26, 0x36, 0x3f, 0xf7, 0xcc, 0x34, 0xa5, 0xe5, 0xf1, 0x71, 0xd8, 0x31, 0x15,
+    0x04, 0xc7, 0x23, 0xc3, 0x18, 0x96, 0x05, 0x9a, 0x07, 0x12, 0x80, 0xe2, 0xeb, 0x27, 0xb2, 0x75,
+    0x09, 0x83, 0x2c, 0x1a, 0x1b, 0x6e, 0x5a, 0xa0, 0x52, 0x3b, 0xd6, 0xb3, 0x29, 0xe3, 0x2f, 0x84,
+    0x53, 0xd1, 0x00, 0xed, 0x20, 0xfc, 0xb1, 0x5b, 0x6a, 0xcb, 0xbe, 0x39, 0x4a, 0x4c, 0x58, 0xcf,
+    0xd0, 0xef, 0xaa, 0xfb, 0x43, 0x4d, 0x33, 0x85, 0x45, 0xf9, 0x02, 0x7f, 0x50, 0x3c, 0x9f, 0xa8,
+    0x51, 0xa3, 0x40, 0x8f, 0x92, 0x9d, 0x38, 0xf5, 0xbc, 0xb6, 0xda, 0x21, 0x10, 0xff, 0xf3, 0xd2,
+    0xcd, 0x0c, 0x13, 0xec, 0x5f, 0x97, 0x44, 0x17, 0xc4, 0xa7, 0x7e, 0x3d, 0x64, 0x5d, 0x19, 0x73,
+    0x60, 0x81, 0x4f, 0xdc, 0x22, 0x2a, 0x90, 0x88, 0x46, 0xee, 0xb8, 0x14, 0xde, 0x5e, 0x0b, 0xdb,
+    0xe0, 0x32, 0x3a, 0x0a, 0x49, 0x06, 0x24, 0x5c, 0xc2, 0xd3, 0xac, 0x62, 0x91, 0x95, 0xe4, 0x79,
+    0xe7, 0xc8, 0x37, 0x6d, 0x8d, 0xd5, 0x4e, 0xa9, 0x6c, 0x56, 0xf4, 0xea, 0x65, 0x7a, 0xae, 0x08,
+    0xba, 0x78, 0x25, 0x2e, 0x1c, 0xa6, 0xb4, 0xc6, 0xe8, 0xdd, 0x74, 0x1f, 0x4b, 0xbd, 0x8b, 0x8a,
+    0x70, 0x3e, 0xb5, 0x66, 0x48, 0x03, 0xf6, 0x0e, 0x61, 0x35, 0x57, 0xb9, 0x86, 0xc1, 0x1d, 0x9e,
+    0xe1, 0xf8, 0x98, 0x11, 0x69, 0xd9, 0x8e, 0x94, 0x9b, 0x1e, 0x87, 0xe9, 0xce, 0x55, 0x28, 0xdf,
+    0x8c, 0xa1, 0x89, 0x0d, 0xbf, 0xe6, 0x42, 0x68, 0x41, 0x99, 0x2d, 0x0f, 0xb0, 0x54, 0xbb, 0x16
+];
+
+static SB2: [u8; 256] = [
+    0xe2, 0x4e, 0x54, 0xfc, 0x94, 0xc2, 0x4a, 0xcc, 0x62, 0x0d, 0x6a, 0x46, 0x3c, 0x4d, 0x8b, 0xd1,
+    0x5e, 0xfa, 0x64, 0xcb, 0xb4, 0x97, 0xbe, 0x2b, 0xbc, 0x77, 0x2e, 0x03, 0xd3, 0x19, 0x59, 0xc1,
+    0x1d, 0x06, 0x41, 0x6b, 0x55, 0xf0, 0x99, 0x69, 0xea, 0x9c, 0x18, 0xae, 0x63, 0xdf, 0xe7, 0xbb,
+    0x00, 0x73, 0x66, 0xfb, 0x96, 0x4c, 0x85, 0xe4, 0x3a, 0x09, 0x45, 0xaa, 0x0f, 0xee, 0x10, 0xeb,
+    0x2d, 0x7f, 0xf4, 0x29, 0xac, 0xcf, 0xad, 0x91, 0x8d, 0x78, 0xc8, 0x95, 0xf9, 0x2f, 0xce, 0xcd,
+    0x08, 0x7a, 0x88, 0x38, 0x5c, 0x83, 0x2a, 0x28, 0x47, 0xdb, 0xb8, 0xc7, 0x93, 0xa4, 0x12, 0x53,
+    0xff, 0x87, 0x0e, 0x31, 0x36, 0x21, 0x58, 0x48, 0x01, 0x8e, 0x37, 0x74, 0x32, 0xca, 0xe9, 0xb1,
+    0xb7, 0xab, 0x0c, 0xd7, 0xc4, 0x56, 0x42, 0x26, 0x07, 0x98, 0x60, 0xd9, 0xb6, 0xb9, 0x11, 0x40,
+    0xec, 0x20, 0x8c, 0xbd, 0xa0, 0xc9, 0x84, 0x04, 0x49, 0x23, 0xf1, 0x4f, 0x50, 0x1f, 0x13, 0xdc,
+    0xd8, 0xc0, 0x9e, 0x57, 0xe3, 0xc3, 0x7b, 0x65, 0x3b, 0x02, 0x8f, 0x3e, 0xe8, 0x25, 0x92, 0xe5,
+    0x15, 0xdd, 0xfd, 0x17, 0xa9, 0xbf, 0xd4, 0x9a, 0x7e, 0xc5, 0x39, 0x67, 0xfe, 0x76, 0x9d, 0x43,
+    0xa7, 0xe1, 0xd0, 0xf5, 0x68, 0xf2, 0x1b, 0x34, 0x70, 0x05, 0xa3, 0x8a, 0xd5, 0x79, 0x86, 0xa8,
+    0x30, 0xc6, 0x51, 0x4b, 0x1e, 0xa6, 0x27, 0xf6, 0x35, 0xd2, 0x6e, 0x24, 0x16, 0x82, 0x5f, 0xda,
+    0xe6, 0x75, 0xa2, 0xef, 0x2c, 0xb2, 0x1c, 0x9f, 0x5d, 0x6f, 0x80, 0x0a, 0x72, 0x44, 0x9b, 0x6c,
+    0x90, 0x0b, 0x5b, 0x33, 0x7d, 0x5a, 0x52, 0xf3, 0x61, 0xa1, 0xf7, 0xb0, 0xd6, 0x3f, 0x7c, 0x6d,
+    0xed, 0x14, 0xe0, 0xa5, 0x3d, 0x22, 0xb3, 0xf8, 0x89, 0xde, 0x71, 0x1a, 0xaf, 0xba, 0xb5, 0x81
+];
+
+static SB3: [u8; 256] = [
+    0x52, 0x09, 0x6a, 0xd5, 0x30, 0x36, 0xa5, 0x38, 0xbf, 0x40, 0xa3, 0x9e, 0x81, 0xf3, 0xd7, 0xfb,
+    0x7c, 0xe3, 0x39, 0x82, 0x9b, 0x2f, 0xff, 0x87, 0x34, 0x8e, 0x43, 0x44, 0xc4, 0xde, 0xe9, 0xcb,
+    0x54, 0x7b, 0x94, 0x32, 0xa6, 0xc2, 0x23, 0x3d, 0xee, 0x4c, 0x95, 0x0b, 0x42, 0xfa, 0xc3, 0x4e,
+    0x08, 0x2e, 0xa1, 0x66, 0x28, 0xd9, 0x24, 0xb2, 0x76, 0x5b, 0xa2, 0x49, 0x6d, 0x8b, 0xd1, 0x25,
+    0x72, 0xf8, 0xf6, 0x64, 0x86, 0x68, 0x98, 0x16, 0xd4, 0xa4, 0x5c, 0xcc, 0x5d, 0x65, 0xb6, 0x92,
+    0x6c, 0x70, 0x48, 0x50, 0xfd, 0xed, 0xb9, 0xda, 0x5e, 0x15, 0x46, 0x57, 0xa7, 0x8d, 0x9d, 0x84,
+    0x90, 0xd8, 0xab, 0x00, 0x8c, 0xbc, 0xd3, 0x0a, 0xf7, 0xe4, 0x58, 0x05, 0xb8, 0xb3, 0x45, 0x06,
+    0xd0, 0x2c, 0x1e, 0x8f, 0xca, 0x3f, 0x0f, 0x02, 0xc1, 0xaf, 0xbd, 0x03, 0x01, 0x13, 0x8a, 0x6b,
+    0x3a, 0x91, 0x11, 0x41, 0x4f, 0x67, 0xdc, 0xea, 0x97, 0xf2, 0xcf, 0xce, 0xf0, 0xb4, 0xe6, 0x73,
+    0x96, 0xac, 0x74, 0x22, 0xe7, 0xad, 0x35, 0x85, 0xe2, 0xf9, 0x37, 0xe8, 0x1c, 0x75, 0xdf, 0x6e,
+    0x47, 0xf1, 0x1a, 0x71, 0x1d, 0x29, 0xc5, 0x89, 0x6f, 0xb7, 0x62, 0x0e, 0xaa, 0x18, 0xbe, 0x1b,
+    0xfc, 0x56, 0x3e, 0x4b, 0xc6, 0xd2, 0x79, 0x20, 0x9a, 0xdb, 0xc0, 0xfe, 0x78, 0xcd, 0x5a, 0xf4,
+    0x1f, 0xdd, 0xa8, 0x33, 0x88, 0x07, 0xc7, 0x31, 0xb1, 0x12, 0x10, 0x59, 0x27, 0x80, 0xec, 0x5f,
+    0x60, 0x51, 0x7f, 0xa9, 0x19, 0xb5, 0x4a, 0x0d, 0x2d, 0xe5, 0x7a, 0x9f, 0x93, 0xc9, 0x9c, 0xef,
+    0xa0, 0xe0, 0x3b, 0x4d, 0xae, 0x2a, 0xf5, 0xb0, 0xc8, 0xeb, 0xbb, 0x3c, 0x83, 0x53, 0x99, 0x61,
+    0x17, 0x2b, 0x04, 0x7e, 0xba, 0x77, 0xd6, 0x26, 0xe1, 0x69, 0x14, 0x63, 0x55, 0x21, 0x0c, 0x7d
+];
+
+static SB4: [u8; 256] = [
+    0x30, 0x68, 0x99, 0x1b, 0x87, 0xb9, 0x21, 0x78, 0x50, 0x39, 0xdb, 0xe1, 0x72, 0x09, 0x62, 0x3c,
+    0x3e, 0x7e, 0x5e, 0x8e, 0xf1, 0xa0, 0xcc, 0xa3, 0x2a, 0x1d, 0xfb, 0xb6, 0xd6, 0x20, 0xc4, 0x8d,
+    0x81, 0x65, 0xf5, 0x89, 0xcb, 0x9d, 0x77, 0xc6, 0x57, 0x43, 0x56, 0x17, 0xd4, 0x40, 0x1a, 0x4d,
+    0xc0, 0x63, 0x6c, 0xe3, 0xb7, 0xc8, 0x64, 0x6a, 0x53, 0xaa, 0x38, 0x98, 0x0c, 0xf4, 0x9b, 0xed,
+    0x7f, 0x22, 0x76, 0xaf, 0xdd, 0x3a, 0x0b, 0x58, 0x67, 0x88, 0x06, 0xc3, 0x35, 0x0d, 0x01, 0x8b,
+    0x8c, 0xc2, 0xe6, 0x5f, 0x02, 0x24, 0x75, 0x93, 0x66, 0x1e, 0xe5, 0xe2, 0x54, 0xd8, 0x10, 0xce,
+    0x7a, 0xe8, 0x08, 0x2c, 0x12, 0x97, 0x32, 0xab, 0xb4, 0x27, 0x0a, 0x23, 0xdf, 0xef, 0xca, 0xd9,
+    0xb8, 0xfa, 0xdc, 0x31, 0x6b, 0xd1, 0xad, 0x19, 0x49, 0xbd, 0x51, 0x96, 0xee, 0xe4, 0xa8, 0x41,
+    0xda, 0xff, 0xcd, 0x55, 0x86, 0x36, 0xbe, 0x61, 0x52, 0xf8, 0xbb, 0x0e, 0x82, 0x48, 0x69, 0x9a,
+    0xe0, 0x47, 0x9e, 0x5c, 0x04, 0x4b, 0x34, 0x15, 0x79, 0x26, 0xa7, 0xde, 0x29, 0xae, 0x92, 0xd7,
+    0x84, 0xe9, 0xd2, 0xba, 0x5d, 0xf3, 0xc5, 0xb0, 0xbf, 0xa4, 0x3b, 0x71, 0x44, 0x46, 0x2b, 0xfc,
+    0xeb, 0x6f, 0xd5, 0xf6, 0x14, 0xfe, 0x7c, 0x70, 0x5a, 0x7d, 0xfd, 0x2f, 0x18, 0x83, 0x16, 0xa5,
+    0x91, 0x1f, 0x05, 0x95, 0x74, 0xa9, 0xc1, 0x5b, 0x4a, 0x85, 0x6d, 0x13, 0x07, 0x4f, 0x4e, 0x45,
+    0xb2, 0x0f, 0xc9, 0x1c, 0xa6, 0xbc, 0xec, 0x73, 0x90, 0x7b, 0xcf, 0x59, 0x8f, 0xa1, 0xf9, 0x2d,
+    0xf2, 0xb1, 0x00, 0x94, 0x37, 0x9f, 0xd0, 0x2e, 0x9c, 0x6e, 0x28, 0x3f, 0x80, 0xf0, 0x3d, 0xd3,
+    0x25, 0x8a, 0xb5, 0xe7, 0x42, 0xb3, 0xc7, 0xea, 0xf7, 0x4c, 0x11, 0x33, 0x03, 0xa2, 0xac, 0x60
+];
+
+const C1: [u8; 16] = [
+    0x51, 0x7c, 0xc1, 0xb7, 0x27, 0x22, 0x0a, 0x94, 0xfe, 0x13, 0xab, 0xe8, 0xfa, 0x9a, 0x6e, 0xe0];
+const C2: [u8; 16] = [
+    0x6d, 0xb1, 0x4a, 0xcc, 0x9e, 0x21, 0xc8, 0x20, 0xff, 0x28, 0xb1, 0xd5, 0xef, 0x5d, 0xe2, 0xb0];
+const C3: [u8; 16] = [
+    0xdb, 0x92, 0x37, 0x1d, 0x21, 0x26, 0xe9, 0x70, 0x03, 0x24, 0x97, 0x75, 0x04, 0xe8, 0xc9, 0x0e];
+
+fn xor(x: &[u8; 16], y: &[u8; 16]) -> [u8; 16] {
+    let mut out = [0u8; 16];
+    for i in 0..16 {
+        out[i] = x[i] ^ y[i];
+    }
+    out
+}
+
+// Rotate the 128 bit big endian value x right by n bits.
+fn rotr(x: &[u8; 16], n: usize) -> [u8; 16] {
+    let bytes = n / 8;
+    let bits = n % 8;
+    let mut t = [0u8; 16];
+    for i in 0..16 {
+        t[(i + bytes) % 16] = x[i];
+    }
+    if bits == 0 {
+        return t;
+    }
+    let mut out = [0u8; 16];
+    for i in 0..16 {
+        out[i] = (t[i] >> bits) | (t[(i + 15) % 16] << (8 - bits));
+    }
+    out
+}
+
+// Substitution layer type 1, used in odd rounds.
+fn sl1(x: &[u8; 16]) -> [u8; 16] {
+    let mut out = [0u8; 16];
+    for i in (0..16).step_up(4) {
+        out[i] = SB1[x[i] as usize];
+        out[i + 1] = SB2[x[i + 1] as usize];
+        out[i + 2] = SB3[x[i + 2] as usize];
+        out[i + 3] = SB4[x[i + 3] as usize];
+    }
+    out
+}
+
+// Substitution layer type 2, used in even rounds. This is the inverse of sl1.
+fn sl2(x: &[u8; 16]) -> [u8; 16] {
+    let mut out = [0u8; 16];
+    for i in (0..16).step_up(4) {
+        out[i] = SB3[x[i] as usize];
+        out[i + 1] = SB4[x[i + 1] as usize];
+        out[i + 2] = SB1[x[i + 2] as usize];
+        out[i + 3] = SB2[x[i + 3] as usize];
+    }
+    out
+}
+
+// The diffusion layer. This is an involution.
+fn a(x: &[u8; 16]) -> [u8; 16] {
+    [
+        x[3] ^ x[4] ^ x[6] ^ x[8] ^ x[9] ^ x[13] ^ x[14],
+        x[2] ^ x[5] ^ x[7] ^ x[8] ^ x[9] ^ x[12] ^ x[15],
+        x[1] ^ x[4] ^ x[6] ^ x[10] ^ x[11] ^ x[12] ^ x[15],
+        x[0] ^ x[5] ^ x[7] ^ x[10] ^ x[11] ^ x[13] ^ x[14],
+        x[0] ^ x[2] ^ x[5] ^ x[8] ^ x[11] ^ x[14] ^ x[15],
+        x[1] ^ x[3] ^ x[4] ^ x[9] ^ x[10] ^ x[14] ^ x[15],
+        x[0] ^ x[2] ^ x[7] ^ x[9] ^ x[10] ^ x[12] ^ x[13],
+        x[1] ^ x[3] ^ x[6] ^ x[8] ^ x[11] ^ x[12] ^ x[13],
+        x[0] ^ x[1] ^ x[4] ^ x[7] ^ x[10] ^ x[13] ^ x[15],
+        x[0] ^ x[1] ^ x[5] ^ x[6] ^ x[11] ^ x[12] ^ x[14],
+        x[2] ^ x[3] ^ x[5] ^ x[6] ^ x[8] ^ x[13] ^ x[15],
+        x[2] ^ x[3] ^ x[4] ^ x[7] ^ x[9] ^ x[12] ^ x[14],
+        x[1] ^ x[2] ^ x[6] ^ x[7] ^ x[9] ^ x[11] ^ x[12],
+        x[0] ^ x[3] ^ x[6] ^ x[7] ^ x[8] ^ x[10] ^ x[13],
+        x[0] ^ x[3] ^ x[4] ^ x[5] ^ x[9] ^ x[11] ^ x[14],
+        x[1] ^ x[2] ^ x[4] ^ x[5] ^ x[8] ^ x[10] ^ x[15]
+    ]
+}
+
+// The odd round function
+fn fo(d: &[u8; 16], rk: &[u8; 16]) -> [u8; 16] {
+    a(&sl1(&xor(d, rk)))
+}
+
+// The even round function
+fn fe(d: &[u8; 16], rk: &[u8; 16]) -> [u8; 16] {
+    a(&sl2(&xor(d, rk)))
+}
+
+// Run the rounds with the given round keys. Encryption and decryption only differ in the keys.
+fn crypt(rounds: usize, keys: &[[u8; 16]], input: &[u8], output: &mut [u8]) {
+    let mut p = [0u8; 16];
+    p.copy_from_slice(input);
+    for i in (0..rounds - 2).step_up(2) {
+        p = fo(&p, &keys[i]);
+        p = fe(&p, &keys[i + 1]);
+    }
+    p = fo(&p, &keys[rounds - 2]);
+    p = xor(&sl2(&xor(&p, &keys[rounds - 1])), &keys[rounds]);
+    output.copy_from_slice(&p);
+}
+
+#[derive(Clone, Copy)]
+pub struct Aria {
+    rounds: usize,
+    ek: [[u8; 16]; 17],
+    dk: [[u8; 16]; 17]
+}
+
+impl Aria {
+    pub fn new(key: &[u8]) -> Aria {
+        let (rounds, ck1, ck2, ck3) = match key.len() {
+            16 => (12, &C1, &C2, &C3),
+            24 => (14, &C2, &C3, &C1),
+            32 => (16, &C3, &C1, &C2),
+            _ => panic!("Invalid ARIA key length")
+        };
+
+        let mut w0 = [0u8; 16];
+        let mut kr = [0u8; 16];
+        w0.copy_from_slice(&key[0..16]);
+        kr[..key.len() - 16].copy_from_slice(&key[16..]);
+
+        let w1 = xor(&fo(&w0, ck1), &kr);
+        let w2 = xor(&fe(&w1, ck2), &w0);
+        let w3 = xor(&fo(&w2, ck3), &w1);
+
+        let w = [w0, w1, w2, w3];
+        let mut ek = [[0u8; 16]; 17];
+        for i in 0..4 {
+            let x = &w[i];
+            let y = &w[(i + 1) % 4];
+            ek[i] = xor(x, &rotr(y, 19));
+            ek[i + 4] = xor(x, &rotr(y, 31));
+            ek[i + 8] = xor(x, &rotr(y, 128 - 61));
+            ek[i + 12] = xor(x, &rotr(y, 128 - 31));
+        }
+        ek[16] = xor(&w0, &rotr(&w1, 128 - 19));
+
+        let mut dk = [[0u8; 16]; 17];
+        dk[0] = ek[rounds];
+        for i in 1..rounds {
+            dk[i] = a(&ek[rounds - i]);
+        }
+        dk[rounds] = ek[0];
+
+        Aria {
+            rounds: rounds,
+            ek: ek,
+            dk: dk
+        }
+    }
+}
+
+impl BlockEncryptor for Aria {
+    fn block_size(&self) -> usize {
+        16
+    }
+
+    fn encrypt_block(&self, input: &[u8], output: &mut [u8]) {
+        assert!(input.len() == 16);
+        assert!(output.len() == 16);
+        crypt(self.rounds, &self.ek, input, output);
+    }
+}
+
+impl BlockDecryptor for Aria {
+    fn block_size(&self) -> usize {
+        16
+    }
+
+    fn decrypt_block(&self, input: &[u8], output: &mut [u8]) {
+        assert!(input.len() == 16);
+        assert!(output.len() == 16);
+        crypt(self.rounds, &self.dk, input, output);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use aria::Aria;
+    use blockmodes::{CbcEncryptor, NoPadding};
+    use buffer::{RefReadBuffer, RefWriteBuffer};
+    use symmetriccipher::{BlockEncryptor, BlockDecryptor, Encryptor};
+    use serialize::hex::FromHex;
+
+    struct Test {
+        key: &'static str,
+        plaintext: &'static str,
+        ciphertext: &'static str
+    }
+
+    // Vectors from RFC 5794, Appendix A
+    fn rfc5794_test_vectors() -> Vec<Test> {
+        vec![
+            Test {
+                key: "000102030405060708090a0b0c0d0e0f",
+                plaintext: "00112233445566778899aabbccddeeff",
+                ciphertext: "d718fbd6ab644c739da95f3be6451778"
+            },
+            Test {
+                key: "000102030405060708090a0b0c0d0e0f1011121314151617",
+                plaintext: "00112233445566778899aabbccddeeff",
+                ciphertext: "26449c1805dbe7aa25a468ce263a9e79"
+            },
+            Test {
+                key: "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+                plaintext: "00112233445566778899aabbccddeeff",
+                ciphertext: "f92bd7c79fb72e2f2b8f80c1972d24fc"
+            }
+        ]
+    }
+
+    #[test]
+    fn encrypt_rfc5794_test_vectors() {
+        let mut output = [0u8; 16];
+        for test in rfc5794_test_vectors().iter() {
+            let state = Aria::new(&test.key.from_hex().unwrap());
+            state.encrypt_block(&test.plaintext.from_hex().unwrap(), &mut output);
+            assert_eq!(&output[..], &test.ciphertext.from_hex().unwrap()[..]);
+        }
+    }
+
+    #[test]
+    fn decrypt_rfc5794_test_vectors() {
+        let mut output = [0u8; 16];
+        for test in rfc5794_test_vectors().iter() {
+            let state = Aria::new(&test.key.from_hex().unwrap());
+            state.decrypt_block(&test.ciphertext.from_hex().unwrap(), &mut output);
+            assert_eq!(&output[..], &test.plaintext.from_hex().unwrap()[..]);
+        }
+    }
+
+    #[test]
+    fn aria_cbc() {
+        let key = "000102030405060708090a0b0c0d0e0f".from_hex().unwrap();
+        let iv = "0f0e0d0c0b0a09080706050403020100".from_hex().unwrap();
+        let plaintext = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f"
+            .from_hex().unwrap();
+        let expected = "012dccb5ffb59ef78fb065d0b8f8b37d48c0467a3f719ca4bb7d30c41b5d0d35"
+            .from_hex().unwrap();
+
+        let mut encryptor = CbcEncryptor::new(Aria::new(&key), NoPadding, iv);
+        let mut output = [0u8; 32];
+        {
+            let mut read_buffer = RefReadBuffer::new(&plaintext);
+            let mut write_buffer = RefWriteBuffer::new(&mut output);
+            encryptor.encrypt(&mut read_buffer, &mut write_buffer, true).unwrap();
+        }
+        assert_eq!(&output[..], &expected[..]);
+    }
+}
+
+#[cfg(all(test, feature = "with-bench"))]
+mod bench {
+    use aria::Aria;
+    use symmetriccipher::BlockEncryptor;
+    use test::Bencher;
+
+    #[bench]
+    fn aria(bh: &mut Bencher) {
+        let key = [0u8; 16];
+        let plaintext = [1u8; 16];
+        let state = Aria::new(&key);
+        let mut ciphertext = [0u8; 16];
+
+        bh.iter(|| {
+            state.encrypt_block(&plaintext, &mut ciphertext);
+        });
+        bh.bytes = 16u64;
+    }
+}
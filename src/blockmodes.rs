@@ -9,14 +9,17 @@
 // TODO - I think padding could be done better. Maybe macros for BlockEngine would help this too.
 
 use std::cmp;
+use std::io;
 use std::iter::repeat;
 
+use rand::{OsRng, Rng};
+
 use buffer::{ReadBuffer, WriteBuffer, OwnedReadBuffer, OwnedWriteBuffer, BufferResult,
     RefReadBuffer, RefWriteBuffer};
 use buffer::BufferResult::{BufferUnderflow, BufferOverflow};
 use cryptoutil::{self, symm_enc_or_dec};
-use symmetriccipher::{BlockEncryptor, BlockEncryptorX8, Encryptor, BlockDecryptor, Decryptor,
-    SynchronousStreamCipher, SymmetricCipherError};
+use symmetriccipher::{BlockEncryptor, BlockEncryptorX8, Encryptor, BlockDecryptor, BlockDecryptorX8,
+    Decryptor, SynchronousStreamCipher, SeekableStreamCipher, SymmetricCipherError, SeekError};
 use symmetriccipher::SymmetricCipherError::{InvalidPadding, InvalidLength};
 
 /// The BlockProcessor trait is used to implement modes that require processing complete blocks of
@@ -26,6 +29,41 @@ trait BlockProcessor {
     /// Process a block of data. The in_hist and out_hist parameters represent the input and output
     /// when the last block was processed. These values are necessary for certain modes.
     fn process_block(&mut self, in_hist: &[u8], out_hist: &[u8], input: &[u8], output: &mut [u8]);
+
+    /// Process several consecutive blocks of data. The input is a multiple of block_size bytes
+    /// long and in_hist and out_hist are the history for the first of the blocks. The default
+    /// implementation calls process_block() for each block, while processors that are able to
+    /// operate on multiple blocks in parallel may override it.
+    fn process_blocks(
+            &mut self,
+            block_size: usize,
+            in_hist: &[u8],
+            out_hist: &[u8],
+            input: &[u8],
+            output: &mut [u8]) {
+        for i in 0..input.len() / block_size {
+            let start = i * block_size;
+            let (prev_out, next_out) = output.split_at_mut(start);
+            let next_in = &input[start..start + block_size];
+            let next_out = &mut next_out[..block_size];
+            if i == 0 {
+                self.process_block(in_hist, out_hist, next_in, next_out);
+            } else {
+                self.process_block(
+                    &input[start - in_hist.len()..start],
+                    &prev_out[start - out_hist.len()..],
+                    next_in,
+                    next_out);
+            }
+        }
+    }
+
+    /// Process the final data of a mode that uses ciphertext stealing. The input is either a
+    /// single block or is more than one block and at most two blocks long. The output is the same
+    /// length as the input. If false is returned, the processing fails.
+    fn process_final(&mut self, _: &[u8], _: &[u8], _: &[u8], _: &mut [u8]) -> bool {
+        unreachable!("process_final() is only called for modes that use ciphertext stealing")
+    }
 }
 
 /// A PaddingProcessor handles adding or removing padding
@@ -79,6 +117,17 @@ struct BlockEngine<P, X> {
     out_write_scratch: Option<OwnedWriteBuffer>,
     out_read_scratch: Option<OwnedReadBuffer>,
 
+    /// Whether the mode uses ciphertext stealing. If so, the final two blocks of data are held
+    /// back instead of only the last one and are passed to the process_final() method of the
+    /// Processor rather than being padded. In that case, in_scratch and out_write_scratch are two
+    /// blocks long.
+    stealing: bool,
+
+    /// When using ciphertext stealing, a block of in_scratch that may be one of the final two
+    /// blocks is moved through this buffer back to the start of in_scratch. It is allocated once so
+    /// that holding a block back doesn't allocate.
+    held: Vec<u8>,
+
     /// The processor that implements the particular block mode.
     processor: P,
 
@@ -115,6 +164,8 @@ impl <P: BlockProcessor, X: PaddingProcessor> BlockEngine<P, X> {
             in_scratch: OwnedWriteBuffer::new(repeat(0).take(block_size).collect()),
             out_write_scratch: Some(OwnedWriteBuffer::new(repeat(0).take(block_size).collect())),
             out_read_scratch: None,
+            stealing: false,
+            held: Vec::new(),
             processor: processor,
             padding: padding,
             state: BlockEngineState::FastMode
@@ -136,74 +187,68 @@ impl <P: BlockProcessor, X: PaddingProcessor> BlockEngine<P, X> {
         }
     }
 
+    /// Create a new BlockProcessor instance for a mode that uses ciphertext stealing with the
+    /// given processor, block_size, and initial input and output history.
+    fn new_with_stealing(
+            processor: P,
+            padding: X,
+            block_size: usize,
+            in_hist: Vec<u8>,
+            out_hist: Vec<u8>) -> BlockEngine<P, X> {
+        BlockEngine {
+            in_scratch: OwnedWriteBuffer::new(repeat(0).take(2 * block_size).collect()),
+            out_write_scratch: Some(OwnedWriteBuffer::new(
+                repeat(0).take(2 * block_size).collect())),
+            stealing: true,
+            held: repeat(0).take(block_size).collect(),
+            ..BlockEngine::new_with_history(processor, padding, block_size, in_hist, out_hist)
+        }
+    }
+
+    /// The amount of input data that must be held back until eof for last block processing.
+    fn final_size(&self) -> usize {
+        if self.stealing { 2 * self.block_size } else { self.block_size }
+    }
+
     /// This implements the FastMode state. Ideally, the encryption or decryption operation should
     /// do the bulk of its work in FastMode. Significantly, FastMode avoids doing copies as much as
-    /// possible. The FastMode state does not handle the final block of data.
+    /// possible by passing all of the blocks that can be processed directly between the input and
+    /// output buffers to the Processor at once. The FastMode state does not handle the final block
+    /// of data.
     fn fast_mode<R: ReadBuffer, W: WriteBuffer>(
             &mut self,
             input: &mut R,
             output: &mut W) -> BlockEngineState {
-        fn has_next<R: ReadBuffer, W: WriteBuffer>(
-                input: &mut R,
-                output: &mut W,
-                block_size: usize) -> bool {
-            // Not the greater than - very important since this method must never process the last
-            // block.
-            let enough_input = input.remaining() > block_size;
-            let enough_output = output.remaining() >= block_size;
-            enough_input && enough_output
-        };
-        fn split_at<'a>(vec: &'a [u8], at: usize) -> (&'a [u8], &'a [u8]) {
-            (&vec[..at], &vec[at..])
-        }
-
-        // First block processing. We have to retrieve the history information from self.in_hist and
-        // self.out_hist.
-        if !has_next(input, output, self.block_size) {
-            if input.is_empty() {
-                return BlockEngineState::FastMode;
-            } else {
-                return BlockEngineState::NeedInput;
-            }
+        // Not the greater than or equal to - very important since this method must never process
+        // the last block (or the last two blocks, for ciphertext stealing).
+        let final_size = self.final_size();
+        let in_blocks = if input.remaining() > final_size {
+            (input.remaining() - final_size - 1) / self.block_size + 1
         } else {
-            let next_in = input.take_next(self.block_size);
-            let next_out = output.take_next(self.block_size);
-            self.processor.process_block(
+            0
+        };
+        let out_blocks = output.remaining() / self.block_size;
+        let count = cmp::min(in_blocks, out_blocks) * self.block_size;
+
+        if count > 0 {
+            let next_in = input.take_next(count);
+            let next_out = output.take_next(count);
+            self.processor.process_blocks(
+                self.block_size,
                 &self.in_hist[..],
                 &self.out_hist[..],
                 next_in,
                 next_out);
-        }
-
-        // Process all remaing blocks. We can pull the history out of the buffers without having to
-        // do any copies
-        let next_in_size = self.in_hist.len() + self.block_size;
-        let next_out_size = self.out_hist.len() + self.block_size;
-        while has_next(input, output, self.block_size) {
-            input.rewind(self.in_hist.len());
-            let (in_hist, next_in) = split_at(input.take_next(next_in_size), self.in_hist.len());
-            output.rewind(self.out_hist.len());
-            let (out_hist, next_out) = output.take_next(next_out_size).split_at_mut(
-                self.out_hist.len());
-            self.processor.process_block(
-                in_hist,
-                out_hist,
-                next_in,
-                next_out);
-        }
 
-        // Save the history and then transition to the next state
-        {
-            input.rewind(self.in_hist.len());
-            let last_in = input.take_next(self.in_hist.len());
-            output.rewind(self.out_hist.len());
-            let last_out = output.take_next(self.out_hist.len());
+            // Save the history and then transition to the next state
+            let last = count - self.block_size;
             update_history(
                 &mut self.in_hist,
                 &mut self.out_hist,
-                last_in,
-                last_out);
+                &next_in[last..],
+                &next_out[last..]);
         }
+
         if input.is_empty() {
             BlockEngineState::FastMode
         } else {
@@ -217,16 +262,23 @@ impl <P: BlockProcessor, X: PaddingProcessor> BlockEngine<P, X> {
             input: &mut R,
             output: &mut W,
             eof: bool) -> Result<BufferResult, SymmetricCipherError> {
-        // Process a block of data from in_scratch and write the result to out_write_scratch.
-        // Finally, convert out_write_scratch into out_read_scratch.
-        fn process_scratch<P: BlockProcessor, X: PaddingProcessor>(me: &mut BlockEngine<P, X>) {
-            let mut rin = me.in_scratch.take_read_buffer();
+        // Process the given number of blocks of data from in_scratch and write the result to
+        // out_write_scratch. Finally, convert out_write_scratch into out_read_scratch. When using
+        // ciphertext stealing, in_scratch may hold a second block that wasn't processed, which is
+        // moved to the start of in_scratch.
+        fn process_scratch<P: BlockProcessor, X: PaddingProcessor>(
+                me: &mut BlockEngine<P, X>,
+                blocks: usize) {
             let mut wout = me.out_write_scratch.take().unwrap();
+            let count = blocks * me.block_size;
+            let held_len;
 
             {
-                let next_in = rin.take_remaining();
-                let next_out = wout.take_remaining();
-                me.processor.process_block(
+                let mut rin = me.in_scratch.take_read_buffer();
+                let next_in = rin.take_next(count);
+                let next_out = wout.take_next(count);
+                me.processor.process_blocks(
+                    me.block_size,
                     &me.in_hist[..],
                     &me.out_hist[..],
                     next_in,
@@ -234,8 +286,15 @@ impl <P: BlockProcessor, X: PaddingProcessor> BlockEngine<P, X> {
                 update_history(
                     &mut me.in_hist,
                     &mut me.out_hist,
-                    next_in,
-                    next_out);
+                    &next_in[count - me.block_size..],
+                    &next_out[count - me.block_size..]);
+                let rest = rin.take_remaining();
+                held_len = rest.len();
+                cryptoutil::copy_memory(rest, &mut me.held[..held_len]);
+            }
+
+            if held_len > 0 {
+                cryptoutil::copy_memory(&me.held[..held_len], me.in_scratch.take_next(held_len));
             }
 
             let rb = wout.into_read_buffer();
@@ -268,8 +327,17 @@ impl <P: BlockProcessor, X: PaddingProcessor> BlockEngine<P, X> {
                     input.push_to(&mut self.in_scratch);
                     if !input.is_empty() {
                         // !is_empty() guarantees two things - in_scratch is full and its not the
-                        // last block. This state must never process the last block.
-                        process_scratch(self);
+                        // last block. This state must never process the last block. When using
+                        // ciphertext stealing, the second block in in_scratch may only be
+                        // processed if more than a block of input follows it, since it may
+                        // otherwise be one of the final two blocks. Processing it empties
+                        // in_scratch, which lets FastMode take over again.
+                        let blocks = if self.stealing && input.remaining() > self.block_size {
+                            2
+                        } else {
+                            1
+                        };
+                        process_scratch(self, blocks);
                         self.state = BlockEngineState::NeedOutput;
                     } else {
                         if eof {
@@ -287,7 +355,13 @@ impl <P: BlockProcessor, X: PaddingProcessor> BlockEngine<P, X> {
                     rout.push_to(output);
                     if rout.is_empty() {
                         self.out_write_scratch = Some(rout.into_write_buffer());
-                        self.state = BlockEngineState::FastMode;
+                        // When using ciphertext stealing, data that is still held in in_scratch
+                        // must be processed before FastMode can continue with the input.
+                        if self.in_scratch.is_empty() {
+                            self.state = BlockEngineState::FastMode;
+                        } else {
+                            self.state = BlockEngineState::NeedInput;
+                        }
                     } else {
                         self.out_read_scratch = Some(rout);
                         return Ok(BufferOverflow);
@@ -298,6 +372,28 @@ impl <P: BlockProcessor, X: PaddingProcessor> BlockEngine<P, X> {
                 // last block handling is a little tricky due to modes have special needs regarding
                 // padding. When the last block of data is detected, this state is transitioned to
                 // for handling.
+                BlockEngineState::LastInput if self.stealing => {
+                    // Modes that use ciphertext stealing don't use padding. Instead, the final
+                    // one or two blocks of data held in in_scratch are processed together.
+                    let mut wout = self.out_write_scratch.take().unwrap();
+                    let ok = {
+                        let mut rin = self.in_scratch.take_read_buffer();
+                        let last_in = rin.take_remaining();
+                        let last_out = wout.take_next(last_in.len());
+                        last_in.len() >= self.block_size && self.processor.process_final(
+                            &self.in_hist[..],
+                            &self.out_hist[..],
+                            last_in,
+                            last_out)
+                    };
+                    self.out_read_scratch = Some(wout.into_read_buffer());
+                    if ok {
+                        self.state = BlockEngineState::Finished;
+                    } else {
+                        self.state = BlockEngineState::Error(InvalidLength);
+                    }
+                }
+
                 BlockEngineState::LastInput => {
                     // We we arrive in this state, we know that all input data that is going to be
                     // supplied has been suplied and that that data has been written to in_scratch
@@ -327,7 +423,7 @@ impl <P: BlockProcessor, X: PaddingProcessor> BlockEngine<P, X> {
                     if !self.in_scratch.is_full() {
                         self.padding.pad_input(&mut self.in_scratch);
                         if self.in_scratch.is_full() {
-                            process_scratch(self);
+                            process_scratch(self, 1);
                             if self.padding.strip_output(self.out_read_scratch.as_mut().unwrap()) {
                                 self.state = BlockEngineState::Finished;
                             } else {
@@ -339,7 +435,7 @@ impl <P: BlockProcessor, X: PaddingProcessor> BlockEngine<P, X> {
                             self.state = BlockEngineState::Error(InvalidLength);
                         }
                     } else {
-                        process_scratch(self);
+                        process_scratch(self, 1);
                         self.padding.pad_input(&mut self.in_scratch);
                         if self.in_scratch.is_full() {
                             self.state = BlockEngineState::LastInput2;
@@ -363,7 +459,7 @@ impl <P: BlockProcessor, X: PaddingProcessor> BlockEngine<P, X> {
                     rout.push_to(output);
                     if rout.is_empty() {
                         self.out_write_scratch = Some(rout.into_write_buffer());
-                        process_scratch(self);
+                        process_scratch(self, 1);
                         if self.padding.strip_output(self.out_read_scratch.as_mut().unwrap()) {
                             self.state = BlockEngineState::Finished;
                         } else {
@@ -460,6 +556,131 @@ impl PaddingProcessor for PkcsPadding {
     }
 }
 
+/// ISO/IEC 7816-4 padding mode for ECB and CBC encryption. A single 0x80 byte is added, followed
+/// by as many zero bytes as are needed to fill the block.
+#[derive(Clone, Copy)]
+pub struct Iso7816Padding;
+
+impl PaddingProcessor for Iso7816Padding {
+    fn pad_input<W: WriteBuffer>(&mut self, input_buffer: &mut W) {
+        let rem = input_buffer.remaining();
+        assert!(rem != 0);
+        let padding = input_buffer.take_remaining();
+        padding[0] = 0x80;
+        for v in padding[1..].iter_mut() {
+            *v = 0;
+        }
+    }
+    fn strip_output<R: ReadBuffer>(&mut self, output_buffer: &mut R) -> bool {
+        let pad_len = {
+            let data = output_buffer.peek_remaining();
+            match data.iter().rposition(|&x| x != 0) {
+                Some(i) if data[i] == 0x80 => data.len() - i,
+                _ => return false
+            }
+        };
+        output_buffer.truncate(pad_len);
+        true
+    }
+}
+
+/// ANSI X9.23 padding mode for ECB and CBC encryption. Zero bytes are added, with the final byte
+/// giving the number of bytes of padding.
+#[derive(Clone, Copy)]
+pub struct AnsiX923Padding;
+
+impl PaddingProcessor for AnsiX923Padding {
+    fn pad_input<W: WriteBuffer>(&mut self, input_buffer: &mut W) {
+        let rem = input_buffer.remaining();
+        assert!(rem != 0 && rem <= 255);
+        let padding = input_buffer.take_remaining();
+        for v in padding.iter_mut() {
+            *v = 0;
+        }
+        padding[rem - 1] = rem as u8;
+    }
+    fn strip_output<R: ReadBuffer>(&mut self, output_buffer: &mut R) -> bool {
+        let pad_len = {
+            let data = output_buffer.peek_remaining();
+            let pad_len = *data.last().unwrap() as usize;
+            if pad_len == 0 || pad_len > data.len() {
+                return false;
+            }
+            if data[data.len() - pad_len..data.len() - 1].iter().any(|&x| x != 0) {
+                return false;
+            }
+            pad_len
+        };
+        output_buffer.truncate(pad_len);
+        true
+    }
+}
+
+/// ISO 10126 padding mode for ECB and CBC encryption. Random bytes are added, with the final byte
+/// giving the number of bytes of padding. The random bytes are read from the operating system's
+/// random number generator.
+pub struct Iso10126Padding {
+    rng: OsRng
+}
+
+impl Iso10126Padding {
+    /// Create a new ISO 10126 padding mode. This fails if the operating system's random number
+    /// generator can't be opened.
+    pub fn new() -> io::Result<Iso10126Padding> {
+        let rng = try!(OsRng::new());
+        Ok(Iso10126Padding {
+            rng: rng
+        })
+    }
+}
+
+impl PaddingProcessor for Iso10126Padding {
+    fn pad_input<W: WriteBuffer>(&mut self, input_buffer: &mut W) {
+        let rem = input_buffer.remaining();
+        assert!(rem != 0 && rem <= 255);
+        let padding = input_buffer.take_remaining();
+        self.rng.fill_bytes(&mut padding[..rem - 1]);
+        padding[rem - 1] = rem as u8;
+    }
+    fn strip_output<R: ReadBuffer>(&mut self, output_buffer: &mut R) -> bool {
+        let pad_len = *output_buffer.peek_remaining().last().unwrap() as usize;
+        if pad_len == 0 || pad_len > output_buffer.remaining() {
+            return false;
+        }
+        output_buffer.truncate(pad_len);
+        true
+    }
+}
+
+/// Zero padding mode for ECB and CBC encryption. Zero bytes are added to fill the final block,
+/// and no padding is added if the input is a multiple of the block size. Since trailing zeros
+/// in the final block are removed on decryption, this mode can only be used for data that does
+/// not end with a zero byte.
+#[derive(Clone, Copy)]
+pub struct ZeroPadding;
+
+impl PaddingProcessor for ZeroPadding {
+    fn pad_input<W: WriteBuffer>(&mut self, input_buffer: &mut W) {
+        if !input_buffer.is_empty() {
+            for v in input_buffer.take_remaining().iter_mut() {
+                *v = 0;
+            }
+        }
+    }
+    fn strip_output<R: ReadBuffer>(&mut self, output_buffer: &mut R) -> bool {
+        let pad_len = {
+            let data = output_buffer.peek_remaining();
+            data.iter().rev().take_while(|&&x| x == 0).count()
+        };
+        // Padding never fills a whole block
+        if pad_len == output_buffer.remaining() {
+            return false;
+        }
+        output_buffer.truncate(pad_len);
+        true
+    }
+}
+
 /// Wraps a PaddingProcessor so that only pad_input() will actually be called.
 pub struct EncPadding<X> {
     padding: X
@@ -488,6 +709,28 @@ impl <X: PaddingProcessor> PaddingProcessor for DecPadding<X> {
     fn strip_output<R: ReadBuffer>(&mut self, a: &mut R) -> bool { self.padding.strip_output(a) }
 }
 
+// Process input, which is a multiple of block_size bytes long, 8 blocks at a time with the given
+// function. If the final group is shorter than 8 blocks, it is padded out using the in_buff and
+// out_buff scratch buffers.
+fn process_blocks_x8<F>(
+        process: F,
+        block_size: usize,
+        in_buff: &mut [u8],
+        out_buff: &mut [u8],
+        input: &[u8],
+        output: &mut [u8]) where F: Fn(&[u8], &mut [u8]) {
+    let x8_size = 8 * block_size;
+    for (in_x8, out_x8) in input.chunks(x8_size).zip(output.chunks_mut(x8_size)) {
+        if in_x8.len() == x8_size {
+            process(in_x8, out_x8);
+        } else {
+            cryptoutil::copy_memory(in_x8, in_buff);
+            process(in_buff, out_buff);
+            cryptoutil::copy_memory(&out_buff[..out_x8.len()], out_x8);
+        }
+    }
+}
+
 struct EcbEncryptorProcessor<T> {
     algo: T
 }
@@ -526,6 +769,66 @@ impl <T: BlockEncryptor, X: PaddingProcessor> Encryptor for EcbEncryptor<T, X> {
     }
 }
 
+struct EcbEncryptorX8Processor<T> {
+    algo: T,
+    in_buff: Vec<u8>,
+    out_buff: Vec<u8>
+}
+
+impl <T: BlockEncryptorX8> BlockProcessor for EcbEncryptorX8Processor<T> {
+    fn process_block(&mut self, in_hist: &[u8], out_hist: &[u8], input: &[u8], output: &mut [u8]) {
+        let block_size = input.len();
+        self.process_blocks(block_size, in_hist, out_hist, input, output);
+    }
+
+    fn process_blocks(
+            &mut self,
+            block_size: usize,
+            _: &[u8],
+            _: &[u8],
+            input: &[u8],
+            output: &mut [u8]) {
+        let algo = &self.algo;
+        process_blocks_x8(
+            |i, o| algo.encrypt_block_x8(i, o),
+            block_size,
+            &mut self.in_buff,
+            &mut self.out_buff,
+            input,
+            output);
+    }
+}
+
+/// ECB Encryption mode that encrypts 8 blocks at a time
+pub struct EcbEncryptorX8<T, X> {
+    block_engine: BlockEngine<EcbEncryptorX8Processor<T>, X>
+}
+
+impl <T: BlockEncryptorX8, X: PaddingProcessor> EcbEncryptorX8<T, X> {
+    /// Create a new ECB encryption mode object that encrypts 8 blocks at a time
+    pub fn new(algo: T, padding: X) -> EcbEncryptorX8<T, EncPadding<X>> {
+        let block_size = algo.block_size();
+        let processor = EcbEncryptorX8Processor {
+            algo: algo,
+            in_buff: repeat(0).take(8 * block_size).collect(),
+            out_buff: repeat(0).take(8 * block_size).collect()
+        };
+        EcbEncryptorX8 {
+            block_engine: BlockEngine::new(processor, EncPadding::wrap(padding), block_size)
+        }
+    }
+    pub fn reset(&mut self) {
+        self.block_engine.reset();
+    }
+}
+
+impl <T: BlockEncryptorX8, X: PaddingProcessor> Encryptor for EcbEncryptorX8<T, X> {
+    fn encrypt(&mut self, input: &mut RefReadBuffer, output: &mut RefWriteBuffer, eof: bool)
+            -> Result<BufferResult, SymmetricCipherError> {
+        self.block_engine.process(input, output, eof)
+    }
+}
+
 struct EcbDecryptorProcessor<T> {
     algo: T
 }
@@ -564,6 +867,66 @@ impl <T: BlockDecryptor, X: PaddingProcessor> Decryptor for EcbDecryptor<T, X> {
     }
 }
 
+struct EcbDecryptorX8Processor<T> {
+    algo: T,
+    in_buff: Vec<u8>,
+    out_buff: Vec<u8>
+}
+
+impl <T: BlockDecryptorX8> BlockProcessor for EcbDecryptorX8Processor<T> {
+    fn process_block(&mut self, in_hist: &[u8], out_hist: &[u8], input: &[u8], output: &mut [u8]) {
+        let block_size = input.len();
+        self.process_blocks(block_size, in_hist, out_hist, input, output);
+    }
+
+    fn process_blocks(
+            &mut self,
+            block_size: usize,
+            _: &[u8],
+            _: &[u8],
+            input: &[u8],
+            output: &mut [u8]) {
+        let algo = &self.algo;
+        process_blocks_x8(
+            |i, o| algo.decrypt_block_x8(i, o),
+            block_size,
+            &mut self.in_buff,
+            &mut self.out_buff,
+            input,
+            output);
+    }
+}
+
+/// ECB Decryption mode that decrypts 8 blocks at a time
+pub struct EcbDecryptorX8<T, X> {
+    block_engine: BlockEngine<EcbDecryptorX8Processor<T>, X>
+}
+
+impl <T: BlockDecryptorX8, X: PaddingProcessor> EcbDecryptorX8<T, X> {
+    /// Create a new ECB decryption mode object that decrypts 8 blocks at a time
+    pub fn new(algo: T, padding: X) -> EcbDecryptorX8<T, DecPadding<X>> {
+        let block_size = algo.block_size();
+        let processor = EcbDecryptorX8Processor {
+            algo: algo,
+            in_buff: repeat(0).take(8 * block_size).collect(),
+            out_buff: repeat(0).take(8 * block_size).collect()
+        };
+        EcbDecryptorX8 {
+            block_engine: BlockEngine::new(processor, DecPadding::wrap(padding), block_size)
+        }
+    }
+    pub fn reset(&mut self) {
+        self.block_engine.reset();
+    }
+}
+
+impl <T: BlockDecryptorX8, X: PaddingProcessor> Decryptor for EcbDecryptorX8<T, X> {
+    fn decrypt(&mut self, input: &mut RefReadBuffer, output: &mut RefWriteBuffer, eof: bool)
+            -> Result<BufferResult, SymmetricCipherError> {
+        self.block_engine.process(input, output, eof)
+    }
+}
+
 struct CbcEncryptorProcessor<T> {
     algo: T,
     temp: Vec<u8>
@@ -660,122 +1023,580 @@ impl <T: BlockDecryptor, X: PaddingProcessor> Decryptor for CbcDecryptor<T, X> {
     }
 }
 
-fn add_ctr(ctr: &mut [u8], mut ammount: u8) {
-    for i in ctr.iter_mut().rev() {
-        let prev = *i;
-        *i = i.wrapping_add(ammount);
-        if *i >= prev {
-            break;
+struct CbcDecryptorX8Processor<T> {
+    algo: T,
+    in_buff: Vec<u8>,
+    out_buff: Vec<u8>
+}
+
+impl <T: BlockDecryptorX8> BlockProcessor for CbcDecryptorX8Processor<T> {
+    fn process_block(&mut self, in_hist: &[u8], out_hist: &[u8], input: &[u8], output: &mut [u8]) {
+        let block_size = input.len();
+        self.process_blocks(block_size, in_hist, out_hist, input, output);
+    }
+
+    fn process_blocks(
+            &mut self,
+            block_size: usize,
+            in_hist: &[u8],
+            _: &[u8],
+            input: &[u8],
+            output: &mut [u8]) {
+        let algo = &self.algo;
+        process_blocks_x8(
+            |i, o| algo.decrypt_block_x8(i, o),
+            block_size,
+            &mut self.in_buff,
+            &mut self.out_buff,
+            input,
+            output);
+
+        // Each block of output is xored with the previous block of ciphertext
+        let prev_blocks = in_hist.iter().chain(input[..input.len() - block_size].iter());
+        for (o, &x) in output.iter_mut().zip(prev_blocks) {
+            *o ^= x;
         }
-        ammount = 1;
     }
 }
 
-/// CTR Mode
-pub struct CtrMode<A> {
-    algo: A,
-    ctr: Vec<u8>,
-    bytes: OwnedReadBuffer
+/// CBC decryption mode that decrypts 8 blocks at a time
+pub struct CbcDecryptorX8<T, X> {
+    block_engine: BlockEngine<CbcDecryptorX8Processor<T>, X>
 }
 
-impl <A: BlockEncryptor> CtrMode<A> {
-    /// Create a new CTR object
-    pub fn new(algo: A, ctr: Vec<u8>) -> CtrMode<A> {
+impl <T: BlockDecryptorX8, X: PaddingProcessor> CbcDecryptorX8<T, X> {
+    /// Create a new CBC decryption mode object that decrypts 8 blocks at a time
+    pub fn new(algo: T, padding: X, iv: Vec<u8>) -> CbcDecryptorX8<T, DecPadding<X>> {
         let block_size = algo.block_size();
-        CtrMode {
+        let processor = CbcDecryptorX8Processor {
             algo: algo,
-            ctr: ctr,
-            bytes: OwnedReadBuffer::new_with_len(repeat(0).take(block_size).collect(), 0)
+            in_buff: repeat(0).take(8 * block_size).collect(),
+            out_buff: repeat(0).take(8 * block_size).collect()
+        };
+        CbcDecryptorX8 {
+            block_engine: BlockEngine::new_with_history(
+                processor,
+                DecPadding::wrap(padding),
+                block_size,
+                iv,
+                Vec::new())
         }
     }
-    pub fn reset(&mut self, ctr: &[u8]) {
-        cryptoutil::copy_memory(ctr, &mut self.ctr);
-        self.bytes.reset();
-    }
-    fn process(&mut self, input: &[u8], output: &mut [u8]) {
-        assert!(input.len() == output.len());
-        let len = input.len();
-        let mut i = 0;
-        while i < len {
-            if self.bytes.is_empty() {
-                let mut wb = self.bytes.borrow_write_buffer();
-                self.algo.encrypt_block(&self.ctr[..], wb.take_remaining());
-                add_ctr(&mut self.ctr, 1);
-            }
-            let count = cmp::min(self.bytes.remaining(), len - i);
-            let bytes_it = self.bytes.take_next(count).iter();
-            let in_it = input[i..].iter();
-            let out_it = output[i..].iter_mut();
-            for ((&x, &y), o) in bytes_it.zip(in_it).zip(out_it) {
-                *o = x ^ y;
-            }
-            i += count;
-        }
+    pub fn reset(&mut self, iv: &[u8]) {
+        self.block_engine.reset_with_history(iv, &[]);
     }
 }
 
-impl <A: BlockEncryptor> SynchronousStreamCipher for CtrMode<A> {
-    fn process(&mut self, input: &[u8], output: &mut [u8]) {
-        self.process(input, output);
+impl <T: BlockDecryptorX8, X: PaddingProcessor> Decryptor for CbcDecryptorX8<T, X> {
+    fn decrypt(&mut self, input: &mut RefReadBuffer, output: &mut RefWriteBuffer, eof: bool)
+            -> Result<BufferResult, SymmetricCipherError> {
+        self.block_engine.process(input, output, eof)
     }
 }
 
-impl <A: BlockEncryptor> Encryptor for CtrMode<A> {
-    fn encrypt(&mut self, input: &mut RefReadBuffer, output: &mut RefWriteBuffer, _: bool)
-            -> Result<BufferResult, SymmetricCipherError> {
-        symm_enc_or_dec(self, input, output)
-    }
+/// The variants of CBC mode with ciphertext stealing from the addendum to NIST SP 800-38A. They
+/// differ only in the order of the final two blocks of ciphertext.
+#[derive(Clone, Copy)]
+pub enum CtsVariant {
+    /// The final partial block follows the block before it, so that the ciphertext is a prefix
+    /// of the padded CBC ciphertext.
+    Cs1,
+    /// The final two blocks are swapped, unless the input is a multiple of the block size, in
+    /// which case the ciphertext is the same as for CBC.
+    Cs2,
+    /// The final two blocks are always swapped. This is the variant used by Kerberos.
+    Cs3
 }
 
-impl <A: BlockEncryptor> Decryptor for CtrMode<A> {
-    fn decrypt(&mut self, input: &mut RefReadBuffer, output: &mut RefWriteBuffer, _: bool)
-            -> Result<BufferResult, SymmetricCipherError> {
-        symm_enc_or_dec(self, input, output)
+impl CtsVariant {
+    // Whether the final full block and the final partial block are stored in reverse order for
+    // a final partial block of the given length.
+    fn swapped(&self, partial_len: usize, block_size: usize) -> bool {
+        match *self {
+            CtsVariant::Cs1 => false,
+            CtsVariant::Cs2 => partial_len != block_size,
+            CtsVariant::Cs3 => true
+        }
     }
 }
 
-/// CTR Mode that operates on 8 blocks at a time
-pub struct CtrModeX8<A> {
-    algo: A,
-    ctr_x8: Vec<u8>,
-    bytes: OwnedReadBuffer
+struct CbcCtsEncryptorProcessor<T> {
+    cbc: CbcEncryptorProcessor<T>,
+    variant: CtsVariant
 }
 
-fn construct_ctr_x8(in_ctr: &[u8], out_ctr_x8: &mut [u8]) {
-    for (i, ctr_i) in out_ctr_x8.chunks_mut(in_ctr.len()).enumerate() {
-        cryptoutil::copy_memory(in_ctr, ctr_i);
-        add_ctr(ctr_i, i as u8);
+impl <T: BlockEncryptor> BlockProcessor for CbcCtsEncryptorProcessor<T> {
+    fn process_block(&mut self, in_hist: &[u8], out_hist: &[u8], input: &[u8], output: &mut [u8]) {
+        self.cbc.process_block(in_hist, out_hist, input, output);
     }
-}
+    fn process_final(&mut self, in_hist: &[u8], out_hist: &[u8], input: &[u8], output: &mut [u8])
+            -> bool {
+        let block_size = self.cbc.temp.len();
+        if input.len() == block_size {
+            self.cbc.process_block(in_hist, out_hist, input, output);
+            return true;
+        }
 
-impl <A: BlockEncryptorX8> CtrModeX8<A> {
-    /// Create a new CTR object that operates on 8 blocks at a time
-    pub fn new(algo: A, ctr: &[u8]) -> CtrModeX8<A> {
-        let block_size = algo.block_size();
-        let mut ctr_x8: Vec<u8> = repeat(0).take(block_size * 8).collect();
-        construct_ctr_x8(ctr, &mut ctr_x8);
-        CtrModeX8 {
-            algo: algo,
-            ctr_x8: ctr_x8,
-            bytes: OwnedReadBuffer::new_with_len(repeat(0).take(block_size * 8).collect(), 0)
+        // Encrypt the last full block and then the final partial block, padded with zeros, as in
+        // CBC mode. The final block is then written in full but only as much of the block before
+        // it as is needed to make the output the same length as the input.
+        let partial_len = input.len() - block_size;
+        let mut last_full: Vec<u8> = repeat(0).take(block_size).collect();
+        let mut last: Vec<u8> = repeat(0).take(block_size).collect();
+        let mut padded: Vec<u8> = repeat(0).take(block_size).collect();
+        self.cbc.process_block(in_hist, out_hist, &input[..block_size], &mut last_full);
+        cryptoutil::copy_memory(&input[block_size..], &mut padded);
+        self.cbc.process_block(in_hist, &last_full, &padded, &mut last);
+
+        if self.variant.swapped(partial_len, block_size) {
+            cryptoutil::copy_memory(&last, &mut output[..block_size]);
+            cryptoutil::copy_memory(&last_full[..partial_len], &mut output[block_size..]);
+        } else {
+            cryptoutil::copy_memory(&last_full[..partial_len], &mut output[..partial_len]);
+            cryptoutil::copy_memory(&last, &mut output[partial_len..]);
+        }
+        true
+    }
+}
+
+/// CBC encryption mode with ciphertext stealing. The ciphertext is the same length as the
+/// plaintext, which must be at least one block long.
+pub struct CbcCtsEncryptor<T> {
+    block_engine: BlockEngine<CbcCtsEncryptorProcessor<T>, NoPadding>
+}
+
+impl <T: BlockEncryptor> CbcCtsEncryptor<T> {
+    /// Create a new CBC encryption mode object that uses ciphertext stealing
+    pub fn new(algo: T, variant: CtsVariant, iv: Vec<u8>) -> CbcCtsEncryptor<T> {
+        let block_size = algo.block_size();
+        let processor = CbcCtsEncryptorProcessor {
+            cbc: CbcEncryptorProcessor {
+                algo: algo,
+                temp: repeat(0).take(block_size).collect()
+            },
+            variant: variant
+        };
+        CbcCtsEncryptor {
+            block_engine: BlockEngine::new_with_stealing(
+                processor,
+                NoPadding,
+                block_size,
+                Vec::new(),
+                iv)
+        }
+    }
+    pub fn reset(&mut self, iv: &[u8]) {
+        self.block_engine.reset_with_history(&[], iv);
+    }
+}
+
+impl <T: BlockEncryptor> Encryptor for CbcCtsEncryptor<T> {
+    fn encrypt(&mut self, input: &mut RefReadBuffer, output: &mut RefWriteBuffer, eof: bool)
+            -> Result<BufferResult, SymmetricCipherError> {
+        self.block_engine.process(input, output, eof)
+    }
+}
+
+struct CbcCtsDecryptorProcessor<T> {
+    cbc: CbcDecryptorProcessor<T>,
+    variant: CtsVariant
+}
+
+impl <T: BlockDecryptor> BlockProcessor for CbcCtsDecryptorProcessor<T> {
+    fn process_block(&mut self, in_hist: &[u8], out_hist: &[u8], input: &[u8], output: &mut [u8]) {
+        self.cbc.process_block(in_hist, out_hist, input, output);
+    }
+    fn process_final(&mut self, in_hist: &[u8], out_hist: &[u8], input: &[u8], output: &mut [u8])
+            -> bool {
+        let block_size = self.cbc.temp.len();
+        if input.len() == block_size {
+            self.cbc.process_block(in_hist, out_hist, input, output);
+            return true;
+        }
+
+        let partial_len = input.len() - block_size;
+        let (last, partial) = if self.variant.swapped(partial_len, block_size) {
+            (&input[..block_size], &input[block_size..])
+        } else {
+            (&input[partial_len..], &input[..partial_len])
+        };
+
+        // Decrypting the final block gives the final partial plaintext block, padded with zeros,
+        // xored with the ciphertext block before it. That recovers the stolen end of the
+        // ciphertext block, which can then be decrypted in the usual way.
+        let mut last_full: Vec<u8> = repeat(0).take(block_size).collect();
+        let mut decrypted: Vec<u8> = repeat(0).take(block_size).collect();
+        self.cbc.algo.decrypt_block(last, &mut decrypted);
+        cryptoutil::copy_memory(partial, &mut last_full);
+        cryptoutil::copy_memory(&decrypted[partial_len..], &mut last_full[partial_len..]);
+        for ((o, &x), &y) in output[block_size..].iter_mut().zip(decrypted.iter()).zip(partial) {
+            *o = x ^ y;
+        }
+        self.cbc.process_block(in_hist, out_hist, &last_full, &mut output[..block_size]);
+        true
+    }
+}
+
+/// CBC decryption mode with ciphertext stealing
+pub struct CbcCtsDecryptor<T> {
+    block_engine: BlockEngine<CbcCtsDecryptorProcessor<T>, NoPadding>
+}
+
+impl <T: BlockDecryptor> CbcCtsDecryptor<T> {
+    /// Create a new CBC decryption mode object that uses ciphertext stealing
+    pub fn new(algo: T, variant: CtsVariant, iv: Vec<u8>) -> CbcCtsDecryptor<T> {
+        let block_size = algo.block_size();
+        let processor = CbcCtsDecryptorProcessor {
+            cbc: CbcDecryptorProcessor {
+                algo: algo,
+                temp: repeat(0).take(block_size).collect()
+            },
+            variant: variant
+        };
+        CbcCtsDecryptor {
+            block_engine: BlockEngine::new_with_stealing(
+                processor,
+                NoPadding,
+                block_size,
+                iv,
+                Vec::new())
+        }
+    }
+    pub fn reset(&mut self, iv: &[u8]) {
+        self.block_engine.reset_with_history(iv, &[]);
+    }
+}
+
+impl <T: BlockDecryptor> Decryptor for CbcCtsDecryptor<T> {
+    fn decrypt(&mut self, input: &mut RefReadBuffer, output: &mut RefWriteBuffer, eof: bool)
+            -> Result<BufferResult, SymmetricCipherError> {
+        self.block_engine.process(input, output, eof)
+    }
+}
+
+struct IgeEncryptorProcessor<T> {
+    algo: T,
+    temp: Vec<u8>
+}
+
+impl <T: BlockEncryptor> BlockProcessor for IgeEncryptorProcessor<T> {
+    fn process_block(&mut self, in_hist: &[u8], out_hist: &[u8], input: &[u8], output: &mut [u8]) {
+        for ((&x, &y), o) in input.iter().zip(out_hist.iter()).zip(self.temp.iter_mut()) {
+            *o = x ^ y;
+        }
+        self.algo.encrypt_block(&self.temp[..], output);
+        for (o, &y) in output.iter_mut().zip(in_hist.iter()) {
+            *o ^= y;
+        }
+    }
+}
+
+/// IGE (Infinite Garble Extension) encryption mode. The IV is two blocks long - the first block is
+/// used as the previous ciphertext block and the second as the previous plaintext block, which is
+/// the same layout that OpenSSL's AES_ige_encrypt uses.
+pub struct IgeEncryptor<T, X> {
+    block_engine: BlockEngine<IgeEncryptorProcessor<T>, X>
+}
+
+impl <T: BlockEncryptor, X: PaddingProcessor> IgeEncryptor<T, X> {
+    /// Create a new IGE encryption mode object
+    pub fn new(algo: T, padding: X, iv: Vec<u8>) -> IgeEncryptor<T, EncPadding<X>> {
+        let block_size = algo.block_size();
+        assert!(iv.len() == 2 * block_size);
+        let processor = IgeEncryptorProcessor {
+            algo: algo,
+            temp: repeat(0).take(block_size).collect()
+        };
+        IgeEncryptor {
+            block_engine: BlockEngine::new_with_history(
+                processor,
+                EncPadding::wrap(padding),
+                block_size,
+                iv[block_size..].to_vec(),
+                iv[..block_size].to_vec())
+        }
+    }
+    pub fn reset(&mut self, iv: &[u8]) {
+        let block_size = self.block_engine.block_size;
+        assert!(iv.len() == 2 * block_size);
+        self.block_engine.reset_with_history(&iv[block_size..], &iv[..block_size]);
+    }
+}
+
+impl <T: BlockEncryptor, X: PaddingProcessor> Encryptor for IgeEncryptor<T, X> {
+    fn encrypt(&mut self, input: &mut RefReadBuffer, output: &mut RefWriteBuffer, eof: bool)
+            -> Result<BufferResult, SymmetricCipherError> {
+        self.block_engine.process(input, output, eof)
+    }
+}
+
+struct IgeDecryptorProcessor<T> {
+    algo: T,
+    temp: Vec<u8>
+}
+
+impl <T: BlockDecryptor> BlockProcessor for IgeDecryptorProcessor<T> {
+    fn process_block(&mut self, in_hist: &[u8], out_hist: &[u8], input: &[u8], output: &mut [u8]) {
+        for ((&x, &y), o) in input.iter().zip(out_hist.iter()).zip(self.temp.iter_mut()) {
+            *o = x ^ y;
+        }
+        self.algo.decrypt_block(&self.temp[..], output);
+        for (o, &y) in output.iter_mut().zip(in_hist.iter()) {
+            *o ^= y;
+        }
+    }
+}
+
+/// IGE (Infinite Garble Extension) decryption mode. The IV uses the same layout as for
+/// IgeEncryptor.
+pub struct IgeDecryptor<T, X> {
+    block_engine: BlockEngine<IgeDecryptorProcessor<T>, X>
+}
+
+impl <T: BlockDecryptor, X: PaddingProcessor> IgeDecryptor<T, X> {
+    /// Create a new IGE decryption mode object
+    pub fn new(algo: T, padding: X, iv: Vec<u8>) -> IgeDecryptor<T, DecPadding<X>> {
+        let block_size = algo.block_size();
+        assert!(iv.len() == 2 * block_size);
+        let processor = IgeDecryptorProcessor {
+            algo: algo,
+            temp: repeat(0).take(block_size).collect()
+        };
+        IgeDecryptor {
+            block_engine: BlockEngine::new_with_history(
+                processor,
+                DecPadding::wrap(padding),
+                block_size,
+                iv[..block_size].to_vec(),
+                iv[block_size..].to_vec())
+        }
+    }
+    pub fn reset(&mut self, iv: &[u8]) {
+        let block_size = self.block_engine.block_size;
+        assert!(iv.len() == 2 * block_size);
+        self.block_engine.reset_with_history(&iv[..block_size], &iv[block_size..]);
+    }
+}
+
+impl <T: BlockDecryptor, X: PaddingProcessor> Decryptor for IgeDecryptor<T, X> {
+    fn decrypt(&mut self, input: &mut RefReadBuffer, output: &mut RefWriteBuffer, eof: bool)
+            -> Result<BufferResult, SymmetricCipherError> {
+        self.block_engine.process(input, output, eof)
+    }
+}
+
+// Add an amount to a big endian counter, wrapping around on overflow. Returns false if the counter
+// wrapped.
+fn add_ctr(ctr: &mut [u8], ammount: u64) -> bool {
+    let mut carry = ammount;
+    for i in ctr.iter_mut().rev() {
+        if carry == 0 {
+            return true;
+        }
+        let sum = *i as u64 + (carry & 0xff);
+        *i = sum as u8;
+        carry = (carry >> 8) + (sum >> 8);
+    }
+    carry == 0
+}
+
+// Check the size of the counter field and return the offset at which it starts
+fn counter_start(block_size: usize, counter_size: usize) -> usize {
+    assert!(counter_size > 0 && counter_size <= block_size);
+    block_size - counter_size
+}
+
+// The number of bytes of key stream that can be produced before every value of the counter field
+// has been used and the key stream would repeat. Limits that don't fit in a u64 are never reached.
+fn key_stream_limit(block_size: usize, counter_size: usize) -> u64 {
+    if counter_size < 8 {
+        (1u64 << (8 * counter_size)).saturating_mul(block_size as u64)
+    } else {
+        u64::MAX
+    }
+}
+
+/// CTR Mode
+pub struct CtrMode<A> {
+    algo: A,
+    start: Vec<u8>,
+    ctr: Vec<u8>,
+    counter_start: usize,
+    bytes: OwnedReadBuffer,
+    limit: u64,
+    // The number of bytes of key stream left before the counter is exhausted
+    remaining: u64
+}
+
+impl <A: BlockEncryptor> CtrMode<A> {
+    /// Create a new CTR object. The whole counter block is incremented as a single big endian
+    /// integer.
+    pub fn new(algo: A, ctr: Vec<u8>) -> CtrMode<A> {
+        let block_size = algo.block_size();
+        CtrMode::new_with_counter_size(algo, ctr, block_size)
+    }
+    /// Create a new CTR object where only the last counter_size bytes of the counter block are
+    /// incremented and the rest is left unchanged, e.g. 4 for the 32 bit counter used by GCM. The
+    /// counter wraps around within those bytes, but processing panics rather than produce more
+    /// than 2^(8 * counter_size) blocks of key stream, after which it would repeat.
+    pub fn new_with_counter_size(algo: A, ctr: Vec<u8>, counter_size: usize) -> CtrMode<A> {
+        let block_size = algo.block_size();
+        assert!(ctr.len() == block_size);
+        let limit = key_stream_limit(block_size, counter_size);
+        CtrMode {
+            algo: algo,
+            start: ctr.clone(),
+            ctr: ctr,
+            counter_start: counter_start(block_size, counter_size),
+            bytes: OwnedReadBuffer::new_with_len(repeat(0).take(block_size).collect(), 0),
+            limit: limit,
+            remaining: limit
+        }
+    }
+    pub fn reset(&mut self, ctr: &[u8]) {
+        cryptoutil::copy_memory(ctr, &mut self.start);
+        cryptoutil::copy_memory(ctr, &mut self.ctr);
+        self.remaining = self.limit;
+        // Discard any unused key stream
+        self.bytes.borrow_write_buffer();
+    }
+    fn next_block(&mut self) {
+        let mut wb = self.bytes.borrow_write_buffer();
+        self.algo.encrypt_block(&self.ctr[..], wb.take_remaining());
+        add_ctr(&mut self.ctr[self.counter_start..], 1);
+    }
+    fn process(&mut self, input: &[u8], output: &mut [u8]) {
+        assert!(input.len() == output.len());
+        let len = input.len();
+        assert!(len as u64 <= self.remaining, "CTR counter exhausted");
+        self.remaining -= len as u64;
+        let mut i = 0;
+        while i < len {
+            if self.bytes.is_empty() {
+                self.next_block();
+            }
+            let count = cmp::min(self.bytes.remaining(), len - i);
+            let bytes_it = self.bytes.take_next(count).iter();
+            let in_it = input[i..].iter();
+            let out_it = output[i..].iter_mut();
+            for ((&x, &y), o) in bytes_it.zip(in_it).zip(out_it) {
+                *o = x ^ y;
+            }
+            i += count;
+        }
+    }
+}
+
+impl <A: BlockEncryptor> SynchronousStreamCipher for CtrMode<A> {
+    fn process(&mut self, input: &[u8], output: &mut [u8]) {
+        self.process(input, output);
+    }
+}
+
+/// Seeking to an offset whose block counter doesn't fit in the counter field without wrapping
+/// around fails with SeekError::InvalidOffset.
+impl <A: BlockEncryptor> SeekableStreamCipher for CtrMode<A> {
+    fn seek(&mut self, byte_offset: u64) -> Result<(), SeekError> {
+        let block_size = self.algo.block_size() as u64;
+        let mut ctr = self.start.clone();
+        if !add_ctr(&mut ctr[self.counter_start..], byte_offset / block_size) {
+            return Err(SeekError::InvalidOffset);
+        }
+        self.ctr = ctr;
+        self.remaining = self.limit.saturating_sub(byte_offset);
+        self.bytes.borrow_write_buffer();
+        let skip = (byte_offset % block_size) as usize;
+        if skip > 0 {
+            self.next_block();
+            self.bytes.take_next(skip);
+        }
+        Ok(())
+    }
+}
+
+impl <A: BlockEncryptor> Encryptor for CtrMode<A> {
+    fn encrypt(&mut self, input: &mut RefReadBuffer, output: &mut RefWriteBuffer, _: bool)
+            -> Result<BufferResult, SymmetricCipherError> {
+        symm_enc_or_dec(self, input, output)
+    }
+}
+
+impl <A: BlockEncryptor> Decryptor for CtrMode<A> {
+    fn decrypt(&mut self, input: &mut RefReadBuffer, output: &mut RefWriteBuffer, _: bool)
+            -> Result<BufferResult, SymmetricCipherError> {
+        symm_enc_or_dec(self, input, output)
+    }
+}
+
+/// CTR Mode that operates on 8 blocks at a time
+pub struct CtrModeX8<A> {
+    algo: A,
+    start: Vec<u8>,
+    ctr_x8: Vec<u8>,
+    counter_start: usize,
+    bytes: OwnedReadBuffer,
+    limit: u64,
+    // The number of bytes of key stream left before the counter is exhausted
+    remaining: u64
+}
+
+fn construct_ctr_x8(in_ctr: &[u8], counter_start: usize, out_ctr_x8: &mut [u8]) {
+    for (i, ctr_i) in out_ctr_x8.chunks_mut(in_ctr.len()).enumerate() {
+        cryptoutil::copy_memory(in_ctr, ctr_i);
+        add_ctr(&mut ctr_i[counter_start..], i as u64);
+    }
+}
+
+impl <A: BlockEncryptorX8> CtrModeX8<A> {
+    /// Create a new CTR object that operates on 8 blocks at a time
+    pub fn new(algo: A, ctr: &[u8]) -> CtrModeX8<A> {
+        let block_size = algo.block_size();
+        CtrModeX8::new_with_counter_size(algo, ctr, block_size)
+    }
+    /// Create a new CTR object that operates on 8 blocks at a time and only increments the last
+    /// counter_size bytes of the counter block. As with CtrMode, processing panics rather than
+    /// produce more than 2^(8 * counter_size) blocks of key stream.
+    pub fn new_with_counter_size(algo: A, ctr: &[u8], counter_size: usize) -> CtrModeX8<A> {
+        let block_size = algo.block_size();
+        assert!(ctr.len() == block_size);
+        let counter_start = counter_start(block_size, counter_size);
+        let mut ctr_x8: Vec<u8> = repeat(0).take(block_size * 8).collect();
+        construct_ctr_x8(ctr, counter_start, &mut ctr_x8);
+        let limit = key_stream_limit(block_size, counter_size);
+        CtrModeX8 {
+            algo: algo,
+            start: ctr.to_vec(),
+            ctr_x8: ctr_x8,
+            counter_start: counter_start,
+            bytes: OwnedReadBuffer::new_with_len(repeat(0).take(block_size * 8).collect(), 0),
+            limit: limit,
+            remaining: limit
+        }
+    }
+    pub fn reset(&mut self, ctr: &[u8]) {
+        cryptoutil::copy_memory(ctr, &mut self.start);
+        construct_ctr_x8(ctr, self.counter_start, &mut self.ctr_x8);
+        self.remaining = self.limit;
+        // Discard any unused key stream
+        self.bytes.borrow_write_buffer();
+    }
+    fn next_blocks(&mut self) {
+        let mut wb = self.bytes.borrow_write_buffer();
+        self.algo.encrypt_block_x8(&self.ctr_x8[..], wb.take_remaining());
+        for ctr_i in &mut self.ctr_x8.chunks_mut(self.algo.block_size()) {
+            add_ctr(&mut ctr_i[self.counter_start..], 8);
         }
     }
-    pub fn reset(&mut self, ctr: &[u8]) {
-        construct_ctr_x8(ctr, &mut self.ctr_x8);
-        self.bytes.reset();
-    }
     fn process(&mut self, input: &[u8], output: &mut [u8]) {
         // TODO - Can some of this be combined with regular CtrMode?
         assert!(input.len() == output.len());
         let len = input.len();
+        assert!(len as u64 <= self.remaining, "CTR counter exhausted");
+        self.remaining -= len as u64;
         let mut i = 0;
         while i < len {
             if self.bytes.is_empty() {
-                let mut wb = self.bytes.borrow_write_buffer();
-                self.algo.encrypt_block_x8(&self.ctr_x8[..], wb.take_remaining());
-                for ctr_i in &mut self.ctr_x8.chunks_mut(self.algo.block_size()) {
-                    add_ctr(ctr_i, 8);
-                }
+                self.next_blocks();
             }
             let count = cmp::min(self.bytes.remaining(), len - i);
             let bytes_it = self.bytes.take_next(count).iter();
@@ -795,14 +1616,243 @@ impl <A: BlockEncryptorX8> SynchronousStreamCipher for CtrModeX8<A> {
     }
 }
 
-impl <A: BlockEncryptorX8> Encryptor for CtrModeX8<A> {
+/// Seeking to an offset whose block counter doesn't fit in the counter field without wrapping
+/// around fails with SeekError::InvalidOffset.
+impl <A: BlockEncryptorX8> SeekableStreamCipher for CtrModeX8<A> {
+    fn seek(&mut self, byte_offset: u64) -> Result<(), SeekError> {
+        let block_size = self.algo.block_size() as u64;
+        let mut ctr = self.start.clone();
+        if !add_ctr(&mut ctr[self.counter_start..], byte_offset / block_size) {
+            return Err(SeekError::InvalidOffset);
+        }
+        construct_ctr_x8(&ctr, self.counter_start, &mut self.ctr_x8);
+        self.remaining = self.limit.saturating_sub(byte_offset);
+        self.bytes.borrow_write_buffer();
+        let skip = (byte_offset % block_size) as usize;
+        if skip > 0 {
+            self.next_blocks();
+            self.bytes.take_next(skip);
+        }
+        Ok(())
+    }
+}
+
+impl <A: BlockEncryptorX8> Encryptor for CtrModeX8<A> {
+    fn encrypt(&mut self, input: &mut RefReadBuffer, output: &mut RefWriteBuffer, _: bool)
+            -> Result<BufferResult, SymmetricCipherError> {
+        symm_enc_or_dec(self, input, output)
+    }
+}
+
+impl <A: BlockEncryptorX8> Decryptor for CtrModeX8<A> {
+    fn decrypt(&mut self, input: &mut RefReadBuffer, output: &mut RefWriteBuffer, _: bool)
+            -> Result<BufferResult, SymmetricCipherError> {
+        symm_enc_or_dec(self, input, output)
+    }
+}
+
+/// The number of bits of the cipher output used to process each segment of data in CFB mode
+#[derive(Clone, Copy)]
+pub enum CfbSegmentSize {
+    /// CFB-1, which processes one bit at a time
+    Bits1,
+    /// CFB-8, which processes one byte at a time
+    Bits8,
+    /// Full-block CFB, which processes a whole block at a time
+    Block
+}
+
+/// The state shared by CfbEncryptor and CfbDecryptor. The shift register holds the previous
+/// ciphertext and in full-block mode the position records how much of the current keystream block
+/// has been used.
+struct CfbState<A> {
+    algo: A,
+    segment_size: CfbSegmentSize,
+    register: Vec<u8>,
+    keystream: Vec<u8>,
+    pos: usize
+}
+
+impl <A: BlockEncryptor> CfbState<A> {
+    fn new(algo: A, segment_size: CfbSegmentSize, iv: Vec<u8>) -> CfbState<A> {
+        let block_size = algo.block_size();
+        assert!(iv.len() == block_size);
+        CfbState {
+            algo: algo,
+            segment_size: segment_size,
+            register: iv,
+            keystream: repeat(0).take(block_size).collect(),
+            pos: block_size
+        }
+    }
+    fn reset(&mut self, iv: &[u8]) {
+        cryptoutil::copy_memory(iv, &mut self.register);
+        self.pos = self.register.len();
+    }
+    // Shift the register left by either 1 or 8 bits and place the feedback in the vacated bits
+    fn shift_register(&mut self, bits: usize, feedback: u8) {
+        let len = self.register.len();
+        if bits == 8 {
+            for i in 0..len - 1 {
+                self.register[i] = self.register[i + 1];
+            }
+            self.register[len - 1] = feedback;
+        } else {
+            for i in 0..len - 1 {
+                self.register[i] =
+                    (self.register[i] << bits) | (self.register[i + 1] >> (8 - bits));
+            }
+            self.register[len - 1] = (self.register[len - 1] << bits) | feedback;
+        }
+    }
+    fn process(&mut self, input: &[u8], output: &mut [u8], decrypt: bool) {
+        assert!(input.len() == output.len());
+        let block_size = self.register.len();
+        for (&x, o) in input.iter().zip(output.iter_mut()) {
+            match self.segment_size {
+                CfbSegmentSize::Block => {
+                    if self.pos == block_size {
+                        self.algo.encrypt_block(&self.register, &mut self.keystream);
+                        self.pos = 0;
+                    }
+                    *o = x ^ self.keystream[self.pos];
+                    self.register[self.pos] = if decrypt { x } else { *o };
+                    self.pos += 1;
+                }
+                CfbSegmentSize::Bits8 => {
+                    self.algo.encrypt_block(&self.register, &mut self.keystream);
+                    *o = x ^ self.keystream[0];
+                    let feedback = if decrypt { x } else { *o };
+                    self.shift_register(8, feedback);
+                }
+                CfbSegmentSize::Bits1 => {
+                    // Bits are processed starting with the most significant bit of each byte
+                    let mut out = 0u8;
+                    for bit in (0..8).rev() {
+                        self.algo.encrypt_block(&self.register, &mut self.keystream);
+                        let in_bit = (x >> bit) & 1;
+                        let out_bit = in_bit ^ (self.keystream[0] >> 7);
+                        out |= out_bit << bit;
+                        self.shift_register(1, if decrypt { in_bit } else { out_bit });
+                    }
+                    *o = out;
+                }
+            }
+        }
+    }
+}
+
+/// CFB encryption mode
+pub struct CfbEncryptor<A> {
+    state: CfbState<A>
+}
+
+impl <A: BlockEncryptor> CfbEncryptor<A> {
+    /// Create a new CFB encryption mode object
+    pub fn new(algo: A, segment_size: CfbSegmentSize, iv: Vec<u8>) -> CfbEncryptor<A> {
+        CfbEncryptor {
+            state: CfbState::new(algo, segment_size, iv)
+        }
+    }
+    pub fn reset(&mut self, iv: &[u8]) {
+        self.state.reset(iv);
+    }
+}
+
+impl <A: BlockEncryptor> SynchronousStreamCipher for CfbEncryptor<A> {
+    fn process(&mut self, input: &[u8], output: &mut [u8]) {
+        self.state.process(input, output, false);
+    }
+}
+
+impl <A: BlockEncryptor> Encryptor for CfbEncryptor<A> {
+    fn encrypt(&mut self, input: &mut RefReadBuffer, output: &mut RefWriteBuffer, _: bool)
+            -> Result<BufferResult, SymmetricCipherError> {
+        symm_enc_or_dec(self, input, output)
+    }
+}
+
+/// CFB decryption mode. Like encryption, this only uses the forward direction of the cipher.
+pub struct CfbDecryptor<A> {
+    state: CfbState<A>
+}
+
+impl <A: BlockEncryptor> CfbDecryptor<A> {
+    /// Create a new CFB decryption mode object
+    pub fn new(algo: A, segment_size: CfbSegmentSize, iv: Vec<u8>) -> CfbDecryptor<A> {
+        CfbDecryptor {
+            state: CfbState::new(algo, segment_size, iv)
+        }
+    }
+    pub fn reset(&mut self, iv: &[u8]) {
+        self.state.reset(iv);
+    }
+}
+
+impl <A: BlockEncryptor> SynchronousStreamCipher for CfbDecryptor<A> {
+    fn process(&mut self, input: &[u8], output: &mut [u8]) {
+        self.state.process(input, output, true);
+    }
+}
+
+impl <A: BlockEncryptor> Decryptor for CfbDecryptor<A> {
+    fn decrypt(&mut self, input: &mut RefReadBuffer, output: &mut RefWriteBuffer, _: bool)
+            -> Result<BufferResult, SymmetricCipherError> {
+        symm_enc_or_dec(self, input, output)
+    }
+}
+
+/// OFB Mode
+pub struct OfbMode<A> {
+    algo: A,
+    register: Vec<u8>,
+    pos: usize
+}
+
+impl <A: BlockEncryptor> OfbMode<A> {
+    /// Create a new OFB object
+    pub fn new(algo: A, iv: Vec<u8>) -> OfbMode<A> {
+        let block_size = algo.block_size();
+        assert!(iv.len() == block_size);
+        OfbMode {
+            algo: algo,
+            register: iv,
+            pos: block_size
+        }
+    }
+    pub fn reset(&mut self, iv: &[u8]) {
+        cryptoutil::copy_memory(iv, &mut self.register);
+        self.pos = self.register.len();
+    }
+    fn process(&mut self, input: &[u8], output: &mut [u8]) {
+        assert!(input.len() == output.len());
+        let block_size = self.register.len();
+        for (&x, o) in input.iter().zip(output.iter_mut()) {
+            if self.pos == block_size {
+                let prev = self.register.clone();
+                self.algo.encrypt_block(&prev, &mut self.register);
+                self.pos = 0;
+            }
+            *o = x ^ self.register[self.pos];
+            self.pos += 1;
+        }
+    }
+}
+
+impl <A: BlockEncryptor> SynchronousStreamCipher for OfbMode<A> {
+    fn process(&mut self, input: &[u8], output: &mut [u8]) {
+        self.process(input, output);
+    }
+}
+
+impl <A: BlockEncryptor> Encryptor for OfbMode<A> {
     fn encrypt(&mut self, input: &mut RefReadBuffer, output: &mut RefWriteBuffer, _: bool)
             -> Result<BufferResult, SymmetricCipherError> {
         symm_enc_or_dec(self, input, output)
     }
 }
 
-impl <A: BlockEncryptorX8> Decryptor for CtrModeX8<A> {
+impl <A: BlockEncryptor> Decryptor for OfbMode<A> {
     fn decrypt(&mut self, input: &mut RefReadBuffer, output: &mut RefWriteBuffer, _: bool)
             -> Result<BufferResult, SymmetricCipherError> {
         symm_enc_or_dec(self, input, output)
@@ -814,12 +1864,17 @@ mod test {
     use std::iter::repeat;
 
     use aessafe;
-    use blockmodes::{EcbEncryptor, EcbDecryptor, CbcEncryptor, CbcDecryptor, CtrMode, CtrModeX8,
-        NoPadding, PkcsPadding};
+    use blockmodes::{EcbEncryptor, EcbDecryptor, EcbEncryptorX8, EcbDecryptorX8, CbcEncryptor,
+        CbcDecryptor, CbcDecryptorX8, CbcCtsEncryptor, CbcCtsDecryptor, CtsVariant, IgeEncryptor,
+        IgeDecryptor, CtrMode, CtrModeX8, CfbEncryptor, CfbDecryptor, CfbSegmentSize, OfbMode,
+        PaddingProcessor, NoPadding, PkcsPadding, Iso7816Padding, AnsiX923Padding, Iso10126Padding,
+        ZeroPadding};
     use buffer::{ReadBuffer, WriteBuffer, RefReadBuffer, RefWriteBuffer, BufferResult};
     use buffer::BufferResult::{BufferUnderflow, BufferOverflow};
-    use symmetriccipher::{Encryptor, Decryptor};
+    use symmetriccipher::{BlockEncryptor, Encryptor, Decryptor, SynchronousStreamCipher,
+        SeekableStreamCipher};
     use symmetriccipher::SymmetricCipherError::{self, InvalidLength, InvalidPadding};
+    use symmetriccipher::test::test_seek;
 
     use std::cmp;
 
@@ -875,6 +1930,22 @@ mod test {
         }
     }
 
+    struct FeedbackTest {
+        key: Vec<u8>,
+        iv: Vec<u8>,
+        plain: Vec<u8>,
+        cipher: Vec<u8>
+    }
+
+    impl CipherTest for FeedbackTest {
+        fn get_plain<'a>(&'a self) -> &'a [u8] {
+            &self.plain[..]
+        }
+        fn get_cipher<'a>(&'a self) -> &'a [u8] {
+            &self.cipher[..]
+        }
+    }
+
     fn aes_ecb_no_padding_tests() -> Vec<EcbTest> {
         vec![
             EcbTest {
@@ -960,6 +2031,254 @@ mod test {
         ]
     }
 
+    // Generated with OpenSSL
+    fn aes_cbc_cs1_tests() -> Vec<CbcTest> {
+        vec![
+            CbcTest {
+                key: repeat(1).take(16).collect(),
+                iv: repeat(3).take(16).collect(),
+                plain: (0..16).collect(),
+                cipher: vec![
+                    0x45, 0x65, 0x90, 0x73, 0x29, 0xa2, 0xe3, 0xfd,
+                    0x45, 0x84, 0xb7, 0xe9, 0x68, 0xb1, 0xdd, 0x7c ]
+            },
+            CbcTest {
+                key: repeat(1).take(16).collect(),
+                iv: repeat(3).take(16).collect(),
+                plain: (0..17).collect(),
+                cipher: vec![
+                    0x45, 0x79, 0xc8, 0x80, 0x01, 0xc4, 0xdc, 0x2c,
+                    0x7b, 0xc8, 0xdc, 0x96, 0x40, 0xe9, 0x1c, 0xab,
+                    0x1c ]
+            },
+            CbcTest {
+                key: repeat(1).take(16).collect(),
+                iv: repeat(3).take(16).collect(),
+                plain: (0..31).collect(),
+                cipher: vec![
+                    0x45, 0x65, 0x90, 0x73, 0x29, 0xa2, 0xe3, 0xfd,
+                    0x45, 0x84, 0xb7, 0xe9, 0x68, 0xb1, 0xdd, 0x43,
+                    0xba, 0x89, 0xaa, 0x8a, 0xa7, 0x3f, 0x10, 0xdb,
+                    0x0a, 0x0d, 0x29, 0x32, 0x1b, 0xc4, 0x35 ]
+            },
+            CbcTest {
+                key: repeat(1).take(16).collect(),
+                iv: repeat(3).take(16).collect(),
+                plain: (0..32).collect(),
+                cipher: vec![
+                    0x45, 0x65, 0x90, 0x73, 0x29, 0xa2, 0xe3, 0xfd,
+                    0x45, 0x84, 0xb7, 0xe9, 0x68, 0xb1, 0xdd, 0x7c,
+                    0xe9, 0xf3, 0x22, 0x4c, 0x93, 0x4b, 0xa8, 0x5b,
+                    0xc4, 0xe6, 0x52, 0x21, 0x8c, 0xef, 0xec, 0x3c ]
+            },
+            CbcTest {
+                key: repeat(1).take(16).collect(),
+                iv: repeat(3).take(16).collect(),
+                plain: (0..33).collect(),
+                cipher: vec![
+                    0x45, 0x65, 0x90, 0x73, 0x29, 0xa2, 0xe3, 0xfd,
+                    0x45, 0x84, 0xb7, 0xe9, 0x68, 0xb1, 0xdd, 0x7c,
+                    0xe9, 0x5b, 0xee, 0x89, 0x05, 0x9a, 0xf9, 0xb4,
+                    0xbb, 0xee, 0x3e, 0x2a, 0x9a, 0x4e, 0x8a, 0xe2,
+                    0xb2 ]
+            },
+            CbcTest {
+                key: repeat(1).take(16).collect(),
+                iv: repeat(3).take(16).collect(),
+                plain: (0..48).collect(),
+                cipher: vec![
+                    0x45, 0x65, 0x90, 0x73, 0x29, 0xa2, 0xe3, 0xfd,
+                    0x45, 0x84, 0xb7, 0xe9, 0x68, 0xb1, 0xdd, 0x7c,
+                    0xe9, 0xf3, 0x22, 0x4c, 0x93, 0x4b, 0xa8, 0x5b,
+                    0xc4, 0xe6, 0x52, 0x21, 0x8c, 0xef, 0xec, 0x3c,
+                    0xdf, 0x05, 0x9a, 0x38, 0xd0, 0x40, 0xcd, 0x83,
+                    0xab, 0x25, 0x0e, 0xec, 0x53, 0x53, 0x7f, 0xe7 ]
+            }
+        ]
+    }
+
+    // Generated with OpenSSL
+    fn aes_cbc_cs2_tests() -> Vec<CbcTest> {
+        vec![
+            CbcTest {
+                key: repeat(1).take(16).collect(),
+                iv: repeat(3).take(16).collect(),
+                plain: (0..16).collect(),
+                cipher: vec![
+                    0x45, 0x65, 0x90, 0x73, 0x29, 0xa2, 0xe3, 0xfd,
+                    0x45, 0x84, 0xb7, 0xe9, 0x68, 0xb1, 0xdd, 0x7c ]
+            },
+            CbcTest {
+                key: repeat(1).take(16).collect(),
+                iv: repeat(3).take(16).collect(),
+                plain: (0..17).collect(),
+                cipher: vec![
+                    0x79, 0xc8, 0x80, 0x01, 0xc4, 0xdc, 0x2c, 0x7b,
+                    0xc8, 0xdc, 0x96, 0x40, 0xe9, 0x1c, 0xab, 0x1c,
+                    0x45 ]
+            },
+            CbcTest {
+                key: repeat(1).take(16).collect(),
+                iv: repeat(3).take(16).collect(),
+                plain: (0..31).collect(),
+                cipher: vec![
+                    0x43, 0xba, 0x89, 0xaa, 0x8a, 0xa7, 0x3f, 0x10,
+                    0xdb, 0x0a, 0x0d, 0x29, 0x32, 0x1b, 0xc4, 0x35,
+                    0x45, 0x65, 0x90, 0x73, 0x29, 0xa2, 0xe3, 0xfd,
+                    0x45, 0x84, 0xb7, 0xe9, 0x68, 0xb1, 0xdd ]
+            },
+            CbcTest {
+                key: repeat(1).take(16).collect(),
+                iv: repeat(3).take(16).collect(),
+                plain: (0..32).collect(),
+                cipher: vec![
+                    0x45, 0x65, 0x90, 0x73, 0x29, 0xa2, 0xe3, 0xfd,
+                    0x45, 0x84, 0xb7, 0xe9, 0x68, 0xb1, 0xdd, 0x7c,
+                    0xe9, 0xf3, 0x22, 0x4c, 0x93, 0x4b, 0xa8, 0x5b,
+                    0xc4, 0xe6, 0x52, 0x21, 0x8c, 0xef, 0xec, 0x3c ]
+            },
+            CbcTest {
+                key: repeat(1).take(16).collect(),
+                iv: repeat(3).take(16).collect(),
+                plain: (0..33).collect(),
+                cipher: vec![
+                    0x45, 0x65, 0x90, 0x73, 0x29, 0xa2, 0xe3, 0xfd,
+                    0x45, 0x84, 0xb7, 0xe9, 0x68, 0xb1, 0xdd, 0x7c,
+                    0x5b, 0xee, 0x89, 0x05, 0x9a, 0xf9, 0xb4, 0xbb,
+                    0xee, 0x3e, 0x2a, 0x9a, 0x4e, 0x8a, 0xe2, 0xb2,
+                    0xe9 ]
+            },
+            CbcTest {
+                key: repeat(1).take(16).collect(),
+                iv: repeat(3).take(16).collect(),
+                plain: (0..48).collect(),
+                cipher: vec![
+                    0x45, 0x65, 0x90, 0x73, 0x29, 0xa2, 0xe3, 0xfd,
+                    0x45, 0x84, 0xb7, 0xe9, 0x68, 0xb1, 0xdd, 0x7c,
+                    0xe9, 0xf3, 0x22, 0x4c, 0x93, 0x4b, 0xa8, 0x5b,
+                    0xc4, 0xe6, 0x52, 0x21, 0x8c, 0xef, 0xec, 0x3c,
+                    0xdf, 0x05, 0x9a, 0x38, 0xd0, 0x40, 0xcd, 0x83,
+                    0xab, 0x25, 0x0e, 0xec, 0x53, 0x53, 0x7f, 0xe7 ]
+            }
+        ]
+    }
+
+    // Vectors from RFC 3962, Appendix B
+    fn aes_cbc_cs3_tests() -> Vec<CbcTest> {
+        vec![
+            CbcTest {
+                key: b"chicken teriyaki".to_vec(),
+                iv: repeat(0).take(16).collect(),
+                plain: b"I would like the ".to_vec(),
+                cipher: vec![
+                    0xc6, 0x35, 0x35, 0x68, 0xf2, 0xbf, 0x8c, 0xb4,
+                    0xd8, 0xa5, 0x80, 0x36, 0x2d, 0xa7, 0xff, 0x7f,
+                    0x97 ]
+            },
+            CbcTest {
+                key: b"chicken teriyaki".to_vec(),
+                iv: repeat(0).take(16).collect(),
+                plain: b"I would like the General Gau's ".to_vec(),
+                cipher: vec![
+                    0xfc, 0x00, 0x78, 0x3e, 0x0e, 0xfd, 0xb2, 0xc1,
+                    0xd4, 0x45, 0xd4, 0xc8, 0xef, 0xf7, 0xed, 0x22,
+                    0x97, 0x68, 0x72, 0x68, 0xd6, 0xec, 0xcc, 0xc0,
+                    0xc0, 0x7b, 0x25, 0xe2, 0x5e, 0xcf, 0xe5 ]
+            },
+            CbcTest {
+                key: b"chicken teriyaki".to_vec(),
+                iv: repeat(0).take(16).collect(),
+                plain: b"I would like the General Gau's C".to_vec(),
+                cipher: vec![
+                    0x39, 0x31, 0x25, 0x23, 0xa7, 0x86, 0x62, 0xd5,
+                    0xbe, 0x7f, 0xcb, 0xcc, 0x98, 0xeb, 0xf5, 0xa8,
+                    0x97, 0x68, 0x72, 0x68, 0xd6, 0xec, 0xcc, 0xc0,
+                    0xc0, 0x7b, 0x25, 0xe2, 0x5e, 0xcf, 0xe5, 0x84 ]
+            },
+            CbcTest {
+                key: b"chicken teriyaki".to_vec(),
+                iv: repeat(0).take(16).collect(),
+                plain: b"I would like the General Gau's Chicken, please,".to_vec(),
+                cipher: vec![
+                    0x97, 0x68, 0x72, 0x68, 0xd6, 0xec, 0xcc, 0xc0,
+                    0xc0, 0x7b, 0x25, 0xe2, 0x5e, 0xcf, 0xe5, 0x84,
+                    0xb3, 0xff, 0xfd, 0x94, 0x0c, 0x16, 0xa1, 0x8c,
+                    0x1b, 0x55, 0x49, 0xd2, 0xf8, 0x38, 0x02, 0x9e,
+                    0x39, 0x31, 0x25, 0x23, 0xa7, 0x86, 0x62, 0xd5,
+                    0xbe, 0x7f, 0xcb, 0xcc, 0x98, 0xeb, 0xf5 ]
+            },
+            CbcTest {
+                key: b"chicken teriyaki".to_vec(),
+                iv: repeat(0).take(16).collect(),
+                plain: b"I would like the General Gau's Chicken, please, ".to_vec(),
+                cipher: vec![
+                    0x97, 0x68, 0x72, 0x68, 0xd6, 0xec, 0xcc, 0xc0,
+                    0xc0, 0x7b, 0x25, 0xe2, 0x5e, 0xcf, 0xe5, 0x84,
+                    0x9d, 0xad, 0x8b, 0xbb, 0x96, 0xc4, 0xcd, 0xc0,
+                    0x3b, 0xc1, 0x03, 0xe1, 0xa1, 0x94, 0xbb, 0xd8,
+                    0x39, 0x31, 0x25, 0x23, 0xa7, 0x86, 0x62, 0xd5,
+                    0xbe, 0x7f, 0xcb, 0xcc, 0x98, 0xeb, 0xf5, 0xa8 ]
+            },
+            CbcTest {
+                key: b"chicken teriyaki".to_vec(),
+                iv: repeat(0).take(16).collect(),
+                plain: b"I would like the General Gau's Chicken, please, and wonton soup.".to_vec(),
+                cipher: vec![
+                    0x97, 0x68, 0x72, 0x68, 0xd6, 0xec, 0xcc, 0xc0,
+                    0xc0, 0x7b, 0x25, 0xe2, 0x5e, 0xcf, 0xe5, 0x84,
+                    0x39, 0x31, 0x25, 0x23, 0xa7, 0x86, 0x62, 0xd5,
+                    0xbe, 0x7f, 0xcb, 0xcc, 0x98, 0xeb, 0xf5, 0xa8,
+                    0x48, 0x07, 0xef, 0xe8, 0x36, 0xee, 0x89, 0xa5,
+                    0x26, 0x73, 0x0d, 0xbc, 0x2f, 0x7b, 0xc8, 0x40,
+                    0x9d, 0xad, 0x8b, 0xbb, 0x96, 0xc4, 0xcd, 0xc0,
+                    0x3b, 0xc1, 0x03, 0xe1, 0xa1, 0x94, 0xbb, 0xd8 ]
+            }
+        ]
+    }
+
+    // The first two tests are from OpenSSL
+    fn aes_ige_no_padding_tests() -> Vec<CbcTest> {
+        vec![
+            CbcTest {
+                key: (0..16).collect(),
+                iv: (0..32).collect(),
+                plain: repeat(0).take(32).collect(),
+                cipher: vec![
+                    0x1a, 0x85, 0x19, 0xa6, 0x55, 0x7b, 0xe6, 0x52,
+                    0xe9, 0xda, 0x8e, 0x43, 0xda, 0x4e, 0xf4, 0x45,
+                    0x3c, 0xf4, 0x56, 0xb4, 0xca, 0x48, 0x8a, 0xa3,
+                    0x83, 0xc7, 0x9c, 0x98, 0xb3, 0x47, 0x97, 0xcb ]
+            },
+            CbcTest {
+                key: b"This is an imple".to_vec(),
+                iv: b"mentation of IGE mode for OpenSS".to_vec(),
+                plain: vec![
+                    0x99, 0x70, 0x64, 0x87, 0xa1, 0xcd, 0xe6, 0x13,
+                    0xbc, 0x6d, 0xe0, 0xb6, 0xf2, 0x4b, 0x1c, 0x7a,
+                    0xa4, 0x48, 0xc8, 0xb9, 0xc3, 0x40, 0x3e, 0x34,
+                    0x67, 0xa8, 0xca, 0xd8, 0x93, 0x40, 0xf5, 0x3b ],
+                cipher: b"L. Let's hope Ben got it right!\n".to_vec()
+            }
+        ]
+    }
+
+    fn aes_ige_pkcs_padding_tests() -> Vec<CbcTest> {
+        vec![
+            CbcTest {
+                key: repeat(1).take(16).collect(),
+                iv: repeat(3).take(32).collect(),
+                plain: repeat(2).take(33).collect(),
+                cipher: vec![
+                    0x5d, 0x74, 0xe6, 0x9c, 0x8c, 0x86, 0x97, 0x37,
+                    0x8a, 0xa1, 0x42, 0x4a, 0xc4, 0x5c, 0x4d, 0xca,
+                    0x8e, 0xdb, 0x18, 0xae, 0x8c, 0xd9, 0xf7, 0x16,
+                    0x9a, 0x3c, 0x6b, 0x97, 0xf3, 0x07, 0x18, 0x14,
+                    0x56, 0x8c, 0x48, 0x4c, 0x63, 0x6f, 0xdf, 0xb0,
+                    0xe9, 0xad, 0xa4, 0x6b, 0xdd, 0xb0, 0x17, 0x95 ]
+            }
+        ]
+    }
+
     fn aes_ctr_tests() -> Vec<CtrTest> {
         vec![
             CtrTest {
@@ -976,6 +2295,110 @@ mod test {
         ]
     }
 
+    // Vectors from NIST SP 800-38A, Appendix F
+    fn aes_cfb1_tests() -> Vec<FeedbackTest> {
+        vec![
+            FeedbackTest {
+                key: vec![
+                    0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6,
+                    0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c ],
+                iv: vec![
+                    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07,
+                    0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f ],
+                plain: vec![
+                    0x6b, 0xc1 ],
+                cipher: vec![
+                    0x68, 0xb3 ]
+            }
+        ]
+    }
+
+    // Vectors from NIST SP 800-38A, Appendix F
+    fn aes_cfb8_tests() -> Vec<FeedbackTest> {
+        vec![
+            FeedbackTest {
+                key: vec![
+                    0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6,
+                    0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c ],
+                iv: vec![
+                    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07,
+                    0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f ],
+                plain: vec![
+                    0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96,
+                    0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17, 0x2a,
+                    0xae, 0x2d ],
+                cipher: vec![
+                    0x3b, 0x79, 0x42, 0x4c, 0x9c, 0x0d, 0xd4, 0x36,
+                    0xba, 0xce, 0x9e, 0x0e, 0xd4, 0x58, 0x6a, 0x4f,
+                    0x32, 0xb9 ]
+            }
+        ]
+    }
+
+    // Vectors from NIST SP 800-38A, Appendix F
+    fn aes_cfb_tests() -> Vec<FeedbackTest> {
+        vec![
+            FeedbackTest {
+                key: vec![
+                    0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6,
+                    0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c ],
+                iv: vec![
+                    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07,
+                    0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f ],
+                plain: vec![
+                    0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96,
+                    0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17, 0x2a,
+                    0xae, 0x2d, 0x8a, 0x57, 0x1e, 0x03, 0xac, 0x9c,
+                    0x9e, 0xb7, 0x6f, 0xac, 0x45, 0xaf, 0x8e, 0x51,
+                    0x30, 0xc8, 0x1c, 0x46, 0xa3, 0x5c, 0xe4, 0x11,
+                    0xe5, 0xfb, 0xc1, 0x19, 0x1a, 0x0a, 0x52, 0xef,
+                    0xf6, 0x9f, 0x24, 0x45, 0xdf, 0x4f, 0x9b, 0x17,
+                    0xad, 0x2b, 0x41, 0x7b, 0xe6, 0x6c, 0x37, 0x10 ],
+                cipher: vec![
+                    0x3b, 0x3f, 0xd9, 0x2e, 0xb7, 0x2d, 0xad, 0x20,
+                    0x33, 0x34, 0x49, 0xf8, 0xe8, 0x3c, 0xfb, 0x4a,
+                    0xc8, 0xa6, 0x45, 0x37, 0xa0, 0xb3, 0xa9, 0x3f,
+                    0xcd, 0xe3, 0xcd, 0xad, 0x9f, 0x1c, 0xe5, 0x8b,
+                    0x26, 0x75, 0x1f, 0x67, 0xa3, 0xcb, 0xb1, 0x40,
+                    0xb1, 0x80, 0x8c, 0xf1, 0x87, 0xa4, 0xf4, 0xdf,
+                    0xc0, 0x4b, 0x05, 0x35, 0x7c, 0x5d, 0x1c, 0x0e,
+                    0xea, 0xc4, 0xc6, 0x6f, 0x9f, 0xf7, 0xf2, 0xe6 ]
+            }
+        ]
+    }
+
+    // Vectors from NIST SP 800-38A, Appendix F
+    fn aes_ofb_tests() -> Vec<FeedbackTest> {
+        vec![
+            FeedbackTest {
+                key: vec![
+                    0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6,
+                    0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c ],
+                iv: vec![
+                    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07,
+                    0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f ],
+                plain: vec![
+                    0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96,
+                    0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17, 0x2a,
+                    0xae, 0x2d, 0x8a, 0x57, 0x1e, 0x03, 0xac, 0x9c,
+                    0x9e, 0xb7, 0x6f, 0xac, 0x45, 0xaf, 0x8e, 0x51,
+                    0x30, 0xc8, 0x1c, 0x46, 0xa3, 0x5c, 0xe4, 0x11,
+                    0xe5, 0xfb, 0xc1, 0x19, 0x1a, 0x0a, 0x52, 0xef,
+                    0xf6, 0x9f, 0x24, 0x45, 0xdf, 0x4f, 0x9b, 0x17,
+                    0xad, 0x2b, 0x41, 0x7b, 0xe6, 0x6c, 0x37, 0x10 ],
+                cipher: vec![
+                    0x3b, 0x3f, 0xd9, 0x2e, 0xb7, 0x2d, 0xad, 0x20,
+                    0x33, 0x34, 0x49, 0xf8, 0xe8, 0x3c, 0xfb, 0x4a,
+                    0x77, 0x89, 0x50, 0x8d, 0x16, 0x91, 0x8f, 0x03,
+                    0xf5, 0x3c, 0x52, 0xda, 0xc5, 0x4e, 0xd8, 0x25,
+                    0x97, 0x40, 0x05, 0x1e, 0x9c, 0x5f, 0xec, 0xf6,
+                    0x43, 0x44, 0xf7, 0xa8, 0x22, 0x60, 0xed, 0xcc,
+                    0x30, 0x4c, 0x65, 0x28, 0xf6, 0x59, 0xc7, 0x78,
+                    0x66, 0xa5, 0x10, 0xd9, 0xc1, 0xd6, 0xae, 0x5e ]
+            }
+        ]
+    }
+
     // Test the mode by encrypting all of the data at once
     fn run_full_test<T: CipherTest, E: Encryptor, D: Decryptor>(
             test: &T,
@@ -1177,111 +2600,477 @@ mod test {
             let mut enc = new_enc();
             let mut dec = new_dec();
 
-            let mut cipher_out: Vec<u8> = repeat(0).take(test.get_cipher().len()).collect();
-            run_inc(
-                test.get_plain(),
-                &mut cipher_out,
-                |in_buff: &mut RefReadBuffer, out_buff: &mut RefWriteBuffer, eof: bool| {
-                    enc.encrypt(in_buff, out_buff, eof)
+            let mut cipher_out: Vec<u8> = repeat(0).take(test.get_cipher().len()).collect();
+            run_inc(
+                test.get_plain(),
+                &mut cipher_out,
+                |in_buff: &mut RefReadBuffer, out_buff: &mut RefWriteBuffer, eof: bool| {
+                    enc.encrypt(in_buff, out_buff, eof)
+                },
+                || { r1() },
+                || { r2() },
+                rng3.gen());
+            assert!(test.get_cipher() == &cipher_out[..]);
+
+            let mut plain_out: Vec<u8> = repeat(0).take(test.get_plain().len()).collect();
+            run_inc(
+                test.get_cipher(),
+                &mut plain_out,
+                |in_buff: &mut RefReadBuffer, out_buff: &mut RefWriteBuffer, eof: bool| {
+                    dec.decrypt(in_buff, out_buff, eof)
+                },
+                || { r1() },
+                || { r2() },
+                rng3.gen());
+            assert!(test.get_plain() == &plain_out[..]);
+        }
+    }
+
+    fn run_test<T, E, D, NewEncFunc, NewDecFunc>(
+            test: &T,
+            mut new_enc: NewEncFunc,
+            mut new_dec: NewDecFunc)
+            where
+                T: CipherTest,
+                E: Encryptor,
+                D: Decryptor,
+                NewEncFunc: FnMut() -> E,
+                NewDecFunc: FnMut() -> D{
+        run_full_test(test, &mut new_enc(), &mut new_dec());
+        run_inc1_test(test, &mut new_enc(), &mut new_dec());
+        run_rand_test(test, new_enc, new_dec);
+    }
+
+    #[test]
+    fn aes_ecb_no_padding() {
+        let tests = aes_ecb_no_padding_tests();
+        for test in tests.iter() {
+            run_test(
+                test,
+                || {
+                    let aes_enc = aessafe::AesSafe128Encryptor::new(&test.key[..]);
+                    EcbEncryptor::new(aes_enc, NoPadding)
+                },
+                || {
+                    let aes_dec = aessafe::AesSafe128Decryptor::new(&test.key[..]);
+                    EcbDecryptor::new(aes_dec, NoPadding)
+                });
+        }
+    }
+
+    #[test]
+    fn aes_ecb_pkcs_padding() {
+        let tests = aes_ecb_pkcs_padding_tests();
+        for test in tests.iter() {
+            run_test(
+                test,
+                || {
+                    let aes_enc = aessafe::AesSafe128Encryptor::new(&test.key[..]);
+                    EcbEncryptor::new(aes_enc, PkcsPadding)
+                },
+                || {
+                    let aes_dec = aessafe::AesSafe128Decryptor::new(&test.key[..]);
+                    EcbDecryptor::new(aes_dec, PkcsPadding)
+                });
+        }
+    }
+
+    #[test]
+    fn aes_ecb_x8() {
+        let mut tests = aes_ecb_pkcs_padding_tests();
+
+        // Build longer tests with the single block EcbEncryptor so that full and partial groups of
+        // 8 blocks are processed
+        for &len in [15, 16, 127, 128, 129, 300, 1000].iter() {
+            let key: Vec<u8> = repeat(1).take(16).collect();
+            let plain: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let mut cipher: Vec<u8> = repeat(0).take(len / 16 * 16 + 16).collect();
+            {
+                let aes_enc = aessafe::AesSafe128Encryptor::new(&key[..]);
+                let mut enc = EcbEncryptor::new(aes_enc, PkcsPadding);
+                let mut buff_in = RefReadBuffer::new(&plain[..]);
+                let mut buff_out = RefWriteBuffer::new(&mut cipher);
+                enc.encrypt(&mut buff_in, &mut buff_out, true).unwrap();
+            }
+            tests.push(EcbTest { key: key, plain: plain, cipher: cipher });
+        }
+
+        for test in tests.iter() {
+            run_test(
+                test,
+                || {
+                    let aes_enc = aessafe::AesSafe128EncryptorX8::new(&test.key[..]);
+                    EcbEncryptorX8::new(aes_enc, PkcsPadding)
+                },
+                || {
+                    let aes_dec = aessafe::AesSafe128DecryptorX8::new(&test.key[..]);
+                    EcbDecryptorX8::new(aes_dec, PkcsPadding)
+                });
+        }
+    }
+
+    #[test]
+    fn aes_cbc_no_padding() {
+        let tests = aes_cbc_no_padding_tests();
+        for test in tests.iter() {
+            run_test(
+                test,
+                || {
+                    let aes_enc = aessafe::AesSafe128Encryptor::new(&test.key[..]);
+                    CbcEncryptor::new(aes_enc, NoPadding, test.iv.clone())
+                },
+                || {
+                    let aes_dec = aessafe::AesSafe128Decryptor::new(&test.key[..]);
+                    CbcDecryptor::new(aes_dec, NoPadding, test.iv.clone())
+                });
+        }
+    }
+
+    #[test]
+    fn aes_cbc_pkcs_padding() {
+        let tests = aes_cbc_pkcs_padding_tests();
+        for test in tests.iter() {
+            run_test(
+                test,
+                || {
+                    let aes_enc = aessafe::AesSafe128Encryptor::new(&test.key[..]);
+                    CbcEncryptor::new(aes_enc, PkcsPadding, test.iv.clone())
                 },
-                || { r1() },
-                || { r2() },
-                rng3.gen());
-            assert!(test.get_cipher() == &cipher_out[..]);
+                || {
+                    let aes_dec = aessafe::AesSafe128Decryptor::new(&test.key[..]);
+                    CbcDecryptor::new(aes_dec, PkcsPadding, test.iv.clone())
+                });
+        }
+    }
 
-            let mut plain_out: Vec<u8> = repeat(0).take(test.get_plain().len()).collect();
-            run_inc(
-                test.get_cipher(),
-                &mut plain_out,
-                |in_buff: &mut RefReadBuffer, out_buff: &mut RefWriteBuffer, eof: bool| {
-                    dec.decrypt(in_buff, out_buff, eof)
+    #[test]
+    fn aes_cbc_x8() {
+        let mut tests = aes_cbc_pkcs_padding_tests();
+
+        // Build longer tests with the single block CbcEncryptor so that the 8 block decryption is
+        // exercised for both full and partial groups of blocks
+        for &len in [15, 16, 127, 128, 129, 300, 1000].iter() {
+            let key: Vec<u8> = repeat(1).take(16).collect();
+            let iv: Vec<u8> = repeat(3).take(16).collect();
+            let plain: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let mut cipher: Vec<u8> = repeat(0).take(len / 16 * 16 + 16).collect();
+            {
+                let aes_enc = aessafe::AesSafe128Encryptor::new(&key[..]);
+                let mut enc = CbcEncryptor::new(aes_enc, PkcsPadding, iv.clone());
+                let mut buff_in = RefReadBuffer::new(&plain[..]);
+                let mut buff_out = RefWriteBuffer::new(&mut cipher);
+                enc.encrypt(&mut buff_in, &mut buff_out, true).unwrap();
+            }
+            tests.push(CbcTest { key: key, iv: iv, plain: plain, cipher: cipher });
+        }
+
+        for test in tests.iter() {
+            run_test(
+                test,
+                || {
+                    let aes_enc = aessafe::AesSafe128Encryptor::new(&test.key[..]);
+                    CbcEncryptor::new(aes_enc, PkcsPadding, test.iv.clone())
                 },
-                || { r1() },
-                || { r2() },
-                rng3.gen());
-            assert!(test.get_plain() == &plain_out[..]);
+                || {
+                    let aes_dec = aessafe::AesSafe128DecryptorX8::new(&test.key[..]);
+                    CbcDecryptorX8::new(aes_dec, PkcsPadding, test.iv.clone())
+                });
         }
     }
 
-    fn run_test<T, E, D, NewEncFunc, NewDecFunc>(
-            test: &T,
-            mut new_enc: NewEncFunc,
-            mut new_dec: NewDecFunc)
-            where
-                T: CipherTest,
-                E: Encryptor,
-                D: Decryptor,
-                NewEncFunc: FnMut() -> E,
-                NewDecFunc: FnMut() -> D{
-        run_full_test(test, &mut new_enc(), &mut new_dec());
-        run_inc1_test(test, &mut new_enc(), &mut new_dec());
-        run_rand_test(test, new_enc, new_dec);
+    #[test]
+    fn aes_cbc_cs1() {
+        let tests = aes_cbc_cs1_tests();
+        for test in tests.iter() {
+            run_test(
+                test,
+                || {
+                    let aes_enc = aessafe::AesSafe128Encryptor::new(&test.key[..]);
+                    CbcCtsEncryptor::new(aes_enc, CtsVariant::Cs1, test.iv.clone())
+                },
+                || {
+                    let aes_dec = aessafe::AesSafe128Decryptor::new(&test.key[..]);
+                    CbcCtsDecryptor::new(aes_dec, CtsVariant::Cs1, test.iv.clone())
+                });
+        }
     }
 
     #[test]
-    fn aes_ecb_no_padding() {
-        let tests = aes_ecb_no_padding_tests();
+    fn aes_cbc_cs2() {
+        let tests = aes_cbc_cs2_tests();
         for test in tests.iter() {
             run_test(
                 test,
                 || {
                     let aes_enc = aessafe::AesSafe128Encryptor::new(&test.key[..]);
-                    EcbEncryptor::new(aes_enc, NoPadding)
+                    CbcCtsEncryptor::new(aes_enc, CtsVariant::Cs2, test.iv.clone())
                 },
                 || {
                     let aes_dec = aessafe::AesSafe128Decryptor::new(&test.key[..]);
-                    EcbDecryptor::new(aes_dec, NoPadding)
+                    CbcCtsDecryptor::new(aes_dec, CtsVariant::Cs2, test.iv.clone())
                 });
         }
     }
 
     #[test]
-    fn aes_ecb_pkcs_padding() {
-        let tests = aes_ecb_pkcs_padding_tests();
+    fn aes_cbc_cs3() {
+        let tests = aes_cbc_cs3_tests();
         for test in tests.iter() {
             run_test(
                 test,
                 || {
                     let aes_enc = aessafe::AesSafe128Encryptor::new(&test.key[..]);
-                    EcbEncryptor::new(aes_enc, PkcsPadding)
+                    CbcCtsEncryptor::new(aes_enc, CtsVariant::Cs3, test.iv.clone())
                 },
                 || {
                     let aes_dec = aessafe::AesSafe128Decryptor::new(&test.key[..]);
-                    EcbDecryptor::new(aes_dec, PkcsPadding)
+                    CbcCtsDecryptor::new(aes_dec, CtsVariant::Cs3, test.iv.clone())
                 });
         }
     }
 
     #[test]
-    fn aes_cbc_no_padding() {
-        let tests = aes_cbc_no_padding_tests();
+    fn aes_cbc_cts_short_input() {
+        let key = [1u8; 16];
+        let iv = vec![3u8; 16];
+        let plain = [2u8; 15];
+        let mut cipher = [0u8; 15];
+        let aes_enc = aessafe::AesSafe128Encryptor::new(&key);
+        let mut enc = CbcCtsEncryptor::new(aes_enc, CtsVariant::Cs3, iv.clone());
+        match enc.encrypt(
+                &mut RefReadBuffer::new(&plain),
+                &mut RefWriteBuffer::new(&mut cipher),
+                true) {
+            Err(InvalidLength) => {}
+            _ => panic!("Input shorter than a block was accepted")
+        }
+        let aes_dec = aessafe::AesSafe128Decryptor::new(&key);
+        let mut dec = CbcCtsDecryptor::new(aes_dec, CtsVariant::Cs3, iv);
+        match dec.decrypt(
+                &mut RefReadBuffer::new(&cipher),
+                &mut RefWriteBuffer::new(&mut plain.clone()),
+                true) {
+            Err(InvalidLength) => {}
+            _ => panic!("Input shorter than a block was accepted")
+        }
+    }
+
+    // Stream a long message through the CTS modes in uneven chunks, which exercises the switch back
+    // to FastMode between chunks. Every block but the last two must match plain CBC.
+    #[test]
+    fn aes_cbc_cts_long_stream() {
+        let key = [1u8; 16];
+        let iv = vec![3u8; 16];
+        let plain: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+        let prefix_len = (plain.len() / 16 - 1) * 16;
+
+        let mut cbc: Vec<u8> = repeat(0).take(prefix_len).collect();
+        {
+            let mut enc = CbcEncryptor::new(
+                aessafe::AesSafe128Encryptor::new(&key), NoPadding, iv.clone());
+            enc.encrypt(
+                &mut RefReadBuffer::new(&plain[..prefix_len]),
+                &mut RefWriteBuffer::new(&mut cbc),
+                true).unwrap();
+        }
+
+        for &chunk in [1usize, 15, 16, 17, 33, 100, 1000].iter() {
+            let mut cipher: Vec<u8> = repeat(0).take(plain.len()).collect();
+            {
+                let mut enc = CbcCtsEncryptor::new(
+                    aessafe::AesSafe128Encryptor::new(&key), CtsVariant::Cs1, iv.clone());
+                let mut buff_out = RefWriteBuffer::new(&mut cipher);
+                for part in plain.chunks(chunk) {
+                    let mut buff_in = RefReadBuffer::new(part);
+                    enc.encrypt(&mut buff_in, &mut buff_out, false).unwrap();
+                    assert!(buff_in.is_empty());
+                }
+                enc.encrypt(&mut RefReadBuffer::new(&[]), &mut buff_out, true).unwrap();
+                assert!(buff_out.is_full());
+            }
+            assert!(cipher[..prefix_len] == cbc[..]);
+
+            let mut decrypted: Vec<u8> = repeat(0).take(plain.len()).collect();
+            {
+                let mut dec = CbcCtsDecryptor::new(
+                    aessafe::AesSafe128Decryptor::new(&key), CtsVariant::Cs1, iv.clone());
+                let mut buff_out = RefWriteBuffer::new(&mut decrypted);
+                for part in cipher.chunks(chunk) {
+                    let mut buff_in = RefReadBuffer::new(part);
+                    dec.decrypt(&mut buff_in, &mut buff_out, false).unwrap();
+                    assert!(buff_in.is_empty());
+                }
+                dec.decrypt(&mut RefReadBuffer::new(&[]), &mut buff_out, true).unwrap();
+                assert!(buff_out.is_full());
+            }
+            assert!(decrypted == plain);
+        }
+    }
+
+    // Encrypt with the given padding and then decrypt without removing it, to return the padded
+    // plaintext
+    fn pad<X: PaddingProcessor>(padding: X, plain: &[u8]) -> Vec<u8> {
+        let key = [1u8; 16];
+        let mut cipher: Vec<u8> = repeat(0).take(plain.len() + 16).collect();
+        let cipher_len = {
+            let mut enc = EcbEncryptor::new(aessafe::AesSafe128Encryptor::new(&key), padding);
+            let mut buff_in = RefReadBuffer::new(plain);
+            let mut buff_out = RefWriteBuffer::new(&mut cipher);
+            enc.encrypt(&mut buff_in, &mut buff_out, true).unwrap();
+            buff_out.position()
+        };
+        let mut padded: Vec<u8> = repeat(0).take(cipher_len).collect();
+        let mut dec = EcbDecryptor::new(aessafe::AesSafe128Decryptor::new(&key), NoPadding);
+        dec.decrypt(
+            &mut RefReadBuffer::new(&cipher[..cipher_len]),
+            &mut RefWriteBuffer::new(&mut padded),
+            true).unwrap();
+        padded
+    }
+
+    // Encrypt already padded data and then decrypt it, removing the padding
+    fn unpad<X: PaddingProcessor>(padding: X, padded: &[u8])
+            -> Result<Vec<u8>, SymmetricCipherError> {
+        let key = [1u8; 16];
+        let mut cipher: Vec<u8> = repeat(0).take(padded.len()).collect();
+        let mut enc = EcbEncryptor::new(aessafe::AesSafe128Encryptor::new(&key), NoPadding);
+        enc.encrypt(
+            &mut RefReadBuffer::new(padded),
+            &mut RefWriteBuffer::new(&mut cipher),
+            true).unwrap();
+        let mut plain: Vec<u8> = repeat(0).take(padded.len()).collect();
+        let plain_len = {
+            let mut dec = EcbDecryptor::new(aessafe::AesSafe128Decryptor::new(&key), padding);
+            let mut buff_in = RefReadBuffer::new(&cipher);
+            let mut buff_out = RefWriteBuffer::new(&mut plain);
+            try!(dec.decrypt(&mut buff_in, &mut buff_out, true));
+            buff_out.position()
+        };
+        plain.truncate(plain_len);
+        Ok(plain)
+    }
+
+    fn assert_invalid_padding(result: Result<Vec<u8>, SymmetricCipherError>) {
+        match result {
+            Err(InvalidPadding) => {}
+            _ => panic!("Invalid padding was accepted")
+        }
+    }
+
+    #[test]
+    fn iso7816_padding() {
+        let plain = [7u8; 13];
+        let padded = pad(Iso7816Padding, &plain);
+        assert_eq!(&padded[..13], &plain[..]);
+        assert_eq!(&padded[13..], &[0x80, 0, 0][..]);
+        assert_eq!(unpad(Iso7816Padding, &padded).unwrap(), &plain[..]);
+
+        let padded = pad(Iso7816Padding, &[7u8; 16]);
+        assert_eq!(padded.len(), 32);
+        assert_eq!(padded[16], 0x80);
+        assert!(padded[17..].iter().all(|&x| x == 0));
+        assert_eq!(unpad(Iso7816Padding, &padded).unwrap(), &[7u8; 16][..]);
+
+        let mut bad = [7u8; 16];
+        assert_invalid_padding(unpad(Iso7816Padding, &bad));
+        bad[14] = 0x80;
+        bad[15] = 1;
+        assert_invalid_padding(unpad(Iso7816Padding, &bad));
+        assert_invalid_padding(unpad(Iso7816Padding, &[0u8; 16]));
+    }
+
+    #[test]
+    fn ansi_x923_padding() {
+        let plain = [7u8; 13];
+        let padded = pad(AnsiX923Padding, &plain);
+        assert_eq!(&padded[..13], &plain[..]);
+        assert_eq!(&padded[13..], &[0, 0, 3][..]);
+        assert_eq!(unpad(AnsiX923Padding, &padded).unwrap(), &plain[..]);
+
+        let padded = pad(AnsiX923Padding, &[7u8; 16]);
+        assert_eq!(padded.len(), 32);
+        assert!(padded[16..31].iter().all(|&x| x == 0));
+        assert_eq!(padded[31], 16);
+        assert_eq!(unpad(AnsiX923Padding, &padded).unwrap(), &[7u8; 16][..]);
+
+        let mut bad = [0u8; 16];
+        assert_invalid_padding(unpad(AnsiX923Padding, &bad));
+        bad[15] = 17;
+        assert_invalid_padding(unpad(AnsiX923Padding, &bad));
+        bad[15] = 3;
+        bad[13] = 1;
+        assert_invalid_padding(unpad(AnsiX923Padding, &bad));
+    }
+
+    #[test]
+    fn iso10126_padding() {
+        let plain = [7u8; 13];
+        let padded = pad(Iso10126Padding::new().unwrap(), &plain);
+        assert_eq!(&padded[..13], &plain[..]);
+        assert_eq!(padded.len(), 16);
+        assert_eq!(padded[15], 3);
+        assert_eq!(unpad(Iso10126Padding::new().unwrap(), &padded).unwrap(), &plain[..]);
+
+        let padded = pad(Iso10126Padding::new().unwrap(), &[7u8; 16]);
+        assert_eq!(padded.len(), 32);
+        assert_eq!(padded[31], 16);
+        assert_eq!(unpad(Iso10126Padding::new().unwrap(), &padded).unwrap(), &[7u8; 16][..]);
+
+        // The padding is random
+        let other = pad(Iso10126Padding::new().unwrap(), &[7u8; 16]);
+        assert!(padded[16..31] != other[16..31]);
+
+        let mut bad = [0u8; 16];
+        assert_invalid_padding(unpad(Iso10126Padding::new().unwrap(), &bad));
+        bad[15] = 17;
+        assert_invalid_padding(unpad(Iso10126Padding::new().unwrap(), &bad));
+    }
+
+    #[test]
+    fn zero_padding() {
+        let plain = [7u8; 13];
+        let padded = pad(ZeroPadding, &plain);
+        assert_eq!(&padded[..13], &plain[..]);
+        assert_eq!(&padded[13..], &[0, 0, 0][..]);
+        assert_eq!(unpad(ZeroPadding, &padded).unwrap(), &plain[..]);
+
+        let padded = pad(ZeroPadding, &[7u8; 16]);
+        assert_eq!(&padded[..], &[7u8; 16][..]);
+        assert_eq!(unpad(ZeroPadding, &padded).unwrap(), &[7u8; 16][..]);
+
+        assert_invalid_padding(unpad(ZeroPadding, &[0u8; 16]));
+    }
+
+    #[test]
+    fn aes_ige_no_padding() {
+        let tests = aes_ige_no_padding_tests();
         for test in tests.iter() {
             run_test(
                 test,
                 || {
                     let aes_enc = aessafe::AesSafe128Encryptor::new(&test.key[..]);
-                    CbcEncryptor::new(aes_enc, NoPadding, test.iv.clone())
+                    IgeEncryptor::new(aes_enc, NoPadding, test.iv.clone())
                 },
                 || {
                     let aes_dec = aessafe::AesSafe128Decryptor::new(&test.key[..]);
-                    CbcDecryptor::new(aes_dec, NoPadding, test.iv.clone())
+                    IgeDecryptor::new(aes_dec, NoPadding, test.iv.clone())
                 });
         }
     }
 
     #[test]
-    fn aes_cbc_pkcs_padding() {
-        let tests = aes_cbc_pkcs_padding_tests();
+    fn aes_ige_pkcs_padding() {
+        let tests = aes_ige_pkcs_padding_tests();
         for test in tests.iter() {
             run_test(
                 test,
                 || {
                     let aes_enc = aessafe::AesSafe128Encryptor::new(&test.key[..]);
-                    CbcEncryptor::new(aes_enc, PkcsPadding, test.iv.clone())
+                    IgeEncryptor::new(aes_enc, PkcsPadding, test.iv.clone())
                 },
                 || {
                     let aes_dec = aessafe::AesSafe128Decryptor::new(&test.key[..]);
-                    CbcDecryptor::new(aes_dec, PkcsPadding, test.iv.clone())
+                    IgeDecryptor::new(aes_dec, PkcsPadding, test.iv.clone())
                 });
         }
     }
@@ -1319,16 +3108,218 @@ mod test {
                 });
         }
     }
+
+    #[test]
+    fn aes_ctr_seek() {
+        let key: Vec<u8> = repeat(1).take(16).collect();
+        let ctr: Vec<u8> = repeat(0).take(16).collect();
+        for &counter_size in [16, 8].iter() {
+            let aes_enc = aessafe::AesSafe128Encryptor::new(&key[..]);
+            test_seek(&mut CtrMode::new_with_counter_size(aes_enc, ctr.clone(), counter_size));
+            let aes_enc = aessafe::AesSafe128EncryptorX8::new(&key[..]);
+            test_seek(&mut CtrModeX8::new_with_counter_size(aes_enc, &ctr[..], counter_size));
+        }
+    }
+
+    // Build the key stream by encrypting each counter block directly
+    fn ctr_key_stream(key: &[u8], ctrs: &[[u8; 4]]) -> Vec<u8> {
+        let aes_enc = aessafe::AesSafe128Encryptor::new(key);
+        let mut result = Vec::new();
+        for low in ctrs.iter() {
+            let mut block: Vec<u8> = repeat(0xaa).take(12).collect();
+            block.extend_from_slice(&low[..]);
+            let mut out = [0u8; 16];
+            aes_enc.encrypt_block(&block[..], &mut out);
+            result.extend_from_slice(&out[..]);
+        }
+        result
+    }
+
+    #[test]
+    fn aes_ctr_counter_size() {
+        let key: Vec<u8> = repeat(1).take(16).collect();
+        let mut ctr: Vec<u8> = repeat(0xaa).take(12).collect();
+        ctr.extend_from_slice(&[0xff, 0xff, 0xff, 0xfe]);
+        let expected = ctr_key_stream(
+            &key[..],
+            &[[0xff, 0xff, 0xff, 0xfe], [0xff, 0xff, 0xff, 0xff], [0, 0, 0, 0], [0, 0, 0, 1]]);
+        let zeros = [0u8; 64];
+
+        let aes_enc = aessafe::AesSafe128Encryptor::new(&key[..]);
+        let mut ctr_mode = CtrMode::new_with_counter_size(aes_enc, ctr.clone(), 4);
+        let mut output = [0u8; 64];
+        ctr_mode.process(&zeros[..], &mut output[..]);
+        assert_eq!(&output[..], &expected[..]);
+
+        let aes_enc = aessafe::AesSafe128EncryptorX8::new(&key[..]);
+        let mut ctr_mode_x8 = CtrModeX8::new_with_counter_size(aes_enc, &ctr[..], 4);
+        let mut output = [0u8; 64];
+        ctr_mode_x8.process(&zeros[..], &mut output[..]);
+        assert_eq!(&output[..], &expected[..]);
+
+        // Seeking is allowed up to the last block before the counter wraps around
+        let mut output = [0u8; 9];
+        ctr_mode.seek(23).unwrap();
+        ctr_mode.process(&zeros[..9], &mut output[..]);
+        assert_eq!(&output[..], &expected[23..32]);
+        ctr_mode_x8.seek(23).unwrap();
+        ctr_mode_x8.process(&zeros[..9], &mut output[..]);
+        assert_eq!(&output[..], &expected[23..32]);
+
+        assert!(ctr_mode.seek(32).is_err());
+        assert!(ctr_mode.seek(0x1_0000_0000 * 16).is_err());
+        assert!(ctr_mode_x8.seek(32).is_err());
+        assert!(ctr_mode_x8.seek(0x1_0000_0000 * 16).is_err());
+    }
+
+    // With a 1 byte counter starting at 0xf0, all 256 counter values can be used, wrapping around
+    // through 0, but not one byte more
+    fn ctr_counter_exhausted<C: SynchronousStreamCipher>(ctr_mode: &mut C) {
+        let zeros = [0u8; 256 * 16];
+        let mut output = [0u8; 256 * 16];
+        ctr_mode.process(&zeros[..100], &mut output[..100]);
+        ctr_mode.process(&zeros[100..], &mut output[100..]);
+        ctr_mode.process(&zeros[..1], &mut output[..1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "CTR counter exhausted")]
+    fn aes_ctr_counter_exhausted() {
+        let key = [1u8; 16];
+        let mut ctr = [0u8; 16];
+        ctr[15] = 0xf0;
+        let aes_enc = aessafe::AesSafe128Encryptor::new(&key);
+        ctr_counter_exhausted(&mut CtrMode::new_with_counter_size(aes_enc, ctr.to_vec(), 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "CTR counter exhausted")]
+    fn aes_ctr_x8_counter_exhausted() {
+        let key = [1u8; 16];
+        let mut ctr = [0u8; 16];
+        ctr[15] = 0xf0;
+        let aes_enc = aessafe::AesSafe128EncryptorX8::new(&key);
+        ctr_counter_exhausted(&mut CtrModeX8::new_with_counter_size(aes_enc, &ctr, 1));
+    }
+
+    // Reset and seek make the whole counter range available again
+    #[test]
+    fn aes_ctr_counter_limit_reset() {
+        let key = [1u8; 16];
+        let ctr = [0u8; 16];
+        let zeros = [0u8; 256 * 16];
+        let mut output = [0u8; 256 * 16];
+        let aes_enc = aessafe::AesSafe128Encryptor::new(&key);
+        let mut ctr_mode = CtrMode::new_with_counter_size(aes_enc, ctr.to_vec(), 1);
+        ctr_mode.process(&zeros, &mut output);
+        ctr_mode.seek(16).unwrap();
+        ctr_mode.process(&zeros[16..], &mut output[16..]);
+        ctr_mode.reset(&ctr);
+        ctr_mode.process(&zeros, &mut output);
+    }
+
+    #[test]
+    fn aes_ctr_reset() {
+        let key: Vec<u8> = repeat(1).take(16).collect();
+        let ctr: Vec<u8> = repeat(0).take(16).collect();
+        let zeros = [0u8; 32];
+        let mut expected = [0u8; 32];
+        let aes_enc = aessafe::AesSafe128Encryptor::new(&key[..]);
+        CtrMode::new(aes_enc, ctr.clone()).process(&zeros[..], &mut expected[..]);
+
+        let mut output = [0u8; 32];
+        let aes_enc = aessafe::AesSafe128Encryptor::new(&key[..]);
+        let mut ctr_mode = CtrMode::new(aes_enc, repeat(5).take(16).collect());
+        ctr_mode.process(&zeros[..5], &mut output[..5]);
+        ctr_mode.reset(&ctr[..]);
+        ctr_mode.process(&zeros[..], &mut output[..]);
+        assert_eq!(&output[..], &expected[..]);
+
+        let aes_enc = aessafe::AesSafe128EncryptorX8::new(&key[..]);
+        let mut ctr_mode_x8 = CtrModeX8::new(aes_enc, &[5u8; 16]);
+        ctr_mode_x8.process(&zeros[..5], &mut output[..5]);
+        ctr_mode_x8.reset(&ctr[..]);
+        ctr_mode_x8.process(&zeros[..], &mut output[..]);
+        assert_eq!(&output[..], &expected[..]);
+    }
+
+    #[test]
+    fn aes_cfb1() {
+        let tests = aes_cfb1_tests();
+        for test in tests.iter() {
+            run_test(
+                test,
+                || {
+                    let aes_enc = aessafe::AesSafe128Encryptor::new(&test.key[..]);
+                    CfbEncryptor::new(aes_enc, CfbSegmentSize::Bits1, test.iv.clone())
+                },
+                || {
+                    let aes_enc = aessafe::AesSafe128Encryptor::new(&test.key[..]);
+                    CfbDecryptor::new(aes_enc, CfbSegmentSize::Bits1, test.iv.clone())
+                });
+        }
+    }
+
+    #[test]
+    fn aes_cfb8() {
+        let tests = aes_cfb8_tests();
+        for test in tests.iter() {
+            run_test(
+                test,
+                || {
+                    let aes_enc = aessafe::AesSafe128Encryptor::new(&test.key[..]);
+                    CfbEncryptor::new(aes_enc, CfbSegmentSize::Bits8, test.iv.clone())
+                },
+                || {
+                    let aes_enc = aessafe::AesSafe128Encryptor::new(&test.key[..]);
+                    CfbDecryptor::new(aes_enc, CfbSegmentSize::Bits8, test.iv.clone())
+                });
+        }
+    }
+
+    #[test]
+    fn aes_cfb() {
+        let tests = aes_cfb_tests();
+        for test in tests.iter() {
+            run_test(
+                test,
+                || {
+                    let aes_enc = aessafe::AesSafe128Encryptor::new(&test.key[..]);
+                    CfbEncryptor::new(aes_enc, CfbSegmentSize::Block, test.iv.clone())
+                },
+                || {
+                    let aes_enc = aessafe::AesSafe128Encryptor::new(&test.key[..]);
+                    CfbDecryptor::new(aes_enc, CfbSegmentSize::Block, test.iv.clone())
+                });
+        }
+    }
+
+    #[test]
+    fn aes_ofb() {
+        let tests = aes_ofb_tests();
+        for test in tests.iter() {
+            run_test(
+                test,
+                || {
+                    let aes_enc = aessafe::AesSafe128Encryptor::new(&test.key[..]);
+                    OfbMode::new(aes_enc, test.iv.clone())
+                },
+                || {
+                    let aes_enc = aessafe::AesSafe128Encryptor::new(&test.key[..]);
+                    OfbMode::new(aes_enc, test.iv.clone())
+                });
+        }
+    }
 }
 
 #[cfg(all(test, feature = "with-bench"))]
 mod bench {
     use aessafe;
-    use blockmodes::{EcbEncryptor, CbcEncryptor, CtrMode, CtrModeX8,
+    use blockmodes::{EcbEncryptor, CbcEncryptor, CbcDecryptor, CbcDecryptorX8, CtrMode, CtrModeX8,
         NoPadding, PkcsPadding};
     use buffer::{ReadBuffer, WriteBuffer, RefReadBuffer, RefWriteBuffer};
     use buffer::BufferResult::{BufferUnderflow, BufferOverflow};
-    use symmetriccipher::{Encryptor};
+    use symmetriccipher::{Encryptor, Decryptor};
 
     use test::Bencher;
 
@@ -1383,6 +3374,58 @@ mod bench {
         bh.bytes = (plain.len()) as u64;
     }
 
+    #[bench]
+    pub fn aes_cbc_decrypt_bench(bh: &mut Bencher) {
+        let key = [1u8; 16];
+        let iv = [2u8; 16];
+        let cipher = [3u8; 512];
+        let mut plain = [3u8; 512];
+
+        let aes_dec = aessafe::AesSafe128Decryptor::new(&key);
+        let mut dec = CbcDecryptor::new(aes_dec, NoPadding, iv.to_vec());
+
+        bh.iter( || {
+            dec.reset(&iv);
+
+            let mut buff_in = RefReadBuffer::new(&cipher);
+            let mut buff_out = RefWriteBuffer::new(&mut plain);
+
+            match dec.decrypt(&mut buff_in, &mut buff_out, true) {
+                Ok(BufferUnderflow) => {}
+                Ok(BufferOverflow) => panic!("Decryption not completed"),
+                Err(_) => panic!("Error"),
+            }
+        });
+
+        bh.bytes = (cipher.len()) as u64;
+    }
+
+    #[bench]
+    pub fn aes_cbc_x8_decrypt_bench(bh: &mut Bencher) {
+        let key = [1u8; 16];
+        let iv = [2u8; 16];
+        let cipher = [3u8; 512];
+        let mut plain = [3u8; 512];
+
+        let aes_dec = aessafe::AesSafe128DecryptorX8::new(&key);
+        let mut dec = CbcDecryptorX8::new(aes_dec, NoPadding, iv.to_vec());
+
+        bh.iter( || {
+            dec.reset(&iv);
+
+            let mut buff_in = RefReadBuffer::new(&cipher);
+            let mut buff_out = RefWriteBuffer::new(&mut plain);
+
+            match dec.decrypt(&mut buff_in, &mut buff_out, true) {
+                Ok(BufferUnderflow) => {}
+                Ok(BufferOverflow) => panic!("Decryption not completed"),
+                Err(_) => panic!("Error"),
+            }
+        });
+
+        bh.bytes = (cipher.len()) as u64;
+    }
+
     #[bench]
     pub fn aes_ctr_bench(bh: &mut Bencher) {
         let key = [1u8; 16];
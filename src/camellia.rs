@@ -0,0 +1,481 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+An implementation of the Camellia block cipher as specified in RFC 3713.
+
+Camellia operates on 128 bit blocks and accepts 128, 192, or 256 bit keys. A 128 bit key uses 18
+rounds of the Feistel network while the longer keys use 24 rounds.
+
+Two types are provided: `Camellia` processes a single block at a time while `CamelliaX8`
+processes 8 blocks at once, interleaving the rounds of all of the blocks. `CamelliaX8` implements
+`BlockEncryptorX8` so that it may be used with `blockmodes::CtrModeX8`.
+
+Like the Blowfish implementation, this implementation uses S-box lookups which are indexed by
+secret data and so it is not protected against cache timing attacks.
+*/
+
+use cryptoutil::{read_u64v_be, write_u64_be};
+use symmetriccipher::{BlockEncryptor, BlockEncryptorX8, BlockDecryptor, BlockDecryptorX8};
+
+const SIGMA: [u64; 6] = [
+    0xa09e667f3bcc908b,
+    0xb67ae8584caa73b2,
+    0xc6ef372fe94f82be,
+    0x54ff53a5f1d36f1c,
+    0x10e527fade682d1d,
+    0xb05688c2b3e6c1fd
+];
+
+static SBOX1: [u8; 256] = [
+    112, 130,  44, 236, 179,  39, 192, 229, 228, 133,  87,  53, 234,  12, 174,  65,
+     35, 239, 107, 147,  69,  25, 165,  33, 237,  14,  79,  78,  29, 101, 146, 189,
+    134, 184, 175, 143, 124, 235,  31, 206,  62,  48, 220,  95,  94, 197,  11,  26,
+    166, 225,  57, 202, 213,  71,  93,  61, 217,   1,  90, 214,  81,  86, 108,  77,
+    139,  13, 154, 102, 251, 204, 176,  45, 116,  18,  43,  32, 240, 177, 132, 153,
+    223,  76, 203, 194,  52, 126, 118,   5, 109, 183, 169,  49, 209,  23,   4, 215,
+     20,  88,  58,  97, 222,  27,  17,  28,  50,  15, 156,  22,  83,  24, 242,  34,
+    254,  68, 207, 178, 195, 181, 122, 145,  36,   8, 232, 168,  96, 252, 105,  80,
+    170, 208, 160, 125, 161, 137,  98, 151,  84,  91,  30, 149, 224, 255, 100, 210,
+     16, 196,   0,  72, 163, 247, 117, 219, 138,   3, 230, 218,   9,  63, 221, 148,
+    135,  92, 131,   2, 205,  74, 144,  51, 115, 103, 246, 243, 157, 127, 191, 226,
+     82, 155, 216,  38, 200,  55, 198,  59, 129, 150, 111,  75,  19, 190,  99,  46,
+    233, 121, 167, 140, 159, 110, 188, 142,  41, 245, 249, 182,  47, 253, 180,  89,
+    120, 152,   6, 106, 231,  70, 113, 186, 212,  37, 171,  66, 136, 162, 141, 250,
+    114,   7, 185,  85, 248, 238, 172,  10,  54,  73,  42, 104,  60,  56, 241, 164,
+     64,  40, 211, 123, 187, 201,  67, 193,  21, 227, 173, 244, 119, 199, 128, 158
+];
+
+// The other three S-boxes are rotations of the first.
+fn sbox1(x: u64) -> u64 { SBOX1[x as usize & 0xff] as u64 }
+fn sbox2(x: u64) -> u64 { SBOX1[x as usize & 0xff].rotate_left(1) as u64 }
+fn sbox3(x: u64) -> u64 { SBOX1[x as usize & 0xff].rotate_left(7) as u64 }
+fn sbox4(x: u64) -> u64 { SBOX1[(x as u8).rotate_left(1) as usize] as u64 }
+
+// The F function: the S-function followed by the P-function.
+fn f(input: u64, k: u64) -> u64 {
+    let x = input ^ k;
+    let t1 = sbox1(x >> 56);
+    let t2 = sbox2(x >> 48);
+    let t3 = sbox3(x >> 40);
+    let t4 = sbox4(x >> 32);
+    let t5 = sbox2(x >> 24);
+    let t6 = sbox3(x >> 16);
+    let t7 = sbox4(x >> 8);
+    let t8 = sbox1(x);
+
+    let y1 = t1 ^ t3 ^ t4 ^ t6 ^ t7 ^ t8;
+    let y2 = t1 ^ t2 ^ t4 ^ t5 ^ t7 ^ t8;
+    let y3 = t1 ^ t2 ^ t3 ^ t5 ^ t6 ^ t8;
+    let y4 = t2 ^ t3 ^ t4 ^ t5 ^ t6 ^ t7;
+    let y5 = t1 ^ t2 ^ t6 ^ t7 ^ t8;
+    let y6 = t2 ^ t3 ^ t5 ^ t7 ^ t8;
+    let y7 = t3 ^ t4 ^ t5 ^ t6 ^ t8;
+    let y8 = t1 ^ t4 ^ t5 ^ t6 ^ t7;
+
+    (y1 << 56) | (y2 << 48) | (y3 << 40) | (y4 << 32) | (y5 << 24) | (y6 << 16) | (y7 << 8) | y8
+}
+
+fn fl(input: u64, k: u64) -> u64 {
+    let mut x1 = (input >> 32) as u32;
+    let mut x2 = input as u32;
+    let k1 = (k >> 32) as u32;
+    let k2 = k as u32;
+    x2 ^= (x1 & k1).rotate_left(1);
+    x1 ^= x2 | k2;
+    ((x1 as u64) << 32) | (x2 as u64)
+}
+
+fn fl_inv(input: u64, k: u64) -> u64 {
+    let mut y1 = (input >> 32) as u32;
+    let mut y2 = input as u32;
+    let k1 = (k >> 32) as u32;
+    let k2 = k as u32;
+    y1 ^= y2 | k2;
+    y2 ^= (y1 & k1).rotate_left(1);
+    ((y1 as u64) << 32) | (y2 as u64)
+}
+
+// Rotate the 128 bit value (hi, lo) left by n bits, where 0 < n < 128, and return the result in
+// the same form.
+fn rotl128(x: (u64, u64), n: u32) -> (u64, u64) {
+    let (hi, lo) = if n >= 64 { (x.1, x.0) } else { x };
+    let n = n % 64;
+    if n == 0 {
+        (hi, lo)
+    } else {
+        ((hi << n) | (lo >> (64 - n)), (lo << n) | (hi >> (64 - n)))
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Camellia {
+    rounds: usize,
+    kw: [u64; 4],
+    k: [u64; 24],
+    ke: [u64; 6]
+}
+
+impl Camellia {
+    pub fn new(key: &[u8]) -> Camellia {
+        assert!(key.len() == 16 || key.len() == 24 || key.len() == 32);
+
+        let mut kl = [0u64; 2];
+        let mut kr = [0u64; 2];
+        read_u64v_be(&mut kl, &key[0..16]);
+        match key.len() {
+            24 => {
+                read_u64v_be(&mut kr[0..1], &key[16..24]);
+                kr[1] = !kr[0];
+            }
+            32 => read_u64v_be(&mut kr, &key[16..32]),
+            _ => {}
+        }
+
+        let mut d1 = kl[0] ^ kr[0];
+        let mut d2 = kl[1] ^ kr[1];
+        d2 ^= f(d1, SIGMA[0]);
+        d1 ^= f(d2, SIGMA[1]);
+        d1 ^= kl[0];
+        d2 ^= kl[1];
+        d2 ^= f(d1, SIGMA[2]);
+        d1 ^= f(d2, SIGMA[3]);
+        let ka = (d1, d2);
+
+        let kl = (kl[0], kl[1]);
+        let kr = (kr[0], kr[1]);
+
+        let mut c = Camellia { rounds: 0, kw: [0; 4], k: [0; 24], ke: [0; 6] };
+
+        if key.len() == 16 {
+            c.rounds = 18;
+            let keys = [
+                kl, ka, rotl128(kl, 15), rotl128(ka, 15), rotl128(ka, 30), rotl128(kl, 45),
+                rotl128(ka, 45), rotl128(kl, 60), rotl128(ka, 60), rotl128(kl, 77),
+                rotl128(kl, 94), rotl128(ka, 94), rotl128(kl, 111), rotl128(ka, 111)];
+            c.kw[0] = keys[0].0;
+            c.kw[1] = keys[0].1;
+            c.k[0] = keys[1].0;
+            c.k[1] = keys[1].1;
+            c.k[2] = keys[2].0;
+            c.k[3] = keys[2].1;
+            c.k[4] = keys[3].0;
+            c.k[5] = keys[3].1;
+            c.ke[0] = keys[4].0;
+            c.ke[1] = keys[4].1;
+            c.k[6] = keys[5].0;
+            c.k[7] = keys[5].1;
+            c.k[8] = keys[6].0;
+            c.k[9] = keys[7].1;
+            c.k[10] = keys[8].0;
+            c.k[11] = keys[8].1;
+            c.ke[2] = keys[9].0;
+            c.ke[3] = keys[9].1;
+            c.k[12] = keys[10].0;
+            c.k[13] = keys[10].1;
+            c.k[14] = keys[11].0;
+            c.k[15] = keys[11].1;
+            c.k[16] = keys[12].0;
+            c.k[17] = keys[12].1;
+            c.kw[2] = keys[13].0;
+            c.kw[3] = keys[13].1;
+        } else {
+            let mut d1 = ka.0 ^ kr.0;
+            let mut d2 = ka.1 ^ kr.1;
+            d2 ^= f(d1, SIGMA[4]);
+            d1 ^= f(d2, SIGMA[5]);
+            let kb = (d1, d2);
+
+            c.rounds = 24;
+            let keys = [
+                kl, kb, rotl128(kr, 15), rotl128(ka, 15), rotl128(kr, 30), rotl128(kb, 30),
+                rotl128(kl, 45), rotl128(ka, 45), rotl128(kl, 60), rotl128(kr, 60),
+                rotl128(kb, 60), rotl128(kl, 77), rotl128(ka, 77), rotl128(kr, 94),
+                rotl128(ka, 94), rotl128(kl, 111), rotl128(kb, 111)];
+            c.kw[0] = keys[0].0;
+            c.kw[1] = keys[0].1;
+            c.k[0] = keys[1].0;
+            c.k[1] = keys[1].1;
+            c.k[2] = keys[2].0;
+            c.k[3] = keys[2].1;
+            c.k[4] = keys[3].0;
+            c.k[5] = keys[3].1;
+            c.ke[0] = keys[4].0;
+            c.ke[1] = keys[4].1;
+            c.k[6] = keys[5].0;
+            c.k[7] = keys[5].1;
+            c.k[8] = keys[6].0;
+            c.k[9] = keys[6].1;
+            c.k[10] = keys[7].0;
+            c.k[11] = keys[7].1;
+            c.ke[2] = keys[8].0;
+            c.ke[3] = keys[8].1;
+            c.k[12] = keys[9].0;
+            c.k[13] = keys[9].1;
+            c.k[14] = keys[10].0;
+            c.k[15] = keys[10].1;
+            c.k[16] = keys[11].0;
+            c.k[17] = keys[11].1;
+            c.ke[4] = keys[12].0;
+            c.ke[5] = keys[12].1;
+            c.k[18] = keys[13].0;
+            c.k[19] = keys[13].1;
+            c.k[20] = keys[14].0;
+            c.k[21] = keys[14].1;
+            c.k[22] = keys[15].0;
+            c.k[23] = keys[15].1;
+            c.kw[2] = keys[16].0;
+            c.kw[3] = keys[16].1;
+        }
+
+        c
+    }
+
+    // Process any number of blocks together. The rounds for all of the blocks are interleaved
+    // which gives the processor independent work to overlap.
+    fn encrypt_blocks(&self, input: &[u8], output: &mut [u8]) {
+        let n = input.len() / 16;
+        let mut d1 = [0u64; 8];
+        let mut d2 = [0u64; 8];
+        for j in 0..n {
+            let mut b = [0u64; 2];
+            read_u64v_be(&mut b, &input[j * 16..(j + 1) * 16]);
+            d1[j] = b[0] ^ self.kw[0];
+            d2[j] = b[1] ^ self.kw[1];
+        }
+        for i in 0..self.rounds {
+            if i > 0 && i % 6 == 0 {
+                let ke1 = self.ke[i / 3 - 2];
+                let ke2 = self.ke[i / 3 - 1];
+                for j in 0..n {
+                    d1[j] = fl(d1[j], ke1);
+                    d2[j] = fl_inv(d2[j], ke2);
+                }
+            }
+            let k = self.k[i];
+            for j in 0..n {
+                if i % 2 == 0 {
+                    d2[j] ^= f(d1[j], k);
+                } else {
+                    d1[j] ^= f(d2[j], k);
+                }
+            }
+        }
+        for j in 0..n {
+            write_u64_be(&mut output[j * 16..j * 16 + 8], d2[j] ^ self.kw[2]);
+            write_u64_be(&mut output[j * 16 + 8..(j + 1) * 16], d1[j] ^ self.kw[3]);
+        }
+    }
+
+    fn decrypt_blocks(&self, input: &[u8], output: &mut [u8]) {
+        let n = input.len() / 16;
+        let mut d1 = [0u64; 8];
+        let mut d2 = [0u64; 8];
+        for j in 0..n {
+            let mut b = [0u64; 2];
+            read_u64v_be(&mut b, &input[j * 16..(j + 1) * 16]);
+            d1[j] = b[0] ^ self.kw[2];
+            d2[j] = b[1] ^ self.kw[3];
+        }
+        for i in 0..self.rounds {
+            if i > 0 && i % 6 == 0 {
+                let ke1 = self.ke[(self.rounds - i) / 3 - 1];
+                let ke2 = self.ke[(self.rounds - i) / 3 - 2];
+                for j in 0..n {
+                    d1[j] = fl(d1[j], ke1);
+                    d2[j] = fl_inv(d2[j], ke2);
+                }
+            }
+            let k = self.k[self.rounds - 1 - i];
+            for j in 0..n {
+                if i % 2 == 0 {
+                    d2[j] ^= f(d1[j], k);
+                } else {
+                    d1[j] ^= f(d2[j], k);
+                }
+            }
+        }
+        for j in 0..n {
+            write_u64_be(&mut output[j * 16..j * 16 + 8], d2[j] ^ self.kw[0]);
+            write_u64_be(&mut output[j * 16 + 8..(j + 1) * 16], d1[j] ^ self.kw[1]);
+        }
+    }
+}
+
+impl BlockEncryptor for Camellia {
+    fn block_size(&self) -> usize {
+        16
+    }
+
+    fn encrypt_block(&self, input: &[u8], output: &mut [u8]) {
+        assert!(input.len() == 16);
+        assert!(output.len() == 16);
+        self.encrypt_blocks(input, output);
+    }
+}
+
+impl BlockDecryptor for Camellia {
+    fn block_size(&self) -> usize {
+        16
+    }
+
+    fn decrypt_block(&self, input: &[u8], output: &mut [u8]) {
+        assert!(input.len() == 16);
+        assert!(output.len() == 16);
+        self.decrypt_blocks(input, output);
+    }
+}
+
+/// Camellia processing 8 blocks at a time
+#[derive(Clone, Copy)]
+pub struct CamelliaX8 {
+    camellia: Camellia
+}
+
+impl CamelliaX8 {
+    pub fn new(key: &[u8]) -> CamelliaX8 {
+        CamelliaX8 {
+            camellia: Camellia::new(key)
+        }
+    }
+}
+
+impl BlockEncryptorX8 for CamelliaX8 {
+    fn block_size(&self) -> usize {
+        16
+    }
+
+    fn encrypt_block_x8(&self, input: &[u8], output: &mut [u8]) {
+        assert!(input.len() == 128);
+        assert!(output.len() == 128);
+        self.camellia.encrypt_blocks(input, output);
+    }
+}
+
+impl BlockDecryptorX8 for CamelliaX8 {
+    fn block_size(&self) -> usize {
+        16
+    }
+
+    fn decrypt_block_x8(&self, input: &[u8], output: &mut [u8]) {
+        assert!(input.len() == 128);
+        assert!(output.len() == 128);
+        self.camellia.decrypt_blocks(input, output);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use camellia::{Camellia, CamelliaX8};
+    use symmetriccipher::{BlockEncryptor, BlockEncryptorX8, BlockDecryptor, BlockDecryptorX8};
+    use serialize::hex::FromHex;
+
+    struct Test {
+        key: &'static str,
+        plaintext: &'static str,
+        ciphertext: &'static str
+    }
+
+    // Vectors from RFC 3713, Appendix A
+    fn rfc3713_test_vectors() -> Vec<Test> {
+        vec![
+            Test {
+                key: "0123456789abcdeffedcba9876543210",
+                plaintext: "0123456789abcdeffedcba9876543210",
+                ciphertext: "67673138549669730857065648eabe43"
+            },
+            Test {
+                key: "0123456789abcdeffedcba98765432100011223344556677",
+                plaintext: "0123456789abcdeffedcba9876543210",
+                ciphertext: "b4993401b3e996f84ee5cee7d79b09b9"
+            },
+            Test {
+                key: "0123456789abcdeffedcba987654321000112233445566778899aabbccddeeff",
+                plaintext: "0123456789abcdeffedcba9876543210",
+                ciphertext: "9acc237dff16d76c20ef7c919e3a7509"
+            }
+        ]
+    }
+
+    #[test]
+    fn encrypt_rfc3713_test_vectors() {
+        let mut output = [0u8; 16];
+        for test in rfc3713_test_vectors().iter() {
+            let state = Camellia::new(&test.key.from_hex().unwrap());
+            state.encrypt_block(&test.plaintext.from_hex().unwrap(), &mut output);
+            assert_eq!(&output[..], &test.ciphertext.from_hex().unwrap()[..]);
+        }
+    }
+
+    #[test]
+    fn decrypt_rfc3713_test_vectors() {
+        let mut output = [0u8; 16];
+        for test in rfc3713_test_vectors().iter() {
+            let state = Camellia::new(&test.key.from_hex().unwrap());
+            state.decrypt_block(&test.ciphertext.from_hex().unwrap(), &mut output);
+            assert_eq!(&output[..], &test.plaintext.from_hex().unwrap()[..]);
+        }
+    }
+
+    #[test]
+    fn camellia_x8_matches_single_block() {
+        let mut plaintext = [0u8; 128];
+        for (i, p) in plaintext.iter_mut().enumerate() {
+            *p = i as u8;
+        }
+        for test in rfc3713_test_vectors().iter() {
+            let key = test.key.from_hex().unwrap();
+            let state = Camellia::new(&key);
+            let state_x8 = CamelliaX8::new(&key);
+
+            let mut expected = [0u8; 128];
+            for (p, c) in plaintext.chunks(16).zip(expected.chunks_mut(16)) {
+                state.encrypt_block(p, c);
+            }
+
+            let mut ciphertext = [0u8; 128];
+            state_x8.encrypt_block_x8(&plaintext, &mut ciphertext);
+            assert_eq!(&ciphertext[..], &expected[..]);
+
+            let mut decrypted = [0u8; 128];
+            state_x8.decrypt_block_x8(&ciphertext, &mut decrypted);
+            assert_eq!(&decrypted[..], &plaintext[..]);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "with-bench"))]
+mod bench {
+    use camellia::{Camellia, CamelliaX8};
+    use symmetriccipher::{BlockEncryptor, BlockEncryptorX8};
+    use test::Bencher;
+
+    #[bench]
+    fn camellia(bh: &mut Bencher) {
+        let key = [0u8; 16];
+        let plaintext = [1u8; 16];
+        let state = Camellia::new(&key);
+        let mut ciphertext = [0u8; 16];
+
+        bh.iter(|| {
+            state.encrypt_block(&plaintext, &mut ciphertext);
+        });
+        bh.bytes = 16u64;
+    }
+
+    #[bench]
+    fn camellia_x8(bh: &mut Bencher) {
+        let key = [0u8; 16];
+        let plaintext = [1u8; 128];
+        let state = CamelliaX8::new(&key);
+        let mut ciphertext = [0u8; 128];
+
+        bh.iter(|| {
+            state.encrypt_block_x8(&plaintext, &mut ciphertext);
+        });
+        bh.bytes = 128u64;
+    }
+}
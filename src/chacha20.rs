@@ -24,6 +24,7 @@ pub struct ChaCha20 {
     state  : ChaChaState,
     output : [u8; 64],
     offset : usize,
+    rounds : usize,
 }
 
 impl Clone for ChaCha20 { fn clone(&self) -> ChaCha20 { *self } }
@@ -90,10 +91,20 @@ impl ChaCha20 {
         assert!(key.len() == 16 || key.len() == 32);
         assert!(nonce.len() == 8 || nonce.len() == 12);
 
-        ChaCha20{ state: ChaCha20::expand(key, nonce), output: [0u8; 64], offset: 64 }
+        ChaCha20{ state: ChaCha20::expand(key, nonce), output: [0u8; 64], offset: 64, rounds: 20 }
     }
 
     pub fn new_xchacha20(key: &[u8], nonce: &[u8]) -> ChaCha20 {
+        ChaCha20::new_xchacha(key, nonce, 20)
+    }
+
+    /// Create an XChaCha12 instance - XChaCha with the number of rounds reduced to 12, for both the
+    /// HChaCha subkey derivation and the keystream, as used by Adiantum.
+    pub fn new_xchacha12(key: &[u8], nonce: &[u8]) -> ChaCha20 {
+        ChaCha20::new_xchacha(key, nonce, 12)
+    }
+
+    fn new_xchacha(key: &[u8], nonce: &[u8], rounds: usize) -> ChaCha20 {
         assert!(key.len() == 32);
         assert!(nonce.len() == 24);
 
@@ -103,7 +114,7 @@ impl ChaCha20 {
         //  * (x0, x1, x2, x3) is the ChaCha20 constant.
         //  * (x4, x5, ... x11) is a 256 bit key.
         //  * (x12, x13, x14, x15) is a 128 bit nonce.
-        let mut xchacha20 = ChaCha20{ state: ChaCha20::expand(key, &nonce[0..16]), output: [0u8; 64], offset: 64 };
+        let mut xchacha20 = ChaCha20{ state: ChaCha20::expand(key, &nonce[0..16]), output: [0u8; 64], offset: 64, rounds: rounds };
 
         // Use HChaCha to derive the subkey, and initialize a ChaCha20 instance
         // with the subkey and the remaining 8 bytes of the nonce.
@@ -179,7 +190,7 @@ impl ChaCha20 {
 
         // Apply r/2 iterations of the same "double-round" function,
         // obtaining (z0, z1, ... z15) = doubleround r/2 (x0, x1, ... x15).
-        for _ in (0..10) {
+        for _ in (0..self.rounds / 2) {
             round!(state);
             let u32x4(b10, b11, b12, b13) = state.b;
             state.b = u32x4(b11, b12, b13, b10);
@@ -214,7 +225,7 @@ impl ChaCha20 {
     fn update(&mut self) {
         let mut state = self.state;
 
-        for _ in (0..10) {
+        for _ in (0..self.rounds / 2) {
             round!(state);
             swizzle!(state.b, state.c, state.d);
             round!(state);
@@ -475,6 +486,46 @@ mod test {
         assert!(stream[..] == result[..]);
     }
 
+    #[test]
+    fn test_xchacha12_basic() {
+        // Generated with an independent implementation of XChaCha12 - HChaCha12 followed by
+        // ChaCha12 - using the same key and nonce as test_xchacha20_basic().
+        let key =
+            [0x1b, 0x27, 0x55, 0x64, 0x73, 0xe9, 0x85, 0xd4,
+             0x62, 0xcd, 0x51, 0x19, 0x7a, 0x9a, 0x46, 0xc7,
+             0x60, 0x09, 0x54, 0x9e, 0xac, 0x64, 0x74, 0xf2,
+             0x06, 0xc4, 0xee, 0x08, 0x44, 0xf6, 0x83, 0x89];
+        let nonce =
+            [0x69, 0x69, 0x6e, 0xe9, 0x55, 0xb6, 0x2b, 0x73,
+             0xcd, 0x62, 0xbd, 0xa8, 0x75, 0xfc, 0x73, 0xd6,
+             0x82, 0x19, 0xe0, 0x03, 0x6b, 0x7a, 0x0b, 0x37];
+        let input = [0u8; 139];
+        let mut stream = [0u8; 139];
+        let result =
+            [0xe0, 0x13, 0x39, 0x39, 0xfa, 0x5b, 0xba, 0x54,
+             0xb5, 0x36, 0x6e, 0x58, 0x0a, 0x48, 0x86, 0x0b,
+             0x9b, 0x93, 0x62, 0x70, 0x0a, 0xf3, 0xa3, 0x99,
+             0x5f, 0x87, 0x42, 0x27, 0x69, 0x75, 0x46, 0x36,
+             0x53, 0x5a, 0xca, 0x95, 0x55, 0x84, 0x4f, 0xd3,
+             0x0c, 0xf9, 0xd1, 0x99, 0xf4, 0x75, 0xf5, 0xce,
+             0x9d, 0x6a, 0xf1, 0x6e, 0xee, 0x0b, 0x3c, 0xda,
+             0xd2, 0x85, 0x71, 0xb3, 0xe2, 0x79, 0xbe, 0x99,
+             0x51, 0x58, 0x6e, 0x31, 0x2d, 0xd7, 0x63, 0xd6,
+             0x75, 0x12, 0xd8, 0xe7, 0xbb, 0x21, 0xde, 0x8e,
+             0x34, 0xb3, 0x85, 0xa6, 0x19, 0x81, 0x9c, 0x81,
+             0xd9, 0xb0, 0x41, 0xe0, 0xe2, 0x69, 0x66, 0x2f,
+             0x0a, 0x0b, 0x48, 0x7b, 0xfb, 0x95, 0xcf, 0x35,
+             0xe9, 0x08, 0x37, 0x87, 0x90, 0x72, 0x98, 0x05,
+             0xf6, 0x60, 0x9e, 0x4a, 0xed, 0x5f, 0xca, 0x23,
+             0x01, 0x7f, 0xbd, 0x29, 0x45, 0xd7, 0x71, 0x96,
+             0x60, 0x80, 0xe4, 0x9a, 0x7a, 0x17, 0x89, 0x13,
+             0x67, 0xaa, 0x7e];
+
+        let mut xchacha12 = ChaCha20::new_xchacha12(&key, &nonce);
+        xchacha12.process(&input, &mut stream);
+        assert!(stream[..] == result[..]);
+    }
+
     #[test]
     fn test_chacha20_256_tls_vectors_96_nonce() {
         struct TestVector {
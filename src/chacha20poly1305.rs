@@ -4,7 +4,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use aead::{AeadEncryptor,AeadDecryptor};
+use aead::{AeadEncryptor, AeadDecryptor, AeadStreamEncryptor, AeadStreamDecryptor};
 
 use chacha20::ChaCha20;
 use symmetriccipher::SynchronousStreamCipher;
@@ -17,66 +17,127 @@ pub struct ChaCha20Poly1305 {
     cipher  : ChaCha20,
     mac: Poly1305,
     finished: bool,
-    data_len: usize
+    data_len: usize,
+    aad_len: usize,
+    // Whether the aad has been completed by its length or padding, which happens when the
+    // message starts
+    aad_finished: bool,
+    // Whether the construction from RFC 8439, which pads the aad and the cipher text to 16 bytes
+    // and places both lengths at the end, is used
+    ietf: bool
+}
+
+// Pads the data authenticated so far with zeros to a multiple of 16 bytes
+fn pad16(mac: &mut Poly1305, len: usize) {
+    let zeros = [0u8; 16];
+    mac.input(&zeros[..(16 - len % 16) % 16]);
+}
+
+fn input_len(mac: &mut Poly1305, len: usize) {
+    let mut len_buf = [0u8; 8];
+    write_u64_le(&mut len_buf, len as u64);
+    mac.input(&len_buf);
 }
 
 impl ChaCha20Poly1305 {
+  /// Create an instance of the original ChaCha20-Poly1305 construction, with an 8 byte nonce,
+  /// as specified by draft-agl-tls-chacha20poly1305.
   pub fn new(key: &[u8], nonce: &[u8], aad: &[u8]) -> ChaCha20Poly1305 {
       assert!(key.len() == 16 || key.len() == 32);
       assert!(nonce.len() == 8);
 
-      let mut cipher = ChaCha20::new(key, nonce);
+      ChaCha20Poly1305::with_cipher(ChaCha20::new(key, nonce), aad, false)
+  }
+
+  /// Create an instance of the ChaCha20-Poly1305 construction from RFC 8439, with a 12 byte
+  /// nonce, as used by TLS 1.3 and WireGuard.
+  pub fn new_ietf(key: &[u8], nonce: &[u8], aad: &[u8]) -> ChaCha20Poly1305 {
+      assert!(key.len() == 32);
+      assert!(nonce.len() == 12);
+
+      ChaCha20Poly1305::with_cipher(ChaCha20::new(key, nonce), aad, true)
+  }
+
+  /// Create an instance of XChaCha20-Poly1305, the RFC 8439 construction with a 24 byte nonce,
+  /// as specified by draft-irtf-cfrg-xchacha. The nonce is long enough to be chosen at random.
+  pub fn new_xchacha20(key: &[u8], nonce: &[u8], aad: &[u8]) -> ChaCha20Poly1305 {
+      assert!(key.len() == 32);
+      assert!(nonce.len() == 24);
+
+      ChaCha20Poly1305::with_cipher(ChaCha20::new_xchacha20(key, nonce), aad, true)
+  }
+
+  fn with_cipher(mut cipher: ChaCha20, aad: &[u8], ietf: bool) -> ChaCha20Poly1305 {
       let mut mac_key = [0u8; 64];
       let zero_key = [0u8; 64];
       cipher.process(&zero_key, &mut mac_key);
 
       let mut mac = Poly1305::new(&mac_key[..32]);
       mac.input(aad);
-      let mut aad_len = [0u8; 8];
-      let aad_len_uint: u64 = aad.len() as u64;
-      write_u64_le(&mut aad_len, aad_len_uint);
-      mac.input(&aad_len);
       ChaCha20Poly1305 {
         cipher: cipher,
         mac: mac,
         finished: false,
-        data_len: 0
+        data_len: 0,
+        aad_len: aad.len(),
+        aad_finished: false,
+        ietf: ietf
+      }
+  }
+
+  fn update_aad(&mut self, aad: &[u8]) {
+      assert!(!self.finished);
+      assert!(!self.aad_finished, "The aad must be supplied before the message");
+      self.mac.input(aad);
+      self.aad_len += aad.len();
+  }
+
+  fn finish_aad(&mut self) {
+      if !self.aad_finished {
+          if self.ietf {
+              pad16(&mut self.mac, self.aad_len);
+          } else {
+              input_len(&mut self.mac, self.aad_len);
+          }
+          self.aad_finished = true;
       }
   }
+
+  fn update_mac(&mut self, cipher_text: &[u8]) {
+      assert!(!self.finished);
+      self.finish_aad();
+      self.mac.input(cipher_text);
+      self.data_len += cipher_text.len();
+  }
+
+  fn compute_tag(&mut self, tag: &mut [u8]) {
+      assert!(!self.finished);
+      self.finished = true;
+      self.finish_aad();
+      if self.ietf {
+          pad16(&mut self.mac, self.data_len);
+          input_len(&mut self.mac, self.aad_len);
+      }
+      input_len(&mut self.mac, self.data_len);
+      self.mac.raw_result(tag);
+  }
 }
 
 impl AeadEncryptor for ChaCha20Poly1305 {
     fn encrypt(&mut self, input: &[u8], output: &mut [u8], out_tag: &mut [u8]) {
         assert!(input.len() == output.len());
-        assert!(self.finished == false);
-        self.cipher.process(input, output);
-        self.data_len += input.len();
-        self.mac.input(output);
-        self.finished = true;
-        let mut data_len_buf = [0u8; 8];
-        write_u64_le(&mut data_len_buf, self.data_len as u64);
-        self.mac.input(&data_len_buf);
-        self.mac.raw_result(out_tag);
+        self.encrypt_update(input, output);
+        self.encrypt_finish(out_tag);
     }
 }
 
 impl AeadDecryptor for ChaCha20Poly1305 {
     fn decrypt(&mut self, input: &[u8], output: &mut [u8], tag: &[u8]) -> bool {
         assert!(input.len() == output.len());
-        assert!(self.finished == false);
-
-        self.finished = true;
-
-        self.mac.input(input);
-
-        self.data_len += input.len();
-        let mut data_len_buf = [0u8; 8];
-
-        write_u64_le(&mut data_len_buf, self.data_len as u64);
-        self.mac.input(&data_len_buf);
 
         let mut calc_tag =  [0u8; 16];
-        self.mac.raw_result(&mut calc_tag);
+        self.update_mac(input);
+        self.compute_tag(&mut calc_tag);
         if fixed_time_eq(&calc_tag, tag) {
             self.cipher.process(input, output);
             true
@@ -85,12 +146,49 @@ impl AeadDecryptor for ChaCha20Poly1305 {
         }
     }
 }
+
+impl AeadStreamEncryptor for ChaCha20Poly1305 {
+    fn encrypt_aad(&mut self, aad: &[u8]) {
+        self.update_aad(aad);
+    }
+
+    fn encrypt_update(&mut self, input: &[u8], output: &mut [u8]) {
+        assert!(input.len() == output.len());
+        assert!(!self.finished);
+        self.cipher.process(input, output);
+        self.update_mac(output);
+    }
+
+    fn encrypt_finish(&mut self, tag: &mut [u8]) {
+        self.compute_tag(tag);
+    }
+}
+
+impl AeadStreamDecryptor for ChaCha20Poly1305 {
+    fn decrypt_aad(&mut self, aad: &[u8]) {
+        self.update_aad(aad);
+    }
+
+    fn decrypt_update(&mut self, input: &[u8], output: &mut [u8]) {
+        assert!(input.len() == output.len());
+        self.update_mac(input);
+        self.cipher.process(input, output);
+    }
+
+    fn decrypt_finish(&mut self, tag: &[u8]) -> bool {
+        let mut calc_tag =  [0u8; 16];
+        self.compute_tag(&mut calc_tag);
+        fixed_time_eq(&calc_tag, tag)
+    }
+}
+
 #[cfg(test)]
 mod test {
   use std::iter::repeat;
 
   use chacha20poly1305::ChaCha20Poly1305;
-  use aead::{AeadEncryptor,AeadDecryptor};
+  use aead::{AeadEncryptor, AeadDecryptor, AeadStreamEncryptor, AeadStreamDecryptor};
+  use serialize::hex::FromHex;
   struct TestVector {
     key:   [u8; 32],
     nonce: [u8; 8],
@@ -129,6 +227,204 @@ mod test {
       assert!(result);
     }
   }
+  fn hex_to_bytes(raw_hex: &str) -> Vec<u8> {
+    raw_hex.from_hex().ok().unwrap()
+  }
+
+  #[derive(Clone)]
+  struct IetfTestVector {
+    key: Vec<u8>,
+    nonce: Vec<u8>,
+    aad: Vec<u8>,
+    plain_text: Vec<u8>,
+    cipher_text: Vec<u8>,
+    tag: Vec<u8>
+  }
+
+  fn new_ietf(tv: &IetfTestVector) -> ChaCha20Poly1305 {
+    if tv.nonce.len() == 24 {
+      ChaCha20Poly1305::new_xchacha20(&tv.key[..], &tv.nonce[..], &tv.aad[..])
+    } else {
+      ChaCha20Poly1305::new_ietf(&tv.key[..], &tv.nonce[..], &tv.aad[..])
+    }
+  }
+
+  #[test]
+  fn test_chacha20_poly1305_ietf_encrypt() {
+    for tv in get_ietf_test_vectors().iter() {
+      let mut c = new_ietf(tv);
+      let mut output: Vec<u8> = repeat(0).take(tv.plain_text.len()).collect();
+      let mut tag: Vec<u8> = repeat(0).take(16).collect();
+      c.encrypt(&tv.plain_text[..], &mut output[..], &mut tag[..]);
+      assert_eq!(output, tv.cipher_text);
+      assert_eq!(tag, tv.tag);
+    }
+  }
+
+  #[test]
+  fn test_chacha20_poly1305_ietf_decrypt() {
+    for tv in get_ietf_test_vectors().iter() {
+      let mut c = new_ietf(tv);
+      let mut output: Vec<u8> = repeat(0).take(tv.plain_text.len()).collect();
+      let result = c.decrypt(&tv.cipher_text[..], &mut output[..], &tv.tag[..]);
+      assert_eq!(output, tv.plain_text);
+      assert!(result);
+    }
+  }
+
+  #[test]
+  fn test_chacha20_poly1305_ietf_decrypt_fail() {
+    for tv in get_ietf_test_vectors().iter() {
+      let mut c = new_ietf(tv);
+      let mut tag = tv.tag.clone();
+      tag[15] ^= 0x80;
+      let mut output: Vec<u8> = repeat(0).take(tv.plain_text.len()).collect();
+      assert!(!c.decrypt(&tv.cipher_text[..], &mut output[..], &tag[..]));
+    }
+  }
+
+  fn stream_encrypt(c: &mut ChaCha20Poly1305, aad: &[u8], plain_text: &[u8], chunk_size: usize)
+      -> (Vec<u8>, Vec<u8>) {
+    for a in aad.chunks(chunk_size) {
+      c.encrypt_aad(a);
+    }
+    let mut output: Vec<u8> = repeat(0).take(plain_text.len()).collect();
+    for (i, o) in plain_text.chunks(chunk_size).zip(output.chunks_mut(chunk_size)) {
+      c.encrypt_update(i, o);
+    }
+    let mut tag: Vec<u8> = repeat(0).take(16).collect();
+    c.encrypt_finish(&mut tag[..]);
+    (output, tag)
+  }
+
+  #[test]
+  fn test_chacha20_poly1305_stream_encrypt() {
+    for &chunk_size in [1, 15, 16, 17, 64].iter() {
+      for tv in get_test_vectors().iter() {
+        if tv.tag.len() < 16 {
+          continue;
+        }
+        let mut c = ChaCha20Poly1305::new(&tv.key, &tv.nonce, &[]);
+        let (output, tag) = stream_encrypt(&mut c, &tv.aad, &tv.plain_text, chunk_size);
+        assert_eq!(output, tv.cipher_text);
+        assert_eq!(tag, tv.tag);
+      }
+      for tv in get_ietf_test_vectors().iter() {
+        let mut c = new_ietf(&IetfTestVector { aad: vec!(), ..tv.clone() });
+        let (output, tag) = stream_encrypt(&mut c, &tv.aad, &tv.plain_text, chunk_size);
+        assert_eq!(output, tv.cipher_text);
+        assert_eq!(tag, tv.tag);
+      }
+    }
+  }
+
+  #[test]
+  fn test_chacha20_poly1305_stream_decrypt() {
+    for &chunk_size in [1, 15, 16, 17, 64].iter() {
+      for tv in get_ietf_test_vectors().iter() {
+        let mut c = new_ietf(&IetfTestVector { aad: vec!(), ..tv.clone() });
+        for a in tv.aad.chunks(chunk_size) {
+          c.decrypt_aad(a);
+        }
+        let mut output: Vec<u8> = repeat(0).take(tv.plain_text.len()).collect();
+        for (i, o) in tv.cipher_text.chunks(chunk_size).zip(output.chunks_mut(chunk_size)) {
+          c.decrypt_update(i, o);
+        }
+        assert_eq!(output, tv.plain_text);
+        assert!(c.decrypt_finish(&tv.tag[..]));
+
+        let mut c = new_ietf(tv);
+        c.decrypt_update(&tv.cipher_text[..], &mut output[..]);
+        assert!(!c.decrypt_finish(&[0u8; 16]));
+      }
+    }
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_chacha20_poly1305_stream_aad_after_message() {
+    let mut c = ChaCha20Poly1305::new_ietf(&[0; 32], &[0; 12], &[]);
+    let mut output = [0u8; 16];
+    c.encrypt_update(&[0; 16], &mut output);
+    c.encrypt_aad(&[0; 16]);
+  }
+
+  // The first vector is from RFC 8439 section 2.8.2 and the second one from
+  // draft-irtf-cfrg-xchacha-03 appendix A.3.1. The others were generated with the Python
+  // cryptography package.
+  fn get_ietf_test_vectors() -> Vec<IetfTestVector> {
+    let sunscreen = hex_to_bytes("4c616469657320616e642047656e746c656d656e206f662074686520636c6173\
+                                  73206f66202739393a204966204920636f756c64206f6666657220796f75206f\
+                                  6e6c79206f6e652074697020666f7220746865206675747572652c2073756e73\
+                                  637265656e20776f756c642062652069742e");
+    vec!(
+      IetfTestVector {
+        key: hex_to_bytes("808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9f"),
+        nonce: hex_to_bytes("070000004041424344454647"),
+        aad: hex_to_bytes("50515253c0c1c2c3c4c5c6c7"),
+        plain_text: sunscreen.clone(),
+        cipher_text: hex_to_bytes("d31a8d34648e60db7b86afbc53ef7ec2a4aded51296e08fea9e2b5a736ee62d6\
+                                   3dbea45e8ca9671282fafb69da92728b1a71de0a9e060b2905d6a5b67ecd3b36\
+                                   92ddbd7f2d778b8c9803aee328091b58fab324e4fad675945585808b4831d7bc\
+                                   3ff4def08e4b7a9de576d26586cec64b6116"),
+        tag: hex_to_bytes("1ae10b594f09e26a7e902ecbd0600691")
+      },
+      IetfTestVector {
+        key: hex_to_bytes("808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9f"),
+        nonce: hex_to_bytes("404142434445464748494a4b4c4d4e4f5051525354555657"),
+        aad: hex_to_bytes("50515253c0c1c2c3c4c5c6c7"),
+        plain_text: sunscreen,
+        cipher_text: hex_to_bytes("bd6d179d3e83d43b9576579493c0e939572a1700252bfaccbed2902c21396cbb\
+                                   731c7f1b0b4aa6440bf3a82f4eda7e39ae64c6708c54c216cb96b72e1213b452\
+                                   2f8c9ba40db5d945b11b69b982c1bb9e3f3fac2bc369488f76b2383565d3fff9\
+                                   21f9664c97637da9768812f615c68b13b52e"),
+        tag: hex_to_bytes("c0875924c1c7987947deafd8780acf49")
+      },
+      IetfTestVector {
+        key: hex_to_bytes("10d4bbf52de6f612be85c27dc8cd4ef796fe3be82309f66ce5bad9945d5ddaa8"),
+        nonce: hex_to_bytes("7198a271244a2bbbba39e0c0"),
+        aad: vec!(),
+        plain_text: vec!(),
+        cipher_text: vec!(),
+        tag: hex_to_bytes("f025226167e22f376b9a6f039be3c54e")
+      },
+      IetfTestVector {
+        key: hex_to_bytes("e8eb5913fafcabbe1858eef4b888a79a9c7271a6e478a81e9f3591b4ef526223"),
+        nonce: hex_to_bytes("17be7b7f068441b041c592ec"),
+        aad: hex_to_bytes("fcff3a6d6a"),
+        plain_text: hex_to_bytes("277aef5f3015b396a88d9ed0b311bfdea8afd379c52f9c65fadea5c65f2602e6e1"),
+        cipher_text: hex_to_bytes("412879435e3dd04ef3df8a8959330479de7d6a1dc56f2089b085fc69f293f67754"),
+        tag: hex_to_bytes("77ed408b99e15a0bb479505c6f3897a1")
+      },
+      IetfTestVector {
+        key: hex_to_bytes("e17d8212745f038707d90fee57233dd9353122052e97f1e54ef73c8ab2ef0f53"),
+        nonce: hex_to_bytes("1d6074b7cf15647d9679331f8ad6298cca36728023d7baad"),
+        aad: vec!(),
+        plain_text: vec!(),
+        cipher_text: vec!(),
+        tag: hex_to_bytes("f6a56cdb8c94cba92b50bf16df7bde83")
+      },
+      IetfTestVector {
+        key: hex_to_bytes("2b4a273cc47443848cab0d7014db7bf2221393cd3a2ec1d80c1fa3f666b22f23"),
+        nonce: hex_to_bytes("3943f4a9b122811ec7e87b7ed80a743f6b2ddd4d1adf3936"),
+        aad: hex_to_bytes("72ecd5980b8f54dcf3ab4e970031f57408"),
+        plain_text: hex_to_bytes("8a"),
+        cipher_text: hex_to_bytes("db"),
+        tag: hex_to_bytes("6b8ab8262508beeeb6e84f229685b9cb")
+      },
+      IetfTestVector {
+        key: hex_to_bytes("8aebc7d8effe978e3d78cd358974be212ea356cbd70203c8207d111ea100f0d2"),
+        nonce: hex_to_bytes("909126070b806b99abeca89e1b7347bdc1eb6d0a0f1cf74f"),
+        aad: vec!(),
+        plain_text: hex_to_bytes("1889580c13be08c53fc26097af0d0ffc76af67d3afca5756c161ceeb8212011d\
+                                  28932b19743b5d3a283b639fd7279618e71c3f2b822b4a84c182c68d430dcdda"),
+        cipher_text: hex_to_bytes("718b7e2cdc9056691bda1692692730ecf7db3c9b98b32c2477c895968e48e0a1\
+                                   9264cbc626b02306631da9aa474a200bfb1ec74716a53017e3ff9665e1d0f6e9"),
+        tag: hex_to_bytes("5253d2ca1e929f6990211e9857540046")
+      }
+    )
+  }
+
   fn get_test_vectors()-> Vec<TestVector>{
     vec!(
       TestVector {
@@ -798,4 +1094,22 @@ mod bench {
          bh.bytes = 65536u64;
 
     }
+
+    #[bench]
+    pub fn chacha20poly1305_ietf_1k(bh: & mut Bencher) {
+      let input = [1u8; 1024];
+      let aad = [3u8; 1024];
+      bh.iter( || {
+        let mut cipher = ChaCha20Poly1305::new_ietf(&[0; 32], &[0; 12], &aad);
+        let mut decipher = ChaCha20Poly1305::new_ietf(&[0; 32], &[0; 12], &aad);
+
+        let mut output = [0u8; 1024];
+        let mut tag = [0u8; 16];
+        let mut output2 = [0u8; 1024];
+
+        cipher.encrypt(&input, &mut output, &mut tag);
+        decipher.decrypt(&output, &mut output2, &tag);
+        });
+      bh.bytes = 1024u64;
+    }
 }
@@ -0,0 +1,238 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+ * This module implements CMAC (also known as OMAC1) - a Message Authentication Code built from a
+ * block cipher, as defined in NIST SP 800-38B [1] and RFC 4493 [2]. Block ciphers with 64 and 128
+ * bit blocks are supported.
+ *
+ * [1] - "Recommendation for Block Cipher Modes of Operation: The CMAC Mode for Authentication"
+ *       <http://csrc.nist.gov/publications/nistpubs/800-38B/SP_800-38B.pdf>
+ * [2] - "The AES-CMAC Algorithm" - J. H. Song, R. Poovendran, J. Lee and T. Iwata
+ *       <https://tools.ietf.org/html/rfc4493>
+ */
+
+use std::iter::repeat;
+
+use cryptoutil::copy_memory;
+use mac::{Mac, MacResult};
+use symmetriccipher::BlockEncryptor;
+
+/**
+ * The Cmac struct represents a CMAC function - a Message Authentication Code using a block cipher.
+ */
+pub struct Cmac<E> {
+    cipher: E,
+    key1: Vec<u8>,
+    key2: Vec<u8>,
+    state: Vec<u8>,
+    buffer: Vec<u8>,
+    buffer_len: usize,
+    finished: bool
+}
+
+// Multiply a big endian value by x in GF(2^n) in place, where n is 64 or 128 bits. This is also
+// used by the modes built on the same doubling operation, such as OCB and SIV.
+pub(crate) fn double(block: &mut [u8]) {
+    let rb = match block.len() {
+        8 => 0x1b,
+        16 => 0x87,
+        _ => panic!("Unsupported block size")
+    };
+    let mut carry = 0;
+    for b in block.iter_mut().rev() {
+        let next_carry = *b >> 7;
+        *b = (*b << 1) | carry;
+        carry = next_carry;
+    }
+    let last = block.len() - 1;
+    block[last] ^= rb & 0u8.wrapping_sub(carry);
+}
+
+impl <E: BlockEncryptor> Cmac<E> {
+    /**
+     * Create a new Cmac instance.
+     *
+     * # Arguments
+     * * cipher - The keyed block cipher to use.
+     *
+     */
+    pub fn new(cipher: E) -> Cmac<E> {
+        let block_size = cipher.block_size();
+        let zero: Vec<u8> = repeat(0).take(block_size).collect();
+        let mut l: Vec<u8> = repeat(0).take(block_size).collect();
+        cipher.encrypt_block(&zero, &mut l);
+        let mut key1 = l;
+        double(&mut key1);
+        let mut key2 = key1.clone();
+        double(&mut key2);
+        Cmac {
+            cipher: cipher,
+            key1: key1,
+            key2: key2,
+            state: zero.clone(),
+            buffer: zero,
+            buffer_len: 0,
+            finished: false
+        }
+    }
+
+    fn process_buffer(&mut self) {
+        for (s, &b) in self.state.iter_mut().zip(self.buffer.iter()) {
+            *s ^= b;
+        }
+        let tmp = self.state.clone();
+        self.cipher.encrypt_block(&tmp, &mut self.state);
+        self.buffer_len = 0;
+    }
+}
+
+impl <E: BlockEncryptor> Mac for Cmac<E> {
+    fn input(&mut self, data: &[u8]) {
+        assert!(!self.finished);
+        let block_size = self.buffer.len();
+        let mut data = data;
+
+        // The last block is treated differently, so a full buffer is only processed once we know
+        // that more data follows it.
+        while !data.is_empty() {
+            if self.buffer_len == block_size {
+                self.process_buffer();
+            }
+            let count = ::std::cmp::min(block_size - self.buffer_len, data.len());
+            copy_memory(&data[..count], &mut self.buffer[self.buffer_len..self.buffer_len + count]);
+            self.buffer_len += count;
+            data = &data[count..];
+        }
+    }
+
+    fn reset(&mut self) {
+        for s in self.state.iter_mut() {
+            *s = 0;
+        }
+        self.buffer_len = 0;
+        self.finished = false;
+    }
+
+    fn result(&mut self) -> MacResult {
+        let mut code: Vec<u8> = repeat(0).take(self.output_bytes()).collect();
+
+        self.raw_result(&mut code);
+
+        MacResult::new_from_owned(code)
+    }
+
+    fn raw_result(&mut self, output: &mut [u8]) {
+        if !self.finished {
+            let block_size = self.buffer.len();
+            if self.buffer_len == block_size {
+                for (b, &k) in self.buffer.iter_mut().zip(self.key1.iter()) {
+                    *b ^= k;
+                }
+            } else {
+                self.buffer[self.buffer_len] = 0x80;
+                for b in self.buffer[self.buffer_len + 1..].iter_mut() {
+                    *b = 0;
+                }
+                for (b, &k) in self.buffer.iter_mut().zip(self.key2.iter()) {
+                    *b ^= k;
+                }
+            }
+            self.process_buffer();
+            self.finished = true;
+        }
+
+        copy_memory(&self.state, output);
+    }
+
+    fn output_bytes(&self) -> usize { self.state.len() }
+}
+
+#[cfg(test)]
+mod test {
+    use aessafe::{AesSafe128Encryptor, AesSafe192Encryptor, AesSafe256Encryptor};
+    use blowfish::Blowfish;
+    use cmac::Cmac;
+    use mac::{Mac, MacResult};
+    use serialize::hex::FromHex;
+    use symmetriccipher::BlockEncryptor;
+
+    // Test vectors from NIST SP 800-38B Appendix D.1 - D.3. The AES-128 vectors also appear in
+    // RFC 4493.
+    static MESSAGE: &'static str =
+        "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51\
+         30c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710";
+
+    // Format: (key, message length, expected mac)
+    static CASES: &'static [(&'static str, usize, &'static str)] = &[
+        ("2b7e151628aed2a6abf7158809cf4f3c", 0, "bb1d6929e95937287fa37d129b756746"),
+        ("2b7e151628aed2a6abf7158809cf4f3c", 16, "070a16b46b4d4144f79bdd9dd04a287c"),
+        ("2b7e151628aed2a6abf7158809cf4f3c", 40, "dfa66747de9ae63030ca32611497c827"),
+        ("2b7e151628aed2a6abf7158809cf4f3c", 64, "51f0bebf7e3b9d92fc49741779363cfe"),
+        ("8e73b0f7da0e6452c810f32b809079e562f8ead2522c6b7b", 0,
+         "d17ddf46adaacde531cac483de7a9367"),
+        ("8e73b0f7da0e6452c810f32b809079e562f8ead2522c6b7b", 16,
+         "9e99a7bf31e710900662f65e617c5184"),
+        ("8e73b0f7da0e6452c810f32b809079e562f8ead2522c6b7b", 40,
+         "8a1de5be2eb31aad089a82e6ee908b0e"),
+        ("8e73b0f7da0e6452c810f32b809079e562f8ead2522c6b7b", 64,
+         "a1d5df0eed790f794d77589659f39a11"),
+        ("603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4", 0,
+         "028962f61b7bf89efc6b551f4667d983"),
+        ("603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4", 16,
+         "28a7023f452e8f82bd4bf28d8c37c35c"),
+        ("603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4", 40,
+         "aaf3d8f1de5640c232f5b169b9c911e6"),
+        ("603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4", 64,
+         "e1992190549f6ed5696a2c056c315410"),
+    ];
+
+    fn check<E: BlockEncryptor>(cipher: E, data: &[u8], expected: &[u8]) {
+        let mut cmac = Cmac::new(cipher);
+        cmac.input(data);
+        assert!(cmac.result() == MacResult::new(expected));
+
+        // Feeding the data in pieces must give the same result
+        for split in 0..data.len() {
+            cmac.reset();
+            cmac.input(&data[..split]);
+            cmac.input(&data[split..]);
+            assert!(cmac.result() == MacResult::new(expected));
+        }
+    }
+
+    #[test]
+    fn test_cmac_aes() {
+        let message = MESSAGE.from_hex().unwrap();
+        for &(key, len, expected) in CASES.iter() {
+            let key = key.from_hex().unwrap();
+            let expected = expected.from_hex().unwrap();
+            match key.len() {
+                16 => check(AesSafe128Encryptor::new(&key), &message[..len], &expected),
+                24 => check(AesSafe192Encryptor::new(&key), &message[..len], &expected),
+                32 => check(AesSafe256Encryptor::new(&key), &message[..len], &expected),
+                _ => unreachable!()
+            }
+        }
+    }
+
+    // CMAC with a 64 bit block cipher uses a different constant when doubling the subkeys. The
+    // expected values were computed with OpenSSL.
+    #[test]
+    fn test_cmac_blowfish() {
+        let key = "0123456789abcdeff0e1d2c3b4a59687".from_hex().unwrap();
+        let message = MESSAGE.from_hex().unwrap();
+        let cases = [
+            (0, "c4384e723f275260"),
+            (8, "b5970cc3319cf7a5"),
+            (20, "e367b824b24ac2f8"),
+            (32, "253c79b17bb91ea1")
+        ];
+        for &(len, expected) in cases.iter() {
+            check(Blowfish::new(&key), &message[..len], &expected.from_hex().unwrap());
+        }
+    }
+}
@@ -0,0 +1,335 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+ * An implementation of the EAX mode of authenticated encryption [1] for block ciphers with a 128
+ * bit block size.
+ *
+ * EAX combines CTR mode encryption with OMAC (CMAC) authentication. The nonce and the associated
+ * data (header) are each authenticated with a separate tweak of OMAC, so nonces of any length are
+ * supported. The tag may be truncated to any length up to 16 bytes.
+ *
+ * [1] - "The EAX Mode of Operation" - M. Bellare, P. Rogaway and D. Wagner
+ *       <http://web.cs.ucdavis.edu/~rogaway/papers/eax.pdf>
+ */
+
+use aead::{AeadEncryptor, AeadDecryptor};
+use blockmodes::CtrMode;
+use cmac::Cmac;
+use cryptoutil::copy_memory;
+use mac::Mac;
+use symmetriccipher::{BlockEncryptor, SynchronousStreamCipher};
+use util::fixed_time_eq;
+
+/// EAX authenticated encryption using a block cipher with a 128 bit block size. A new instance
+/// must be created for each message.
+pub struct Eax<E> {
+    cipher: CtrMode<E>,
+    mac: Cmac<E>,
+    // OMAC of the nonce xored with OMAC of the header
+    nonce_header_mac: [u8; 16],
+    finished: bool
+}
+
+// Computes OMAC^t_K(data) = CMAC_K([t]_16 || data)
+fn omac<E: BlockEncryptor>(mac: &mut Cmac<E>, tweak: u8, data: &[u8]) -> [u8; 16] {
+    let mut tweak_block = [0u8; 16];
+    tweak_block[15] = tweak;
+    let mut result = [0u8; 16];
+    mac.reset();
+    mac.input(&tweak_block);
+    mac.input(data);
+    mac.raw_result(&mut result);
+    result
+}
+
+impl <E: BlockEncryptor + Clone> Eax<E> {
+    /**
+     * Create a new Eax instance.
+     *
+     * # Arguments
+     * * cipher - The keyed block cipher to use. It must have a 128 bit block size.
+     * * nonce - The nonce, which may be of any length.
+     * * aad - The associated data (header) to authenticate.
+     *
+     */
+    pub fn new(cipher: E, nonce: &[u8], aad: &[u8]) -> Eax<E> {
+        assert!(cipher.block_size() == 16);
+
+        let mut mac = Cmac::new(cipher.clone());
+        let nonce_mac = omac(&mut mac, 0, nonce);
+        let header_mac = omac(&mut mac, 1, aad);
+
+        let mut nonce_header_mac = [0u8; 16];
+        for ((x, &n), &h) in
+                nonce_header_mac.iter_mut().zip(nonce_mac.iter()).zip(header_mac.iter()) {
+            *x = n ^ h;
+        }
+
+        // The ciphertext is authenticated with the third tweak
+        let mut tweak_block = [0u8; 16];
+        tweak_block[15] = 2;
+        mac.reset();
+        mac.input(&tweak_block);
+
+        Eax {
+            cipher: CtrMode::new(cipher, nonce_mac.to_vec()),
+            mac: mac,
+            nonce_header_mac: nonce_header_mac,
+            finished: false
+        }
+    }
+
+    fn compute_tag(&mut self, cipher_text: &[u8]) -> [u8; 16] {
+        let mut tag = [0u8; 16];
+        self.mac.input(cipher_text);
+        self.mac.raw_result(&mut tag);
+        for (t, &x) in tag.iter_mut().zip(self.nonce_header_mac.iter()) {
+            *t ^= x;
+        }
+        tag
+    }
+}
+
+impl <E: BlockEncryptor + Clone> AeadEncryptor for Eax<E> {
+    fn encrypt(&mut self, input: &[u8], output: &mut [u8], tag: &mut [u8]) {
+        assert!(input.len() == output.len());
+        assert!(!tag.is_empty() && tag.len() <= 16);
+        assert!(!self.finished);
+        self.finished = true;
+        self.cipher.process(input, output);
+        let full_tag = self.compute_tag(output);
+        copy_memory(&full_tag[..tag.len()], tag);
+    }
+}
+
+impl <E: BlockEncryptor + Clone> AeadDecryptor for Eax<E> {
+    fn decrypt(&mut self, input: &[u8], output: &mut [u8], tag: &[u8]) -> bool {
+        assert!(input.len() == output.len());
+        assert!(!tag.is_empty() && tag.len() <= 16);
+        assert!(!self.finished);
+        self.finished = true;
+        let calc_tag = self.compute_tag(input);
+        if fixed_time_eq(&calc_tag[..tag.len()], tag) {
+            self.cipher.process(input, output);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::iter::repeat;
+
+    use aead::{AeadEncryptor, AeadDecryptor};
+    use aessafe::{AesSafe128Encryptor, AesSafe192Encryptor, AesSafe256Encryptor};
+    use eax::Eax;
+    use serialize::hex::FromHex;
+    use symmetriccipher::BlockEncryptor;
+
+    struct TestVector {
+        key: &'static str,
+        nonce: &'static str,
+        aad: &'static str,
+        plain_text: &'static str,
+        cipher_text: &'static str,
+        tag: &'static str
+    }
+
+    // The first four vectors are from the EAX paper. The remaining vectors cover other key, nonce
+    // and message sizes and were generated with an independent implementation built on the CMAC and
+    // CTR primitives of the Python cryptography package.
+    static TESTS: [TestVector; 8] = [
+        TestVector {
+            key: "233952dee4d5ed5f9b9c6d6ff80ff478",
+            nonce: "62ec67f9c3a4a407fcb2a8c49031a8b3",
+            aad: "6bfb914fd07eae6b",
+            plain_text: "",
+            cipher_text: "",
+            tag: "e037830e8389f27b025a2d6527e79d01"
+        },
+        TestVector {
+            key: "91945d3f4dcbee0bf45ef52255f095a4",
+            nonce: "becaf043b0a23d843194ba972c66debd",
+            aad: "fa3bfd4806eb53fa",
+            plain_text: "f7fb",
+            cipher_text: "19dd",
+            tag: "5c4c9331049d0bdab0277408f67967e5"
+        },
+        TestVector {
+            key: "01f74ad64077f2e704c0f60ada3dd523",
+            nonce: "70c3db4f0d26368400a10ed05d2bff5e",
+            aad: "234a3463c1264ac6",
+            plain_text: "1a47cb4933",
+            cipher_text: "d851d5bae0",
+            tag: "3a59f238a23e39199dc9266626c40f80"
+        },
+        TestVector {
+            key: "d07cf6cbb7f313bdde66b727afd3c5e8",
+            nonce: "8408dfff3c1a2b1292dc199e46b7d617",
+            aad: "33cce2eabff5a79d",
+            plain_text: "481c9e39b1",
+            cipher_text: "632a9d131a",
+            tag: "d4c168a4225d8e1ff755939974a7bede"
+        },
+        TestVector {
+            key: "98be0d89e338f5c702c60b4a8ee1818d0475259ded90c17e",
+            nonce: "d00e59ce22b26331ea79f513",
+            aad: "",
+            plain_text: "a7ace332b138735e01ecc3f30d8ee9ebd45ed123800538a2628801c92c15722bc5",
+            cipher_text: "336d46c8b76b1a100d3096e959552c753fe02bb5e1f117546dbf4f9ac11d36ec44",
+            tag: "b8b46b492e9063e35f1b1170fe852929"
+        },
+        TestVector {
+            key: "71a9fbc9a6ba624bffe9125ecd4b8b9af55b995df6bca72f87c89d4ca6ee164e",
+            nonce: "43",
+            aad: "59b891e7ea5469e3cd1cf001f6942e511394297d",
+            plain_text: "5dbd8e2ec967e427f39b36a6e3d324d5f517cc58dca838b125c0545c52516d93\
+                         c35eac7debf91f5458a088f553e620e82d59f0e6b8189d1ebf71461c37d4fee8\
+                         5dbd8e2ec967",
+            cipher_text: "19ff688c207b5d0af74a3009e9cd65ca16d721acb4799ff9dfa89d4030c459cf\
+                          6f10c255547ec5d7d9cdeec35c57c1d46ee61384b0b08cea4be89506b6b07fa6\
+                          cabd5809f9c6",
+            tag: "3b1889b138ba1076026dcd97b5e2287a"
+        },
+        TestVector {
+            key: "71a9fbc9a6ba624bffe9125ecd4b8b9af55b995df6bca72f87c89d4ca6ee164e",
+            nonce: "df17deb49d0a8d6c8a8b6b842b857524",
+            aad: "3dabc77e4210c26affa0e3202315662c67ff7854634be69e0338fcb7fe12ddf4\
+                  55c91dec93c1169487883061e9e0b7b834ca5f7bfb01061300d0a2ddb5363041\
+                  3d",
+            plain_text: "7ac109a2e5ba525e75f6b1df9318642965839aca1ca73cf4d119af60d147945a\
+                         7e6eaa7505241c03627466b6cbeb4ee0939eebe38e41a44f2b07e3671dc2d61c\
+                         7ac109a2e5ba525e75f6b1df9318642965839aca1ca73cf4d119af60d147945a\
+                         7e6eaa7505241c03627466b6cbeb4ee0939eebe38e41a44f2b07e3671dc2d61c",
+            cipher_text: "7b3216c1de0681f619156d4a1f1dcf12bdd02b329694da6761b4df0171e2b067\
+                          c2d3d08d578b9225db868388aba969055b479ba22e7db80ee8648069e110fc24\
+                          9dfb6bedefc9bab50d329851e4c40a2432fb9ad4dfd60b63cba40a0f4705f288\
+                          6d926ff400486a6a634b70ee6038fbe9a606e91e5e11444b20302454e8a06ac1",
+            tag: "11ec247c7a97657097b53f456c755d2a"
+        },
+        TestVector {
+            key: "69efa54e2e440ebd3c195a303ffebb64",
+            nonce: "e44ed67a087f77",
+            aad: "7e72d9f51d7e4ff40b758f4b555115a2",
+            plain_text: "257e14fcd85f4eec907eadd40d5e53695b",
+            cipher_text: "bbdd7b3994b9fd143a6e400a4841427197",
+            tag: "321ed33a325248ab093189094736ed99"
+        },
+    ];
+
+    fn encrypt<E: BlockEncryptor + Clone>(cipher: E, test: &TestVector, tag_len: usize)
+            -> (Vec<u8>, Vec<u8>) {
+        let plain_text = test.plain_text.from_hex().unwrap();
+        let mut eax = Eax::new(
+            cipher, &test.nonce.from_hex().unwrap(), &test.aad.from_hex().unwrap());
+        let mut out: Vec<u8> = repeat(0).take(plain_text.len()).collect();
+        let mut tag: Vec<u8> = repeat(0).take(tag_len).collect();
+        eax.encrypt(&plain_text, &mut out, &mut tag);
+        (out, tag)
+    }
+
+    fn decrypt<E: BlockEncryptor + Clone>(cipher: E, test: &TestVector, tag: &[u8])
+            -> (Vec<u8>, bool) {
+        let cipher_text = test.cipher_text.from_hex().unwrap();
+        let mut eax = Eax::new(
+            cipher, &test.nonce.from_hex().unwrap(), &test.aad.from_hex().unwrap());
+        let mut out: Vec<u8> = repeat(0).take(cipher_text.len()).collect();
+        let result = eax.decrypt(&cipher_text, &mut out, tag);
+        (out, result)
+    }
+
+    fn run_encrypt(test: &TestVector, tag_len: usize) -> (Vec<u8>, Vec<u8>) {
+        let key = test.key.from_hex().unwrap();
+        match key.len() {
+            16 => encrypt(AesSafe128Encryptor::new(&key), test, tag_len),
+            24 => encrypt(AesSafe192Encryptor::new(&key), test, tag_len),
+            32 => encrypt(AesSafe256Encryptor::new(&key), test, tag_len),
+            _ => unreachable!()
+        }
+    }
+
+    fn run_decrypt(test: &TestVector, tag: &[u8]) -> (Vec<u8>, bool) {
+        let key = test.key.from_hex().unwrap();
+        match key.len() {
+            16 => decrypt(AesSafe128Encryptor::new(&key), test, tag),
+            24 => decrypt(AesSafe192Encryptor::new(&key), test, tag),
+            32 => decrypt(AesSafe256Encryptor::new(&key), test, tag),
+            _ => unreachable!()
+        }
+    }
+
+    #[test]
+    fn eax_encrypt_test() {
+        for test in TESTS.iter() {
+            let (out, tag) = run_encrypt(test, 16);
+            assert_eq!(out, test.cipher_text.from_hex().unwrap());
+            assert_eq!(tag, test.tag.from_hex().unwrap());
+        }
+    }
+
+    #[test]
+    fn eax_decrypt_test() {
+        for test in TESTS.iter() {
+            let (out, result) = run_decrypt(test, &test.tag.from_hex().unwrap());
+            assert!(result);
+            assert_eq!(out, test.plain_text.from_hex().unwrap());
+        }
+    }
+
+    #[test]
+    fn eax_truncated_tag_test() {
+        for test in TESTS.iter() {
+            let full_tag = test.tag.from_hex().unwrap();
+            let (_, tag) = run_encrypt(test, 8);
+            assert_eq!(tag, &full_tag[..8]);
+            let (out, result) = run_decrypt(test, &full_tag[..8]);
+            assert!(result);
+            assert_eq!(out, test.plain_text.from_hex().unwrap());
+        }
+    }
+
+    #[test]
+    fn eax_decrypt_fail_test() {
+        for test in TESTS.iter() {
+            let mut tag = test.tag.from_hex().unwrap();
+            tag[0] ^= 1;
+            let (out, result) = run_decrypt(test, &tag);
+            assert!(!result);
+            let zero: Vec<u8> = repeat(0).take(out.len()).collect();
+            assert_eq!(out, zero);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "with-bench"))]
+mod bench {
+    use test::Bencher;
+    use aead::{AeadEncryptor, AeadDecryptor};
+    use aessafe::AesSafe256Encryptor;
+    use eax::Eax;
+
+    #[bench]
+    pub fn eax_1k(bh: & mut Bencher) {
+        let input = [1u8; 1024];
+        let aad = [3u8; 1024];
+        let aes = AesSafe256Encryptor::new(&[0; 32]);
+        bh.iter( || {
+            let mut cipher = Eax::new(aes, &[0; 16], &aad);
+            let mut decipher = Eax::new(aes, &[0; 16], &aad);
+
+            let mut output = [0u8; 1024];
+            let mut tag = [0u8; 16];
+            let mut output2 = [0u8; 1024];
+
+            cipher.encrypt(&input, &mut output, &mut tag);
+            decipher.decrypt(&output, &mut output2, &tag);
+        });
+        bh.bytes = 1024u64;
+    }
+}
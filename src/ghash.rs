@@ -144,7 +144,7 @@ fn update(state: &mut Gf128, len: &mut usize, data: &[u8], srest: &mut Option<[u
         }
     };
 
-    let (data, rest) = data.split_at(data_len - data_len % 16);
+    let (data, rest) = data.split_at(data.len() - data.len() % 16);
 
     for chunk in data.chunks(16) {
         let x = Gf128::from_bytes(chunk);
@@ -528,6 +528,23 @@ mod test {
                             .result()[..], g);
         }
     }
+
+    #[test]
+    fn split_input_chunks() {
+        for &(h, a, c, g) in CASES.iter() {
+            for &chunk_size in [1, 3, 17, 33].iter() {
+                let mut ghash = Ghash::new(h);
+                for chunk in a.chunks(chunk_size) {
+                    ghash = ghash.input_a(chunk);
+                }
+                let mut ghash = ghash.input_c(&[]);
+                for chunk in c.chunks(chunk_size) {
+                    ghash = ghash.input_c(chunk);
+                }
+                assert_eq!(&ghash.result()[..], g);
+            }
+        }
+    }
 }
 
 #[cfg(all(test, feature = "with-bench"))]
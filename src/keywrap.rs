@@ -0,0 +1,416 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+An implementation of the AES Key Wrap algorithm, as specified in RFC 3394, and of AES Key Wrap
+with Padding, as specified in RFC 5649.
+
+The functions in this module work with any block cipher with a 128 bit block, such as the
+ciphers in the `aessafe` and `aesni` modules. Unwrapping checks the integrity of the wrapped key
+and returns `KeyWrapError::IntegrityCheckFailed` if it has been modified or was wrapped under a
+different key. In that case the output buffer is zeroed.
+
+# Usage
+
+```
+use crypto::aessafe::{AesSafe256Encryptor, AesSafe256Decryptor};
+use crypto::keywrap;
+
+let kek = [1u8; 32];
+let key = [2u8; 32];
+
+let mut wrapped = [0u8; 40];
+keywrap::wrap(&AesSafe256Encryptor::new(&kek), &key, &mut wrapped).unwrap();
+
+let mut unwrapped = [0u8; 32];
+keywrap::unwrap(&AesSafe256Decryptor::new(&kek), &wrapped, &mut unwrapped).unwrap();
+assert_eq!(key, unwrapped);
+```
+*/
+
+use cryptoutil::{copy_memory, write_u32_be, read_u32_be, write_u64_be};
+use symmetriccipher::{BlockEncryptor, BlockDecryptor};
+use util::fixed_time_eq;
+
+// The default initial value from RFC 3394, section 2.2.3.1
+static DEFAULT_IV: [u8; 8] = [0xa6, 0xa6, 0xa6, 0xa6, 0xa6, 0xa6, 0xa6, 0xa6];
+
+// The constant half of the alternative initial value from RFC 5649, section 3
+static AIV_PREFIX: [u8; 4] = [0xa6, 0x59, 0x59, 0xa6];
+
+#[derive(Debug, Clone, Copy)]
+pub enum KeyWrapError {
+    InvalidLength,
+    IntegrityCheckFailed
+}
+
+/// Returns the length of the output of `wrap_with_padding` for a key of `len` bytes.
+pub fn wrapped_len_with_padding(len: usize) -> usize {
+    (len + 7) / 8 * 8 + 8
+}
+
+// The wrapping process W from RFC 3394, section 2.2.1, in its index based form. `data` holds
+// the initial value followed by the 64 bit blocks to be wrapped and is transformed in place.
+fn w<E: BlockEncryptor + ?Sized>(cipher: &E, data: &mut [u8]) {
+    let n = data.len() / 8 - 1;
+    let mut block = [0u8; 16];
+    let mut t_bytes = [0u8; 8];
+    copy_memory(&data[0..8], &mut block[0..8]);
+    for j in 0..6 {
+        for i in 1..n + 1 {
+            copy_memory(&data[i * 8..(i + 1) * 8], &mut block[8..16]);
+            let input = block;
+            cipher.encrypt_block(&input, &mut block);
+            write_u64_be(&mut t_bytes, (n * j + i) as u64);
+            for (a, t) in block[0..8].iter_mut().zip(t_bytes.iter()) {
+                *a ^= *t;
+            }
+            copy_memory(&block[8..16], &mut data[i * 8..(i + 1) * 8]);
+        }
+    }
+    copy_memory(&block[0..8], &mut data[0..8]);
+}
+
+// The unwrapping process W^-1 from RFC 3394, section 2.2.2. On return the first 8 bytes of
+// `data` hold the recovered initial value, which the caller must check.
+fn w_inv<D: BlockDecryptor + ?Sized>(cipher: &D, data: &mut [u8]) {
+    let n = data.len() / 8 - 1;
+    let mut block = [0u8; 16];
+    let mut t_bytes = [0u8; 8];
+    copy_memory(&data[0..8], &mut block[0..8]);
+    for j in (0..6).rev() {
+        for i in (1..n + 1).rev() {
+            write_u64_be(&mut t_bytes, (n * j + i) as u64);
+            for (a, t) in block[0..8].iter_mut().zip(t_bytes.iter()) {
+                *a ^= *t;
+            }
+            copy_memory(&data[i * 8..(i + 1) * 8], &mut block[8..16]);
+            let input = block;
+            cipher.decrypt_block(&input, &mut block);
+            copy_memory(&block[8..16], &mut data[i * 8..(i + 1) * 8]);
+        }
+    }
+    copy_memory(&block[0..8], &mut data[0..8]);
+}
+
+/// Wraps `input` under the key encryption key held by `cipher`, as specified in RFC 3394. The
+/// input must be a multiple of 8 bytes and at least 16 bytes long. The output must be 8 bytes
+/// longer than the input.
+pub fn wrap<E: BlockEncryptor + ?Sized>(cipher: &E, input: &[u8], output: &mut [u8])
+        -> Result<(), KeyWrapError> {
+    assert!(cipher.block_size() == 16);
+    assert!(output.len() == input.len() + 8);
+    if input.len() % 8 != 0 || input.len() < 16 {
+        return Err(KeyWrapError::InvalidLength);
+    }
+    copy_memory(&DEFAULT_IV, &mut output[0..8]);
+    copy_memory(input, &mut output[8..]);
+    w(cipher, output);
+    Ok(())
+}
+
+/// Unwraps a key that was wrapped using `wrap`. The output must be 8 bytes shorter than the
+/// input.
+pub fn unwrap<D: BlockDecryptor + ?Sized>(cipher: &D, input: &[u8], output: &mut [u8])
+        -> Result<(), KeyWrapError> {
+    assert!(cipher.block_size() == 16);
+    assert!(output.len() + 8 == input.len());
+    if input.len() % 8 != 0 || input.len() < 24 {
+        return Err(KeyWrapError::InvalidLength);
+    }
+    let mut data = input.to_vec();
+    w_inv(cipher, &mut data);
+    let result = if fixed_time_eq(&data[0..8], &DEFAULT_IV) {
+        copy_memory(&data[8..], output);
+        Ok(())
+    } else {
+        for b in output.iter_mut() {
+            *b = 0;
+        }
+        Err(KeyWrapError::IntegrityCheckFailed)
+    };
+    for b in data.iter_mut() {
+        *b = 0;
+    }
+    result
+}
+
+/// Wraps `input` under the key encryption key held by `cipher`, as specified in RFC 5649. The
+/// input may be of any non-zero length that fits in 32 bits. The output must be
+/// `wrapped_len_with_padding(input.len())` bytes long.
+pub fn wrap_with_padding<E: BlockEncryptor + ?Sized>(
+        cipher: &E,
+        input: &[u8],
+        output: &mut [u8]) -> Result<(), KeyWrapError> {
+    assert!(cipher.block_size() == 16);
+    assert!(output.len() == wrapped_len_with_padding(input.len()));
+    if input.is_empty() || input.len() as u64 > 0xffffffff {
+        return Err(KeyWrapError::InvalidLength);
+    }
+    for b in output.iter_mut() {
+        *b = 0;
+    }
+    copy_memory(&AIV_PREFIX, &mut output[0..4]);
+    write_u32_be(&mut output[4..8], input.len() as u32);
+    copy_memory(input, &mut output[8..]);
+    if output.len() == 16 {
+        // A single padded block is encrypted directly, as in RFC 5649, section 4.1
+        let mut block = [0u8; 16];
+        copy_memory(output, &mut block);
+        cipher.encrypt_block(&block, output);
+    } else {
+        w(cipher, output);
+    }
+    Ok(())
+}
+
+/// Unwraps a key that was wrapped using `wrap_with_padding`. The output must be at least 8 bytes
+/// shorter than the input. On success, the length of the unwrapped key is returned and the key
+/// is at the start of the output.
+pub fn unwrap_with_padding<D: BlockDecryptor + ?Sized>(
+        cipher: &D,
+        input: &[u8],
+        output: &mut [u8]) -> Result<usize, KeyWrapError> {
+    assert!(cipher.block_size() == 16);
+    assert!(output.len() + 8 >= input.len());
+    if input.len() % 8 != 0 || input.len() < 16 {
+        return Err(KeyWrapError::InvalidLength);
+    }
+    let mut data = input.to_vec();
+    if data.len() == 16 {
+        cipher.decrypt_block(input, &mut data);
+    } else {
+        w_inv(cipher, &mut data);
+    }
+
+    // Check the prefix, the message length indicator and that the padding is all zeros, as in
+    // RFC 5649, section 3
+    let padded_len = data.len() - 8;
+    let mli = read_u32_be(&data[4..8]) as usize;
+    let mut valid = fixed_time_eq(&data[0..4], &AIV_PREFIX) && mli + 8 > padded_len &&
+        mli <= padded_len;
+    if valid {
+        let mut diff = 0u8;
+        for b in data[8 + mli..].iter() {
+            diff |= *b;
+        }
+        valid = diff == 0;
+    }
+
+    let result = if valid {
+        copy_memory(&data[8..8 + mli], output);
+        Ok(mli)
+    } else {
+        for b in output.iter_mut() {
+            *b = 0;
+        }
+        Err(KeyWrapError::IntegrityCheckFailed)
+    };
+    for b in data.iter_mut() {
+        *b = 0;
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use aessafe::{AesSafe128Encryptor, AesSafe128Decryptor, AesSafe192Encryptor,
+        AesSafe192Decryptor, AesSafe256Encryptor, AesSafe256Decryptor};
+    use keywrap::{wrap, unwrap, wrap_with_padding, unwrap_with_padding,
+        wrapped_len_with_padding, KeyWrapError};
+    use symmetriccipher::{BlockEncryptor, BlockDecryptor};
+    use serialize::hex::FromHex;
+
+    struct Test {
+        kek: &'static str,
+        key: &'static str,
+        wrapped: &'static str
+    }
+
+    // Vectors from RFC 3394, section 4
+    fn tests() -> Vec<Test> {
+        vec![
+            Test {
+                kek: "000102030405060708090a0b0c0d0e0f",
+                key: "00112233445566778899aabbccddeeff",
+                wrapped: "1fa68b0a8112b447aef34bd8fb5a7b829d3e862371d2cfe5"
+            },
+            Test {
+                kek: "000102030405060708090a0b0c0d0e0f1011121314151617",
+                key: "00112233445566778899aabbccddeeff",
+                wrapped: "96778b25ae6ca435f92b5b97c050aed2468ab8a17ad84e5d"
+            },
+            Test {
+                kek: "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+                key: "00112233445566778899aabbccddeeff",
+                wrapped: "64e8c3f9ce0f5ba263e9777905818a2a93c8191e7d6e8ae7"
+            },
+            Test {
+                kek: "000102030405060708090a0b0c0d0e0f1011121314151617",
+                key: "00112233445566778899aabbccddeeff0001020304050607",
+                wrapped: "031d33264e15d33268f24ec260743edce1c6c7ddee725a936ba814915c6762d2"
+            },
+            Test {
+                kek: "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+                key: "00112233445566778899aabbccddeeff0001020304050607",
+                wrapped: "a8f9bc1612c68b3ff6e6f4fbe30e71e4769c8b80a32cb8958cd5d17d6b254da1"
+            },
+            Test {
+                kek: "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+                key: "00112233445566778899aabbccddeeff000102030405060708090a0b0c0d0e0f",
+                wrapped: "28c9f404c4b810f4cbccb35cfb87f8263f5786e2d80ed326cbc7f0e71a99f43b\
+                          fb988b9b7a02dd21"
+            }
+        ]
+    }
+
+    // Vectors from RFC 5649, section 6
+    fn padding_tests() -> Vec<Test> {
+        vec![
+            Test {
+                kek: "5840df6e29b02af1ab493b705bf16ea1ae8338f4dcc176a8",
+                key: "c37b7e6492584340bed12207808941155068f738",
+                wrapped: "138bdeaa9b8fa7fc61f97742e72248ee5ae6ae5360d1ae6a5f54f373fa543b6a"
+            },
+            Test {
+                kek: "5840df6e29b02af1ab493b705bf16ea1ae8338f4dcc176a8",
+                key: "466f7250617369",
+                wrapped: "afbeb0f07dfbf5419200f2ccb50bb24f"
+            }
+        ]
+    }
+
+    fn encryptor(kek: &[u8]) -> Box<BlockEncryptor> {
+        match kek.len() {
+            16 => Box::new(AesSafe128Encryptor::new(kek)),
+            24 => Box::new(AesSafe192Encryptor::new(kek)),
+            _ => Box::new(AesSafe256Encryptor::new(kek))
+        }
+    }
+
+    fn decryptor(kek: &[u8]) -> Box<BlockDecryptor> {
+        match kek.len() {
+            16 => Box::new(AesSafe128Decryptor::new(kek)),
+            24 => Box::new(AesSafe192Decryptor::new(kek)),
+            _ => Box::new(AesSafe256Decryptor::new(kek))
+        }
+    }
+
+    #[test]
+    fn test_wrap() {
+        for test in tests().iter() {
+            let kek = test.kek.from_hex().unwrap();
+            let key = test.key.from_hex().unwrap();
+            let mut wrapped = vec![0u8; key.len() + 8];
+            wrap(&*encryptor(&kek), &key, &mut wrapped).unwrap();
+            assert_eq!(wrapped, test.wrapped.from_hex().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_unwrap() {
+        for test in tests().iter() {
+            let kek = test.kek.from_hex().unwrap();
+            let wrapped = test.wrapped.from_hex().unwrap();
+            let mut key = vec![0u8; wrapped.len() - 8];
+            unwrap(&*decryptor(&kek), &wrapped, &mut key).unwrap();
+            assert_eq!(key, test.key.from_hex().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_unwrap_tampered() {
+        for test in tests().iter() {
+            let kek = test.kek.from_hex().unwrap();
+            let wrapped = test.wrapped.from_hex().unwrap();
+            for i in 0..wrapped.len() {
+                let mut tampered = wrapped.clone();
+                tampered[i] ^= 0x80;
+                let mut key = vec![0u8; wrapped.len() - 8];
+                match unwrap(&*decryptor(&kek), &tampered, &mut key) {
+                    Err(KeyWrapError::IntegrityCheckFailed) => {}
+                    _ => panic!("Tampered input was accepted")
+                }
+                assert!(key.iter().all(|&b| b == 0));
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_lengths() {
+        let kek = [0u8; 16];
+        let mut output = [0u8; 16];
+        match wrap(&AesSafe128Encryptor::new(&kek), &[0u8; 8], &mut output) {
+            Err(KeyWrapError::InvalidLength) => {}
+            _ => panic!("A single block was wrapped")
+        }
+        let mut output = [0u8; 12];
+        match unwrap(&AesSafe128Decryptor::new(&kek), &[0u8; 20], &mut output) {
+            Err(KeyWrapError::InvalidLength) => {}
+            _ => panic!("A partial block was unwrapped")
+        }
+        let mut output = [0u8; 8];
+        match wrap_with_padding(&AesSafe128Encryptor::new(&kek), &[], &mut output) {
+            Err(KeyWrapError::InvalidLength) => {}
+            _ => panic!("An empty key was wrapped")
+        }
+    }
+
+    #[test]
+    fn test_wrap_with_padding() {
+        for test in padding_tests().iter() {
+            let kek = test.kek.from_hex().unwrap();
+            let key = test.key.from_hex().unwrap();
+            let mut wrapped = vec![0u8; wrapped_len_with_padding(key.len())];
+            wrap_with_padding(&AesSafe192Encryptor::new(&kek), &key, &mut wrapped).unwrap();
+            assert_eq!(wrapped, test.wrapped.from_hex().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_unwrap_with_padding() {
+        for test in padding_tests().iter() {
+            let kek = test.kek.from_hex().unwrap();
+            let wrapped = test.wrapped.from_hex().unwrap();
+            let mut key = vec![0u8; wrapped.len() - 8];
+            let len = unwrap_with_padding(&AesSafe192Decryptor::new(&kek), &wrapped, &mut key)
+                .unwrap();
+            assert_eq!(&key[..len], &test.key.from_hex().unwrap()[..]);
+        }
+    }
+
+    #[test]
+    fn test_unwrap_with_padding_tampered() {
+        for test in padding_tests().iter() {
+            let kek = test.kek.from_hex().unwrap();
+            let wrapped = test.wrapped.from_hex().unwrap();
+            for i in 0..wrapped.len() {
+                let mut tampered = wrapped.clone();
+                tampered[i] ^= 1;
+                let mut key = vec![0u8; wrapped.len() - 8];
+                match unwrap_with_padding(&AesSafe192Decryptor::new(&kek), &tampered, &mut key) {
+                    Err(KeyWrapError::IntegrityCheckFailed) => {}
+                    _ => panic!("Tampered input was accepted")
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_padding_round_trip() {
+        let kek = [3u8; 32];
+        for len in 1..70 {
+            let key: Vec<u8> = (0..len as u8).collect();
+            let mut wrapped = vec![0u8; wrapped_len_with_padding(len)];
+            wrap_with_padding(&AesSafe256Encryptor::new(&kek), &key, &mut wrapped).unwrap();
+            let mut unwrapped = vec![0u8; wrapped.len() - 8];
+            let out_len =
+                unwrap_with_padding(&AesSafe256Decryptor::new(&kek), &wrapped, &mut unwrapped)
+                .unwrap();
+            assert_eq!(&unwrapped[..out_len], &key[..]);
+        }
+    }
+}
@@ -0,0 +1,310 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+An implementation of the Kuznyechik block cipher.
+
+Kuznyechik is the 128 bit block cipher standardized in GOST R 34.12-2015 (and RFC 7801). It is a
+substitution-permutation network with a 256 bit key and 10 round keys. Each of the first nine
+rounds adds a round key, substitutes every byte, and applies a linear transformation over
+GF(2^8).
+
+The standard defines the linear transformation as 16 iterations of a shift register. Since it is
+linear, it is applied here as a multiplication by the equivalent 16 by 16 matrix.
+
+This implementation uses table lookups which are indexed by secret data and so it is not
+protected against cache timing attacks.
+*/
+
+use symmetriccipher::{BlockEncryptor, BlockDecryptor};
+
+// The substitution and its inverse
+static PI: [u8; 256] = [
+    0xfc, 0xee, 0xdd, 0x11, 0xcf, 0x6e, 0x31, 0x16, 0xfb, 0xc4, 0xfa, 0xda, 0x23, 0xc5, 0x04, 0x4d,
+    0xe9, 0x77, 0xf0, 0xdb, 0x93, 0x2e, 0x99, 0xba, 0x17, 0x36, 0xf1, 0xbb, 0x14, 0xcd, 0x5f, 0xc1,
+    0xf9, 0x18, 0x65, 0x5a, 0xe2, 0x5c, 0xef, 0x21, 0x81, 0x1c, 0x3c, 0x42, 0x8b, 0x01, 0x8e, 0x4f,
+    0x05, 0x84, 0x02, 0xae, 0xe3, 0x6a, 0x8f, 0xa0, 0x06, 0x0b, 0xed, 0x98, 0x7f, 0xd4, 0xd3, 0x1f,
+    0xeb, 0x34, 0x2c, 0x51, 0xea, 0xc8, 0x48, 0xab, 0xf2, 0x2a, 0x68, 0xa2, 0xfd, 0x3a, 0xce, 0xcc,
+    0xb5, 0x70, 0x0e, 0x56, 0x08, 0x0c, 0x76, 0x12, 0xbf, 0x72, 0x13, 0x47, 0x9c, 0xb7, 0x5d, 0x87,
+    0x15, 0xa1, 0x96, 0x29, 0x10, 0x7b, 0x9a, 0xc7, 0xf3, 0x91, 0x78, 0x6f, 0x9d, 0x9e, 0xb2, 0xb1,
+    0x32, 0x75, 0x19, 0x3d, 0xff, 0x35, 0x8a, 0x7e, 0x6d, 0x54, 0xc6, 0x80, 0xc3, 0xbd, 0x0d, 0x57,
+    0xdf, 0xf5, 0x24, 0xa9, 0x3e, 0xa8, 0x43, 0xc9, 0xd7, 0x79, 0xd6, 0xf6, 0x7c, 0x22, 0xb9, 0x03,
+    0xe0, 0x0f, 0xec, 0xde, 0x7a, 0x94, 0xb0, 0xbc, 0xdc, 0xe8, 0x28, 0x50, 0x4e, 0x33, 0x0a, 0x4a,
+    0xa7, 0x97, 0x60, 0x73, 0x1e, 0x00, 0x62, 0x44, 0x1a, 0xb8, 0x38, 0x82, 0x64, 0x9f, 0x26, 0x41,
+    0xad, 0x45, 0x46, 0x92, 0x27, 0x5e, 0x55, 0x2f, 0x8c, 0xa3, 0xa5, 0x7d, 0x69, 0xd5, 0x95, 0x3b,
+    0x07, 0x58, 0xb3, 0x40, 0x86, 0xac, 0x1d, 0xf7, 0x30, 0x37, 0x6b, 0xe4, 0x88, 0xd9, 0xe7, 0x89,
+    0xe1, 0x1b, 0x83, 0x49, 0x4c, 0x3f, 0xf8, 0xfe, 0x8d, 0x53, 0xaa, 0x90, 0xca, 0xd8, 0x85, 0x61,
+    0x20, 0x71, 0x67, 0xa4, 0x2d, 0x2b, 0x09, 0x5b, 0xcb, 0x9b, 0x25, 0xd0, 0xbe, 0xe5, 0x6c, 0x52,
+    0x59, 0xa6, 0x74, 0xd2, 0xe6, 0xf4, 0xb4, 0xc0, 0xd1, 0x66, 0xaf, 0xc2, 0x39, 0x4b, 0x63, 0xb6
+];
+
+static PI_INV: [u8; 256] = [
+    0xa5, 0x2d, 0x32, 0x8f, 0x0e, 0x30, 0x38, 0xc0, 0x54, 0xe6, 0x9e, 0x39, 0x55, 0x7e, 0x52, 0x91,
+    0x64, 0x03, 0x57, 0x5a, 0x1c, 0x60, 0x07, 0x18, 0x21, 0x72, 0xa8, 0xd1, 0x29, 0xc6, 0xa4, 0x3f,
+    0xe0, 0x27, 0x8d, 0x0c, 0x82, 0xea, 0xae, 0xb4, 0x9a, 0x63, 0x49, 0xe5, 0x42, 0xe4, 0x15, 0xb7,
+    0xc8, 0x06, 0x70, 0x9d, 0x41, 0x75, 0x19, 0xc9, 0xaa, 0xfc, 0x4d, 0xbf, 0x2a, 0x73, 0x84, 0xd5,
+    0xc3, 0xaf, 0x2b, 0x86, 0xa7, 0xb1, 0xb2, 0x5b, 0x46, 0xd3, 0x9f, 0xfd, 0xd4, 0x0f, 0x9c, 0x2f,
+    0x9b, 0x43, 0xef, 0xd9, 0x79, 0xb6, 0x53, 0x7f, 0xc1, 0xf0, 0x23, 0xe7, 0x25, 0x5e, 0xb5, 0x1e,
+    0xa2, 0xdf, 0xa6, 0xfe, 0xac, 0x22, 0xf9, 0xe2, 0x4a, 0xbc, 0x35, 0xca, 0xee, 0x78, 0x05, 0x6b,
+    0x51, 0xe1, 0x59, 0xa3, 0xf2, 0x71, 0x56, 0x11, 0x6a, 0x89, 0x94, 0x65, 0x8c, 0xbb, 0x77, 0x3c,
+    0x7b, 0x28, 0xab, 0xd2, 0x31, 0xde, 0xc4, 0x5f, 0xcc, 0xcf, 0x76, 0x2c, 0xb8, 0xd8, 0x2e, 0x36,
+    0xdb, 0x69, 0xb3, 0x14, 0x95, 0xbe, 0x62, 0xa1, 0x3b, 0x16, 0x66, 0xe9, 0x5c, 0x6c, 0x6d, 0xad,
+    0x37, 0x61, 0x4b, 0xb9, 0xe3, 0xba, 0xf1, 0xa0, 0x85, 0x83, 0xda, 0x47, 0xc5, 0xb0, 0x33, 0xfa,
+    0x96, 0x6f, 0x6e, 0xc2, 0xf6, 0x50, 0xff, 0x5d, 0xa9, 0x8e, 0x17, 0x1b, 0x97, 0x7d, 0xec, 0x58,
+    0xf7, 0x1f, 0xfb, 0x7c, 0x09, 0x0d, 0x7a, 0x67, 0x45, 0x87, 0xdc, 0xe8, 0x4f, 0x1d, 0x4e, 0x04,
+    0xeb, 0xf8, 0xf3, 0x3e, 0x3d, 0xbd, 0x8a, 0x88, 0xdd, 0xcd, 0x0b, 0x13, 0x98, 0x02, 0x93, 0x80,
+    0x90, 0xd0, 0x24, 0x34, 0xcb, 0xed, 0xf4, 0xce, 0x99, 0x10, 0x44, 0x40, 0x92, 0x3a, 0x01, 0x26,
+    0x12, 0x1a, 0x48, 0x68, 0xf5, 0x81, 0x8b, 0xc7, 0xd6, 0x20, 0x0a, 0x08, 0x00, 0x4c, 0xd7, 0x74
+];
+
+// The matrix of the L transformation and of its inverse
+static L: [[u8; 16]; 16] = [
+    [0xcf, 0x98, 0x74, 0xbf, 0x93, 0x8e, 0xf2, 0xf3, 0x0a, 0xbf, 0xf6, 0xa9, 0xea, 0x8e, 0x4d, 0x6e],
+    [0x6e, 0x20, 0xc6, 0xda, 0x90, 0x48, 0x89, 0x9c, 0xc1, 0x64, 0xb8, 0x2d, 0x86, 0x44, 0xd0, 0xa2],
+    [0xa2, 0xc8, 0x87, 0x70, 0x68, 0x43, 0x1c, 0x2b, 0xa1, 0x63, 0x30, 0x6b, 0x9f, 0x30, 0xe3, 0x76],
+    [0x76, 0x33, 0x10, 0x0c, 0x1c, 0x11, 0xd6, 0x6a, 0xa6, 0xd7, 0xf6, 0x49, 0x07, 0x14, 0xe8, 0x72],
+    [0x72, 0xf2, 0x6b, 0xca, 0x20, 0xeb, 0x02, 0xa4, 0x8d, 0xd4, 0xc4, 0x01, 0x65, 0xdd, 0x4c, 0x6c],
+    [0x6c, 0x76, 0xec, 0x0c, 0xc5, 0xbc, 0xaf, 0x6e, 0xa3, 0xe1, 0x90, 0x58, 0x0e, 0x02, 0xc3, 0x48],
+    [0x48, 0xd5, 0x62, 0x17, 0x06, 0x2d, 0xc4, 0xe7, 0xd5, 0xeb, 0x99, 0x78, 0x52, 0xf5, 0x16, 0x7a],
+    [0x7a, 0xe6, 0x4e, 0x1a, 0xbb, 0x2e, 0xf1, 0xbe, 0xd4, 0xaf, 0x37, 0xb1, 0xd4, 0x2a, 0x6e, 0xb8],
+    [0xb8, 0x49, 0x87, 0x14, 0xcb, 0x8d, 0xab, 0x49, 0x09, 0x6c, 0x2a, 0x01, 0x60, 0x8e, 0x4b, 0x5d],
+    [0x5d, 0xd4, 0xb8, 0x2f, 0x8d, 0x12, 0xee, 0xf6, 0x08, 0x54, 0x0f, 0xf3, 0x98, 0xc8, 0x7f, 0x27],
+    [0x27, 0x9f, 0xbe, 0x68, 0x1a, 0x7c, 0xad, 0xc9, 0x84, 0x2f, 0xeb, 0xfe, 0xc6, 0x48, 0xa2, 0xbd],
+    [0xbd, 0x95, 0x5e, 0x30, 0xe9, 0x60, 0xbf, 0x10, 0xef, 0x39, 0xec, 0x91, 0x7f, 0x48, 0x89, 0x10],
+    [0x10, 0xe9, 0xd0, 0xd9, 0xf3, 0x94, 0x3d, 0xaf, 0x7b, 0xff, 0x64, 0x91, 0x52, 0xf8, 0x0d, 0xdd],
+    [0xdd, 0x99, 0x75, 0xca, 0x97, 0x44, 0x5a, 0xe0, 0x30, 0xa6, 0x31, 0xd3, 0xdf, 0x48, 0x64, 0x84],
+    [0x84, 0x2d, 0x74, 0x96, 0x5d, 0x77, 0x6f, 0xde, 0x54, 0xb4, 0x8d, 0xd1, 0x44, 0x3c, 0xa5, 0x94],
+    [0x94, 0x20, 0x85, 0x10, 0xc2, 0xc0, 0x01, 0xfb, 0x01, 0xc0, 0xc2, 0x10, 0x85, 0x20, 0x94, 0x01]
+];
+
+static L_INV: [[u8; 16]; 16] = [
+    [0x01, 0x94, 0x20, 0x85, 0x10, 0xc2, 0xc0, 0x01, 0xfb, 0x01, 0xc0, 0xc2, 0x10, 0x85, 0x20, 0x94],
+    [0x94, 0xa5, 0x3c, 0x44, 0xd1, 0x8d, 0xb4, 0x54, 0xde, 0x6f, 0x77, 0x5d, 0x96, 0x74, 0x2d, 0x84],
+    [0x84, 0x64, 0x48, 0xdf, 0xd3, 0x31, 0xa6, 0x30, 0xe0, 0x5a, 0x44, 0x97, 0xca, 0x75, 0x99, 0xdd],
+    [0xdd, 0x0d, 0xf8, 0x52, 0x91, 0x64, 0xff, 0x7b, 0xaf, 0x3d, 0x94, 0xf3, 0xd9, 0xd0, 0xe9, 0x10],
+    [0x10, 0x89, 0x48, 0x7f, 0x91, 0xec, 0x39, 0xef, 0x10, 0xbf, 0x60, 0xe9, 0x30, 0x5e, 0x95, 0xbd],
+    [0xbd, 0xa2, 0x48, 0xc6, 0xfe, 0xeb, 0x2f, 0x84, 0xc9, 0xad, 0x7c, 0x1a, 0x68, 0xbe, 0x9f, 0x27],
+    [0x27, 0x7f, 0xc8, 0x98, 0xf3, 0x0f, 0x54, 0x08, 0xf6, 0xee, 0x12, 0x8d, 0x2f, 0xb8, 0xd4, 0x5d],
+    [0x5d, 0x4b, 0x8e, 0x60, 0x01, 0x2a, 0x6c, 0x09, 0x49, 0xab, 0x8d, 0xcb, 0x14, 0x87, 0x49, 0xb8],
+    [0xb8, 0x6e, 0x2a, 0xd4, 0xb1, 0x37, 0xaf, 0xd4, 0xbe, 0xf1, 0x2e, 0xbb, 0x1a, 0x4e, 0xe6, 0x7a],
+    [0x7a, 0x16, 0xf5, 0x52, 0x78, 0x99, 0xeb, 0xd5, 0xe7, 0xc4, 0x2d, 0x06, 0x17, 0x62, 0xd5, 0x48],
+    [0x48, 0xc3, 0x02, 0x0e, 0x58, 0x90, 0xe1, 0xa3, 0x6e, 0xaf, 0xbc, 0xc5, 0x0c, 0xec, 0x76, 0x6c],
+    [0x6c, 0x4c, 0xdd, 0x65, 0x01, 0xc4, 0xd4, 0x8d, 0xa4, 0x02, 0xeb, 0x20, 0xca, 0x6b, 0xf2, 0x72],
+    [0x72, 0xe8, 0x14, 0x07, 0x49, 0xf6, 0xd7, 0xa6, 0x6a, 0xd6, 0x11, 0x1c, 0x0c, 0x10, 0x33, 0x76],
+    [0x76, 0xe3, 0x30, 0x9f, 0x6b, 0x30, 0x63, 0xa1, 0x2b, 0x1c, 0x43, 0x68, 0x70, 0x87, 0xc8, 0xa2],
+    [0xa2, 0xd0, 0x44, 0x86, 0x2d, 0xb8, 0x64, 0xc1, 0x9c, 0x89, 0x48, 0x90, 0xda, 0xc6, 0x20, 0x6e],
+    [0x6e, 0x4d, 0x8e, 0xea, 0xa9, 0xf6, 0xbf, 0x0a, 0xf3, 0xf2, 0x8e, 0x93, 0xbf, 0x74, 0x98, 0xcf]
+];
+
+// Powers and logarithms of the generator x, for multiplication in GF(2^8)
+static EXP: [u8; 256] = [
+    0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0xc3, 0x45, 0x8a, 0xd7, 0x6d, 0xda, 0x77, 0xee,
+    0x1f, 0x3e, 0x7c, 0xf8, 0x33, 0x66, 0xcc, 0x5b, 0xb6, 0xaf, 0x9d, 0xf9, 0x31, 0x62, 0xc4, 0x4b,
+    0x96, 0xef, 0x1d, 0x3a, 0x74, 0xe8, 0x13, 0x26, 0x4c, 0x98, 0xf3, 0x25, 0x4a, 0x94, 0xeb, 0x15,
+    0x2a, 0x54, 0xa8, 0x93, 0xe5, 0x09, 0x12, 0x24, 0x48, 0x90, 0xe3, 0x05, 0x0a, 0x14, 0x28, 0x50,
+    0xa0, 0x83, 0xc5, 0x49, 0x92, 0xe7, 0x0d, 0x1a, 0x34, 0x68, 0xd0, 0x63, 0xc6, 0x4f, 0x9e, 0xff,
+    0x3d, 0x7a, 0xf4, 0x2b, 0x56, 0xac, 0x9b, 0xf5, 0x29, 0x52, 0xa4, 0x8b, 0xd5, 0x69, 0xd2, 0x67,
+    0xce, 0x5f, 0xbe, 0xbf, 0xbd, 0xb9, 0xb1, 0xa1, 0x81, 0xc1, 0x41, 0x82, 0xc7, 0x4d, 0x9a, 0xf7,
+    0x2d, 0x5a, 0xb4, 0xab, 0x95, 0xe9, 0x11, 0x22, 0x44, 0x88, 0xd3, 0x65, 0xca, 0x57, 0xae, 0x9f,
+    0xfd, 0x39, 0x72, 0xe4, 0x0b, 0x16, 0x2c, 0x58, 0xb0, 0xa3, 0x85, 0xc9, 0x51, 0xa2, 0x87, 0xcd,
+    0x59, 0xb2, 0xa7, 0x8d, 0xd9, 0x71, 0xe2, 0x07, 0x0e, 0x1c, 0x38, 0x70, 0xe0, 0x03, 0x06, 0x0c,
+    0x18, 0x30, 0x60, 0xc0, 0x43, 0x86, 0xcf, 0x5d, 0xba, 0xb7, 0xad, 0x99, 0xf1, 0x21, 0x42, 0x84,
+    0xcb, 0x55, 0xaa, 0x97, 0xed, 0x19, 0x32, 0x64, 0xc8, 0x53, 0xa6, 0x8f, 0xdd, 0x79, 0xf2, 0x27,
+    0x4e, 0x9c, 0xfb, 0x35, 0x6a, 0xd4, 0x6b, 0xd6, 0x6f, 0xde, 0x7f, 0xfe, 0x3f, 0x7e, 0xfc, 0x3b,
+    0x76, 0xec, 0x1b, 0x36, 0x6c, 0xd8, 0x73, 0xe6, 0x0f, 0x1e, 0x3c, 0x78, 0xf0, 0x23, 0x46, 0x8c,
+    0xdb, 0x75, 0xea, 0x17, 0x2e, 0x5c, 0xb8, 0xb3, 0xa5, 0x89, 0xd1, 0x61, 0xc2, 0x47, 0x8e, 0xdf,
+    0x7d, 0xfa, 0x37, 0x6e, 0xdc, 0x7b, 0xf6, 0x2f, 0x5e, 0xbc, 0xbb, 0xb5, 0xa9, 0x91, 0xe1, 0x01
+];
+
+static LOG: [u8; 256] = [
+    0x00, 0x00, 0x01, 0x9d, 0x02, 0x3b, 0x9e, 0x97, 0x03, 0x35, 0x3c, 0x84, 0x9f, 0x46, 0x98, 0xd8,
+    0x04, 0x76, 0x36, 0x26, 0x3d, 0x2f, 0x85, 0xe3, 0xa0, 0xb5, 0x47, 0xd2, 0x99, 0x22, 0xd9, 0x10,
+    0x05, 0xad, 0x77, 0xdd, 0x37, 0x2b, 0x27, 0xbf, 0x3e, 0x58, 0x30, 0x53, 0x86, 0x70, 0xe4, 0xf7,
+    0xa1, 0x1c, 0xb6, 0x14, 0x48, 0xc3, 0xd3, 0xf2, 0x9a, 0x81, 0x23, 0xcf, 0xda, 0x50, 0x11, 0xcc,
+    0x06, 0x6a, 0xae, 0xa4, 0x78, 0x09, 0xde, 0xed, 0x38, 0x43, 0x2c, 0x1f, 0x28, 0x6d, 0xc0, 0x4d,
+    0x3f, 0x8c, 0x59, 0xb9, 0x31, 0xb1, 0x54, 0x7d, 0x87, 0x90, 0x71, 0x17, 0xe5, 0xa7, 0xf8, 0x61,
+    0xa2, 0xeb, 0x1d, 0x4b, 0xb7, 0x7b, 0x15, 0x5f, 0x49, 0x5d, 0xc4, 0xc6, 0xd4, 0x0c, 0xf3, 0xc8,
+    0x9b, 0x95, 0x82, 0xd6, 0x24, 0xe1, 0xd0, 0x0e, 0xdb, 0xbd, 0x51, 0xf5, 0x12, 0xf0, 0xcd, 0xca,
+    0x07, 0x68, 0x6b, 0x41, 0xaf, 0x8a, 0xa5, 0x8e, 0x79, 0xe9, 0x0a, 0x5b, 0xdf, 0x93, 0xee, 0xbb,
+    0x39, 0xfd, 0x44, 0x33, 0x2d, 0x74, 0x20, 0xb3, 0x29, 0xab, 0x6e, 0x56, 0xc1, 0x1a, 0x4e, 0x7f,
+    0x40, 0x67, 0x8d, 0x89, 0x5a, 0xe8, 0xba, 0x92, 0x32, 0xfc, 0xb2, 0x73, 0x55, 0xaa, 0x7e, 0x19,
+    0x88, 0x66, 0x91, 0xe7, 0x72, 0xfb, 0x18, 0xa9, 0xe6, 0x65, 0xa8, 0xfa, 0xf9, 0x64, 0x62, 0x63,
+    0xa3, 0x69, 0xec, 0x08, 0x1e, 0x42, 0x4c, 0x6c, 0xb8, 0x8b, 0x7c, 0xb0, 0x16, 0x8f, 0x60, 0xa6,
+    0x4a, 0xea, 0x5e, 0x7a, 0xc5, 0x5c, 0xc7, 0x0b, 0xd5, 0x94, 0x0d, 0xe0, 0xf4, 0xbc, 0xc9, 0xef,
+    0x9c, 0xfe, 0x96, 0x3a, 0x83, 0x34, 0xd7, 0x45, 0x25, 0x75, 0xe2, 0x2e, 0xd1, 0xb4, 0x0f, 0x21,
+    0xdc, 0xac, 0xbe, 0x2a, 0x52, 0x57, 0xf6, 0x6f, 0x13, 0x1b, 0xf1, 0xc2, 0xce, 0x80, 0xcb, 0x4f
+];
+
+// Multiplication in GF(2^8) modulo x^8 + x^7 + x^6 + x + 1
+fn mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        0
+    } else {
+        EXP[(LOG[a as usize] as usize + LOG[b as usize] as usize) % 255]
+    }
+}
+
+fn xor(a: &[u8; 16], b: &[u8; 16]) -> [u8; 16] {
+    let mut out = [0u8; 16];
+    for i in 0..16 {
+        out[i] = a[i] ^ b[i];
+    }
+    out
+}
+
+fn linear(m: &[[u8; 16]; 16], x: &[u8; 16]) -> [u8; 16] {
+    let mut out = [0u8; 16];
+    for i in 0..16 {
+        for j in 0..16 {
+            out[i] ^= mul(m[i][j], x[j]);
+        }
+    }
+    out
+}
+
+// One full round without the key addition: the S transformation followed by the L transformation
+fn ls(x: &[u8; 16]) -> [u8; 16] {
+    let mut s = [0u8; 16];
+    for i in 0..16 {
+        s[i] = PI[x[i] as usize];
+    }
+    linear(&L, &s)
+}
+
+// The inverse of ls
+fn ls_inv(x: &[u8; 16]) -> [u8; 16] {
+    let mut s = linear(&L_INV, x);
+    for b in s.iter_mut() {
+        *b = PI_INV[*b as usize];
+    }
+    s
+}
+
+#[derive(Clone, Copy)]
+pub struct Kuznyechik {
+    keys: [[u8; 16]; 10]
+}
+
+impl Kuznyechik {
+    pub fn new(key: &[u8]) -> Kuznyechik {
+        assert!(key.len() == 32);
+
+        let mut keys = [[0u8; 16]; 10];
+        keys[0].copy_from_slice(&key[0..16]);
+        keys[1].copy_from_slice(&key[16..32]);
+
+        // Each pair of round keys is derived from the previous pair with 8 rounds of a Feistel
+        // network, whose round constants C_i are the L transformation of the number i.
+        let mut a = keys[0];
+        let mut b = keys[1];
+        for i in 0..32 {
+            let mut c = [0u8; 16];
+            c[15] = i as u8 + 1;
+            let c = linear(&L, &c);
+            let t = xor(&ls(&xor(&a, &c)), &b);
+            b = a;
+            a = t;
+            if i % 8 == 7 {
+                keys[(i + 1) / 4] = a;
+                keys[(i + 1) / 4 + 1] = b;
+            }
+        }
+
+        Kuznyechik {
+            keys: keys
+        }
+    }
+}
+
+impl BlockEncryptor for Kuznyechik {
+    fn block_size(&self) -> usize {
+        16
+    }
+
+    fn encrypt_block(&self, input: &[u8], output: &mut [u8]) {
+        assert!(input.len() == 16);
+        assert!(output.len() == 16);
+        let mut x = [0u8; 16];
+        x.copy_from_slice(input);
+        for k in self.keys[..9].iter() {
+            x = ls(&xor(&x, k));
+        }
+        output.copy_from_slice(&xor(&x, &self.keys[9]));
+    }
+}
+
+impl BlockDecryptor for Kuznyechik {
+    fn block_size(&self) -> usize {
+        16
+    }
+
+    fn decrypt_block(&self, input: &[u8], output: &mut [u8]) {
+        assert!(input.len() == 16);
+        assert!(output.len() == 16);
+        let mut x = [0u8; 16];
+        x.copy_from_slice(input);
+        x = xor(&x, &self.keys[9]);
+        for k in self.keys[..9].iter().rev() {
+            x = xor(&ls_inv(&x), k);
+        }
+        output.copy_from_slice(&x);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use kuznyechik::Kuznyechik;
+    use symmetriccipher::{BlockEncryptor, BlockDecryptor};
+    use serialize::hex::FromHex;
+
+    // The example from GOST R 34.12-2015, Appendix A.1
+    const KEY: &str = "8899aabbccddeeff0011223344556677fedcba98765432100123456789abcdef";
+    const PLAINTEXT: &str = "1122334455667700ffeeddccbbaa9988";
+    const CIPHERTEXT: &str = "7f679d90bebc24305a468d42b9d4edcd";
+
+    #[test]
+    fn test_encrypt() {
+        let state = Kuznyechik::new(&KEY.from_hex().unwrap());
+        let mut output = [0u8; 16];
+        state.encrypt_block(&PLAINTEXT.from_hex().unwrap(), &mut output);
+        assert_eq!(&output[..], &CIPHERTEXT.from_hex().unwrap()[..]);
+    }
+
+    #[test]
+    fn test_decrypt() {
+        let state = Kuznyechik::new(&KEY.from_hex().unwrap());
+        let mut output = [0u8; 16];
+        state.decrypt_block(&CIPHERTEXT.from_hex().unwrap(), &mut output);
+        assert_eq!(&output[..], &PLAINTEXT.from_hex().unwrap()[..]);
+    }
+
+    #[test]
+    fn test_key_schedule() {
+        // The last round key from the example
+        let state = Kuznyechik::new(&KEY.from_hex().unwrap());
+        assert_eq!(&state.keys[9][..], &"72e9dd7416bcf45b755dbaa88e4a4043".from_hex().unwrap()[..]);
+    }
+}
+
+#[cfg(all(test, feature = "with-bench"))]
+mod bench {
+    use kuznyechik::Kuznyechik;
+    use symmetriccipher::BlockEncryptor;
+    use test::Bencher;
+
+    #[bench]
+    fn kuznyechik(bh: &mut Bencher) {
+        let key = [0u8; 32];
+        let plaintext = [1u8; 16];
+        let state = Kuznyechik::new(&key);
+        let mut ciphertext = [0u8; 16];
+
+        bh.iter(|| {
+            state.encrypt_block(&plaintext, &mut ciphertext);
+        });
+        bh.bytes = 16u64;
+    }
+}
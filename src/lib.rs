@@ -14,43 +14,65 @@ extern crate libc;
 #[cfg(all(test, feature = "with-bench"))]
 extern crate test;
 
+pub mod adiantum;
 pub mod aead;
 pub mod aes;
+pub mod aes_ccm;
 pub mod aes_gcm;
+pub mod aes_gcm_siv;
+pub mod aes_siv;
 pub mod aessafe;
+pub mod aria;
 pub mod bcrypt;
 pub mod bcrypt_pbkdf;
 pub mod blake2b;
 pub mod blockmodes;
 pub mod blowfish;
 pub mod buffer;
+pub mod camellia;
 pub mod chacha20;
 pub mod chacha20poly1305;
+pub mod cmac;
 mod cryptoutil;
 pub mod curve25519;
 pub mod digest;
+pub mod eax;
 pub mod ed25519;
 pub mod fortuna;
 pub mod ghash;
 pub mod hc128;
+pub mod hctr2;
 pub mod hmac;
 pub mod hkdf;
+pub mod keywrap;
+pub mod kuznyechik;
 pub mod mac;
+pub mod magma;
 pub mod md5;
+pub mod nacl;
+pub mod ocb;
 pub mod pbkdf2;
 pub mod poly1305;
+pub mod polyval;
+pub mod present;
 pub mod rc4;
 pub mod ripemd160;
 pub mod salsa20;
 pub mod scrypt;
+pub mod seed;
+pub mod serpent;
 pub mod sha1;
 pub mod sha2;
+pub mod simon;
 mod simd;
 pub mod sosemanuk;
+pub mod speck;
 mod step_by;
+pub mod streebog;
 pub mod symmetriccipher;
 pub mod util;
 pub mod whirlpool;
+pub mod xts;
 
 #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
 pub mod aesni;
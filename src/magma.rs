@@ -0,0 +1,161 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+An implementation of the Magma block cipher.
+
+Magma is the 64 bit block cipher standardized in GOST R 34.12-2015 (and RFC 8891). It is the
+GOST 28147-89 cipher with its S-boxes fixed to the id-tc26-gost-28147-param-Z set. It is a 32
+round Feistel network with a 256 bit key.
+
+This implementation uses S-box lookups which are indexed by secret data and so it is not
+protected against cache timing attacks.
+*/
+
+use cryptoutil::{read_u32v_be, write_u32_be};
+use symmetriccipher::{BlockEncryptor, BlockDecryptor};
+
+static PI: [[u8; 16]; 8] = [
+    [12, 4, 6, 2, 10, 5, 11, 9, 14, 8, 13, 7, 0, 3, 15, 1],
+    [6, 8, 2, 3, 9, 10, 5, 12, 1, 14, 4, 7, 11, 13, 0, 15],
+    [11, 3, 5, 8, 2, 15, 10, 13, 14, 1, 7, 4, 12, 9, 6, 0],
+    [12, 8, 2, 1, 13, 4, 15, 6, 7, 0, 10, 5, 3, 14, 9, 11],
+    [7, 15, 5, 10, 8, 1, 6, 13, 0, 9, 3, 14, 11, 4, 2, 12],
+    [5, 13, 15, 6, 9, 2, 12, 10, 11, 7, 8, 1, 4, 3, 14, 0],
+    [8, 14, 2, 5, 6, 9, 1, 12, 15, 4, 11, 0, 13, 10, 3, 7],
+    [1, 7, 14, 13, 0, 5, 8, 3, 4, 15, 10, 6, 9, 12, 11, 2]
+];
+
+// The round function g[k]. The S-box PI[i] substitutes the i-th least significant nibble.
+fn g(k: u32, a: u32) -> u32 {
+    let x = a.wrapping_add(k);
+    let mut t = 0u32;
+    for i in 0..8 {
+        t |= (PI[i][((x >> (4 * i)) & 0xf) as usize] as u32) << (4 * i);
+    }
+    t.rotate_left(11)
+}
+
+#[derive(Clone, Copy)]
+pub struct Magma {
+    k: [u32; 8]
+}
+
+impl Magma {
+    pub fn new(key: &[u8]) -> Magma {
+        assert!(key.len() == 32);
+        let mut k = [0u32; 8];
+        read_u32v_be(&mut k, key);
+        Magma {
+            k: k
+        }
+    }
+
+    // The first 24 rounds use the key words in order three times and the last 8 use them in
+    // reverse, so decryption is encryption with the schedule reversed.
+    fn round_key(&self, round: usize) -> u32 {
+        if round < 24 {
+            self.k[round % 8]
+        } else {
+            self.k[31 - round]
+        }
+    }
+
+    fn crypt(&self, input: &[u8], output: &mut [u8], decrypt: bool) {
+        let mut x = [0u32; 2];
+        read_u32v_be(&mut x, input);
+        let (mut a1, mut a0) = (x[0], x[1]);
+
+        for i in 0..32 {
+            let k = if decrypt { self.round_key(31 - i) } else { self.round_key(i) };
+            let t = g(k, a0) ^ a1;
+            a1 = a0;
+            a0 = t;
+        }
+
+        // The halves are not swapped after the last round.
+        write_u32_be(&mut output[0..4], a0);
+        write_u32_be(&mut output[4..8], a1);
+    }
+}
+
+impl BlockEncryptor for Magma {
+    fn block_size(&self) -> usize {
+        8
+    }
+
+    fn encrypt_block(&self, input: &[u8], output: &mut [u8]) {
+        assert!(input.len() == 8);
+        assert!(output.len() == 8);
+        self.crypt(input, output, false);
+    }
+}
+
+impl BlockDecryptor for Magma {
+    fn block_size(&self) -> usize {
+        8
+    }
+
+    fn decrypt_block(&self, input: &[u8], output: &mut [u8]) {
+        assert!(input.len() == 8);
+        assert!(output.len() == 8);
+        self.crypt(input, output, true);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use magma::{g, Magma};
+    use symmetriccipher::{BlockEncryptor, BlockDecryptor};
+    use serialize::hex::FromHex;
+
+    // The example from GOST R 34.12-2015, Appendix A.2
+    const KEY: &str = "ffeeddccbbaa99887766554433221100f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff";
+    const PLAINTEXT: &str = "fedcba9876543210";
+    const CIPHERTEXT: &str = "4ee901e5c2d8ca3d";
+
+    #[test]
+    fn test_round_function() {
+        assert_eq!(g(0x87654321, 0xfedcba98), 0xfdcbc20c);
+        assert_eq!(g(0xfdcbc20c, 0x87654321), 0x7e791a4b);
+    }
+
+    #[test]
+    fn test_encrypt() {
+        let state = Magma::new(&KEY.from_hex().unwrap());
+        let mut output = [0u8; 8];
+        state.encrypt_block(&PLAINTEXT.from_hex().unwrap(), &mut output);
+        assert_eq!(&output[..], &CIPHERTEXT.from_hex().unwrap()[..]);
+    }
+
+    #[test]
+    fn test_decrypt() {
+        let state = Magma::new(&KEY.from_hex().unwrap());
+        let mut output = [0u8; 8];
+        state.decrypt_block(&CIPHERTEXT.from_hex().unwrap(), &mut output);
+        assert_eq!(&output[..], &PLAINTEXT.from_hex().unwrap()[..]);
+    }
+}
+
+#[cfg(all(test, feature = "with-bench"))]
+mod bench {
+    use magma::Magma;
+    use symmetriccipher::BlockEncryptor;
+    use test::Bencher;
+
+    #[bench]
+    fn magma(bh: &mut Bencher) {
+        let key = [0u8; 32];
+        let plaintext = [1u8; 8];
+        let state = Magma::new(&key);
+        let mut ciphertext = [0u8; 8];
+
+        bh.iter(|| {
+            state.encrypt_block(&plaintext, &mut ciphertext);
+        });
+        bh.bytes = 8u64;
+    }
+}
@@ -0,0 +1,420 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+Implementations of the NaCl `crypto_secretbox` and `crypto_box` constructions and of libsodium's
+sealed boxes. The output of each function is byte for byte identical to the corresponding
+libsodium "easy" function: the 16 byte authenticator comes first, followed by the cipher text.
+
+* `secretbox` encrypts and authenticates a message under a 32 byte secret key and a 24 byte nonce
+  using XSalsa20-Poly1305.
+* `crypto_box` encrypts and authenticates a message from one Curve25519 key pair to another. The
+  shared key is derived from the Curve25519 shared secret with HSalsa20 and used with
+  `secretbox`. It can be computed once with `box_shared_key` and then used with `secretbox`
+  directly.
+* `sealed_box` anonymously encrypts a message to a public key, using a fresh ephemeral key pair.
+  The ephemeral public key is prepended to the output and the nonce is derived from the two
+  public keys with BLAKE2b, so the output is 48 bytes longer than the message.
+
+Nonces must never be reused with the same key. They are long enough to be picked at random.
+
+Like libsodium, the functions that do a key exchange refuse public keys that give an all zero
+Curve25519 shared secret, which are the points of low order.
+
+# Usage
+
+```
+use crypto::nacl;
+
+let (alice_secret, alice_public) = nacl::box_keypair().unwrap();
+let (bob_secret, bob_public) = nacl::box_keypair().unwrap();
+let nonce = [7u8; 24];
+
+let message = b"hello";
+let mut boxed = [0u8; 21];
+assert!(nacl::crypto_box(&bob_public, &alice_secret, &nonce, message, &mut boxed));
+
+let mut opened = [0u8; 5];
+assert!(nacl::crypto_box_open(&alice_public, &bob_secret, &nonce, &boxed, &mut opened));
+assert_eq!(&opened, message);
+```
+*/
+
+use std::io;
+
+use rand::{OsRng, Rng};
+
+use blake2b::Blake2b;
+use cryptoutil::{copy_memory, zero};
+use curve25519::{curve25519, curve25519_base};
+use mac::Mac;
+use poly1305::Poly1305;
+use salsa20::{Salsa20, hsalsa20};
+use symmetriccipher::SynchronousStreamCipher;
+use util::fixed_time_eq;
+
+/// The length of the authenticator added by `secretbox` and `crypto_box`.
+pub const MAC_BYTES: usize = 16;
+
+/// The length of the nonce used by `secretbox` and `crypto_box`.
+pub const NONCE_BYTES: usize = 24;
+
+/// The number of bytes added to a message by `sealed_box`.
+pub const SEAL_BYTES: usize = 32 + MAC_BYTES;
+
+/// Encrypts and authenticates `message` using XSalsa20-Poly1305. The output must be `MAC_BYTES`
+/// longer than the message.
+pub fn secretbox(key: &[u8], nonce: &[u8], message: &[u8], output: &mut [u8]) {
+    assert!(key.len() == 32);
+    assert!(nonce.len() == NONCE_BYTES);
+    assert!(output.len() == message.len() + MAC_BYTES);
+
+    let (tag, cipher_text) = output.split_at_mut(MAC_BYTES);
+    let mut cipher = Salsa20::new_xsalsa20(key, nonce);
+    let mut mac = secretbox_mac(&mut cipher);
+    cipher.process(message, cipher_text);
+    mac.input(cipher_text);
+    mac.raw_result(tag);
+}
+
+/// Verifies and decrypts the output of `secretbox`. The output must be `MAC_BYTES` shorter than
+/// the input. Returns false, and leaves the output zeroed, if the input is not authentic.
+pub fn secretbox_open(key: &[u8], nonce: &[u8], input: &[u8], output: &mut [u8]) -> bool {
+    assert!(key.len() == 32);
+    assert!(nonce.len() == NONCE_BYTES);
+    assert!(output.len() + MAC_BYTES == input.len());
+
+    let (tag, cipher_text) = input.split_at(MAC_BYTES);
+    let mut cipher = Salsa20::new_xsalsa20(key, nonce);
+    let mut mac = secretbox_mac(&mut cipher);
+    mac.input(cipher_text);
+    let mut calc_tag = [0u8; MAC_BYTES];
+    mac.raw_result(&mut calc_tag);
+    if fixed_time_eq(&calc_tag, tag) {
+        cipher.process(cipher_text, output);
+        true
+    } else {
+        zero(output);
+        false
+    }
+}
+
+// The Poly1305 key is the first 32 bytes of the key stream. The message is encrypted with the
+// rest of it.
+fn secretbox_mac(cipher: &mut Salsa20) -> Poly1305 {
+    let mut mac_key = [0u8; 32];
+    cipher.process(&[0u8; 32], &mut mac_key);
+    Poly1305::new(&mac_key)
+}
+
+/// Generates a new Curve25519 key pair using the operating system's random number generator.
+/// Returns the secret key and the public key.
+pub fn box_keypair() -> io::Result<([u8; 32], [u8; 32])> {
+    let mut rng = try!(OsRng::new());
+    let mut secret_key = [0u8; 32];
+    rng.fill_bytes(&mut secret_key);
+    Ok((secret_key, box_public_key(&secret_key)))
+}
+
+/// Computes the public key that corresponds to a Curve25519 secret key.
+pub fn box_public_key(secret_key: &[u8]) -> [u8; 32] {
+    assert!(secret_key.len() == 32);
+    curve25519_base(secret_key)
+}
+
+/// Computes the key shared between the owner of `secret_key` and the owner of the secret key
+/// behind `public_key`, as done by `crypto_box_beforenm`. It can be used with `secretbox` and
+/// `secretbox_open` to exchange many messages without repeating the key exchange. Returns None if
+/// the shared secret is all zeros, because `public_key` is a point of low order.
+pub fn box_shared_key(public_key: &[u8], secret_key: &[u8]) -> Option<[u8; 32]> {
+    assert!(public_key.len() == 32);
+    assert!(secret_key.len() == 32);
+    let mut shared_secret = curve25519(secret_key, public_key);
+    if fixed_time_eq(&shared_secret, &[0u8; 32]) {
+        return None;
+    }
+    let mut key = [0u8; 32];
+    hsalsa20(&shared_secret, &[0u8; 16], &mut key);
+    zero(&mut shared_secret);
+    Some(key)
+}
+
+/// Encrypts and authenticates `message` from the owner of `secret_key` to the owner of
+/// `public_key`. The output must be `MAC_BYTES` longer than the message. Returns false, and leaves
+/// the output untouched, if `public_key` is a point of low order.
+pub fn crypto_box(public_key: &[u8], secret_key: &[u8], nonce: &[u8], message: &[u8],
+                  output: &mut [u8]) -> bool {
+    match box_shared_key(public_key, secret_key) {
+        Some(mut key) => {
+            secretbox(&key, nonce, message, output);
+            zero(&mut key);
+            true
+        }
+        None => false
+    }
+}
+
+/// Verifies and decrypts the output of `crypto_box`, using the public key of the sender and the
+/// secret key of the recipient. The output must be `MAC_BYTES` shorter than the input. Returns
+/// false, and leaves the output zeroed, if the input is not authentic or `public_key` is a point
+/// of low order.
+pub fn crypto_box_open(public_key: &[u8], secret_key: &[u8], nonce: &[u8], input: &[u8],
+                       output: &mut [u8]) -> bool {
+    match box_shared_key(public_key, secret_key) {
+        Some(mut key) => {
+            let result = secretbox_open(&key, nonce, input, output);
+            zero(&mut key);
+            result
+        }
+        None => {
+            zero(output);
+            false
+        }
+    }
+}
+
+/// Anonymously encrypts `message` to the owner of `public_key`. The output must be `SEAL_BYTES`
+/// longer than the message. The sender can not decrypt the output. Fails with `InvalidInput` if
+/// `public_key` is a point of low order.
+pub fn sealed_box(public_key: &[u8], message: &[u8], output: &mut [u8]) -> io::Result<()> {
+    let (mut ephemeral_secret, _) = try!(box_keypair());
+    let sealed = sealed_box_with_ephemeral(public_key, &ephemeral_secret, message, output);
+    zero(&mut ephemeral_secret);
+    if sealed {
+        Ok(())
+    } else {
+        Err(io::Error::new(io::ErrorKind::InvalidInput, "Public key is a point of low order"))
+    }
+}
+
+fn sealed_box_with_ephemeral(public_key: &[u8], ephemeral_secret: &[u8], message: &[u8],
+                             output: &mut [u8]) -> bool {
+    assert!(public_key.len() == 32);
+    assert!(output.len() == message.len() + SEAL_BYTES);
+
+    let ephemeral_public = box_public_key(ephemeral_secret);
+    let nonce = sealed_box_nonce(&ephemeral_public, public_key);
+    let (epk, boxed) = output.split_at_mut(32);
+    copy_memory(&ephemeral_public, epk);
+    crypto_box(public_key, ephemeral_secret, &nonce, message, boxed)
+}
+
+/// Decrypts the output of `sealed_box` using the recipient's key pair. The output must be
+/// `SEAL_BYTES` shorter than the input. Returns false, and leaves the output zeroed, if the input
+/// is not authentic.
+pub fn sealed_box_open(public_key: &[u8], secret_key: &[u8], input: &[u8], output: &mut [u8])
+        -> bool {
+    assert!(public_key.len() == 32);
+    assert!(output.len() + SEAL_BYTES == input.len());
+
+    let (ephemeral_public, boxed) = input.split_at(32);
+    let nonce = sealed_box_nonce(ephemeral_public, public_key);
+    crypto_box_open(ephemeral_public, secret_key, &nonce, boxed, output)
+}
+
+// The nonce of a sealed box is BLAKE2b-192(ephemeral public key || recipient public key)
+fn sealed_box_nonce(ephemeral_public: &[u8], public_key: &[u8]) -> [u8; NONCE_BYTES] {
+    let mut keys = [0u8; 64];
+    copy_memory(ephemeral_public, &mut keys[..32]);
+    copy_memory(public_key, &mut keys[32..]);
+    let mut nonce = [0u8; NONCE_BYTES];
+    Blake2b::blake2b(&mut nonce, &keys, &[]);
+    nonce
+}
+
+#[cfg(test)]
+mod test {
+    use std::iter::repeat;
+
+    use nacl::{secretbox, secretbox_open, box_keypair, box_public_key, box_shared_key, crypto_box,
+               crypto_box_open, sealed_box, sealed_box_with_ephemeral, sealed_box_open,
+               MAC_BYTES, SEAL_BYTES};
+    use serialize::hex::FromHex;
+
+    fn hex_to_bytes(raw_hex: &str) -> Vec<u8> {
+        raw_hex.from_hex().ok().unwrap()
+    }
+
+    fn alice_secret() -> Vec<u8> {
+        hex_to_bytes("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a")
+    }
+
+    fn alice_public() -> Vec<u8> {
+        hex_to_bytes("8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a")
+    }
+
+    fn bob_secret() -> Vec<u8> {
+        hex_to_bytes("5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb")
+    }
+
+    fn bob_public() -> Vec<u8> {
+        hex_to_bytes("de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f")
+    }
+
+    // The message, nonce and expected output of the box test from the NaCl distribution
+    // (tests/box.c), which are also used by libsodium's test suite.
+    fn nacl_nonce() -> Vec<u8> {
+        hex_to_bytes("69696ee955b62b73cd62bda875fc73d68219e0036b7a0b37")
+    }
+
+    fn nacl_message() -> Vec<u8> {
+        hex_to_bytes("be075fc53c81f2d5cf141316ebeb0c7b5228c52a4c62cbd44b66849b64244ffc\
+                      e5ecbaaf33bd751a1ac728d45e6c61296cdc3c01233561f41db66cce314adb31\
+                      0e3be8250c46f06dceea3a7fa1348057e2f6556ad6b1318a024a838f21af1fde\
+                      048977eb48f59ffd4924ca1c60902e52f0a089bc76897040e082f93776384864\
+                      5e0705")
+    }
+
+    fn nacl_boxed() -> Vec<u8> {
+        hex_to_bytes("f3ffc7703f9400e52a7dfb4b3d3305d98e993b9f48681273c29650ba32fc76ce\
+                      48332ea7164d96a4476fb8c531a1186ac0dfc17c98dce87b4da7f011ec48c972\
+                      71d2c20f9b928fe2270d6fb863d51738b48eeee314a7cc8ab932164548e526ae\
+                      90224368517acfeabd6bb3732bc0e9da99832b61ca01b6de56244a9e88d5f9b3\
+                      7973f622a43d14a6599b1f654cb45a74e355a5")
+    }
+
+    #[test]
+    fn test_box_keys() {
+        assert_eq!(box_public_key(&alice_secret()).to_vec(), alice_public());
+        assert_eq!(box_public_key(&bob_secret()).to_vec(), bob_public());
+        let shared =
+            hex_to_bytes("1b27556473e985d462cd51197a9a46c76009549eac6474f206c4ee0844f68389");
+        assert_eq!(box_shared_key(&bob_public(), &alice_secret()).unwrap().to_vec(), shared);
+        assert_eq!(box_shared_key(&alice_public(), &bob_secret()).unwrap().to_vec(), shared);
+    }
+
+    // Public keys of low order give an all zero shared secret, which libsodium rejects
+    #[test]
+    fn test_low_order_public_key() {
+        let mut one = [0u8; 32];
+        one[0] = 1;
+        for public_key in [[0u8; 32], one].iter() {
+            assert!(box_shared_key(public_key, &alice_secret()).is_none());
+
+            let mut boxed = [3u8; 21];
+            assert!(!crypto_box(public_key, &alice_secret(), &nacl_nonce(), b"hello", &mut boxed));
+            assert_eq!(boxed, [3u8; 21]);
+
+            let mut opened = [3u8; 5];
+            assert!(!crypto_box_open(public_key, &alice_secret(), &nacl_nonce(), &boxed,
+                                     &mut opened));
+            assert_eq!(opened, [0u8; 5]);
+
+            let mut sealed = [0u8; 5 + SEAL_BYTES];
+            assert!(sealed_box(public_key, b"hello", &mut sealed).is_err());
+        }
+    }
+
+    #[test]
+    fn test_crypto_box() {
+        let message = nacl_message();
+        let mut output: Vec<u8> = repeat(0).take(message.len() + MAC_BYTES).collect();
+        assert!(crypto_box(&bob_public(), &alice_secret(), &nacl_nonce(), &message, &mut output));
+        assert_eq!(output, nacl_boxed());
+    }
+
+    #[test]
+    fn test_crypto_box_open() {
+        let boxed = nacl_boxed();
+        let mut output: Vec<u8> = repeat(0).take(boxed.len() - MAC_BYTES).collect();
+        assert!(crypto_box_open(&alice_public(), &bob_secret(), &nacl_nonce(), &boxed,
+                                &mut output));
+        assert_eq!(output, nacl_message());
+    }
+
+    #[test]
+    fn test_crypto_box_open_fail() {
+        for &i in [0, 15, 16, 146].iter() {
+            let mut boxed = nacl_boxed();
+            boxed[i] ^= 1;
+            let mut output: Vec<u8> = repeat(1).take(boxed.len() - MAC_BYTES).collect();
+            assert!(!crypto_box_open(&alice_public(), &bob_secret(), &nacl_nonce(), &boxed,
+                                     &mut output));
+            assert!(output.iter().all(|&b| b == 0));
+        }
+    }
+
+    // Generated with an implementation of XSalsa20-Poly1305 in Python
+    #[test]
+    fn test_secretbox() {
+        let key: Vec<u8> = (0..32).collect();
+        let nonce: Vec<u8> = (100..124).collect();
+        let tests = [
+            (vec![], hex_to_bytes("f49572d6194281e3c87fbb4e2106932c")),
+            ((0..70).collect(),
+             hex_to_bytes("6c49cf7d8d7d68311cb45f5d4951dc3102b89bca3eb3c8eeb8f4299f3b89ad17\
+                           2ebb2ab4f0b35d0cbe15588f1696d797884ed63b6b12bdc3dd3f3d492c3ef34a\
+                           f1a19c2b26b30f683bd8693fe78fa05b03ca0e2e8c79"))
+        ];
+        for (message, expected) in tests.iter() {
+            let mut output: Vec<u8> = repeat(0).take(message.len() + MAC_BYTES).collect();
+            secretbox(&key, &nonce, message, &mut output);
+            assert_eq!(&output, expected);
+
+            let mut opened: Vec<u8> = repeat(0).take(message.len()).collect();
+            assert!(secretbox_open(&key, &nonce, &output, &mut opened));
+            assert_eq!(&opened, message);
+
+            output[0] ^= 0x80;
+            assert!(!secretbox_open(&key, &nonce, &output, &mut opened));
+        }
+    }
+
+    // The ephemeral key is fixed so that the output can be compared with one computed in Python,
+    // with the nonce derived with BLAKE2b as done by libsodium's crypto_box_seal
+    #[test]
+    fn test_sealed_box_with_ephemeral() {
+        let message = b"libsodium sealed box";
+        let ephemeral_secret: Vec<u8> = (1..33).collect();
+        let expected =
+            hex_to_bytes("07a37cbc142093c8b755dc1b10e86cb426374ad16aa853ed0bdfc0b2b86d1c7c\
+                          a92403570cf645206dc211cd5668e818da82ba0e3bcc8a51ce95cb4828f58f76\
+                          91c3169f");
+        let mut output: Vec<u8> = repeat(0).take(message.len() + SEAL_BYTES).collect();
+        assert!(sealed_box_with_ephemeral(&bob_public(), &ephemeral_secret, message, &mut output));
+        assert_eq!(output, expected);
+
+        let mut opened: Vec<u8> = repeat(0).take(message.len()).collect();
+        assert!(sealed_box_open(&bob_public(), &bob_secret(), &expected, &mut opened));
+        assert_eq!(&opened[..], &message[..]);
+        assert!(!sealed_box_open(&alice_public(), &alice_secret(), &expected, &mut opened));
+    }
+
+    #[test]
+    fn test_sealed_box_round_trip() {
+        let (secret_key, public_key) = box_keypair().unwrap();
+        let message = [42u8; 100];
+        let mut sealed = [0u8; 100 + SEAL_BYTES];
+        sealed_box(&public_key, &message, &mut sealed).unwrap();
+
+        let mut opened = [0u8; 100];
+        assert!(sealed_box_open(&public_key, &secret_key, &sealed, &mut opened));
+        assert_eq!(&opened[..], &message[..]);
+
+        sealed[0] ^= 1;
+        assert!(!sealed_box_open(&public_key, &secret_key, &sealed, &mut opened));
+    }
+}
+
+#[cfg(all(test, feature = "with-bench"))]
+mod bench {
+    use test::Bencher;
+    use nacl::{secretbox, secretbox_open};
+
+    #[bench]
+    pub fn secretbox_1k(bh: & mut Bencher) {
+        let key = [1u8; 32];
+        let nonce = [2u8; 24];
+        let input = [3u8; 1024];
+        bh.iter( || {
+            let mut output = [0u8; 1040];
+            let mut output2 = [0u8; 1024];
+            secretbox(&key, &nonce, &input, &mut output);
+            secretbox_open(&key, &nonce, &output, &mut output2);
+        });
+        bh.bytes = 1024u64;
+    }
+}
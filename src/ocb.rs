@@ -0,0 +1,581 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+ * An implementation of the OCB3 mode of authenticated encryption, as defined in RFC 7253 [1], for
+ * block ciphers with a 128 bit block size.
+ *
+ * OCB encrypts and authenticates in a single pass, with one block cipher call per block of input.
+ * Since the blocks are independent of each other, full blocks are processed eight at a time using
+ * BlockEncryptorX8 / BlockDecryptorX8, which allows the bitsliced implementations in aessafe to
+ * be used efficiently. The single block interface is used for the nonce, the final partial block
+ * and the tag.
+ *
+ * Decryption needs both directions of the block cipher: the decryption function for the full blocks
+ * and the encryption function for everything else.
+ *
+ * The nonce may be between 1 and 15 bytes long and the tag between 1 and 16 bytes long. RFC 7253
+ * only defines the 16, 12 and 8 byte tags.
+ *
+ * [1] - "The OCB Authenticated-Encryption Algorithm" - T. Krovetz and P. Rogaway
+ *       <https://tools.ietf.org/html/rfc7253>
+ */
+
+use std::cmp;
+
+use aead::{AeadEncryptor, AeadDecryptor};
+use cmac::double;
+use cryptoutil::{copy_memory, zero};
+use symmetriccipher::{BlockEncryptor, BlockEncryptorX8, BlockDecryptor, BlockDecryptorX8};
+use util::fixed_time_eq;
+
+fn xor_block(dst: &mut [u8; 16], src: &[u8]) {
+    for (d, &s) in dst.iter_mut().zip(src.iter()) {
+        *d ^= s;
+    }
+}
+
+// The state shared by encryption and decryption, which only needs the encryption direction of
+// the block cipher
+struct Ocb<E> {
+    cipher: E,
+    l_star: [u8; 16],
+    l_dollar: [u8; 16],
+    // L_i for every possible number of trailing zeros of a 64 bit block index
+    l: [[u8; 16]; 64],
+    offset: [u8; 16],
+    aad_hash: [u8; 16],
+    tag_len: usize,
+    finished: bool
+}
+
+impl <E: BlockEncryptor + BlockEncryptorX8> Ocb<E> {
+    fn new(cipher: E, nonce: &[u8], aad: &[u8], tag_len: usize) -> Ocb<E> {
+        assert!(BlockEncryptor::block_size(&cipher) == 16);
+        assert!(!nonce.is_empty() && nonce.len() <= 15);
+        assert!(tag_len > 0 && tag_len <= 16);
+
+        let mut l_star = [0u8; 16];
+        cipher.encrypt_block(&[0u8; 16], &mut l_star);
+        let mut l_dollar = l_star;
+        double(&mut l_dollar);
+        let mut l = [[0u8; 16]; 64];
+        l[0] = l_dollar;
+        double(&mut l[0]);
+        for i in 1..64 {
+            l[i] = l[i - 1];
+            double(&mut l[i]);
+        }
+
+        // Nonce = num2str(TAGLEN mod 128, 7) || zeros(120 - bitlen(N)) || 1 || N
+        let mut nonce_block = [0u8; 16];
+        copy_memory(nonce, &mut nonce_block[16 - nonce.len()..]);
+        nonce_block[15 - nonce.len()] |= 1;
+        nonce_block[0] |= (((tag_len * 8) % 128) << 1) as u8;
+
+        // Offset_0 is Stretch[1 + bottom..128 + bottom], where Stretch = Ktop || (Ktop[1..64] xor
+        // Ktop[9..72]) and Ktop is the encryption of the nonce with its last 6 bits cleared
+        let bottom = (nonce_block[15] & 0x3f) as usize;
+        nonce_block[15] &= 0xc0;
+        let mut stretch = [0u8; 24];
+        cipher.encrypt_block(&nonce_block, &mut stretch[..16]);
+        for i in 0..8 {
+            stretch[16 + i] = stretch[i] ^ stretch[i + 1];
+        }
+        let (byte_shift, bit_shift) = (bottom / 8, bottom % 8);
+        let mut offset = [0u8; 16];
+        for (i, o) in offset.iter_mut().enumerate() {
+            *o = stretch[i + byte_shift] << bit_shift;
+            if bit_shift > 0 {
+                *o |= stretch[i + byte_shift + 1] >> (8 - bit_shift);
+            }
+        }
+
+        let mut ocb = Ocb {
+            cipher: cipher,
+            l_star: l_star,
+            l_dollar: l_dollar,
+            l: l,
+            offset: offset,
+            aad_hash: [0u8; 16],
+            tag_len: tag_len,
+            finished: false
+        };
+        ocb.aad_hash = ocb.hash(aad);
+        ocb
+    }
+
+    // Fills offsets with the offsets of the next blocks, updating offset and index as it goes
+    fn next_offsets(&self, offset: &mut [u8; 16], index: &mut u64, offsets: &mut [u8]) {
+        for chunk in offsets.chunks_mut(16) {
+            *index += 1;
+            xor_block(offset, &self.l[index.trailing_zeros() as usize]);
+            copy_memory(offset, chunk);
+        }
+    }
+
+    // HASH(K, A) from RFC 7253
+    fn hash(&self, aad: &[u8]) -> [u8; 16] {
+        let mut sum = [0u8; 16];
+        let mut offset = [0u8; 16];
+        let mut index = 0;
+        let mut offsets = [0u8; 128];
+        let mut tmp = [0u8; 128];
+        let mut out = [0u8; 128];
+
+        let full = aad.len() / 16 * 16;
+        for chunk in aad[..full].chunks(128) {
+            let n = chunk.len();
+            self.next_offsets(&mut offset, &mut index, &mut offsets[..n]);
+            for ((t, &a), &o) in tmp.iter_mut().zip(chunk.iter()).zip(offsets.iter()) {
+                *t = a ^ o;
+            }
+            // A final group of fewer than 8 blocks is processed padded out to 8 blocks and the
+            // extra output is ignored
+            self.cipher.encrypt_block_x8(&tmp, &mut out);
+            for block in out[..n].chunks(16) {
+                xor_block(&mut sum, block);
+            }
+        }
+
+        if full < aad.len() {
+            xor_block(&mut offset, &self.l_star);
+            let mut block = [0u8; 16];
+            copy_memory(&aad[full..], &mut block);
+            block[aad.len() - full] = 0x80;
+            xor_block(&mut block, &offset);
+            let mut encrypted = [0u8; 16];
+            self.cipher.encrypt_block(&block, &mut encrypted);
+            xor_block(&mut sum, &encrypted);
+        }
+
+        sum
+    }
+
+    // Encrypts or decrypts the input, depending on the block function passed in, and returns the
+    // full tag. The checksum is computed over the input when encrypting and over the output when
+    // decrypting. As in hash, a final group of fewer than 8 full blocks is padded out to 8 blocks.
+    fn process<F>(&self, input: &[u8], output: &mut [u8], decrypt: bool, block_x8: F) -> [u8; 16]
+            where F: Fn(&[u8], &mut [u8]) {
+        assert!(input.len() == output.len());
+        let mut offset = self.offset;
+        let mut checksum = [0u8; 16];
+        let mut index = 0;
+        let mut offsets = [0u8; 128];
+        let mut tmp = [0u8; 128];
+        let mut out = [0u8; 128];
+
+        let full = input.len() / 16 * 16;
+        let mut pos = 0;
+        while pos < full {
+            let n = cmp::min(128, full - pos);
+            self.next_offsets(&mut offset, &mut index, &mut offsets[..n]);
+            for ((t, &x), &o) in
+                    tmp.iter_mut().zip(input[pos..pos + n].iter()).zip(offsets.iter()) {
+                *t = x ^ o;
+            }
+            block_x8(&tmp, &mut out);
+            for ((y, &x), &o) in
+                    output[pos..pos + n].iter_mut().zip(out.iter()).zip(offsets.iter()) {
+                *y = x ^ o;
+            }
+            let plain_text = if decrypt { &output[pos..pos + n] } else { &input[pos..pos + n] };
+            for p in plain_text.chunks(16) {
+                xor_block(&mut checksum, p);
+            }
+            pos += n;
+        }
+
+        if full < input.len() {
+            let remaining = input.len() - full;
+            xor_block(&mut offset, &self.l_star);
+            let mut pad = [0u8; 16];
+            self.cipher.encrypt_block(&offset, &mut pad);
+            for ((y, &x), &p) in
+                    output[full..].iter_mut().zip(input[full..].iter()).zip(pad.iter()) {
+                *y = x ^ p;
+            }
+            let mut last = [0u8; 16];
+            copy_memory(if decrypt { &output[full..] } else { &input[full..] }, &mut last);
+            last[remaining] = 0x80;
+            xor_block(&mut checksum, &last);
+        }
+
+        xor_block(&mut checksum, &offset);
+        xor_block(&mut checksum, &self.l_dollar);
+        let mut tag = [0u8; 16];
+        self.cipher.encrypt_block(&checksum, &mut tag);
+        xor_block(&mut tag, &self.aad_hash);
+        tag
+    }
+}
+
+/// OCB encryption using a block cipher that can process eight blocks at a time
+pub struct OcbEncryptor<E> {
+    ocb: Ocb<E>
+}
+
+impl <E: BlockEncryptor + BlockEncryptorX8> OcbEncryptor<E> {
+    /**
+     * Create a new OcbEncryptor instance.
+     *
+     * # Arguments
+     * * cipher - The keyed block cipher to use. It must have a 128 bit block size.
+     * * nonce - The nonce, between 1 and 15 bytes long.
+     * * aad - The associated data to authenticate.
+     * * tag_len - The length of the tag in bytes, between 1 and 16.
+     *
+     */
+    pub fn new(cipher: E, nonce: &[u8], aad: &[u8], tag_len: usize) -> OcbEncryptor<E> {
+        OcbEncryptor {
+            ocb: Ocb::new(cipher, nonce, aad, tag_len)
+        }
+    }
+}
+
+impl <E: BlockEncryptor + BlockEncryptorX8> AeadEncryptor for OcbEncryptor<E> {
+    fn encrypt(&mut self, input: &[u8], output: &mut [u8], tag: &mut [u8]) {
+        assert!(tag.len() == self.ocb.tag_len);
+        assert!(!self.ocb.finished);
+        self.ocb.finished = true;
+        let full_tag = {
+            let cipher = &self.ocb.cipher;
+            self.ocb.process(
+                input,
+                output,
+                false,
+                |i, o| cipher.encrypt_block_x8(i, o))
+        };
+        copy_memory(&full_tag[..tag.len()], tag);
+    }
+}
+
+/// OCB decryption using a block cipher that can process eight blocks at a time. The encryption
+/// direction of the same cipher is needed as well.
+pub struct OcbDecryptor<E, D> {
+    ocb: Ocb<E>,
+    cipher: D
+}
+
+impl <E: BlockEncryptor + BlockEncryptorX8, D: BlockDecryptor + BlockDecryptorX8>
+        OcbDecryptor<E, D> {
+    /**
+     * Create a new OcbDecryptor instance.
+     *
+     * # Arguments
+     * * encryptor - The keyed block cipher to use for encryption. It must have a 128 bit block
+     *   size.
+     * * decryptor - The keyed block cipher to use for decryption, with the same key as encryptor.
+     * * nonce - The nonce, between 1 and 15 bytes long.
+     * * aad - The associated data to authenticate.
+     * * tag_len - The length of the tag in bytes, between 1 and 16.
+     *
+     */
+    pub fn new(encryptor: E, decryptor: D, nonce: &[u8], aad: &[u8], tag_len: usize)
+            -> OcbDecryptor<E, D> {
+        assert!(BlockDecryptor::block_size(&decryptor) == 16);
+        OcbDecryptor {
+            ocb: Ocb::new(encryptor, nonce, aad, tag_len),
+            cipher: decryptor
+        }
+    }
+}
+
+impl <E: BlockEncryptor + BlockEncryptorX8, D: BlockDecryptor + BlockDecryptorX8> AeadDecryptor
+        for OcbDecryptor<E, D> {
+    fn decrypt(&mut self, input: &[u8], output: &mut [u8], tag: &[u8]) -> bool {
+        assert!(tag.len() == self.ocb.tag_len);
+        assert!(!self.ocb.finished);
+        self.ocb.finished = true;
+        let calc_tag = {
+            let cipher = &self.cipher;
+            self.ocb.process(
+                input,
+                output,
+                true,
+                |i, o| cipher.decrypt_block_x8(i, o))
+        };
+        if fixed_time_eq(&calc_tag[..tag.len()], tag) {
+            true
+        } else {
+            zero(output);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::iter::repeat;
+
+    use aead::{AeadEncryptor, AeadDecryptor};
+    use aessafe;
+    use ocb::{OcbEncryptor, OcbDecryptor};
+    use serialize::hex::FromHex;
+    use symmetriccipher::{BlockEncryptor, BlockEncryptorX8, BlockDecryptor, BlockDecryptorX8};
+
+    struct Test {
+        key: Vec<u8>,
+        nonce: Vec<u8>,
+        aad: Vec<u8>,
+        plain_text: Vec<u8>,
+        cipher_text: Vec<u8>,
+        tag: Vec<u8>
+    }
+
+    fn hex_to_bytes(raw_hex: &str) -> Vec<u8> {
+        raw_hex.from_hex().ok().unwrap()
+    }
+
+    fn range(len: usize, step: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * step) as u8).collect()
+    }
+
+    // The first six tests are sample results from RFC 7253 Appendix A. The remaining tests are
+    // long enough to use the eight block code paths and were generated with an independent
+    // implementation, which was checked against the AESOCB3 implementation of the Python
+    // cryptography package and against the RFC.
+    fn tests() -> Vec<Test> {
+        vec![
+            Test {
+                key: range(16, 1),
+                nonce: hex_to_bytes("bbaa99887766554433221100"),
+                aad: vec![],
+                plain_text: vec![],
+                cipher_text: vec![],
+                tag: hex_to_bytes("785407bfffc8ad9edcc5520ac9111ee6")
+            },
+            Test {
+                key: range(16, 1),
+                nonce: hex_to_bytes("bbaa99887766554433221101"),
+                aad: range(8, 1),
+                plain_text: range(8, 1),
+                cipher_text: hex_to_bytes("6820b3657b6f615a"),
+                tag: hex_to_bytes("5725bda0d3b4eb3a257c9af1f8f03009")
+            },
+            Test {
+                key: range(16, 1),
+                nonce: hex_to_bytes("bbaa99887766554433221102"),
+                aad: range(8, 1),
+                plain_text: vec![],
+                cipher_text: vec![],
+                tag: hex_to_bytes("81017f8203f081277152fade694a0a00")
+            },
+            Test {
+                key: range(16, 1),
+                nonce: hex_to_bytes("bbaa99887766554433221103"),
+                aad: vec![],
+                plain_text: range(8, 1),
+                cipher_text: hex_to_bytes("45dd69f8f5aae724"),
+                tag: hex_to_bytes("14054cd1f35d82760b2cd00d2f99bfa9")
+            },
+            Test {
+                key: range(16, 1),
+                nonce: hex_to_bytes("bbaa9988776655443322110d"),
+                aad: range(40, 1),
+                plain_text: range(40, 1),
+                cipher_text: hex_to_bytes("d5ca91748410c1751ff8a2f618255b68a0a12e093ff454606e59f9c1d0ddc54b\
+                                           65e8628e568bad7a"),
+                tag: hex_to_bytes("ed07ba06a4a69483a7035490c5769e60")
+            },
+            Test {
+                key: hex_to_bytes("0f0e0d0c0b0a09080706050403020100"),
+                nonce: hex_to_bytes("bbaa9988776655443322110d"),
+                aad: range(40, 1),
+                plain_text: range(40, 1),
+                cipher_text: hex_to_bytes("1792a4e31e0755fb03e31b22116e6c2ddf9efd6e33d536f1a0124b0a55bae884\
+                                           ed93481529c76b6a"),
+                tag: hex_to_bytes("d0c515f4d1cdd4fdac4f02aa")
+            },
+            Test {
+                key: range(32, 1),
+                nonce: range(15, 1),
+                aad: range(150, 1),
+                plain_text: range(300, 3),
+                cipher_text: hex_to_bytes(LONG_CIPHER_TEXT_1),
+                tag: hex_to_bytes("b28e5ec22d07894fad9c293e2ad9575e")
+            },
+            Test {
+                key: range(16, 1),
+                nonce: range(12, 1),
+                aad: vec![],
+                plain_text: range(256, 3),
+                cipher_text: hex_to_bytes(LONG_CIPHER_TEXT_2),
+                tag: hex_to_bytes("0b9f07c25e527e89d7f3c628")
+            },
+            Test {
+                key: range(24, 1),
+                nonce: range(7, 1),
+                aad: range(1000, 1),
+                plain_text: range(129, 3),
+                cipher_text: hex_to_bytes(LONG_CIPHER_TEXT_3),
+                tag: hex_to_bytes("c75fd68abedb6240285003b52fbce5aa")
+            },
+        ]
+    }
+
+    static LONG_CIPHER_TEXT_1: &'static str =
+        "523332d6dd2418d19decb44826b0b9a1b3e7654a0d973e563785e04cb7c84e1b\
+         b56b06f0d87afec2c5163057773059fa6efb2c8305b2e7238e3eaa1858bdf935\
+         950eabc9deb1a598f25e29489835a76018f97e1ce2c2467bbf7e87d534d51b72\
+         12e2caa3879f37fdaa18dde878a48e79488d1941193605ce8d100724557b43bc\
+         9d114c05d70740134dce11610fee5c3bf899128b9c8da50ff6e6941b23f27bb6\
+         4beb06cb962dd5008e9fc9222035cfefc778ccd9a37ba7b17175a23c8a1b431a\
+         e82f8f76297d865a8fd7818d777de92ff342753155e928b0f8ae0d161e0b65ab\
+         08b55d525bc0adbcb92cc0dd3f142f8ff16c3d9b79b9713a31870d898a4a3e13\
+         8408c0e419f1e0d3ecf8a025330345a75d8ad2e8d96e5dde4f64384370d05cc4\
+         d1b40f1878d5ad41fba2b5dc";
+
+    static LONG_CIPHER_TEXT_2: &'static str =
+        "ce56d27ca8993b8a9fd5c5ae3bf70b50304477bcd9f2f2135f27a80bc04cd505\
+         6027a585d0aea879be82a80444b1c5e7a8716e78f902d643df5f0674558dcb73\
+         c9bc13fe38907cabeffcdacc9f9c0d70fb25baa9de418a368ecd5a0512e7fe6d\
+         d8b54591c63551e9afda4c6040c423cd45f7991afe45fd3dbde4f8fe11c45b35\
+         c473ee4f61872ada83aa580c386986ef5e2101fe0fb1e7cba32c630ed988cf1a\
+         8095e03cbc566824ee1023b1cead80e74f553d1cef8e4f239ae8266ce47df431\
+         828f9b7303b0f7fe7377ac7afe35be0f575ac4f6a639bf4b442f9dfa49cf3587\
+         3683c84d2768e111b9c423cca76b743a99c1f5e88b769007f2280b2aa6ceda9c";
+
+    static LONG_CIPHER_TEXT_3: &'static str =
+        "d9958ba1c0e572fe6a1a207ab79715845929ff838a9d31f8c6fca91b0aa2a8f2\
+         99ff9d6c1db12d61d5079cd1574243afcac93f50665916ce6bcf0668f21bef37\
+         02bd91cb00c06ea658f33ba2d1daa4907f8017b8e06ccc9ca56213c2d3b2fb82\
+         7ca405a9a3f8409d08a559739ff9394b0bf6bb7735d354a2f978ba9003ac9e72\
+         95";
+
+    fn encrypt<E: BlockEncryptor + BlockEncryptorX8>(cipher: E, test: &Test) -> (Vec<u8>, Vec<u8>) {
+        let mut ocb = OcbEncryptor::new(cipher, &test.nonce, &test.aad, test.tag.len());
+        let mut out: Vec<u8> = repeat(0).take(test.plain_text.len()).collect();
+        let mut tag: Vec<u8> = repeat(0).take(test.tag.len()).collect();
+        ocb.encrypt(&test.plain_text, &mut out, &mut tag);
+        (out, tag)
+    }
+
+    fn decrypt<E, D>(encryptor: E, decryptor: D, test: &Test, tag: &[u8]) -> (Vec<u8>, bool)
+            where E: BlockEncryptor + BlockEncryptorX8, D: BlockDecryptor + BlockDecryptorX8 {
+        let mut ocb = OcbDecryptor::new(encryptor, decryptor, &test.nonce, &test.aad, tag.len());
+        let mut out: Vec<u8> = repeat(0).take(test.cipher_text.len()).collect();
+        let result = ocb.decrypt(&test.cipher_text, &mut out, tag);
+        (out, result)
+    }
+
+    // The cipher text and tag from encryption, and the plain text and result from decryption
+    type Output = (Vec<u8>, Vec<u8>, Vec<u8>, bool);
+
+    // Runs the test with both the 4 and 8 block bitsliced AES implementations
+    fn run_test(test: &Test, tag: &[u8]) -> Vec<Output> {
+        let key = &test.key[..];
+        macro_rules! run(
+            ($enc:ident, $dec:ident) => ({
+                let (c, t) = encrypt(aessafe::$enc::new(key), test);
+                let (p, r) = decrypt(aessafe::$enc::new(key), aessafe::$dec::new(key), test, tag);
+                (c, t, p, r)
+            })
+        );
+        match key.len() {
+            16 => vec![run!(AesSafe128EncryptorX4, AesSafe128DecryptorX4),
+                       run!(AesSafe128EncryptorX8, AesSafe128DecryptorX8)],
+            24 => vec![run!(AesSafe192EncryptorX4, AesSafe192DecryptorX4),
+                       run!(AesSafe192EncryptorX8, AesSafe192DecryptorX8)],
+            32 => vec![run!(AesSafe256EncryptorX4, AesSafe256DecryptorX4),
+                       run!(AesSafe256EncryptorX8, AesSafe256DecryptorX8)],
+            _ => unreachable!()
+        }
+    }
+
+    #[test]
+    fn ocb_test() {
+        for test in tests().iter() {
+            for (cipher_text, tag, plain_text, result) in run_test(test, &test.tag) {
+                assert_eq!(cipher_text, test.cipher_text);
+                assert_eq!(tag, test.tag);
+                assert_eq!(plain_text, test.plain_text);
+                assert!(result);
+            }
+        }
+    }
+
+    #[test]
+    fn ocb_decrypt_fail_test() {
+        for test in tests().iter() {
+            let mut tag = test.tag.clone();
+            tag[0] ^= 1;
+            for (_, _, plain_text, result) in run_test(test, &tag) {
+                let zero: Vec<u8> = repeat(0).take(plain_text.len()).collect();
+                assert_eq!(plain_text, zero);
+                assert!(!result);
+            }
+        }
+    }
+
+    // The iterative test from RFC 7253 Appendix A, which covers every message and associated data
+    // length up to 127 bytes, for each of the tag lengths defined by the RFC.
+    #[test]
+    fn ocb_rfc_iterative_test() {
+        fn ocb_encrypt(key: &[u8], nonce: u32, aad: &[u8], plain_text: &[u8], tag_len: usize)
+                -> Vec<u8> {
+            let mut nonce_bytes = [0u8; 12];
+            nonce_bytes[8] = (nonce >> 24) as u8;
+            nonce_bytes[9] = (nonce >> 16) as u8;
+            nonce_bytes[10] = (nonce >> 8) as u8;
+            nonce_bytes[11] = nonce as u8;
+            let mut ocb = OcbEncryptor::new(
+                aessafe::AesSafe128EncryptorX4::new(key), &nonce_bytes, aad, tag_len);
+            let mut out: Vec<u8> = repeat(0).take(plain_text.len() + tag_len).collect();
+            let (c, t) = out.split_at_mut(plain_text.len());
+            ocb.encrypt(plain_text, c, t);
+            out
+        }
+
+        let cases = [
+            (16, "67e944d23256c5e0b6c61fa22fdf1ea2"),
+            (12, "77a3d8e73589158d25d01209"),
+            (8, "192c9b7bd90ba06a")
+        ];
+        for &(tag_len, expected) in cases.iter() {
+            let mut key = [0u8; 16];
+            key[15] = (tag_len * 8) as u8;
+            let mut c = Vec::new();
+            for i in 0..128 {
+                let s: Vec<u8> = repeat(0).take(i).collect();
+                let n = 3 * i as u32;
+                c.extend(ocb_encrypt(&key, n + 1, &s, &s, tag_len));
+                c.extend(ocb_encrypt(&key, n + 2, &[], &s, tag_len));
+                c.extend(ocb_encrypt(&key, n + 3, &s, &[], tag_len));
+            }
+            let output = ocb_encrypt(&key, 385, &c, &[], tag_len);
+            assert_eq!(output, hex_to_bytes(expected));
+        }
+    }
+}
+
+#[cfg(all(test, feature = "with-bench"))]
+mod bench {
+    use test::Bencher;
+    use aead::{AeadEncryptor, AeadDecryptor};
+    use aessafe::{AesSafe256EncryptorX4, AesSafe256DecryptorX4};
+    use ocb::{OcbEncryptor, OcbDecryptor};
+
+    #[bench]
+    pub fn ocb_1k(bh: & mut Bencher) {
+        let input = [1u8; 1024];
+        let aad = [3u8; 1024];
+        let enc = AesSafe256EncryptorX4::new(&[0; 32]);
+        let dec = AesSafe256DecryptorX4::new(&[0; 32]);
+        bh.iter( || {
+            let mut cipher = OcbEncryptor::new(enc, &[0; 12], &aad, 16);
+            let mut decipher = OcbDecryptor::new(enc, dec, &[0; 12], &aad, 16);
+
+            let mut output = [0u8; 1024];
+            let mut tag = [0u8; 16];
+            let mut output2 = [0u8; 1024];
+
+            cipher.encrypt(&input, &mut output, &mut tag);
+            decipher.decrypt(&output, &mut output2, &tag);
+        });
+        bh.bytes = 1024u64;
+    }
+}
@@ -0,0 +1,248 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+An implementation of POLYVAL, the universal hash function defined in RFC 8452 [1].
+
+POLYVAL is closely related to GHASH, but works in GF(2^128) modulo x^128 + x^127 + x^126 + x^121 + 1
+with little endian byte and bit ordering. For a key H and input blocks X_1, ..., X_s, the result is
+S_s where S_0 = 0 and S_j = (S_{j-1} + X_j) * H * x^-128.
+
+The input is processed in 16 byte blocks, and a final partial block is padded with zeros. Unlike
+GHASH, POLYVAL does not append a block of lengths - callers that need one must provide it as part of
+the input.
+
+In order to ensure constant time computation, multiplication uses a precomputed table of the key
+multiplied by each power of x, and selects entries using masks rather than branches, in the same way
+as the GHASH implementation.
+
+[1] - "AES-GCM-SIV: Nonce Misuse-Resistant Authenticated Encryption" - S. Gueron, A. Langley and
+      Y. Lindell <https://tools.ietf.org/html/rfc8452>
+*/
+
+use std::ops::BitXor;
+
+use cryptoutil::{copy_memory, read_u64_le, write_u64_le};
+use mac::{Mac, MacResult};
+
+// A struct representing an element in GF(2^128)
+// x^0 is the lsb of lo, while x^127 is the msb of hi
+#[derive(Clone, Copy)]
+struct Gf128 { lo: u64, hi: u64 }
+
+impl Gf128 {
+    fn zero() -> Gf128 {
+        Gf128 { lo: 0, hi: 0 }
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Gf128 {
+        assert!(bytes.len() == 16);
+        Gf128 { lo: read_u64_le(&bytes[0..8]), hi: read_u64_le(&bytes[8..16]) }
+    }
+
+    fn to_bytes(&self) -> [u8; 16] {
+        let mut result = [0u8; 16];
+        write_u64_le(&mut result[0..8], self.lo);
+        write_u64_le(&mut result[8..16], self.hi);
+        result
+    }
+
+    // Multiply the element by x^-1 modulo x^128 + x^127 + x^126 + x^121 + 1
+    // If the x^0 coefficient is set, the modulus is added first so that the division by x is exact.
+    // Either way, this is a rightshift, followed by an XOR iff the lsb was set.
+    fn times_x_inv(self) -> Gf128 {
+        let mask = 0u64.wrapping_sub(self.lo & 1);
+        Gf128 {
+            lo: (self.lo >> 1) | (self.hi << 63),
+            hi: (self.hi >> 1) ^ (mask & 0xe100_0000_0000_0000)
+        }
+    }
+
+    // Computes self * h * x^-128 using a precomputed array of the values h * x^-128 to h * x^-1
+    fn dot(self, hs: &[Gf128; 128]) -> Gf128 {
+        let mut result = Gf128::zero();
+        for (i, h) in hs.iter().enumerate() {
+            let bit = if i < 64 { self.lo >> i } else { self.hi >> (i - 64) };
+            let mask = 0u64.wrapping_sub(bit & 1);
+            result.lo ^= h.lo & mask;
+            result.hi ^= h.hi & mask;
+        }
+        result
+    }
+}
+
+impl BitXor for Gf128 {
+    type Output = Gf128;
+
+    fn bitxor(self, rhs: Gf128) -> Gf128 {
+        Gf128 { lo: self.lo ^ rhs.lo, hi: self.hi ^ rhs.hi }
+    }
+}
+
+/// A structure representing the state of a POLYVAL computation
+#[derive(Copy)]
+pub struct Polyval {
+    hs: [Gf128; 128],
+    state: Gf128,
+    buffer: [u8; 16],
+    buffer_len: usize,
+    finished: bool
+}
+
+impl Clone for Polyval { fn clone(&self) -> Polyval { *self } }
+
+impl Polyval {
+    /// Creates a new POLYVAL state, with `h` as the key
+    pub fn new(h: &[u8]) -> Polyval {
+        assert!(h.len() == 16);
+
+        // Precompute values for h * x^-128 to h * x^-1
+        let mut table = [Gf128::zero(); 128];
+        let mut h = Gf128::from_bytes(h);
+        for poly in table.iter_mut().rev() {
+            h = h.times_x_inv();
+            *poly = h;
+        }
+
+        Polyval {
+            hs: table,
+            state: Gf128::zero(),
+            buffer: [0u8; 16],
+            buffer_len: 0,
+            finished: false
+        }
+    }
+
+    fn block(&mut self, block: &[u8]) {
+        self.state = (self.state ^ Gf128::from_bytes(block)).dot(&self.hs);
+    }
+}
+
+impl Mac for Polyval {
+    fn input(&mut self, data: &[u8]) {
+        assert!(!self.finished);
+        let mut data = data;
+
+        if self.buffer_len > 0 {
+            let want = ::std::cmp::min(16 - self.buffer_len, data.len());
+            copy_memory(&data[..want], &mut self.buffer[self.buffer_len..self.buffer_len + want]);
+            self.buffer_len += want;
+            data = &data[want..];
+
+            if self.buffer_len < 16 {
+                return;
+            }
+
+            let tmp = self.buffer;
+            self.block(&tmp);
+            self.buffer_len = 0;
+        }
+
+        while data.len() >= 16 {
+            self.block(&data[..16]);
+            data = &data[16..];
+        }
+
+        copy_memory(data, &mut self.buffer[..data.len()]);
+        self.buffer_len = data.len();
+    }
+
+    fn reset(&mut self) {
+        self.state = Gf128::zero();
+        self.buffer_len = 0;
+        self.finished = false;
+    }
+
+    fn result(&mut self) -> MacResult {
+        let mut mac = [0u8; 16];
+        self.raw_result(&mut mac[..]);
+        MacResult::new(&mac[..])
+    }
+
+    fn raw_result(&mut self, output: &mut [u8]) {
+        assert!(output.len() >= 16);
+        if !self.finished {
+            if self.buffer_len > 0 {
+                for b in self.buffer[self.buffer_len..].iter_mut() {
+                    *b = 0;
+                }
+                let tmp = self.buffer;
+                self.block(&tmp);
+                self.buffer_len = 0;
+            }
+            self.finished = true;
+        }
+
+        copy_memory(&self.state.to_bytes(), output);
+    }
+
+    fn output_bytes(&self) -> usize { 16 }
+}
+
+#[cfg(test)]
+mod test {
+    use mac::Mac;
+    use polyval::Polyval;
+    use serialize::hex::FromHex;
+
+    // Format: (H, X, POLYVAL(H, X))
+    // The first test is from RFC 8452 Appendix A and the last is the POLYVAL computation from the
+    // first AES-GCM-SIV test in Appendix C.1.
+    static CASES: &'static [(&'static str, &'static str, &'static str)] = &[
+        ("25629347589242761d31f826ba4b757b",
+         "4f4f95668c83dfb6401762bb2d01a262d1a24ddd2721d006bbe45f20d3c9f362",
+         "f7a3b47b846119fae5b7866cf5e5b77e"),
+        ("25629347589242761d31f826ba4b757b",
+         "",
+         "00000000000000000000000000000000"),
+        ("d9b360279694941ac5dbc6987ada7377",
+         "0100000000000000000000000000000000000000000000004000000000000000",
+         "eb93b7740962c5e49d2a90a7dc5cec74"),
+    ];
+
+    #[test]
+    fn hash() {
+        for &(h, x, expected) in CASES.iter() {
+            let mut polyval = Polyval::new(&h.from_hex().unwrap());
+            polyval.input(&x.from_hex().unwrap());
+            let mut result = [0u8; 16];
+            polyval.raw_result(&mut result);
+            assert!(result[..] == expected.from_hex().unwrap()[..]);
+        }
+    }
+
+    #[test]
+    fn split_input() {
+        for &(h, x, expected) in CASES.iter() {
+            let x = x.from_hex().unwrap();
+            for split in 0..x.len() {
+                let mut polyval = Polyval::new(&h.from_hex().unwrap());
+                polyval.input(&x[..split]);
+                polyval.input(&x[split..]);
+                let mut result = [0u8; 16];
+                polyval.raw_result(&mut result);
+                assert!(result[..] == expected.from_hex().unwrap()[..]);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "with-bench"))]
+mod bench {
+    use test::Bencher;
+    use mac::Mac;
+    use polyval::Polyval;
+
+    #[bench]
+    pub fn polyval_1k(bh: & mut Bencher) {
+        let mut polyval = Polyval::new(&[1u8; 16]);
+        let bytes = [1u8; 1024];
+        bh.iter( || {
+            polyval.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+}
@@ -0,0 +1,222 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+An implementation of the PRESENT lightweight block cipher.
+
+PRESENT is a 31 round substitution-permutation network operating on 64 bit blocks, specified in
+"PRESENT: An Ultra-Lightweight Block Cipher" and in ISO/IEC 29192-2. Both the 80 and the 128 bit
+key variants are supported. Blocks and keys are read as big endian numbers.
+
+The permutation layer is designed for hardware and is computed here one bit at a time, so this
+implementation is slow.
+*/
+
+use cryptoutil::{read_u64v_be, write_u64_be};
+use symmetriccipher::{BlockEncryptor, BlockDecryptor};
+
+static SBOX: [u8; 16] = [0xc, 0x5, 0x6, 0xb, 0x9, 0x0, 0xa, 0xd, 0x3, 0xe, 0xf, 0x8, 0x4, 0x7, 0x1, 0x2];
+
+static SBOX_INV: [u8; 16] = [0x5, 0xe, 0xf, 0x8, 0xc, 0x1, 0x2, 0xd, 0xb, 0x4, 0x6, 0x3, 0x0, 0x7, 0x9, 0xa];
+
+fn read_u64_be(input: &[u8]) -> u64 {
+    let mut x = [0u64; 1];
+    read_u64v_be(&mut x, input);
+    x[0]
+}
+
+fn s_layer(x: u64, sbox: &[u8; 16]) -> u64 {
+    let mut out = 0u64;
+    for i in 0..16 {
+        out |= (sbox[((x >> (4 * i)) & 0xf) as usize] as u64) << (4 * i);
+    }
+    out
+}
+
+// Bit i moves to bit 16 * i mod 63, except for bit 63 which stays in place.
+fn p_layer(x: u64) -> u64 {
+    let mut out = 0u64;
+    for i in 0..64 {
+        let j = if i == 63 { 63 } else { (16 * i) % 63 };
+        out |= ((x >> i) & 1) << j;
+    }
+    out
+}
+
+fn p_layer_inv(x: u64) -> u64 {
+    let mut out = 0u64;
+    for i in 0..64 {
+        let j = if i == 63 { 63 } else { (16 * i) % 63 };
+        out |= ((x >> j) & 1) << i;
+    }
+    out
+}
+
+#[derive(Clone, Copy)]
+pub struct Present {
+    round_keys: [u64; 32]
+}
+
+impl Present {
+    pub fn new(key: &[u8]) -> Present {
+        assert!(key.len() == 10 || key.len() == 16);
+
+        // The key register is split into its most significant 64 bits, which form the round key,
+        // and its remaining 16 or 64 bits.
+        let mut hi = read_u64_be(&key[0..8]);
+        let mut lo = if key.len() == 10 {
+            ((key[8] as u64) << 8) | key[9] as u64
+        } else {
+            read_u64_be(&key[8..16])
+        };
+
+        let mut round_keys = [0u64; 32];
+        for (i, round_key) in round_keys.iter_mut().enumerate() {
+            *round_key = hi;
+            let counter = i as u64 + 1;
+            if key.len() == 10 {
+                // Rotate the 80 bit register left by 61 bits
+                let new_hi = (hi >> 19) | (((hi & 0x7) << 16 | lo) << 45);
+                lo = (hi >> 3) & 0xffff;
+                hi = new_hi;
+                hi = (hi & 0x0fffffffffffffff) | ((SBOX[(hi >> 60) as usize] as u64) << 60);
+                hi ^= counter >> 1;
+                lo ^= (counter & 1) << 15;
+            } else {
+                // Rotate the 128 bit register left by 61 bits
+                let new_hi = (hi << 61) | (lo >> 3);
+                lo = (lo << 61) | (hi >> 3);
+                hi = new_hi;
+                hi = (hi & 0x00ffffffffffffff) |
+                    ((SBOX[(hi >> 60) as usize] as u64) << 60) |
+                    ((SBOX[((hi >> 56) & 0xf) as usize] as u64) << 56);
+                hi ^= counter >> 2;
+                lo ^= (counter & 3) << 62;
+            }
+        }
+
+        Present {
+            round_keys: round_keys
+        }
+    }
+}
+
+impl BlockEncryptor for Present {
+    fn block_size(&self) -> usize {
+        8
+    }
+
+    fn encrypt_block(&self, input: &[u8], output: &mut [u8]) {
+        assert!(input.len() == 8);
+        assert!(output.len() == 8);
+        let mut x = read_u64_be(input);
+        for k in self.round_keys[..31].iter() {
+            x = p_layer(s_layer(x ^ k, &SBOX));
+        }
+        write_u64_be(output, x ^ self.round_keys[31]);
+    }
+}
+
+impl BlockDecryptor for Present {
+    fn block_size(&self) -> usize {
+        8
+    }
+
+    fn decrypt_block(&self, input: &[u8], output: &mut [u8]) {
+        assert!(input.len() == 8);
+        assert!(output.len() == 8);
+        let mut x = read_u64_be(input) ^ self.round_keys[31];
+        for k in self.round_keys[..31].iter().rev() {
+            x = s_layer(p_layer_inv(x), &SBOX_INV) ^ k;
+        }
+        write_u64_be(output, x);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use present::Present;
+    use symmetriccipher::{BlockEncryptor, BlockDecryptor};
+    use serialize::hex::FromHex;
+
+    struct Test {
+        key: &'static str,
+        plaintext: &'static str,
+        ciphertext: &'static str
+    }
+
+    fn tests() -> Vec<Test> {
+        vec![
+            // Vectors from the PRESENT paper, Appendix I
+            Test {
+                key: "00000000000000000000",
+                plaintext: "0000000000000000",
+                ciphertext: "5579c1387b228445"
+            },
+            Test {
+                key: "ffffffffffffffffffff",
+                plaintext: "0000000000000000",
+                ciphertext: "e72c46c0f5945049"
+            },
+            Test {
+                key: "00000000000000000000",
+                plaintext: "ffffffffffffffff",
+                ciphertext: "a112ffc72f68417b"
+            },
+            Test {
+                key: "ffffffffffffffffffff",
+                plaintext: "ffffffffffffffff",
+                ciphertext: "3333dcd3213210d2"
+            },
+            // PRESENT-128
+            Test {
+                key: "00000000000000000000000000000000",
+                plaintext: "0000000000000000",
+                ciphertext: "96db702a2e6900af"
+            }
+        ]
+    }
+
+    #[test]
+    fn test_encrypt() {
+        for test in tests().iter() {
+            let state = Present::new(&test.key.from_hex().unwrap());
+            let mut output = [0u8; 8];
+            state.encrypt_block(&test.plaintext.from_hex().unwrap(), &mut output);
+            assert_eq!(&output[..], &test.ciphertext.from_hex().unwrap()[..]);
+        }
+    }
+
+    #[test]
+    fn test_decrypt() {
+        for test in tests().iter() {
+            let state = Present::new(&test.key.from_hex().unwrap());
+            let mut output = [0u8; 8];
+            state.decrypt_block(&test.ciphertext.from_hex().unwrap(), &mut output);
+            assert_eq!(&output[..], &test.plaintext.from_hex().unwrap()[..]);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "with-bench"))]
+mod bench {
+    use present::Present;
+    use symmetriccipher::BlockEncryptor;
+    use test::Bencher;
+
+    #[bench]
+    fn present80(bh: &mut Bencher) {
+        let key = [0u8; 10];
+        let plaintext = [1u8; 8];
+        let state = Present::new(&key);
+        let mut ciphertext = [0u8; 8];
+
+        bh.iter(|| {
+            state.encrypt_block(&plaintext, &mut ciphertext);
+        });
+        bh.bytes = 8u64;
+    }
+}
@@ -0,0 +1,265 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+An implementation of the SEED block cipher as specified in RFC 4269.
+
+SEED is a 16 round Feistel network operating on 128 bit blocks with a 128 bit key.
+
+This implementation uses S-box lookups which are indexed by secret data and so it is not
+protected against cache timing attacks.
+*/
+
+use cryptoutil::{read_u32v_be, write_u32_be};
+use symmetriccipher::{BlockEncryptor, BlockDecryptor};
+
+static S0: [u8; 256] = [
+    0xa9, 0x85, 0xd6, 0xd3, 0x54, 0x1d, 0xac, 0x25, 0x5d, 0x43, 0x18, 0x1e, 0x51, 0xfc, 0xca, 0x63,
+    0x28, 0x44, 0x20, 0x9d, 0xe0, 0xe2, 0xc8, 0x17, 0xa5, 0x8f, 0x03, 0x7b, 0xbb, 0x13, 0xd2, 0xee,
+    0x70, 0x8c, 0x3f, 0xa8, 0x32, 0xdd, 0xf6, 0x74, 0xec, 0x95, 0x0b, 0x57, 0x5c, 0x5b, 0xbd, 0x01,
+    0x24, 0x1c, 0x73, 0x98, 0x10, 0xcc, 0xf2, 0xd9, 0x2c, 0xe7, 0x72, 0x83, 0x9b, 0xd1, 0x86, 0xc9,
+    0x60, 0x50, 0xa3, 0xeb, 0x0d, 0xb6, 0x9e, 0x4f, 0xb7, 0x5a, 0xc6, 0x78, 0xa6, 0x12, 0xaf, 0xd5,
+    0x61, 0xc3, 0xb4, 0x41, 0x52, 0x7d, 0x8d, 0x08, 0x1f, 0x99, 0x00, 0x19, 0x04, 0x53, 0xf7, 0xe1,
+    0xfd, 0x76, 0x2f, 0x27, 0xb0, 0x8b, 0x0e, 0xab, 0xa2, 0x6e, 0x93, 0x4d, 0x69, 0x7c, 0x09, 0x0a,
+    0xbf, 0xef, 0xf3, 0xc5, 0x87, 0x14, 0xfe, 0x64, 0xde, 0x2e, 0x4b, 0x1a, 0x06, 0x21, 0x6b, 0x66,
+    0x02, 0xf5, 0x92, 0x8a, 0x0c, 0xb3, 0x7e, 0xd0, 0x7a, 0x47, 0x96, 0xe5, 0x26, 0x80, 0xad, 0xdf,
+    0xa1, 0x30, 0x37, 0xae, 0x36, 0x15, 0x22, 0x38, 0xf4, 0xa7, 0x45, 0x4c, 0x81, 0xe9, 0x84, 0x97,
+    0x35, 0xcb, 0xce, 0x3c, 0x71, 0x11, 0xc7, 0x89, 0x75, 0xfb, 0xda, 0xf8, 0x94, 0x59, 0x82, 0xc4,
+    0xff, 0x49, 0x39, 0x67, 0xc0, 0xcf, 0xd7, 0xb8, 0x0f, 0x8e, 0x42, 0x23, 0x91, 0x6c, 0xdb, 0xa4,
+    0x34, 0xf1, 0x48, 0xc2, 0x6f, 0x3d, 0x2d, 0x40, 0xbe, 0x3e, 0xbc, 0xc1, 0xaa, 0xba, 0x4e, 0x55,
+    0x3b, 0xdc, 0x68, 0x7f, 0x9c, 0xd8, 0x4a, 0x56, 0x77, 0xa0, 0xed, 0x46, 0xb5, 0x2b, 0x65, 0xfa,
+    0xe3, 0xb9, 0xb1, 0x9f, 0x5e, 0xf9, 0xe6, 0xb2, 0x31, 0xea, 0x6d, 0x5f, 0xe4, 0xf0, 0xcd, 0x88,
+    0x16, 0x3a, 0x58, 0xd4, 0x62, 0x29, 0x07, 0x33, 0xe8, 0x1b, 0x05, 0x79, 0x90, 0x6a, 0x2a, 0x9a
+];
+
+static S1: [u8; 256] = [
+    0x38, 0xe8, 0x2d, 0xa6, 0xcf, 0xde, 0xb3, 0xb8, 0xaf, 0x60, 0x55, 0xc7, 0x44, 0x6f, 0x6b, 0x5b,
+    0xc3, 0x62, 0x33, 0xb5, 0x29, 0xa0, 0xe2, 0xa7, 0xd3, 0x91, 0x11, 0x06, 0x1c, 0xbc, 0x36, 0x4b,
+    0xef, 0x88, 0x6c, 0xa8, 0x17, 0xc4, 0x16, 0xf4, 0xc2, 0x45, 0xe1, 0xd6, 0x3f, 0x3d, 0x8e, 0x98,
+    0x28, 0x4e, 0xf6, 0x3e, 0xa5, 0xf9, 0x0d, 0xdf, 0xd8, 0x2b, 0x66, 0x7a, 0x27, 0x2f, 0xf1, 0x72,
+    0x42, 0xd4, 0x41, 0xc0, 0x73, 0x67, 0xac, 0x8b, 0xf7, 0xad, 0x80, 0x1f, 0xca, 0x2c, 0xaa, 0x34,
+    0xd2, 0x0b, 0xee, 0xe9, 0x5d, 0x94, 0x18, 0xf8, 0x57, 0xae, 0x08, 0xc5, 0x13, 0xcd, 0x86, 0xb9,
+    0xff, 0x7d, 0xc1, 0x31, 0xf5, 0x8a, 0x6a, 0xb1, 0xd1, 0x20, 0xd7, 0x02, 0x22, 0x04, 0x68, 0x71,
+    0x07, 0xdb, 0x9d, 0x99, 0x61, 0xbe, 0xe6, 0x59, 0xdd, 0x51, 0x90, 0xdc, 0x9a, 0xa3, 0xab, 0xd0,
+    0x81, 0x0f, 0x47, 0x1a, 0xe3, 0xec, 0x8d, 0xbf, 0x96, 0x7b, 0x5c, 0xa2, 0xa1, 0x63, 0x23, 0x4d,
+    0xc8, 0x9e, 0x9c, 0x3a, 0x0c, 0x2e, 0xba, 0x6e, 0x9f, 0x5a, 0xf2, 0x92, 0xf3, 0x49, 0x78, 0xcc,
+    0x15, 0xfb, 0x70, 0x75, 0x7f, 0x35, 0x10, 0x03, 0x64, 0x6d, 0xc6, 0x74, 0xd5, 0xb4, 0xea, 0x09,
+    0x76, 0x19, 0xfe, 0x40, 0x12, 0xe0, 0xbd, 0x05, 0xfa, 0x01, 0xf0, 0x2a, 0x5e, 0xa9, 0x56, 0x43,
+    0x85, 0x14, 0x89, 0x9b, 0xb0, 0xe5, 0x48, 0x79, 0x97, 0xfc, 0x1e, 0x82, 0x21, 0x8c, 0x1b, 0x5f,
+    0x77, 0x54, 0xb2, 0x1d, 0x25, 0x4f, 0x00, 0x46, 0xed, 0x58, 0x52, 0xeb, 0x7e, 0xda, 0xc9, 0xfd,
+    0x30, 0x95, 0x65, 0x3c, 0xb6, 0xe4, 0xbb, 0x7c, 0x0e, 0x50, 0x39, 0x26, 0x32, 0x84, 0x69, 0x93,
+    0x37, 0xe7, 0x24, 0xa4, 0xcb, 0x53, 0x0a, 0x87, 0xd9, 0x4c, 0x83, 0x8f, 0xce, 0x3b, 0x4a, 0xb7
+];
+
+// Key schedule constants, derived from the golden ratio
+const KC: [u32; 16] = [
+    0x9e3779b9, 0x3c6ef373, 0x78dde6e6, 0xf1bbcdcc,
+    0xe3779b99, 0xc6ef3733, 0x8dde6e67, 0x1bbcdccf,
+    0x3779b99e, 0x6ef3733c, 0xdde6e678, 0xbbcdccf1,
+    0x779b99e3, 0xef3733c6, 0xde6e678d, 0xbcdccf1b
+];
+
+fn g(x: u32) -> u32 {
+    let y0 = S0[x as usize & 0xff] as u32;
+    let y1 = S1[(x >> 8) as usize & 0xff] as u32;
+    let y2 = S0[(x >> 16) as usize & 0xff] as u32;
+    let y3 = S1[(x >> 24) as usize] as u32;
+
+    let z0 = (y0 & 0xfc) ^ (y1 & 0xf3) ^ (y2 & 0xcf) ^ (y3 & 0x3f);
+    let z1 = (y0 & 0xf3) ^ (y1 & 0xcf) ^ (y2 & 0x3f) ^ (y3 & 0xfc);
+    let z2 = (y0 & 0xcf) ^ (y1 & 0x3f) ^ (y2 & 0xfc) ^ (y3 & 0xf3);
+    let z3 = (y0 & 0x3f) ^ (y1 & 0xfc) ^ (y2 & 0xf3) ^ (y3 & 0xcf);
+
+    (z3 << 24) | (z2 << 16) | (z1 << 8) | z0
+}
+
+// The round function, which takes the right half of the state and the round key
+fn f(r0: u32, r1: u32, k0: u32, k1: u32) -> (u32, u32) {
+    let c = r0 ^ k0;
+    let d = g((r1 ^ k1) ^ c);
+    let c = g(c.wrapping_add(d));
+    let d = g(d.wrapping_add(c));
+    (c.wrapping_add(d), d)
+}
+
+#[derive(Clone, Copy)]
+pub struct Seed {
+    k: [u32; 32]
+}
+
+impl Seed {
+    pub fn new(key: &[u8]) -> Seed {
+        assert!(key.len() == 16);
+
+        let mut x = [0u32; 4];
+        read_u32v_be(&mut x, key);
+
+        let mut k = [0u32; 32];
+        for i in 0..16 {
+            k[2 * i] = g(x[0].wrapping_add(x[2]).wrapping_sub(KC[i]));
+            k[2 * i + 1] = g(x[1].wrapping_sub(x[3]).wrapping_add(KC[i]));
+            if i % 2 == 0 {
+                let t = x[0];
+                x[0] = (x[0] >> 8) | (x[1] << 24);
+                x[1] = (x[1] >> 8) | (t << 24);
+            } else {
+                let t = x[2];
+                x[2] = (x[2] << 8) | (x[3] >> 24);
+                x[3] = (x[3] << 8) | (t >> 24);
+            }
+        }
+
+        Seed {
+            k: k
+        }
+    }
+
+    fn crypt(&self, input: &[u8], output: &mut [u8], decrypt: bool) {
+        let mut x = [0u32; 4];
+        read_u32v_be(&mut x, input);
+        let (mut l0, mut l1, mut r0, mut r1) = (x[0], x[1], x[2], x[3]);
+
+        for i in 0..16 {
+            let round = if decrypt { 15 - i } else { i };
+            let (t0, t1) = f(r0, r1, self.k[2 * round], self.k[2 * round + 1]);
+            let (n0, n1) = (l0 ^ t0, l1 ^ t1);
+            l0 = r0;
+            l1 = r1;
+            r0 = n0;
+            r1 = n1;
+        }
+
+        // The halves are not swapped after the last round.
+        write_u32_be(&mut output[0..4], r0);
+        write_u32_be(&mut output[4..8], r1);
+        write_u32_be(&mut output[8..12], l0);
+        write_u32_be(&mut output[12..16], l1);
+    }
+}
+
+impl BlockEncryptor for Seed {
+    fn block_size(&self) -> usize {
+        16
+    }
+
+    fn encrypt_block(&self, input: &[u8], output: &mut [u8]) {
+        assert!(input.len() == 16);
+        assert!(output.len() == 16);
+        self.crypt(input, output, false);
+    }
+}
+
+impl BlockDecryptor for Seed {
+    fn block_size(&self) -> usize {
+        16
+    }
+
+    fn decrypt_block(&self, input: &[u8], output: &mut [u8]) {
+        assert!(input.len() == 16);
+        assert!(output.len() == 16);
+        self.crypt(input, output, true);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use seed::Seed;
+    use blockmodes::{CbcEncryptor, NoPadding};
+    use buffer::{RefReadBuffer, RefWriteBuffer};
+    use symmetriccipher::{BlockEncryptor, BlockDecryptor, Encryptor};
+    use serialize::hex::FromHex;
+
+    struct Test {
+        key: &'static str,
+        plaintext: &'static str,
+        ciphertext: &'static str
+    }
+
+    // Vectors from RFC 4269, Appendix B
+    fn rfc4269_test_vectors() -> Vec<Test> {
+        vec![
+            Test {
+                key: "00000000000000000000000000000000",
+                plaintext: "000102030405060708090a0b0c0d0e0f",
+                ciphertext: "5ebac6e0054e166819aff1cc6d346cdb"
+            },
+            Test {
+                key: "000102030405060708090a0b0c0d0e0f",
+                plaintext: "00000000000000000000000000000000",
+                ciphertext: "c11f22f20140505084483597e4370f43"
+            },
+            Test {
+                key: "4706480851e61be85d74bfb3fd956185",
+                plaintext: "83a2f8a288641fb9a4e9a5cc2f131c7d",
+                ciphertext: "ee54d13ebcae706d226bc3142cd40d4a"
+            },
+            Test {
+                key: "28dbc3bc49ffd87dcfa509b11d422be7",
+                plaintext: "b41e6be2eba84a148e2eed84593c5ec7",
+                ciphertext: "9b9b7bfcd1813cb95d0b3618f40f5122"
+            }
+        ]
+    }
+
+    #[test]
+    fn encrypt_rfc4269_test_vectors() {
+        let mut output = [0u8; 16];
+        for test in rfc4269_test_vectors().iter() {
+            let state = Seed::new(&test.key.from_hex().unwrap());
+            state.encrypt_block(&test.plaintext.from_hex().unwrap(), &mut output);
+            assert_eq!(&output[..], &test.ciphertext.from_hex().unwrap()[..]);
+        }
+    }
+
+    #[test]
+    fn decrypt_rfc4269_test_vectors() {
+        let mut output = [0u8; 16];
+        for test in rfc4269_test_vectors().iter() {
+            let state = Seed::new(&test.key.from_hex().unwrap());
+            state.decrypt_block(&test.ciphertext.from_hex().unwrap(), &mut output);
+            assert_eq!(&output[..], &test.plaintext.from_hex().unwrap()[..]);
+        }
+    }
+
+    #[test]
+    fn seed_cbc() {
+        let key = "000102030405060708090a0b0c0d0e0f".from_hex().unwrap();
+        let iv = "0f0e0d0c0b0a09080706050403020100".from_hex().unwrap();
+        let plaintext = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f"
+            .from_hex().unwrap();
+        let expected = "b3ace4b2bb2ea06ac134056ec2fdb1253f3c75b9941930fa14c65afb32601c6c"
+            .from_hex().unwrap();
+
+        let mut encryptor = CbcEncryptor::new(Seed::new(&key), NoPadding, iv);
+        let mut output = [0u8; 32];
+        {
+            let mut read_buffer = RefReadBuffer::new(&plaintext);
+            let mut write_buffer = RefWriteBuffer::new(&mut output);
+            encryptor.encrypt(&mut read_buffer, &mut write_buffer, true).unwrap();
+        }
+        assert_eq!(&output[..], &expected[..]);
+    }
+}
+
+#[cfg(all(test, feature = "with-bench"))]
+mod bench {
+    use seed::Seed;
+    use symmetriccipher::BlockEncryptor;
+    use test::Bencher;
+
+    #[bench]
+    fn seed(bh: &mut Bencher) {
+        let key = [0u8; 16];
+        let plaintext = [1u8; 16];
+        let state = Seed::new(&key);
+        let mut ciphertext = [0u8; 16];
+
+        bh.iter(|| {
+            state.encrypt_block(&plaintext, &mut ciphertext);
+        });
+        bh.bytes = 16u64;
+    }
+}
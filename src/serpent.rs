@@ -0,0 +1,523 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+An implementation of the Serpent block cipher.
+
+Serpent operates on 128 bit blocks and accepts 128, 192, or 256 bit keys. The implementation here
+is the bitsliced form described in the submission to the AES competition: every S-box is evaluated
+as a short sequence of boolean operations on four 32 bit words, so no table lookups depend on the
+key or the data. The byte order follows the reference implementation and matches the NESSIE test
+vectors.
+
+The key schedule, S-boxes and linear transformation are also used by the Sosemanuk stream cipher,
+which runs a reduced round variant of Serpent to initialize its state.
+
+For details see <http://www.cl.cam.ac.uk/~rja14/serpent.html>.
+*/
+
+use cryptoutil::{read_u32v_le, write_u32v_le};
+use symmetriccipher::{BlockEncryptor, BlockDecryptor};
+
+const PHI: u32 = 0x9e3779b9;
+const ROUNDS: usize = 32;
+
+#[derive(Clone, Copy)]
+pub struct Serpent {
+    sk: [[u32; 4]; ROUNDS + 1]
+}
+
+impl Serpent {
+    pub fn new(key: &[u8]) -> Serpent {
+        assert!(key.len() == 16 || key.len() == 24 || key.len() == 32);
+        let mut serpent = Serpent { sk: [[0u32; 4]; ROUNDS + 1] };
+        expand_key(key, &mut serpent.sk);
+        serpent
+    }
+}
+
+// The S-boxes take the four words of the state as (x0, x1, x2, x3), where x0 holds the least
+// significant bit of each 4 bit S-box input, and return the output in the same order.
+
+fn sb0(a: u32, b: u32, c: u32, d: u32) -> [u32; 4] {
+    let t1 = a ^ d;
+    let t3 = c ^ t1;
+    let t4 = b ^ t3;
+    let x3 = (a & d) ^ t4;
+    let t7 = a ^ (b & t1);
+    let x2 = t4 ^ (c | t7);
+    let t12 = x3 & (t3 ^ t7);
+    let x1 = !t3 ^ t12;
+    let x0 = t12 ^ !t7;
+    [x0, x1, x2, x3]
+}
+
+fn ib0(a: u32, b: u32, c: u32, d: u32) -> [u32; 4] {
+    let t1 = !a;
+    let t2 = a ^ b;
+    let t4 = d ^ (t1 | t2);
+    let t5 = c ^ t4;
+    let x2 = t2 ^ t5;
+    let t8 = t1 ^ (d & t2);
+    let x1 = t4 ^ (x2 & t8);
+    let x3 = (a & t4) ^ (t5 | x1);
+    let x0 = x3 ^ (t5 ^ t8);
+    [x0, x1, x2, x3]
+}
+
+fn sb1(a: u32, b: u32, c: u32, d: u32) -> [u32; 4] {
+    let t2 = b ^ !a;
+    let t5 = c ^ (a | t2);
+    let x2 = d ^ t5;
+    let t7 = b ^ (d | t2);
+    let t8 = t2 ^ x2;
+    let x3 = t8 ^ (t5 & t7);
+    let t11 = t5 ^ t7;
+    let x1 = x3 ^ t11;
+    let x0 = t5 ^ (t8 & t11);
+    [x0, x1, x2, x3]
+}
+
+fn ib1(a: u32, b: u32, c: u32, d: u32) -> [u32; 4] {
+    let t1 = b ^ d;
+    let t3 = a ^ (b & t1);
+    let t4 = t1 ^ t3;
+    let x3 = c ^ t4;
+    let t7 = b ^ (t1 & t3);
+    let t8 = x3 | t7;
+    let x1 = t3 ^ t8;
+    let t10 = !x1;
+    let t11 = x3 ^ t7;
+    let x0 = t10 ^ t11;
+    let x2 = t4 ^ (t10 | t11);
+    [x0, x1, x2, x3]
+}
+
+fn sb2(a: u32, b: u32, c: u32, d: u32) -> [u32; 4] {
+    let t1 = !a;
+    let t2 = b ^ d;
+    let t3 = c & t1;
+    let x0 = t2 ^ t3;
+    let t5 = c ^ t1;
+    let t6 = c ^ x0;
+    let t7 = b & t6;
+    let x3 = t5 ^ t7;
+    let x2 = a ^ ((d | t7) & (x0 | t5));
+    let x1 = (t2 ^ x3) ^ (x2 ^ (d | t1));
+    [x0, x1, x2, x3]
+}
+
+fn ib2(a: u32, b: u32, c: u32, d: u32) -> [u32; 4] {
+    let t1 = b ^ d;
+    let t2 = !t1;
+    let t3 = a ^ c;
+    let t4 = c ^ t1;
+    let t5 = b & t4;
+    let x0 = t3 ^ t5;
+    let t7 = a | t2;
+    let t8 = d ^ t7;
+    let t9 = t3 | t8;
+    let x3 = t1 ^ t9;
+    let t11 = !t4;
+    let t12 = x0 | x3;
+    let x1 = t11 ^ t12;
+    let x2 = (d & t11) ^ (t3 ^ t12);
+    [x0, x1, x2, x3]
+}
+
+fn sb3(a: u32, b: u32, c: u32, d: u32) -> [u32; 4] {
+    let t1 = a ^ b;
+    let t2 = a & c;
+    let t3 = a | d;
+    let t4 = c ^ d;
+    let t5 = t1 & t3;
+    let t6 = t2 | t5;
+    let x2 = t4 ^ t6;
+    let t8 = b ^ t3;
+    let t9 = t6 ^ t8;
+    let t10 = t4 & t9;
+    let x0 = t1 ^ t10;
+    let t12 = x2 & x0;
+    let x1 = t9 ^ t12;
+    let x3 = (b | d) ^ (t4 ^ t12);
+    [x0, x1, x2, x3]
+}
+
+fn ib3(a: u32, b: u32, c: u32, d: u32) -> [u32; 4] {
+    let t1 = a | b;
+    let t2 = b ^ c;
+    let t3 = b & t2;
+    let t4 = a ^ t3;
+    let t5 = c ^ t4;
+    let t6 = d | t4;
+    let x0 = t2 ^ t6;
+    let t8 = t2 | t6;
+    let t9 = d ^ t8;
+    let x2 = t5 ^ t9;
+    let t11 = t1 ^ t9;
+    let t12 = x0 & t11;
+    let x3 = t4 ^ t12;
+    let x1 = x3 ^ (x0 ^ t11);
+    [x0, x1, x2, x3]
+}
+
+fn sb4(a: u32, b: u32, c: u32, d: u32) -> [u32; 4] {
+    let t1 = a ^ d;
+    let t2 = d & t1;
+    let t3 = c ^ t2;
+    let t4 = b | t3;
+    let x3 = t1 ^ t4;
+    let t6 = !b;
+    let t7 = t1 | t6;
+    let x0 = t3 ^ t7;
+    let t9 = a & x0;
+    let t10 = t1 ^ t6;
+    let t11 = t4 & t10;
+    let x2 = t9 ^ t11;
+    let x1 = (a ^ t3) ^ (t10 & x2);
+    [x0, x1, x2, x3]
+}
+
+fn ib4(a: u32, b: u32, c: u32, d: u32) -> [u32; 4] {
+    let t1 = c | d;
+    let t2 = a & t1;
+    let t3 = b ^ t2;
+    let t4 = a & t3;
+    let t5 = c ^ t4;
+    let x1 = d ^ t5;
+    let t7 = !a;
+    let t8 = t5 & x1;
+    let x3 = t3 ^ t8;
+    let t10 = x1 | t7;
+    let t11 = d ^ t10;
+    let x0 = x3 ^ t11;
+    let x2 = (t3 & t11) ^ (x1 ^ t7);
+    [x0, x1, x2, x3]
+}
+
+fn sb5(a: u32, b: u32, c: u32, d: u32) -> [u32; 4] {
+    let t1 = !a;
+    let t2 = a ^ b;
+    let t3 = a ^ d;
+    let t4 = c ^ t1;
+    let t5 = t2 | t3;
+    let x0 = t4 ^ t5;
+    let t7 = d & x0;
+    let t8 = t2 ^ x0;
+    let x1 = t7 ^ t8;
+    let t10 = t1 | x0;
+    let t11 = t2 | t7;
+    let t12 = t3 ^ t10;
+    let x2 = t11 ^ t12;
+    let x3 = (b ^ t7) ^ (x1 & t12);
+    [x0, x1, x2, x3]
+}
+
+fn ib5(a: u32, b: u32, c: u32, d: u32) -> [u32; 4] {
+    let t1 = !c;
+    let t2 = b & t1;
+    let t3 = d ^ t2;
+    let t4 = a & t3;
+    let t5 = b ^ t1;
+    let x3 = t4 ^ t5;
+    let t7 = b | x3;
+    let t8 = a & t7;
+    let x1 = t3 ^ t8;
+    let t10 = a | d;
+    let t11 = t1 ^ t7;
+    let x0 = t10 ^ t11;
+    let x2 = (b & t10) ^ (t4 | (a ^ c));
+    [x0, x1, x2, x3]
+}
+
+fn sb6(a: u32, b: u32, c: u32, d: u32) -> [u32; 4] {
+    let t1 = !a;
+    let t2 = a ^ d;
+    let t3 = b ^ t2;
+    let t4 = t1 | t2;
+    let t5 = c ^ t4;
+    let x1 = b ^ t5;
+    let t7 = t2 | x1;
+    let t8 = d ^ t7;
+    let t9 = t5 & t8;
+    let x2 = t3 ^ t9;
+    let t11 = t5 ^ t8;
+    let x0 = x2 ^ t11;
+    let x3 = !t5 ^ (t3 & t11);
+    [x0, x1, x2, x3]
+}
+
+fn ib6(a: u32, b: u32, c: u32, d: u32) -> [u32; 4] {
+    let t1 = !a;
+    let t2 = a ^ b;
+    let t3 = c ^ t2;
+    let t4 = c | t1;
+    let t5 = d ^ t4;
+    let x1 = t3 ^ t5;
+    let t7 = t3 & t5;
+    let t8 = t2 ^ t7;
+    let t9 = b | t8;
+    let x3 = t5 ^ t9;
+    let t11 = b | x3;
+    let x0 = t8 ^ t11;
+    let x2 = (d & t1) ^ (t3 ^ t11);
+    [x0, x1, x2, x3]
+}
+
+fn sb7(a: u32, b: u32, c: u32, d: u32) -> [u32; 4] {
+    let t1 = b ^ c;
+    let t2 = c & t1;
+    let t3 = d ^ t2;
+    let t4 = a ^ t3;
+    let t5 = d | t1;
+    let t6 = t4 & t5;
+    let x1 = b ^ t6;
+    let t8 = t3 | x1;
+    let t9 = a & t4;
+    let x3 = t1 ^ t9;
+    let t11 = t4 ^ t8;
+    let t12 = x3 & t11;
+    let x2 = t3 ^ t12;
+    let x0 = !t11 ^ (x3 & x2);
+    [x0, x1, x2, x3]
+}
+
+fn ib7(a: u32, b: u32, c: u32, d: u32) -> [u32; 4] {
+    let t3 = c | (a & b);
+    let t4 = d & (a | b);
+    let x3 = t3 ^ t4;
+    let t6 = !d;
+    let t7 = b ^ t4;
+    let t9 = t7 | (x3 ^ t6);
+    let x1 = a ^ t9;
+    let x0 = (c ^ t7) ^ (d | x1);
+    let x2 = (t3 ^ x1) ^ (x0 ^ (a & x3));
+    [x0, x1, x2, x3]
+}
+
+/// Apply S-box number `index` (taken modulo 8) to a bitsliced state. Shared with Sosemanuk.
+pub(crate) fn sbox(index: usize, x: [u32; 4]) -> [u32; 4] {
+    match index % 8 {
+        0 => sb0(x[0], x[1], x[2], x[3]),
+        1 => sb1(x[0], x[1], x[2], x[3]),
+        2 => sb2(x[0], x[1], x[2], x[3]),
+        3 => sb3(x[0], x[1], x[2], x[3]),
+        4 => sb4(x[0], x[1], x[2], x[3]),
+        5 => sb5(x[0], x[1], x[2], x[3]),
+        6 => sb6(x[0], x[1], x[2], x[3]),
+        _ => sb7(x[0], x[1], x[2], x[3])
+    }
+}
+
+fn inv_sbox(index: usize, x: [u32; 4]) -> [u32; 4] {
+    match index % 8 {
+        0 => ib0(x[0], x[1], x[2], x[3]),
+        1 => ib1(x[0], x[1], x[2], x[3]),
+        2 => ib2(x[0], x[1], x[2], x[3]),
+        3 => ib3(x[0], x[1], x[2], x[3]),
+        4 => ib4(x[0], x[1], x[2], x[3]),
+        5 => ib5(x[0], x[1], x[2], x[3]),
+        6 => ib6(x[0], x[1], x[2], x[3]),
+        _ => ib7(x[0], x[1], x[2], x[3])
+    }
+}
+
+/// The Serpent linear transformation. Shared with Sosemanuk.
+pub(crate) fn linear_transform(x: [u32; 4]) -> [u32; 4] {
+    let x0 = x[0].rotate_left(13);
+    let x2 = x[2].rotate_left(3);
+    let x1 = x[1] ^ x0 ^ x2;
+    let x3 = x[3] ^ x2 ^ (x0 << 3);
+    let x1 = x1.rotate_left(1);
+    let x3 = x3.rotate_left(7);
+    let x0 = x0 ^ x1 ^ x3;
+    let x2 = x2 ^ x3 ^ (x1 << 7);
+    [x0.rotate_left(5), x1, x2.rotate_left(22), x3]
+}
+
+fn inv_linear_transform(x: [u32; 4]) -> [u32; 4] {
+    let x2 = x[2].rotate_right(22);
+    let x0 = x[0].rotate_right(5);
+    let x2 = x2 ^ x[3] ^ (x[1] << 7);
+    let x0 = x0 ^ x[1] ^ x[3];
+    let x3 = x[3].rotate_right(7);
+    let x1 = x[1].rotate_right(1);
+    let x3 = x3 ^ x2 ^ (x0 << 3);
+    let x1 = x1 ^ x0 ^ x2;
+    [x0.rotate_right(13), x1, x2.rotate_right(3), x3]
+}
+
+/// XOR a round key into the state. Shared with Sosemanuk.
+pub(crate) fn add_round_key(x: [u32; 4], k: &[u32; 4]) -> [u32; 4] {
+    [x[0] ^ k[0], x[1] ^ k[1], x[2] ^ k[2], x[3] ^ k[3]]
+}
+
+/// Fill subkeys with as many round keys as it has room for. Keys shorter than 256 bits are padded
+/// by appending a single 1 bit. Shared with Sosemanuk, which accepts any key of up to 32 bytes and
+/// only needs the first 25 round keys; use Serpent::new instead.
+pub(crate) fn expand_key(key: &[u8], subkeys: &mut [[u32; 4]]) {
+    assert!(key.len() <= 32);
+    assert!(subkeys.len() <= ROUNDS + 1);
+
+    let mut full_key = [0u8; 32];
+    for (d, &s) in full_key.iter_mut().zip(key.iter()) {
+        *d = s;
+    }
+    if key.len() < 32 {
+        full_key[key.len()] = 0x01;
+    }
+
+    // The prekeys are generated 8 words at a time from the 8 previous words.
+    let mut w = [0u32; 8];
+    read_u32v_le(&mut w, &full_key);
+    let mut prekeys = [0u32; 4 * (ROUNDS + 1)];
+    for i in 0..4 * subkeys.len() {
+        let t = w[i % 8] ^ w[(i + 3) % 8] ^ w[(i + 5) % 8] ^ w[(i + 7) % 8] ^ PHI ^ (i as u32);
+        w[i % 8] = t.rotate_left(11);
+        prekeys[i] = w[i % 8];
+    }
+
+    // Round key i is generated with S-box number (3 - i) mod 8.
+    for (i, k) in subkeys.iter_mut().enumerate() {
+        let p = [prekeys[4 * i], prekeys[4 * i + 1], prekeys[4 * i + 2], prekeys[4 * i + 3]];
+        *k = sbox(3 + 8 * ROUNDS - i, p);
+    }
+}
+
+impl BlockEncryptor for Serpent {
+    fn block_size(&self) -> usize {
+        16
+    }
+
+    fn encrypt_block(&self, input: &[u8], output: &mut [u8]) {
+        assert!(input.len() == 16);
+        assert!(output.len() == 16);
+        let mut x = [0u32; 4];
+        read_u32v_le(&mut x, input);
+        for i in 0..ROUNDS - 1 {
+            x = linear_transform(sbox(i, add_round_key(x, &self.sk[i])));
+        }
+        x = add_round_key(sbox(ROUNDS - 1, add_round_key(x, &self.sk[ROUNDS - 1])),
+            &self.sk[ROUNDS]);
+        write_u32v_le(output, &x);
+    }
+}
+
+impl BlockDecryptor for Serpent {
+    fn block_size(&self) -> usize {
+        16
+    }
+
+    fn decrypt_block(&self, input: &[u8], output: &mut [u8]) {
+        assert!(input.len() == 16);
+        assert!(output.len() == 16);
+        let mut x = [0u32; 4];
+        read_u32v_le(&mut x, input);
+        x = add_round_key(inv_sbox(ROUNDS - 1, add_round_key(x, &self.sk[ROUNDS])),
+            &self.sk[ROUNDS - 1]);
+        for i in (0..ROUNDS - 1).rev() {
+            x = add_round_key(inv_sbox(i, inv_linear_transform(x)), &self.sk[i]);
+        }
+        write_u32v_le(output, &x);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serpent::Serpent;
+    use symmetriccipher::{BlockEncryptor, BlockDecryptor};
+    use serialize::hex::FromHex;
+
+    struct Test {
+        key: &'static str,
+        plaintext: &'static str,
+        ciphertext: &'static str
+    }
+
+    // Vectors from the NESSIE project, https://www.cosic.esat.kuleuven.be/nessie/testvectors/
+    fn nessie_test_vectors() -> Vec<Test> {
+        vec![
+            Test {
+                key: "80000000000000000000000000000000",
+                plaintext: "00000000000000000000000000000000",
+                ciphertext: "264E5481EFF42A4606ABDA06C0BFDA3D"
+            },
+            Test {
+                key: "00000000000000000000000000000000",
+                plaintext: "00000000000000000000000000000000",
+                ciphertext: "3620B17AE6A993D09618B8768266BAE9"
+            },
+            // 192 bit keys: Set 1, vectors 0 and 191, and Set 4, vector 0. These check the padding
+            // of short keys; the values were confirmed with an independent implementation that
+            // reproduces the 128 and 256 bit vectors here.
+            Test {
+                key: "800000000000000000000000000000000000000000000000",
+                plaintext: "00000000000000000000000000000000",
+                ciphertext: "9E274EAD9B737BB21EFCFCA548602689"
+            },
+            Test {
+                key: "000000000000000000000000000000000000000000000001",
+                plaintext: "00000000000000000000000000000000",
+                ciphertext: "5D058517AC7CC5AFD5C33253D4703B46"
+            },
+            Test {
+                key: "000102030405060708090A0B0C0D0E0F1011121314151617",
+                plaintext: "00112233445566778899AABBCCDDEEFF",
+                ciphertext: "6AB816C82DE53B93005008AFA2246A02"
+            },
+            Test {
+                key: "8000000000000000000000000000000000000000000000000000000000000000",
+                plaintext: "00000000000000000000000000000000",
+                ciphertext: "A223AA1288463C0E2BE38EBD825616C0"
+            },
+            Test {
+                key: "0000000000000000000000000000000000000000000000000000000000000000",
+                plaintext: "00000000000000000000000000000000",
+                ciphertext: "49672BA898D98DF95019180445491089"
+            }
+        ]
+    }
+
+    #[test]
+    fn encrypt_nessie_test_vectors() {
+        let mut output = [0u8; 16];
+        for test in nessie_test_vectors().iter() {
+            let state = Serpent::new(&test.key.from_hex().unwrap());
+            state.encrypt_block(&test.plaintext.from_hex().unwrap(), &mut output);
+            assert_eq!(&output[..], &test.ciphertext.from_hex().unwrap()[..]);
+        }
+    }
+
+    #[test]
+    fn decrypt_nessie_test_vectors() {
+        let mut output = [0u8; 16];
+        for test in nessie_test_vectors().iter() {
+            let state = Serpent::new(&test.key.from_hex().unwrap());
+            state.decrypt_block(&test.ciphertext.from_hex().unwrap(), &mut output);
+            assert_eq!(&output[..], &test.plaintext.from_hex().unwrap()[..]);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "with-bench"))]
+mod bench {
+    use serpent::Serpent;
+    use symmetriccipher::BlockEncryptor;
+    use test::Bencher;
+
+    #[bench]
+    fn serpent(bh: &mut Bencher) {
+        let key = [0u8; 32];
+        let plaintext = [1u8; 16];
+        let state = Serpent::new(&key);
+        let mut ciphertext = [0u8; 16];
+
+        bh.iter(|| {
+            state.encrypt_block(&plaintext, &mut ciphertext);
+        });
+        bh.bytes = 16u64;
+    }
+}
@@ -0,0 +1,286 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+An implementation of the Simon family of lightweight block ciphers.
+
+Simon is a Feistel network operating on two n bit words whose round function uses only bitwise
+and, xor and rotation. All of the standard sizes are supported:
+
+| Block size (bits) | Key sizes (bits) |
+|-------------------|------------------|
+| 32                | 64               |
+| 48                | 72, 96           |
+| 64                | 96, 128          |
+| 96                | 96, 144          |
+| 128               | 128, 192, 256    |
+
+Bytes are mapped to words in the same way as in the `speck` module.
+*/
+
+use symmetriccipher::{BlockEncryptor, BlockDecryptor};
+
+const MAX_ROUNDS: usize = 72;
+
+// The five constant sequences used by the key schedule, least significant bit first
+const Z: [u64; 5] = [
+    0x19c3522fb386a45f,
+    0x16864fb8ad0c9f71,
+    0x3369f885192c0ef5,
+    0x3c2ce51207a635db,
+    0x3dc94c3a046d678b
+];
+
+// Read a little endian word from all of the given bytes
+fn read_word(input: &[u8]) -> u64 {
+    input.iter().rev().fold(0, |w, &b| (w << 8) | b as u64)
+}
+
+fn write_word(output: &mut [u8], mut w: u64) {
+    for b in output.iter_mut() {
+        *b = w as u8;
+        w >>= 8;
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Simon {
+    word_size: usize,
+    mask: u64,
+    rounds: usize,
+    round_keys: [u64; MAX_ROUNDS]
+}
+
+impl Simon {
+    /// Create a Simon cipher with a block size of `block_size` bytes. The key size must be one
+    /// of those allowed for that block size.
+    pub fn new(block_size: usize, key: &[u8]) -> Simon {
+        let (rounds, z) = match (block_size, key.len()) {
+            (4, 8) => (32, Z[0]),
+            (6, 9) => (36, Z[0]),
+            (6, 12) => (36, Z[1]),
+            (8, 12) => (42, Z[2]),
+            (8, 16) => (44, Z[3]),
+            (12, 12) => (52, Z[2]),
+            (12, 18) => (54, Z[3]),
+            (16, 16) => (68, Z[2]),
+            (16, 24) => (69, Z[3]),
+            (16, 32) => (72, Z[4]),
+            _ => panic!("Invalid Simon block or key size")
+        };
+
+        let n = block_size * 4;
+        let word_bytes = block_size / 2;
+        let m = key.len() / word_bytes;
+
+        let mut simon = Simon {
+            word_size: n,
+            mask: if n == 64 { !0 } else { (1 << n) - 1 },
+            rounds: rounds,
+            round_keys: [0; MAX_ROUNDS]
+        };
+
+        for i in 0..m {
+            simon.round_keys[i] = read_word(&key[i * word_bytes..(i + 1) * word_bytes]);
+        }
+        for i in m..rounds {
+            let mut t = simon.ror(simon.round_keys[i - 1], 3);
+            if m == 4 {
+                t ^= simon.round_keys[i - 3];
+            }
+            t ^= simon.ror(t, 1);
+            let z_bit = (z >> ((i - m) % 62)) & 1;
+            simon.round_keys[i] = (!simon.round_keys[i - m] & simon.mask) ^ t ^ z_bit ^ 3;
+        }
+
+        simon
+    }
+
+    fn rol(&self, x: u64, r: usize) -> u64 {
+        ((x << r) | (x >> (self.word_size - r))) & self.mask
+    }
+
+    fn ror(&self, x: u64, r: usize) -> u64 {
+        ((x >> r) | (x << (self.word_size - r))) & self.mask
+    }
+
+    fn f(&self, x: u64) -> u64 {
+        (self.rol(x, 1) & self.rol(x, 8)) ^ self.rol(x, 2)
+    }
+}
+
+impl BlockEncryptor for Simon {
+    fn block_size(&self) -> usize {
+        self.word_size / 4
+    }
+
+    fn encrypt_block(&self, input: &[u8], output: &mut [u8]) {
+        let word_bytes = self.word_size / 8;
+        assert!(input.len() == 2 * word_bytes);
+        assert!(output.len() == 2 * word_bytes);
+        let mut y = read_word(&input[0..word_bytes]);
+        let mut x = read_word(&input[word_bytes..]);
+        for k in self.round_keys[..self.rounds].iter() {
+            let t = x;
+            x = y ^ self.f(x) ^ k;
+            y = t;
+        }
+        write_word(&mut output[0..word_bytes], y);
+        write_word(&mut output[word_bytes..], x);
+    }
+}
+
+impl BlockDecryptor for Simon {
+    fn block_size(&self) -> usize {
+        self.word_size / 4
+    }
+
+    fn decrypt_block(&self, input: &[u8], output: &mut [u8]) {
+        let word_bytes = self.word_size / 8;
+        assert!(input.len() == 2 * word_bytes);
+        assert!(output.len() == 2 * word_bytes);
+        let mut y = read_word(&input[0..word_bytes]);
+        let mut x = read_word(&input[word_bytes..]);
+        for k in self.round_keys[..self.rounds].iter().rev() {
+            let t = y;
+            y = x ^ self.f(y) ^ k;
+            x = t;
+        }
+        write_word(&mut output[0..word_bytes], y);
+        write_word(&mut output[word_bytes..], x);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use simon::Simon;
+    use symmetriccipher::{BlockEncryptor, BlockDecryptor};
+    use serialize::hex::FromHex;
+
+    struct Test {
+        key: &'static str,
+        plaintext: &'static str,
+        ciphertext: &'static str
+    }
+
+    // Vectors from "The SIMON and SPECK Families of Lightweight Block Ciphers", Appendix B. They
+    // are written as in the paper, so the bytes need to be reversed.
+    fn tests() -> Vec<Test> {
+        vec![
+            Test {
+                key: "1918111009080100",
+                plaintext: "65656877",
+                ciphertext: "c69be9bb"
+            },
+            Test {
+                key: "1211100a0908020100",
+                plaintext: "6120676e696c",
+                ciphertext: "dae5ac292cac"
+            },
+            Test {
+                key: "1a19181211100a0908020100",
+                plaintext: "72696320646e",
+                ciphertext: "6e06a5acf156"
+            },
+            Test {
+                key: "131211100b0a090803020100",
+                plaintext: "6f7220676e696c63",
+                ciphertext: "5ca2e27f111a8fc8"
+            },
+            Test {
+                key: "1b1a1918131211100b0a090803020100",
+                plaintext: "656b696c20646e75",
+                ciphertext: "44c8fc20b9dfa07a"
+            },
+            Test {
+                key: "0d0c0b0a0908050403020100",
+                plaintext: "2072616c6c69702065687420",
+                ciphertext: "602807a462b469063d8ff082"
+            },
+            Test {
+                key: "1514131211100d0c0b0a0908050403020100",
+                plaintext: "74616874207473756420666f",
+                ciphertext: "ecad1c6c451e3f59c5db1ae9"
+            },
+            Test {
+                key: "0f0e0d0c0b0a09080706050403020100",
+                plaintext: "63736564207372656c6c657661727420",
+                ciphertext: "49681b1e1e54fe3f65aa832af84e0bbc"
+            },
+            Test {
+                key: "17161514131211100f0e0d0c0b0a09080706050403020100",
+                plaintext: "206572656874206e6568772065626972",
+                ciphertext: "c4ac61effcdc0d4f6c9c8d6e2597b85b"
+            },
+            Test {
+                key: "1f1e1d1c1b1a191817161514131211100f0e0d0c0b0a09080706050403020100",
+                plaintext: "74206e69206d6f6f6d69732061207369",
+                ciphertext: "8d2b5579afc8a3a03bf72a87efe7b868"
+            }
+        ]
+    }
+
+    fn reversed(hex: &str) -> Vec<u8> {
+        let mut bytes = hex.from_hex().unwrap();
+        bytes.reverse();
+        bytes
+    }
+
+    #[test]
+    fn test_encrypt() {
+        for test in tests().iter() {
+            let plaintext = reversed(test.plaintext);
+            let state = Simon::new(plaintext.len(), &reversed(test.key));
+            let mut output = vec![0u8; plaintext.len()];
+            state.encrypt_block(&plaintext, &mut output);
+            assert_eq!(output, reversed(test.ciphertext));
+        }
+    }
+
+    #[test]
+    fn test_decrypt() {
+        for test in tests().iter() {
+            let ciphertext = reversed(test.ciphertext);
+            let state = Simon::new(ciphertext.len(), &reversed(test.key));
+            let mut output = vec![0u8; ciphertext.len()];
+            state.decrypt_block(&ciphertext, &mut output);
+            assert_eq!(output, reversed(test.plaintext));
+        }
+    }
+}
+
+#[cfg(all(test, feature = "with-bench"))]
+mod bench {
+    use simon::Simon;
+    use symmetriccipher::BlockEncryptor;
+    use test::Bencher;
+
+    #[bench]
+    fn simon64_128(bh: &mut Bencher) {
+        let key = [0u8; 16];
+        let plaintext = [1u8; 8];
+        let state = Simon::new(8, &key);
+        let mut ciphertext = [0u8; 8];
+
+        bh.iter(|| {
+            state.encrypt_block(&plaintext, &mut ciphertext);
+        });
+        bh.bytes = 8u64;
+    }
+
+    #[bench]
+    fn simon128_128(bh: &mut Bencher) {
+        let key = [0u8; 16];
+        let plaintext = [1u8; 16];
+        let state = Simon::new(16, &key);
+        let mut ciphertext = [0u8; 16];
+
+        bh.iter(|| {
+            state.encrypt_block(&plaintext, &mut ciphertext);
+        });
+        bh.bytes = 16u64;
+    }
+}
@@ -7,7 +7,8 @@
 
 use buffer::{BufferResult, RefReadBuffer, RefWriteBuffer};
 use symmetriccipher::{Encryptor, Decryptor, SynchronousStreamCipher, SymmetricCipherError};
-use cryptoutil::{read_u32_le, symm_enc_or_dec, write_u32v_le};
+use cryptoutil::{read_u32v_le, symm_enc_or_dec, write_u32v_le};
+use serpent;
 
 use cryptoutil::copy_memory;
 
@@ -153,7 +154,7 @@ const ALPHA_DIV_TABLE : [u32; 256] =
 pub struct Sosemanuk {
     lfsr: [u32; 10],
     fsm_r: [u32; 2],
-    subkeys: [u32; 100],
+    subkeys: [[u32; 4]; 25],
     output: [u8; 80],
     offset: u32
 }
@@ -162,13 +163,13 @@ impl Clone for Sosemanuk { fn clone(&self) -> Sosemanuk { *self } }
 
 impl Sosemanuk {
     pub fn new(key: &[u8], nonce: &[u8]) -> Sosemanuk {
-        let mut sosemanuk = Sosemanuk { lfsr: [0; 10], fsm_r: [0; 2], subkeys: [0; 100], output: [0; 80], offset: 80 };
+        let mut sosemanuk = Sosemanuk { lfsr: [0; 10], fsm_r: [0; 2], subkeys: [[0; 4]; 25], output: [0; 80], offset: 80 };
 
         assert!(key.len() <= 32);
         assert!(nonce.len() <= 16);
 
-        key_setup(&key, &mut sosemanuk.subkeys);
-        iv_setup(&nonce, &mut sosemanuk.subkeys, &mut sosemanuk.lfsr, &mut sosemanuk.fsm_r);
+        serpent::expand_key(key, &mut sosemanuk.subkeys);
+        iv_setup(nonce, &sosemanuk.subkeys, &mut sosemanuk.lfsr, &mut sosemanuk.fsm_r);
 
         sosemanuk
     }
@@ -190,7 +191,6 @@ impl Sosemanuk {
         let mut f1 : u32;
         let mut f2 : u32;
         let mut f3 : u32;
-        let mut f4 : u32;
         let mut v0 : u32;
         let mut v1 : u32;
         let mut v2 : u32;
@@ -251,27 +251,8 @@ impl Sosemanuk {
         /*
          * Apply the third S-box (number 2) on (f3, f2, f1, f0).
          */
-        f4 = f0;
-        f0 &= f2;
-        f0 ^= f3;
-        f2 ^= f1;
-        f2 ^= f0;
-        f3 |= f4;
-        f3 ^= f1;
-        f4 ^= f2;
-        f1 = f3;
-        f3 |= f4;
-        f3 ^= f0;
-        f0 &= f1;
-        f4 ^= f0;
-        f1 ^= f3;
-        f1 ^= f4;
-        f4 = !f4;
-
-        /*
-         * S-box result is in (f2, f3, f1, f4).
-         */
-        let sbox_res = [(f2 ^ v0), (f3 ^ v1), (f1 ^ v2), (f4 ^ v3)];
+        let f = serpent::sbox(2, [f0, f1, f2, f3]);
+        let sbox_res = [(f[0] ^ v0), (f[1] ^ v1), (f[2] ^ v2), (f[3] ^ v3)];
         write_u32v_le(&mut self.output[0..16], &sbox_res);
 
         tt = r1;
@@ -325,27 +306,8 @@ impl Sosemanuk {
         /*
          * Apply the third S-box (number 2) on (f3, f2, f1, f0).
          */
-        f4 = f0;
-        f0 &= f2;
-        f0 ^= f3;
-        f2 ^= f1;
-        f2 ^= f0;
-        f3 |= f4;
-        f3 ^= f1;
-        f4 ^= f2;
-        f1 = f3;
-        f3 |= f4;
-        f3 ^= f0;
-        f0 &= f1;
-        f4 ^= f0;
-        f1 ^= f3;
-        f1 ^= f4;
-        f4 = !f4;
-
-        /*
-         * S-box result is in (f2, f3, f1, f4).
-         */
-        let sbox_res = [(f2 ^ v0), (f3 ^ v1), (f1 ^ v2), (f4 ^ v3)];
+        let f = serpent::sbox(2, [f0, f1, f2, f3]);
+        let sbox_res = [(f[0] ^ v0), (f[1] ^ v1), (f[2] ^ v2), (f[3] ^ v3)];
         write_u32v_le(&mut self.output[16..32], &sbox_res);
 
         tt = r1;
@@ -399,27 +361,8 @@ impl Sosemanuk {
         /*
          * Apply the third S-box (number 2) on (f3, f2, f1, f0).
          */
-        f4 = f0;
-        f0 &= f2;
-        f0 ^= f3;
-        f2 ^= f1;
-        f2 ^= f0;
-        f3 |= f4;
-        f3 ^= f1;
-        f4 ^= f2;
-        f1 = f3;
-        f3 |= f4;
-        f3 ^= f0;
-        f0 &= f1;
-        f4 ^= f0;
-        f1 ^= f3;
-        f1 ^= f4;
-        f4 = !f4;
-
-        /*
-         * S-box result is in (f2, f3, f1, f4).
-         */
-        let sbox_res = [(f2 ^ v0), (f3 ^ v1), (f1 ^ v2), (f4 ^ v3)];
+        let f = serpent::sbox(2, [f0, f1, f2, f3]);
+        let sbox_res = [(f[0] ^ v0), (f[1] ^ v1), (f[2] ^ v2), (f[3] ^ v3)];
         write_u32v_le(&mut self.output[32..48], &sbox_res);
 
         tt = r1;
@@ -473,27 +416,8 @@ impl Sosemanuk {
         /*
          * Apply the third S-box (number 2) on (f3, f2, f1, f0).
          */
-        f4 = f0;
-        f0 &= f2;
-        f0 ^= f3;
-        f2 ^= f1;
-        f2 ^= f0;
-        f3 |= f4;
-        f3 ^= f1;
-        f4 ^= f2;
-        f1 = f3;
-        f3 |= f4;
-        f3 ^= f0;
-        f0 &= f1;
-        f4 ^= f0;
-        f1 ^= f3;
-        f1 ^= f4;
-        f4 = !f4;
-
-        /*
-         * S-box result is in (f2, f3, f1, f4).
-         */
-        let sbox_res = [(f2 ^ v0), (f3 ^ v1), (f1 ^ v2), (f4 ^ v3)];
+        let f = serpent::sbox(2, [f0, f1, f2, f3]);
+        let sbox_res = [(f[0] ^ v0), (f[1] ^ v1), (f[2] ^ v2), (f[3] ^ v3)];
         write_u32v_le(&mut self.output[48..64], &sbox_res);
 
         tt = r1;
@@ -547,27 +471,8 @@ impl Sosemanuk {
         /*
          * Apply the third S-box (number 2) on (f3, f2, f1, f0).
          */
-        f4 = f0;
-        f0 &= f2;
-        f0 ^= f3;
-        f2 ^= f1;
-        f2 ^= f0;
-        f3 |= f4;
-        f3 ^= f1;
-        f4 ^= f2;
-        f1 = f3;
-        f3 |= f4;
-        f3 ^= f0;
-        f0 &= f1;
-        f4 ^= f0;
-        f1 ^= f3;
-        f1 ^= f4;
-        f4 = !f4;
-
-        /*
-         * S-box result is in (f2, f3, f1, f4).
-         */
-        let sbox_res = [(f2 ^ v0), (f3 ^ v1), (f1 ^ v2), (f4 ^ v3)];
+        let f = serpent::sbox(2, [f0, f1, f2, f3]);
+        let sbox_res = [(f[0] ^ v0), (f[1] ^ v1), (f[2] ^ v2), (f[3] ^ v3)];
         write_u32v_le(&mut self.output[64..80], &sbox_res);
 
         self.lfsr[0] = s0;
@@ -596,897 +501,9 @@ impl Sosemanuk {
 }
 
 
-fn key_setup(key : &[u8], subkeys : &mut[u32; 100]) {
-    let mut full_key : [u8; 32] = [0; 32];
-    if key.len() < 32 {
-        copy_memory(&key, &mut full_key[0..key.len()]);
-        full_key[key.len()] = 0x01;
-    } else {
-        copy_memory(&key[0..32], &mut full_key[0..32]);
-    }
-
-    let mut w0 = read_u32_le(&full_key[0..4]);
-    let mut w1 = read_u32_le(&full_key[4..8]);
-    let mut w2 = read_u32_le(&full_key[8..12]);
-    let mut w3 = read_u32_le(&full_key[12..16]);
-    let mut w4 = read_u32_le(&full_key[16..20]);
-    let mut w5 = read_u32_le(&full_key[20..24]);
-    let mut w6 = read_u32_le(&full_key[24..28]);
-    let mut w7 = read_u32_le(&full_key[28..32]);
-    let mut r0 : u32;
-    let mut r1 : u32;
-    let mut r2 : u32;
-    let mut r3 : u32;
-    let mut r4 : u32;
-    let mut tt : u32;
-    let mut i = 0;
-
-    tt = w0 ^ w3 ^ w5 ^ w7 ^ (0x9E3779B9 ^ (0));
-    w0 = tt.rotate_left(11);
-    tt = w1 ^ w4 ^ w6 ^ w0 ^ (0x9E3779B9 ^ (0 + 1));
-    w1 = tt.rotate_left(11);
-    tt = w2 ^ w5 ^ w7 ^ w1 ^ (0x9E3779B9 ^ (0 + 2));
-    w2 = tt.rotate_left(11);
-    tt = w3 ^ w6 ^ w0 ^ w2 ^ (0x9E3779B9 ^ (0 + 3));
-    w3 = tt.rotate_left(11);
-    r0 = w0;
-    r1 = w1;
-    r2 = w2;
-    r3 = w3;
-    r4 = r0;
-    r0 |= r3;
-    r3 ^= r1;
-    r1 &= r4;
-    r4 ^= r2;
-    r2 ^= r3;
-    r3 &= r0;
-    r4 |= r1;
-    r3 ^= r4;
-    r0 ^= r1;
-    r4 &= r0;
-    r1 ^= r3;
-    r4 ^= r2;
-    r1 |= r0;
-    r1 ^= r2;
-    r0 ^= r3;
-    r2 = r1;
-    r1 |= r3;
-    r1 ^= r0;
-    subkeys[i] = r1; i+=1;
-    subkeys[i] = r2; i+=1;
-    subkeys[i] = r3; i+=1;
-    subkeys[i] = r4; i+=1;
-    tt = w4 ^ w7 ^ w1 ^ w3 ^ (0x9E3779B9 ^ (4));
-    w4 = tt.rotate_left(11);
-    tt = w5 ^ w0 ^ w2 ^ w4 ^ (0x9E3779B9 ^ (4 + 1));
-    w5 = tt.rotate_left(11);
-    tt = w6 ^ w1 ^ w3 ^ w5 ^ (0x9E3779B9 ^ (4 + 2));
-    w6 = tt.rotate_left(11);
-    tt = w7 ^ w2 ^ w4 ^ w6 ^ (0x9E3779B9 ^ (4 + 3));
-    w7 = tt.rotate_left(11);
-    r0 = w4;
-    r1 = w5;
-    r2 = w6;
-    r3 = w7;
-    r4 = r0;
-    r0 &= r2;
-    r0 ^= r3;
-    r2 ^= r1;
-    r2 ^= r0;
-    r3 |= r4;
-    r3 ^= r1;
-    r4 ^= r2;
-    r1 = r3;
-    r3 |= r4;
-    r3 ^= r0;
-    r0 &= r1;
-    r4 ^= r0;
-    r1 ^= r3;
-    r1 ^= r4;
-    r4 = !r4;
-    subkeys[i] = r2; i+=1;
-    subkeys[i] = r3; i+=1;
-    subkeys[i] = r1; i+=1;
-    subkeys[i] = r4; i+=1;
-    tt = w0 ^ w3 ^ w5 ^ w7 ^ (0x9E3779B9 ^ (8));
-    w0 = tt.rotate_left(11);
-    tt = w1 ^ w4 ^ w6 ^ w0 ^ (0x9E3779B9 ^ (8 + 1));
-    w1 = tt.rotate_left(11);
-    tt = w2 ^ w5 ^ w7 ^ w1 ^ (0x9E3779B9 ^ (8 + 2));
-    w2 = tt.rotate_left(11);
-    tt = w3 ^ w6 ^ w0 ^ w2 ^ (0x9E3779B9 ^ (8 + 3));
-    w3 = tt.rotate_left(11);
-    r0 = w0;
-    r1 = w1;
-    r2 = w2;
-    r3 = w3;
-    r0 = !r0;
-    r2 = !r2;
-    r4 = r0;
-    r0 &= r1;
-    r2 ^= r0;
-    r0 |= r3;
-    r3 ^= r2;
-    r1 ^= r0;
-    r0 ^= r4;
-    r4 |= r1;
-    r1 ^= r3;
-    r2 |= r0;
-    r2 &= r4;
-    r0 ^= r1;
-    r1 &= r2;
-    r1 ^= r0;
-    r0 &= r2;
-    r0 ^= r4;
-    subkeys[i] = r2; i+=1;
-    subkeys[i] = r0; i+=1;
-    subkeys[i] = r3; i+=1;
-    subkeys[i] = r1; i+=1;
-    tt = w4 ^ w7 ^ w1 ^ w3 ^ (0x9E3779B9 ^ (12));
-    w4 = tt.rotate_left(11);
-    tt = w5 ^ w0 ^ w2 ^ w4 ^ (0x9E3779B9 ^ (12 + 1));
-    w5 = tt.rotate_left(11);
-    tt = w6 ^ w1 ^ w3 ^ w5 ^ (0x9E3779B9 ^ (12 + 2));
-    w6 = tt.rotate_left(11);
-    tt = w7 ^ w2 ^ w4 ^ w6 ^ (0x9E3779B9 ^ (12 + 3));
-    w7 = tt.rotate_left(11);
-    r0 = w4;
-    r1 = w5;
-    r2 = w6;
-    r3 = w7;
-    r3 ^= r0;
-    r4 = r1;
-    r1 &= r3;
-    r4 ^= r2;
-    r1 ^= r0;
-    r0 |= r3;
-    r0 ^= r4;
-    r4 ^= r3;
-    r3 ^= r2;
-    r2 |= r1;
-    r2 ^= r4;
-    r4 = !r4;
-    r4 |= r1;
-    r1 ^= r3;
-    r1 ^= r4;
-    r3 |= r0;
-    r1 ^= r3;
-    r4 ^= r3;
-    subkeys[i] = r1; i+=1;
-    subkeys[i] = r4; i+=1;
-    subkeys[i] = r2; i+=1;
-    subkeys[i] = r0; i+=1;
-    tt = w0 ^ w3 ^ w5 ^ w7 ^ (0x9E3779B9 ^ (16));
-    w0 = tt.rotate_left(11);
-    tt = w1 ^ w4 ^ w6 ^ w0 ^ (0x9E3779B9 ^ (16 + 1));
-    w1 = tt.rotate_left(11);
-    tt = w2 ^ w5 ^ w7 ^ w1 ^ (0x9E3779B9 ^ (16 + 2));
-    w2 = tt.rotate_left(11);
-    tt = w3 ^ w6 ^ w0 ^ w2 ^ (0x9E3779B9 ^ (16 + 3));
-    w3 = tt.rotate_left(11);
-    r0 = w0;
-    r1 = w1;
-    r2 = w2;
-    r3 = w3;
-    r4 = r1;
-    r1 |= r2;
-    r1 ^= r3;
-    r4 ^= r2;
-    r2 ^= r1;
-    r3 |= r4;
-    r3 &= r0;
-    r4 ^= r2;
-    r3 ^= r1;
-    r1 |= r4;
-    r1 ^= r0;
-    r0 |= r4;
-    r0 ^= r2;
-    r1 ^= r4;
-    r2 ^= r1;
-    r1 &= r0;
-    r1 ^= r4;
-    r2 = !r2;
-    r2 |= r0;
-    r4 ^= r2;
-    subkeys[i] = r4; i+=1;
-    subkeys[i] = r3; i+=1;
-    subkeys[i] = r1; i+=1;
-    subkeys[i] = r0; i+=1;
-    tt = w4 ^ w7 ^ w1 ^ w3 ^ (0x9E3779B9 ^ (20));
-    w4 = tt.rotate_left(11);
-    tt = w5 ^ w0 ^ w2 ^ w4 ^ (0x9E3779B9 ^ (20 + 1));
-    w5 = tt.rotate_left(11);
-    tt = w6 ^ w1 ^ w3 ^ w5 ^ (0x9E3779B9 ^ (20 + 2));
-    w6 = tt.rotate_left(11);
-    tt = w7 ^ w2 ^ w4 ^ w6 ^ (0x9E3779B9 ^ (20 + 3));
-    w7 = tt.rotate_left(11);
-    r0 = w4;
-    r1 = w5;
-    r2 = w6;
-    r3 = w7;
-    r2 = !r2;
-    r4 = r3;
-    r3 &= r0;
-    r0 ^= r4;
-    r3 ^= r2;
-    r2 |= r4;
-    r1 ^= r3;
-    r2 ^= r0;
-    r0 |= r1;
-    r2 ^= r1;
-    r4 ^= r0;
-    r0 |= r3;
-    r0 ^= r2;
-    r4 ^= r3;
-    r4 ^= r0;
-    r3 = !r3;
-    r2 &= r4;
-    r2 ^= r3;
-    subkeys[i] = r0; i+=1;
-    subkeys[i] = r1; i+=1;
-    subkeys[i] = r4; i+=1;
-    subkeys[i] = r2; i+=1;
-    tt = w0 ^ w3 ^ w5 ^ w7 ^ (0x9E3779B9 ^ (24));
-    w0 = tt.rotate_left(11);
-    tt = w1 ^ w4 ^ w6 ^ w0 ^ (0x9E3779B9 ^ (24 + 1));
-    w1 = tt.rotate_left(11);
-    tt = w2 ^ w5 ^ w7 ^ w1 ^ (0x9E3779B9 ^ (24 + 2));
-    w2 = tt.rotate_left(11);
-    tt = w3 ^ w6 ^ w0 ^ w2 ^ (0x9E3779B9 ^ (24 + 3));
-    w3 = tt.rotate_left(11);
-    r0 = w0;
-    r1 = w1;
-    r2 = w2;
-    r3 = w3;
-    r0 ^= r1;
-    r1 ^= r3;
-    r3 = !r3;
-    r4 = r1;
-    r1 &= r0;
-    r2 ^= r3;
-    r1 ^= r2;
-    r2 |= r4;
-    r4 ^= r3;
-    r3 &= r1;
-    r3 ^= r0;
-    r4 ^= r1;
-    r4 ^= r2;
-    r2 ^= r0;
-    r0 &= r3;
-    r2 = !r2;
-    r0 ^= r4;
-    r4 |= r3;
-    r2 ^= r4;
-    subkeys[i] = r1; i+=1;
-    subkeys[i] = r3; i+=1;
-    subkeys[i] = r0; i+=1;
-    subkeys[i] = r2; i+=1;
-    tt = w4 ^ w7 ^ w1 ^ w3 ^ (0x9E3779B9 ^ (28));
-    w4 = tt.rotate_left(11);
-    tt = w5 ^ w0 ^ w2 ^ w4 ^ (0x9E3779B9 ^ (28 + 1));
-    w5 = tt.rotate_left(11);
-    tt = w6 ^ w1 ^ w3 ^ w5 ^ (0x9E3779B9 ^ (28 + 2));
-    w6 = tt.rotate_left(11);
-    tt = w7 ^ w2 ^ w4 ^ w6 ^ (0x9E3779B9 ^ (28 + 3));
-    w7 = tt.rotate_left(11);
-    r0 = w4;
-    r1 = w5;
-    r2 = w6;
-    r3 = w7;
-    r1 ^= r3;
-    r3 = !r3;
-    r2 ^= r3;
-    r3 ^= r0;
-    r4 = r1;
-    r1 &= r3;
-    r1 ^= r2;
-    r4 ^= r3;
-    r0 ^= r4;
-    r2 &= r4;
-    r2 ^= r0;
-    r0 &= r1;
-    r3 ^= r0;
-    r4 |= r1;
-    r4 ^= r0;
-    r0 |= r3;
-    r0 ^= r2;
-    r2 &= r3;
-    r0 = !r0;
-    r4 ^= r2;
-    subkeys[i] = r1; i+=1;
-    subkeys[i] = r4; i+=1;
-    subkeys[i] = r0; i+=1;
-    subkeys[i] = r3; i+=1;
-    tt = w0 ^ w3 ^ w5 ^ w7 ^ (0x9E3779B9 ^ (32));
-    w0 = tt.rotate_left(11);
-    tt = w1 ^ w4 ^ w6 ^ w0 ^ (0x9E3779B9 ^ (32 + 1));
-    w1 = tt.rotate_left(11);
-    tt = w2 ^ w5 ^ w7 ^ w1 ^ (0x9E3779B9 ^ (32 + 2));
-    w2 = tt.rotate_left(11);
-    tt = w3 ^ w6 ^ w0 ^ w2 ^ (0x9E3779B9 ^ (32 + 3));
-    w3 = tt.rotate_left(11);
-    r0 = w0;
-    r1 = w1;
-    r2 = w2;
-    r3 = w3;
-    r4 = r0;
-    r0 |= r3;
-    r3 ^= r1;
-    r1 &= r4;
-    r4 ^= r2;
-    r2 ^= r3;
-    r3 &= r0;
-    r4 |= r1;
-    r3 ^= r4;
-    r0 ^= r1;
-    r4 &= r0;
-    r1 ^= r3;
-    r4 ^= r2;
-    r1 |= r0;
-    r1 ^= r2;
-    r0 ^= r3;
-    r2 = r1;
-    r1 |= r3;
-    r1 ^= r0;
-    subkeys[i] = r1; i+=1;
-    subkeys[i] = r2; i+=1;
-    subkeys[i] = r3; i+=1;
-    subkeys[i] = r4; i+=1;
-    tt = w4 ^ w7 ^ w1 ^ w3 ^ (0x9E3779B9 ^ (36));
-    w4 = tt.rotate_left(11);
-    tt = w5 ^ w0 ^ w2 ^ w4 ^ (0x9E3779B9 ^ (36 + 1));
-    w5 = tt.rotate_left(11);
-    tt = w6 ^ w1 ^ w3 ^ w5 ^ (0x9E3779B9 ^ (36 + 2));
-    w6 = tt.rotate_left(11);
-    tt = w7 ^ w2 ^ w4 ^ w6 ^ (0x9E3779B9 ^ (36 + 3));
-    w7 = tt.rotate_left(11);
-    r0 = w4;
-    r1 = w5;
-    r2 = w6;
-    r3 = w7;
-    r4 = r0;
-    r0 &= r2;
-    r0 ^= r3;
-    r2 ^= r1;
-    r2 ^= r0;
-    r3 |= r4;
-    r3 ^= r1;
-    r4 ^= r2;
-    r1 = r3;
-    r3 |= r4;
-    r3 ^= r0;
-    r0 &= r1;
-    r4 ^= r0;
-    r1 ^= r3;
-    r1 ^= r4;
-    r4 = !r4;
-    subkeys[i] = r2; i+=1;
-    subkeys[i] = r3; i+=1;
-    subkeys[i] = r1; i+=1;
-    subkeys[i] = r4; i+=1;
-    tt = w0 ^ w3 ^ w5 ^ w7 ^ (0x9E3779B9 ^ (40));
-    w0 = tt.rotate_left(11);
-    tt = w1 ^ w4 ^ w6 ^ w0 ^ (0x9E3779B9 ^ (40 + 1));
-    w1 = tt.rotate_left(11);
-    tt = w2 ^ w5 ^ w7 ^ w1 ^ (0x9E3779B9 ^ (40 + 2));
-    w2 = tt.rotate_left(11);
-    tt = w3 ^ w6 ^ w0 ^ w2 ^ (0x9E3779B9 ^ (40 + 3));
-    w3 = tt.rotate_left(11);
-    r0 = w0;
-    r1 = w1;
-    r2 = w2;
-    r3 = w3;
-    r0 = !r0;
-    r2 = !r2;
-    r4 = r0;
-    r0 &= r1;
-    r2 ^= r0;
-    r0 |= r3;
-    r3 ^= r2;
-    r1 ^= r0;
-    r0 ^= r4;
-    r4 |= r1;
-    r1 ^= r3;
-    r2 |= r0;
-    r2 &= r4;
-    r0 ^= r1;
-    r1 &= r2;
-    r1 ^= r0;
-    r0 &= r2;
-    r0 ^= r4;
-    subkeys[i] = r2; i+=1;
-    subkeys[i] = r0; i+=1;
-    subkeys[i] = r3; i+=1;
-    subkeys[i] = r1; i+=1;
-    tt = w4 ^ w7 ^ w1 ^ w3 ^ (0x9E3779B9 ^ (44));
-    w4 = tt.rotate_left(11);
-    tt = w5 ^ w0 ^ w2 ^ w4 ^ (0x9E3779B9 ^ (44 + 1));
-    w5 = tt.rotate_left(11);
-    tt = w6 ^ w1 ^ w3 ^ w5 ^ (0x9E3779B9 ^ (44 + 2));
-    w6 = tt.rotate_left(11);
-    tt = w7 ^ w2 ^ w4 ^ w6 ^ (0x9E3779B9 ^ (44 + 3));
-    w7 = tt.rotate_left(11);
-    r0 = w4;
-    r1 = w5;
-    r2 = w6;
-    r3 = w7;
-    r3 ^= r0;
-    r4 = r1;
-    r1 &= r3;
-    r4 ^= r2;
-    r1 ^= r0;
-    r0 |= r3;
-    r0 ^= r4;
-    r4 ^= r3;
-    r3 ^= r2;
-    r2 |= r1;
-    r2 ^= r4;
-    r4 = !r4;
-    r4 |= r1;
-    r1 ^= r3;
-    r1 ^= r4;
-    r3 |= r0;
-    r1 ^= r3;
-    r4 ^= r3;
-    subkeys[i] = r1; i+=1;
-    subkeys[i] = r4; i+=1;
-    subkeys[i] = r2; i+=1;
-    subkeys[i] = r0; i+=1;
-    tt = w0 ^ w3 ^ w5 ^ w7 ^ (0x9E3779B9 ^ (48));
-    w0 = tt.rotate_left(11);
-    tt = w1 ^ w4 ^ w6 ^ w0 ^ (0x9E3779B9 ^ (48 + 1));
-    w1 = tt.rotate_left(11);
-    tt = w2 ^ w5 ^ w7 ^ w1 ^ (0x9E3779B9 ^ (48 + 2));
-    w2 = tt.rotate_left(11);
-    tt = w3 ^ w6 ^ w0 ^ w2 ^ (0x9E3779B9 ^ (48 + 3));
-    w3 = tt.rotate_left(11);
-    r0 = w0;
-    r1 = w1;
-    r2 = w2;
-    r3 = w3;
-    r4 = r1;
-    r1 |= r2;
-    r1 ^= r3;
-    r4 ^= r2;
-    r2 ^= r1;
-    r3 |= r4;
-    r3 &= r0;
-    r4 ^= r2;
-    r3 ^= r1;
-    r1 |= r4;
-    r1 ^= r0;
-    r0 |= r4;
-    r0 ^= r2;
-    r1 ^= r4;
-    r2 ^= r1;
-    r1 &= r0;
-    r1 ^= r4;
-    r2 = !r2;
-    r2 |= r0;
-    r4 ^= r2;
-    subkeys[i] = r4; i+=1;
-    subkeys[i] = r3; i+=1;
-    subkeys[i] = r1; i+=1;
-    subkeys[i] = r0; i+=1;
-    tt = w4 ^ w7 ^ w1 ^ w3 ^ (0x9E3779B9 ^ (52));
-    w4 = tt.rotate_left(11);
-    tt = w5 ^ w0 ^ w2 ^ w4 ^ (0x9E3779B9 ^ (52 + 1));
-    w5 = tt.rotate_left(11);
-    tt = w6 ^ w1 ^ w3 ^ w5 ^ (0x9E3779B9 ^ (52 + 2));
-    w6 = tt.rotate_left(11);
-    tt = w7 ^ w2 ^ w4 ^ w6 ^ (0x9E3779B9 ^ (52 + 3));
-    w7 = tt.rotate_left(11);
-    r0 = w4;
-    r1 = w5;
-    r2 = w6;
-    r3 = w7;
-    r2 = !r2;
-    r4 = r3;
-    r3 &= r0;
-    r0 ^= r4;
-    r3 ^= r2;
-    r2 |= r4;
-    r1 ^= r3;
-    r2 ^= r0;
-    r0 |= r1;
-    r2 ^= r1;
-    r4 ^= r0;
-    r0 |= r3;
-    r0 ^= r2;
-    r4 ^= r3;
-    r4 ^= r0;
-    r3 = !r3;
-    r2 &= r4;
-    r2 ^= r3;
-    subkeys[i] = r0; i+=1;
-    subkeys[i] = r1; i+=1;
-    subkeys[i] = r4; i+=1;
-    subkeys[i] = r2; i+=1;
-    tt = w0 ^ w3 ^ w5 ^ w7 ^ (0x9E3779B9 ^ (56));
-    w0 = tt.rotate_left(11);
-    tt = w1 ^ w4 ^ w6 ^ w0 ^ (0x9E3779B9 ^ (56 + 1));
-    w1 = tt.rotate_left(11);
-    tt = w2 ^ w5 ^ w7 ^ w1 ^ (0x9E3779B9 ^ (56 + 2));
-    w2 = tt.rotate_left(11);
-    tt = w3 ^ w6 ^ w0 ^ w2 ^ (0x9E3779B9 ^ (56 + 3));
-    w3 = tt.rotate_left(11);
-    r0 = w0;
-    r1 = w1;
-    r2 = w2;
-    r3 = w3;
-    r0 ^= r1;
-    r1 ^= r3;
-    r3 = !r3;
-    r4 = r1;
-    r1 &= r0;
-    r2 ^= r3;
-    r1 ^= r2;
-    r2 |= r4;
-    r4 ^= r3;
-    r3 &= r1;
-    r3 ^= r0;
-    r4 ^= r1;
-    r4 ^= r2;
-    r2 ^= r0;
-    r0 &= r3;
-    r2 = !r2;
-    r0 ^= r4;
-    r4 |= r3;
-    r2 ^= r4;
-    subkeys[i] = r1; i+=1;
-    subkeys[i] = r3; i+=1;
-    subkeys[i] = r0; i+=1;
-    subkeys[i] = r2; i+=1;
-    tt = w4 ^ w7 ^ w1 ^ w3 ^ (0x9E3779B9 ^ (60));
-    w4 = tt.rotate_left(11);
-    tt = w5 ^ w0 ^ w2 ^ w4 ^ (0x9E3779B9 ^ (60 + 1));
-    w5 = tt.rotate_left(11);
-    tt = w6 ^ w1 ^ w3 ^ w5 ^ (0x9E3779B9 ^ (60 + 2));
-    w6 = tt.rotate_left(11);
-    tt = w7 ^ w2 ^ w4 ^ w6 ^ (0x9E3779B9 ^ (60 + 3));
-    w7 = tt.rotate_left(11);
-    r0 = w4;
-    r1 = w5;
-    r2 = w6;
-    r3 = w7;
-    r1 ^= r3;
-    r3 = !r3;
-    r2 ^= r3;
-    r3 ^= r0;
-    r4 = r1;
-    r1 &= r3;
-    r1 ^= r2;
-    r4 ^= r3;
-    r0 ^= r4;
-    r2 &= r4;
-    r2 ^= r0;
-    r0 &= r1;
-    r3 ^= r0;
-    r4 |= r1;
-    r4 ^= r0;
-    r0 |= r3;
-    r0 ^= r2;
-    r2 &= r3;
-    r0 = !r0;
-    r4 ^= r2;
-    subkeys[i] = r1; i+=1;
-    subkeys[i] = r4; i+=1;
-    subkeys[i] = r0; i+=1;
-    subkeys[i] = r3; i+=1;
-    tt = w0 ^ w3 ^ w5 ^ w7 ^ (0x9E3779B9 ^ (64));
-    w0 = tt.rotate_left(11);
-    tt = w1 ^ w4 ^ w6 ^ w0 ^ (0x9E3779B9 ^ (64 + 1));
-    w1 = tt.rotate_left(11);
-    tt = w2 ^ w5 ^ w7 ^ w1 ^ (0x9E3779B9 ^ (64 + 2));
-    w2 = tt.rotate_left(11);
-    tt = w3 ^ w6 ^ w0 ^ w2 ^ (0x9E3779B9 ^ (64 + 3));
-    w3 = tt.rotate_left(11);
-    r0 = w0;
-    r1 = w1;
-    r2 = w2;
-    r3 = w3;
-    r4 = r0;
-    r0 |= r3;
-    r3 ^= r1;
-    r1 &= r4;
-    r4 ^= r2;
-    r2 ^= r3;
-    r3 &= r0;
-    r4 |= r1;
-    r3 ^= r4;
-    r0 ^= r1;
-    r4 &= r0;
-    r1 ^= r3;
-    r4 ^= r2;
-    r1 |= r0;
-    r1 ^= r2;
-    r0 ^= r3;
-    r2 = r1;
-    r1 |= r3;
-    r1 ^= r0;
-    subkeys[i] = r1; i+=1;
-    subkeys[i] = r2; i+=1;
-    subkeys[i] = r3; i+=1;
-    subkeys[i] = r4; i+=1;
-    tt = w4 ^ w7 ^ w1 ^ w3 ^ (0x9E3779B9 ^ (68));
-    w4 = tt.rotate_left(11);
-    tt = w5 ^ w0 ^ w2 ^ w4 ^ (0x9E3779B9 ^ (68 + 1));
-    w5 = tt.rotate_left(11);
-    tt = w6 ^ w1 ^ w3 ^ w5 ^ (0x9E3779B9 ^ (68 + 2));
-    w6 = tt.rotate_left(11);
-    tt = w7 ^ w2 ^ w4 ^ w6 ^ (0x9E3779B9 ^ (68 + 3));
-    w7 = tt.rotate_left(11);
-    r0 = w4;
-    r1 = w5;
-    r2 = w6;
-    r3 = w7;
-    r4 = r0;
-    r0 &= r2;
-    r0 ^= r3;
-    r2 ^= r1;
-    r2 ^= r0;
-    r3 |= r4;
-    r3 ^= r1;
-    r4 ^= r2;
-    r1 = r3;
-    r3 |= r4;
-    r3 ^= r0;
-    r0 &= r1;
-    r4 ^= r0;
-    r1 ^= r3;
-    r1 ^= r4;
-    r4 = !r4;
-    subkeys[i] = r2; i+=1;
-    subkeys[i] = r3; i+=1;
-    subkeys[i] = r1; i+=1;
-    subkeys[i] = r4; i+=1;
-    tt = w0 ^ w3 ^ w5 ^ w7 ^ (0x9E3779B9 ^ (72));
-    w0 = tt.rotate_left(11);
-    tt = w1 ^ w4 ^ w6 ^ w0 ^ (0x9E3779B9 ^ (72 + 1));
-    w1 = tt.rotate_left(11);
-    tt = w2 ^ w5 ^ w7 ^ w1 ^ (0x9E3779B9 ^ (72 + 2));
-    w2 = tt.rotate_left(11);
-    tt = w3 ^ w6 ^ w0 ^ w2 ^ (0x9E3779B9 ^ (72 + 3));
-    w3 = tt.rotate_left(11);
-    r0 = w0;
-    r1 = w1;
-    r2 = w2;
-    r3 = w3;
-    r0 = !r0;
-    r2 = !r2;
-    r4 = r0;
-    r0 &= r1;
-    r2 ^= r0;
-    r0 |= r3;
-    r3 ^= r2;
-    r1 ^= r0;
-    r0 ^= r4;
-    r4 |= r1;
-    r1 ^= r3;
-    r2 |= r0;
-    r2 &= r4;
-    r0 ^= r1;
-    r1 &= r2;
-    r1 ^= r0;
-    r0 &= r2;
-    r0 ^= r4;
-    subkeys[i] = r2; i+=1;
-    subkeys[i] = r0; i+=1;
-    subkeys[i] = r3; i+=1;
-    subkeys[i] = r1; i+=1;
-    tt = w4 ^ w7 ^ w1 ^ w3 ^ (0x9E3779B9 ^ (76));
-    w4 = tt.rotate_left(11);
-    tt = w5 ^ w0 ^ w2 ^ w4 ^ (0x9E3779B9 ^ (76 + 1));
-    w5 = tt.rotate_left(11);
-    tt = w6 ^ w1 ^ w3 ^ w5 ^ (0x9E3779B9 ^ (76 + 2));
-    w6 = tt.rotate_left(11);
-    tt = w7 ^ w2 ^ w4 ^ w6 ^ (0x9E3779B9 ^ (76 + 3));
-    w7 = tt.rotate_left(11);
-    r0 = w4;
-    r1 = w5;
-    r2 = w6;
-    r3 = w7;
-    r3 ^= r0;
-    r4 = r1;
-    r1 &= r3;
-    r4 ^= r2;
-    r1 ^= r0;
-    r0 |= r3;
-    r0 ^= r4;
-    r4 ^= r3;
-    r3 ^= r2;
-    r2 |= r1;
-    r2 ^= r4;
-    r4 = !r4;
-    r4 |= r1;
-    r1 ^= r3;
-    r1 ^= r4;
-    r3 |= r0;
-    r1 ^= r3;
-    r4 ^= r3;
-    subkeys[i] = r1; i+=1;
-    subkeys[i] = r4; i+=1;
-    subkeys[i] = r2; i+=1;
-    subkeys[i] = r0; i+=1;
-    tt = w0 ^ w3 ^ w5 ^ w7 ^ (0x9E3779B9 ^ (80));
-    w0 = tt.rotate_left(11);
-    tt = w1 ^ w4 ^ w6 ^ w0 ^ (0x9E3779B9 ^ (80 + 1));
-    w1 = tt.rotate_left(11);
-    tt = w2 ^ w5 ^ w7 ^ w1 ^ (0x9E3779B9 ^ (80 + 2));
-    w2 = tt.rotate_left(11);
-    tt = w3 ^ w6 ^ w0 ^ w2 ^ (0x9E3779B9 ^ (80 + 3));
-    w3 = tt.rotate_left(11);
-    r0 = w0;
-    r1 = w1;
-    r2 = w2;
-    r3 = w3;
-    r4 = r1;
-    r1 |= r2;
-    r1 ^= r3;
-    r4 ^= r2;
-    r2 ^= r1;
-    r3 |= r4;
-    r3 &= r0;
-    r4 ^= r2;
-    r3 ^= r1;
-    r1 |= r4;
-    r1 ^= r0;
-    r0 |= r4;
-    r0 ^= r2;
-    r1 ^= r4;
-    r2 ^= r1;
-    r1 &= r0;
-    r1 ^= r4;
-    r2 = !r2;
-    r2 |= r0;
-    r4 ^= r2;
-    subkeys[i] = r4; i+=1;
-    subkeys[i] = r3; i+=1;
-    subkeys[i] = r1; i+=1;
-    subkeys[i] = r0; i+=1;
-    tt = w4 ^ w7 ^ w1 ^ w3 ^ (0x9E3779B9 ^ (84));
-    w4 = tt.rotate_left(11);
-    tt = w5 ^ w0 ^ w2 ^ w4 ^ (0x9E3779B9 ^ (84 + 1));
-    w5 = tt.rotate_left(11);
-    tt = w6 ^ w1 ^ w3 ^ w5 ^ (0x9E3779B9 ^ (84 + 2));
-    w6 = tt.rotate_left(11);
-    tt = w7 ^ w2 ^ w4 ^ w6 ^ (0x9E3779B9 ^ (84 + 3));
-    w7 = tt.rotate_left(11);
-    r0 = w4;
-    r1 = w5;
-    r2 = w6;
-    r3 = w7;
-    r2 = !r2;
-    r4 = r3;
-    r3 &= r0;
-    r0 ^= r4;
-    r3 ^= r2;
-    r2 |= r4;
-    r1 ^= r3;
-    r2 ^= r0;
-    r0 |= r1;
-    r2 ^= r1;
-    r4 ^= r0;
-    r0 |= r3;
-    r0 ^= r2;
-    r4 ^= r3;
-    r4 ^= r0;
-    r3 = !r3;
-    r2 &= r4;
-    r2 ^= r3;
-    subkeys[i] = r0; i+=1;
-    subkeys[i] = r1; i+=1;
-    subkeys[i] = r4; i+=1;
-    subkeys[i] = r2; i+=1;
-    tt = w0 ^ w3 ^ w5 ^ w7 ^ (0x9E3779B9 ^ (88));
-    w0 = tt.rotate_left(11);
-    tt = w1 ^ w4 ^ w6 ^ w0 ^ (0x9E3779B9 ^ (88 + 1));
-    w1 = tt.rotate_left(11);
-    tt = w2 ^ w5 ^ w7 ^ w1 ^ (0x9E3779B9 ^ (88 + 2));
-    w2 = tt.rotate_left(11);
-    tt = w3 ^ w6 ^ w0 ^ w2 ^ (0x9E3779B9 ^ (88 + 3));
-    w3 = tt.rotate_left(11);
-    r0 = w0;
-    r1 = w1;
-    r2 = w2;
-    r3 = w3;
-    r0 ^= r1;
-    r1 ^= r3;
-    r3 = !r3;
-    r4 = r1;
-    r1 &= r0;
-    r2 ^= r3;
-    r1 ^= r2;
-    r2 |= r4;
-    r4 ^= r3;
-    r3 &= r1;
-    r3 ^= r0;
-    r4 ^= r1;
-    r4 ^= r2;
-    r2 ^= r0;
-    r0 &= r3;
-    r2 = !r2;
-    r0 ^= r4;
-    r4 |= r3;
-    r2 ^= r4;
-    subkeys[i] = r1; i+=1;
-    subkeys[i] = r3; i+=1;
-    subkeys[i] = r0; i+=1;
-    subkeys[i] = r2; i+=1;
-    tt = w4 ^ w7 ^ w1 ^ w3 ^ (0x9E3779B9 ^ (92));
-    w4 = tt.rotate_left(11);
-    tt = w5 ^ w0 ^ w2 ^ w4 ^ (0x9E3779B9 ^ (92 + 1));
-    w5 = tt.rotate_left(11);
-    tt = w6 ^ w1 ^ w3 ^ w5 ^ (0x9E3779B9 ^ (92 + 2));
-    w6 = tt.rotate_left(11);
-    tt = w7 ^ w2 ^ w4 ^ w6 ^ (0x9E3779B9 ^ (92 + 3));
-    w7 = tt.rotate_left(11);
-    r0 = w4;
-    r1 = w5;
-    r2 = w6;
-    r3 = w7;
-    r1 ^= r3;
-    r3 = !r3;
-    r2 ^= r3;
-    r3 ^= r0;
-    r4 = r1;
-    r1 &= r3;
-    r1 ^= r2;
-    r4 ^= r3;
-    r0 ^= r4;
-    r2 &= r4;
-    r2 ^= r0;
-    r0 &= r1;
-    r3 ^= r0;
-    r4 |= r1;
-    r4 ^= r0;
-    r0 |= r3;
-    r0 ^= r2;
-    r2 &= r3;
-    r0 = !r0;
-    r4 ^= r2;
-    subkeys[i] = r1; i+=1;
-    subkeys[i] = r4; i+=1;
-    subkeys[i] = r0; i+=1;
-    subkeys[i] = r3; i+=1;
-    tt = w0 ^ w3 ^ w5 ^ w7 ^ (0x9E3779B9 ^ (96));
-    w0 = tt.rotate_left(11);
-    tt = w1 ^ w4 ^ w6 ^ w0 ^ (0x9E3779B9 ^ (96 + 1));
-    w1 = tt.rotate_left(11);
-    tt = w2 ^ w5 ^ w7 ^ w1 ^ (0x9E3779B9 ^ (96 + 2));
-    w2 = tt.rotate_left(11);
-    tt = w3 ^ w6 ^ w0 ^ w2 ^ (0x9E3779B9 ^ (96 + 3));
-    w3 = tt.rotate_left(11);
-    r0 = w0;
-    r1 = w1;
-    r2 = w2;
-    r3 = w3;
-    r4 = r0;
-    r0 |= r3;
-    r3 ^= r1;
-    r1 &= r4;
-    r4 ^= r2;
-    r2 ^= r3;
-    r3 &= r0;
-    r4 |= r1;
-    r3 ^= r4;
-    r0 ^= r1;
-    r4 &= r0;
-    r1 ^= r3;
-    r4 ^= r2;
-    r1 |= r0;
-    r1 ^= r2;
-    r0 ^= r3;
-    r2 = r1;
-    r1 |= r3;
-    r1 ^= r0;
-    subkeys[i] = r1; i+=1;
-    subkeys[i] = r2; i+=1;
-    subkeys[i] = r3; i+=1;
-    subkeys[i] = r4;
-}
-
-fn iv_setup(iv : &[u8], subkeys : &mut[u32; 100], lfsr : &mut[u32; 10], fsm_r : &mut[u32; 2]) {
+// Sosemanuk runs the nonce through 24 rounds of Serpent (Serpent24). The LFSR and FSM registers
+// are loaded from the intermediate state after rounds 12 and 18 and from the final output.
+fn iv_setup(iv : &[u8], subkeys : &[[u32; 4]; 25], lfsr : &mut[u32; 10], fsm_r : &mut[u32; 2]) {
     let mut nonce : [u8; 16] = [0; 16];
     if iv.len() < 16 {
         copy_memory(&iv, &mut nonce[0..iv.len()]);
@@ -1494,812 +511,28 @@ fn iv_setup(iv : &[u8], subkeys : &mut[u32; 100], lfsr : &mut[u32; 10], fsm_r :
         copy_memory(&iv[0..16], &mut nonce[0..16]);
     }
 
-    let mut r0 : u32;
-    let mut r1 : u32;
-    let mut r2 : u32;
-    let mut r3 : u32;
-    let mut r4 : u32;
-    r0 = read_u32_le(&nonce[0..4]);
-    r1 = read_u32_le(&nonce[4..8]);
-    r2 = read_u32_le(&nonce[8..12]);
-    r3 = read_u32_le(&nonce[12..16]);
-
-    r0 ^= subkeys[0];
-    r1 ^= subkeys[0 + 1];
-    r2 ^= subkeys[0 + 2];
-    r3 ^= subkeys[0 + 3];
-    r3 ^= r0;
-    r4 = r1;
-    r1 &= r3;
-    r4 ^= r2;
-    r1 ^= r0;
-    r0 |= r3;
-    r0 ^= r4;
-    r4 ^= r3;
-    r3 ^= r2;
-    r2 |= r1;
-    r2 ^= r4;
-    r4 = !r4;
-    r4 |= r1;
-    r1 ^= r3;
-    r1 ^= r4;
-    r3 |= r0;
-    r1 ^= r3;
-    r4 ^= r3;
-    r1 = r1.rotate_left(13);
-    r2 = r2.rotate_left(3);
-    r4 = r4 ^ r1 ^ r2;
-    r0 = r0 ^ r2 ^ (r1 << 3);
-    r4 = r4.rotate_left(1);
-    r0 = r0.rotate_left(7);
-    r1 = r1 ^ r4 ^ r0;
-    r2 = r2 ^ r0 ^ (r4 << 7);
-    r1 = r1.rotate_left(5);
-    r2 = r2.rotate_left(22);
-    r1 ^= subkeys[4];
-    r4 ^= subkeys[4 + 1];
-    r2 ^= subkeys[4 + 2];
-    r0 ^= subkeys[4 + 3];
-    r1 = !r1;
-    r2 = !r2;
-    r3 = r1;
-    r1 &= r4;
-    r2 ^= r1;
-    r1 |= r0;
-    r0 ^= r2;
-    r4 ^= r1;
-    r1 ^= r3;
-    r3 |= r4;
-    r4 ^= r0;
-    r2 |= r1;
-    r2 &= r3;
-    r1 ^= r4;
-    r4 &= r2;
-    r4 ^= r1;
-    r1 &= r2;
-    r1 ^= r3;
-    r2 = r2.rotate_left(13);
-    r0 = r0.rotate_left(3);
-    r1 = r1 ^ r2 ^ r0;
-    r4 = r4 ^ r0 ^ (r2 << 3);
-    r1 = r1.rotate_left(1);
-    r4 = r4.rotate_left(7);
-    r2 = r2 ^ r1 ^ r4;
-    r0 = r0 ^ r4 ^ (r1 << 7);
-    r2 = r2.rotate_left(5);
-    r0 = r0.rotate_left(22);
-    r2 ^= subkeys[8];
-    r1 ^= subkeys[8 + 1];
-    r0 ^= subkeys[8 + 2];
-    r4 ^= subkeys[8 + 3];
-    r3 = r2;
-    r2 &= r0;
-    r2 ^= r4;
-    r0 ^= r1;
-    r0 ^= r2;
-    r4 |= r3;
-    r4 ^= r1;
-    r3 ^= r0;
-    r1 = r4;
-    r4 |= r3;
-    r4 ^= r2;
-    r2 &= r1;
-    r3 ^= r2;
-    r1 ^= r4;
-    r1 ^= r3;
-    r3 = !r3;
-    r0 = r0.rotate_left(13);
-    r1 = r1.rotate_left(3);
-    r4 = r4 ^ r0 ^ r1;
-    r3 = r3 ^ r1 ^ (r0 << 3);
-    r4 = r4.rotate_left(1);
-    r3 = r3.rotate_left(7);
-    r0 = r0 ^ r4 ^ r3;
-    r1 = r1 ^ r3 ^ (r4 << 7);
-    r0 = r0.rotate_left(5);
-    r1 = r1.rotate_left(22);
-    r0 ^= subkeys[12];
-    r4 ^= subkeys[12 + 1];
-    r1 ^= subkeys[12 + 2];
-    r3 ^= subkeys[12 + 3];
-    r2 = r0;
-    r0 |= r3;
-    r3 ^= r4;
-    r4 &= r2;
-    r2 ^= r1;
-    r1 ^= r3;
-    r3 &= r0;
-    r2 |= r4;
-    r3 ^= r2;
-    r0 ^= r4;
-    r2 &= r0;
-    r4 ^= r3;
-    r2 ^= r1;
-    r4 |= r0;
-    r4 ^= r1;
-    r0 ^= r3;
-    r1 = r4;
-    r4 |= r3;
-    r4 ^= r0;
-    r4 = r4.rotate_left(13);
-    r3 = r3.rotate_left(3);
-    r1 = r1 ^ r4 ^ r3;
-    r2 = r2 ^ r3 ^ (r4 << 3);
-    r1 = r1.rotate_left(1);
-    r2 = r2.rotate_left(7);
-    r4 = r4 ^ r1 ^ r2;
-    r3 = r3 ^ r2 ^ (r1 << 7);
-    r4 = r4.rotate_left(5);
-    r3 = r3.rotate_left(22);
-    r4 ^= subkeys[16];
-    r1 ^= subkeys[16 + 1];
-    r3 ^= subkeys[16 + 2];
-    r2 ^= subkeys[16 + 3];
-    r1 ^= r2;
-    r2 = !r2;
-    r3 ^= r2;
-    r2 ^= r4;
-    r0 = r1;
-    r1 &= r2;
-    r1 ^= r3;
-    r0 ^= r2;
-    r4 ^= r0;
-    r3 &= r0;
-    r3 ^= r4;
-    r4 &= r1;
-    r2 ^= r4;
-    r0 |= r1;
-    r0 ^= r4;
-    r4 |= r2;
-    r4 ^= r3;
-    r3 &= r2;
-    r4 = !r4;
-    r0 ^= r3;
-    r1 = r1.rotate_left(13);
-    r4 = r4.rotate_left(3);
-    r0 = r0 ^ r1 ^ r4;
-    r2 = r2 ^ r4 ^ (r1 << 3);
-    r0 = r0.rotate_left(1);
-    r2 = r2.rotate_left(7);
-    r1 = r1 ^ r0 ^ r2;
-    r4 = r4 ^ r2 ^ (r0 << 7);
-    r1 = r1.rotate_left(5);
-    r4 = r4.rotate_left(22);
-    r1 ^= subkeys[20];
-    r0 ^= subkeys[20 + 1];
-    r4 ^= subkeys[20 + 2];
-    r2 ^= subkeys[20 + 3];
-    r1 ^= r0;
-    r0 ^= r2;
-    r2 = !r2;
-    r3 = r0;
-    r0 &= r1;
-    r4 ^= r2;
-    r0 ^= r4;
-    r4 |= r3;
-    r3 ^= r2;
-    r2 &= r0;
-    r2 ^= r1;
-    r3 ^= r0;
-    r3 ^= r4;
-    r4 ^= r1;
-    r1 &= r2;
-    r4 = !r4;
-    r1 ^= r3;
-    r3 |= r2;
-    r4 ^= r3;
-    r0 = r0.rotate_left(13);
-    r1 = r1.rotate_left(3);
-    r2 = r2 ^ r0 ^ r1;
-    r4 = r4 ^ r1 ^ (r0 << 3);
-    r2 = r2.rotate_left(1);
-    r4 = r4.rotate_left(7);
-    r0 = r0 ^ r2 ^ r4;
-    r1 = r1 ^ r4 ^ (r2 << 7);
-    r0 = r0.rotate_left(5);
-    r1 = r1.rotate_left(22);
-    r0 ^= subkeys[24];
-    r2 ^= subkeys[24 + 1];
-    r1 ^= subkeys[24 + 2];
-    r4 ^= subkeys[24 + 3];
-    r1 = !r1;
-    r3 = r4;
-    r4 &= r0;
-    r0 ^= r3;
-    r4 ^= r1;
-    r1 |= r3;
-    r2 ^= r4;
-    r1 ^= r0;
-    r0 |= r2;
-    r1 ^= r2;
-    r3 ^= r0;
-    r0 |= r4;
-    r0 ^= r1;
-    r3 ^= r4;
-    r3 ^= r0;
-    r4 = !r4;
-    r1 &= r3;
-    r1 ^= r4;
-    r0 = r0.rotate_left(13);
-    r3 = r3.rotate_left(3);
-    r2 = r2 ^ r0 ^ r3;
-    r1 = r1 ^ r3 ^ (r0 << 3);
-    r2 = r2.rotate_left(1);
-    r1 = r1.rotate_left(7);
-    r0 = r0 ^ r2 ^ r1;
-    r3 = r3 ^ r1 ^ (r2 << 7);
-    r0 = r0.rotate_left(5);
-    r3 = r3.rotate_left(22);
-    r0 ^= subkeys[28];
-    r2 ^= subkeys[28 + 1];
-    r3 ^= subkeys[28 + 2];
-    r1 ^= subkeys[28 + 3];
-    r4 = r2;
-    r2 |= r3;
-    r2 ^= r1;
-    r4 ^= r3;
-    r3 ^= r2;
-    r1 |= r4;
-    r1 &= r0;
-    r4 ^= r3;
-    r1 ^= r2;
-    r2 |= r4;
-    r2 ^= r0;
-    r0 |= r4;
-    r0 ^= r3;
-    r2 ^= r4;
-    r3 ^= r2;
-    r2 &= r0;
-    r2 ^= r4;
-    r3 = !r3;
-    r3 |= r0;
-    r4 ^= r3;
-    r4 = r4.rotate_left(13);
-    r2 = r2.rotate_left(3);
-    r1 = r1 ^ r4 ^ r2;
-    r0 = r0 ^ r2 ^ (r4 << 3);
-    r1 = r1.rotate_left(1);
-    r0 = r0.rotate_left(7);
-    r4 = r4 ^ r1 ^ r0;
-    r2 = r2 ^ r0 ^ (r1 << 7);
-    r4 = r4.rotate_left(5);
-    r2 = r2.rotate_left(22);
-    r4 ^= subkeys[32];
-    r1 ^= subkeys[32 + 1];
-    r2 ^= subkeys[32 + 2];
-    r0 ^= subkeys[32 + 3];
-    r0 ^= r4;
-    r3 = r1;
-    r1 &= r0;
-    r3 ^= r2;
-    r1 ^= r4;
-    r4 |= r0;
-    r4 ^= r3;
-    r3 ^= r0;
-    r0 ^= r2;
-    r2 |= r1;
-    r2 ^= r3;
-    r3 = !r3;
-    r3 |= r1;
-    r1 ^= r0;
-    r1 ^= r3;
-    r0 |= r4;
-    r1 ^= r0;
-    r3 ^= r0;
-    r1 = r1.rotate_left(13);
-    r2 = r2.rotate_left(3);
-    r3 = r3 ^ r1 ^ r2;
-    r4 = r4 ^ r2 ^ (r1 << 3);
-    r3 = r3.rotate_left(1);
-    r4 = r4.rotate_left(7);
-    r1 = r1 ^ r3 ^ r4;
-    r2 = r2 ^ r4 ^ (r3 << 7);
-    r1 = r1.rotate_left(5);
-    r2 = r2.rotate_left(22);
-    r1 ^= subkeys[36];
-    r3 ^= subkeys[36 + 1];
-    r2 ^= subkeys[36 + 2];
-    r4 ^= subkeys[36 + 3];
-    r1 = !r1;
-    r2 = !r2;
-    r0 = r1;
-    r1 &= r3;
-    r2 ^= r1;
-    r1 |= r4;
-    r4 ^= r2;
-    r3 ^= r1;
-    r1 ^= r0;
-    r0 |= r3;
-    r3 ^= r4;
-    r2 |= r1;
-    r2 &= r0;
-    r1 ^= r3;
-    r3 &= r2;
-    r3 ^= r1;
-    r1 &= r2;
-    r1 ^= r0;
-    r2 = r2.rotate_left(13);
-    r4 = r4.rotate_left(3);
-    r1 = r1 ^ r2 ^ r4;
-    r3 = r3 ^ r4 ^ (r2 << 3);
-    r1 = r1.rotate_left(1);
-    r3 = r3.rotate_left(7);
-    r2 = r2 ^ r1 ^ r3;
-    r4 = r4 ^ r3 ^ (r1 << 7);
-    r2 = r2.rotate_left(5);
-    r4 = r4.rotate_left(22);
-    r2 ^= subkeys[40];
-    r1 ^= subkeys[40 + 1];
-    r4 ^= subkeys[40 + 2];
-    r3 ^= subkeys[40 + 3];
-    r0 = r2;
-    r2 &= r4;
-    r2 ^= r3;
-    r4 ^= r1;
-    r4 ^= r2;
-    r3 |= r0;
-    r3 ^= r1;
-    r0 ^= r4;
-    r1 = r3;
-    r3 |= r0;
-    r3 ^= r2;
-    r2 &= r1;
-    r0 ^= r2;
-    r1 ^= r3;
-    r1 ^= r0;
-    r0 = !r0;
-    r4 = r4.rotate_left(13);
-    r1 = r1.rotate_left(3);
-    r3 = r3 ^ r4 ^ r1;
-    r0 = r0 ^ r1 ^ (r4 << 3);
-    r3 = r3.rotate_left(1);
-    r0 = r0.rotate_left(7);
-    r4 = r4 ^ r3 ^ r0;
-    r1 = r1 ^ r0 ^ (r3 << 7);
-    r4 = r4.rotate_left(5);
-    r1 = r1.rotate_left(22);
-    r4 ^= subkeys[44];
-    r3 ^= subkeys[44 + 1];
-    r1 ^= subkeys[44 + 2];
-    r0 ^= subkeys[44 + 3];
-    r2 = r4;
-    r4 |= r0;
-    r0 ^= r3;
-    r3 &= r2;
-    r2 ^= r1;
-    r1 ^= r0;
-    r0 &= r4;
-    r2 |= r3;
-    r0 ^= r2;
-    r4 ^= r3;
-    r2 &= r4;
-    r3 ^= r0;
-    r2 ^= r1;
-    r3 |= r4;
-    r3 ^= r1;
-    r4 ^= r0;
-    r1 = r3;
-    r3 |= r0;
-    r3 ^= r4;
-    r3 = r3.rotate_left(13);
-    r0 = r0.rotate_left(3);
-    r1 = r1 ^ r3 ^ r0;
-    r2 = r2 ^ r0 ^ (r3 << 3);
-    r1 = r1.rotate_left(1);
-    r2 = r2.rotate_left(7);
-    r3 = r3 ^ r1 ^ r2;
-    r0 = r0 ^ r2 ^ (r1 << 7);
-    r3 = r3.rotate_left(5);
-    r0 = r0.rotate_left(22);
-    lfsr[9] = r3;
-    lfsr[8] = r1;
-    lfsr[7] = r0;
-    lfsr[6] = r2;
-    r3 ^= subkeys[48];
-    r1 ^= subkeys[48 + 1];
-    r0 ^= subkeys[48 + 2];
-    r2 ^= subkeys[48 + 3];
-    r1 ^= r2;
-    r2 = !r2;
-    r0 ^= r2;
-    r2 ^= r3;
-    r4 = r1;
-    r1 &= r2;
-    r1 ^= r0;
-    r4 ^= r2;
-    r3 ^= r4;
-    r0 &= r4;
-    r0 ^= r3;
-    r3 &= r1;
-    r2 ^= r3;
-    r4 |= r1;
-    r4 ^= r3;
-    r3 |= r2;
-    r3 ^= r0;
-    r0 &= r2;
-    r3 = !r3;
-    r4 ^= r0;
-    r1 = r1.rotate_left(13);
-    r3 = r3.rotate_left(3);
-    r4 = r4 ^ r1 ^ r3;
-    r2 = r2 ^ r3 ^ (r1 << 3);
-    r4 = r4.rotate_left(1);
-    r2 = r2.rotate_left(7);
-    r1 = r1 ^ r4 ^ r2;
-    r3 = r3 ^ r2 ^ (r4 << 7);
-    r1 = r1.rotate_left(5);
-    r3 = r3.rotate_left(22);
-    r1 ^= subkeys[52];
-    r4 ^= subkeys[52 + 1];
-    r3 ^= subkeys[52 + 2];
-    r2 ^= subkeys[52 + 3];
-    r1 ^= r4;
-    r4 ^= r2;
-    r2 = !r2;
-    r0 = r4;
-    r4 &= r1;
-    r3 ^= r2;
-    r4 ^= r3;
-    r3 |= r0;
-    r0 ^= r2;
-    r2 &= r4;
-    r2 ^= r1;
-    r0 ^= r4;
-    r0 ^= r3;
-    r3 ^= r1;
-    r1 &= r2;
-    r3 = !r3;
-    r1 ^= r0;
-    r0 |= r2;
-    r3 ^= r0;
-    r4 = r4.rotate_left(13);
-    r1 = r1.rotate_left(3);
-    r2 = r2 ^ r4 ^ r1;
-    r3 = r3 ^ r1 ^ (r4 << 3);
-    r2 = r2.rotate_left(1);
-    r3 = r3.rotate_left(7);
-    r4 = r4 ^ r2 ^ r3;
-    r1 = r1 ^ r3 ^ (r2 << 7);
-    r4 = r4.rotate_left(5);
-    r1 = r1.rotate_left(22);
-    r4 ^= subkeys[56];
-    r2 ^= subkeys[56 + 1];
-    r1 ^= subkeys[56 + 2];
-    r3 ^= subkeys[56 + 3];
-    r1 = !r1;
-    r0 = r3;
-    r3 &= r4;
-    r4 ^= r0;
-    r3 ^= r1;
-    r1 |= r0;
-    r2 ^= r3;
-    r1 ^= r4;
-    r4 |= r2;
-    r1 ^= r2;
-    r0 ^= r4;
-    r4 |= r3;
-    r4 ^= r1;
-    r0 ^= r3;
-    r0 ^= r4;
-    r3 = !r3;
-    r1 &= r0;
-    r1 ^= r3;
-    r4 = r4.rotate_left(13);
-    r0 = r0.rotate_left(3);
-    r2 = r2 ^ r4 ^ r0;
-    r1 = r1 ^ r0 ^ (r4 << 3);
-    r2 = r2.rotate_left(1);
-    r1 = r1.rotate_left(7);
-    r4 = r4 ^ r2 ^ r1;
-    r0 = r0 ^ r1 ^ (r2 << 7);
-    r4 = r4.rotate_left(5);
-    r0 = r0.rotate_left(22);
-    r4 ^= subkeys[60];
-    r2 ^= subkeys[60 + 1];
-    r0 ^= subkeys[60 + 2];
-    r1 ^= subkeys[60 + 3];
-    r3 = r2;
-    r2 |= r0;
-    r2 ^= r1;
-    r3 ^= r0;
-    r0 ^= r2;
-    r1 |= r3;
-    r1 &= r4;
-    r3 ^= r0;
-    r1 ^= r2;
-    r2 |= r3;
-    r2 ^= r4;
-    r4 |= r3;
-    r4 ^= r0;
-    r2 ^= r3;
-    r0 ^= r2;
-    r2 &= r4;
-    r2 ^= r3;
-    r0 = !r0;
-    r0 |= r4;
-    r3 ^= r0;
-    r3 = r3.rotate_left(13);
-    r2 = r2.rotate_left(3);
-    r1 = r1 ^ r3 ^ r2;
-    r4 = r4 ^ r2 ^ (r3 << 3);
-    r1 = r1.rotate_left(1);
-    r4 = r4.rotate_left(7);
-    r3 = r3 ^ r1 ^ r4;
-    r2 = r2 ^ r4 ^ (r1 << 7);
-    r3 = r3.rotate_left(5);
-    r2 = r2.rotate_left(22);
-    r3 ^= subkeys[64];
-    r1 ^= subkeys[64 + 1];
-    r2 ^= subkeys[64 + 2];
-    r4 ^= subkeys[64 + 3];
-    r4 ^= r3;
-    r0 = r1;
-    r1 &= r4;
-    r0 ^= r2;
-    r1 ^= r3;
-    r3 |= r4;
-    r3 ^= r0;
-    r0 ^= r4;
-    r4 ^= r2;
-    r2 |= r1;
-    r2 ^= r0;
-    r0 = !r0;
-    r0 |= r1;
-    r1 ^= r4;
-    r1 ^= r0;
-    r4 |= r3;
-    r1 ^= r4;
-    r0 ^= r4;
-    r1 = r1.rotate_left(13);
-    r2 = r2.rotate_left(3);
-    r0 = r0 ^ r1 ^ r2;
-    r3 = r3 ^ r2 ^ (r1 << 3);
-    r0 = r0.rotate_left(1);
-    r3 = r3.rotate_left(7);
-    r1 = r1 ^ r0 ^ r3;
-    r2 = r2 ^ r3 ^ (r0 << 7);
-    r1 = r1.rotate_left(5);
-    r2 = r2.rotate_left(22);
-    r1 ^= subkeys[68];
-    r0 ^= subkeys[68 + 1];
-    r2 ^= subkeys[68 + 2];
-    r3 ^= subkeys[68 + 3];
-    r1 = !r1;
-    r2 = !r2;
-    r4 = r1;
-    r1 &= r0;
-    r2 ^= r1;
-    r1 |= r3;
-    r3 ^= r2;
-    r0 ^= r1;
-    r1 ^= r4;
-    r4 |= r0;
-    r0 ^= r3;
-    r2 |= r1;
-    r2 &= r4;
-    r1 ^= r0;
-    r0 &= r2;
-    r0 ^= r1;
-    r1 &= r2;
-    r1 ^= r4;
-    r2 = r2.rotate_left(13);
-    r3 = r3.rotate_left(3);
-    r1 = r1 ^ r2 ^ r3;
-    r0 = r0 ^ r3 ^ (r2 << 3);
-    r1 = r1.rotate_left(1);
-    r0 = r0.rotate_left(7);
-    r2 = r2 ^ r1 ^ r0;
-    r3 = r3 ^ r0 ^ (r1 << 7);
-    r2 = r2.rotate_left(5);
-    r3 = r3.rotate_left(22);
-    fsm_r[0] = r2;
-    lfsr[4] = r1;
-    fsm_r[1] = r3;
-    lfsr[5] = r0;
-    r2 ^= subkeys[72];
-    r1 ^= subkeys[72 + 1];
-    r3 ^= subkeys[72 + 2];
-    r0 ^= subkeys[72 + 3];
-    r4 = r2;
-    r2 &= r3;
-    r2 ^= r0;
-    r3 ^= r1;
-    r3 ^= r2;
-    r0 |= r4;
-    r0 ^= r1;
-    r4 ^= r3;
-    r1 = r0;
-    r0 |= r4;
-    r0 ^= r2;
-    r2 &= r1;
-    r4 ^= r2;
-    r1 ^= r0;
-    r1 ^= r4;
-    r4 = !r4;
-    r3 = r3.rotate_left(13);
-    r1 = r1.rotate_left(3);
-    r0 = r0 ^ r3 ^ r1;
-    r4 = r4 ^ r1 ^ (r3 << 3);
-    r0 = r0.rotate_left(1);
-    r4 = r4.rotate_left(7);
-    r3 = r3 ^ r0 ^ r4;
-    r1 = r1 ^ r4 ^ (r0 << 7);
-    r3 = r3.rotate_left(5);
-    r1 = r1.rotate_left(22);
-    r3 ^= subkeys[76];
-    r0 ^= subkeys[76 + 1];
-    r1 ^= subkeys[76 + 2];
-    r4 ^= subkeys[76 + 3];
-    r2 = r3;
-    r3 |= r4;
-    r4 ^= r0;
-    r0 &= r2;
-    r2 ^= r1;
-    r1 ^= r4;
-    r4 &= r3;
-    r2 |= r0;
-    r4 ^= r2;
-    r3 ^= r0;
-    r2 &= r3;
-    r0 ^= r4;
-    r2 ^= r1;
-    r0 |= r3;
-    r0 ^= r1;
-    r3 ^= r4;
-    r1 = r0;
-    r0 |= r4;
-    r0 ^= r3;
-    r0 = r0.rotate_left(13);
-    r4 = r4.rotate_left(3);
-    r1 = r1 ^ r0 ^ r4;
-    r2 = r2 ^ r4 ^ (r0 << 3);
-    r1 = r1.rotate_left(1);
-    r2 = r2.rotate_left(7);
-    r0 = r0 ^ r1 ^ r2;
-    r4 = r4 ^ r2 ^ (r1 << 7);
-    r0 = r0.rotate_left(5);
-    r4 = r4.rotate_left(22);
-    r0 ^= subkeys[80];
-    r1 ^= subkeys[80 + 1];
-    r4 ^= subkeys[80 + 2];
-    r2 ^= subkeys[80 + 3];
-    r1 ^= r2;
-    r2 = !r2;
-    r4 ^= r2;
-    r2 ^= r0;
-    r3 = r1;
-    r1 &= r2;
-    r1 ^= r4;
-    r3 ^= r2;
-    r0 ^= r3;
-    r4 &= r3;
-    r4 ^= r0;
-    r0 &= r1;
-    r2 ^= r0;
-    r3 |= r1;
-    r3 ^= r0;
-    r0 |= r2;
-    r0 ^= r4;
-    r4 &= r2;
-    r0 = !r0;
-    r3 ^= r4;
-    r1 = r1.rotate_left(13);
-    r0 = r0.rotate_left(3);
-    r3 = r3 ^ r1 ^ r0;
-    r2 = r2 ^ r0 ^ (r1 << 3);
-    r3 = r3.rotate_left(1);
-    r2 = r2.rotate_left(7);
-    r1 = r1 ^ r3 ^ r2;
-    r0 = r0 ^ r2 ^ (r3 << 7);
-    r1 = r1.rotate_left(5);
-    r0 = r0.rotate_left(22);
-    r1 ^= subkeys[84];
-    r3 ^= subkeys[84 + 1];
-    r0 ^= subkeys[84 + 2];
-    r2 ^= subkeys[84 + 3];
-    r1 ^= r3;
-    r3 ^= r2;
-    r2 = !r2;
-    r4 = r3;
-    r3 &= r1;
-    r0 ^= r2;
-    r3 ^= r0;
-    r0 |= r4;
-    r4 ^= r2;
-    r2 &= r3;
-    r2 ^= r1;
-    r4 ^= r3;
-    r4 ^= r0;
-    r0 ^= r1;
-    r1 &= r2;
-    r0 = !r0;
-    r1 ^= r4;
-    r4 |= r2;
-    r0 ^= r4;
-    r3 = r3.rotate_left(13);
-    r1 = r1.rotate_left(3);
-    r2 = r2 ^ r3 ^ r1;
-    r0 = r0 ^ r1 ^ (r3 << 3);
-    r2 = r2.rotate_left(1);
-    r0 = r0.rotate_left(7);
-    r3 = r3 ^ r2 ^ r0;
-    r1 = r1 ^ r0 ^ (r2 << 7);
-    r3 = r3.rotate_left(5);
-    r1 = r1.rotate_left(22);
-    r3 ^= subkeys[88];
-    r2 ^= subkeys[88 + 1];
-    r1 ^= subkeys[88 + 2];
-    r0 ^= subkeys[88 + 3];
-    r1 = !r1;
-    r4 = r0;
-    r0 &= r3;
-    r3 ^= r4;
-    r0 ^= r1;
-    r1 |= r4;
-    r2 ^= r0;
-    r1 ^= r3;
-    r3 |= r2;
-    r1 ^= r2;
-    r4 ^= r3;
-    r3 |= r0;
-    r3 ^= r1;
-    r4 ^= r0;
-    r4 ^= r3;
-    r0 = !r0;
-    r1 &= r4;
-    r1 ^= r0;
-    r3 = r3.rotate_left(13);
-    r4 = r4.rotate_left(3);
-    r2 = r2 ^ r3 ^ r4;
-    r1 = r1 ^ r4 ^ (r3 << 3);
-    r2 = r2.rotate_left(1);
-    r1 = r1.rotate_left(7);
-    r3 = r3 ^ r2 ^ r1;
-    r4 = r4 ^ r1 ^ (r2 << 7);
-    r3 = r3.rotate_left(5);
-    r4 = r4.rotate_left(22);
-    r3 ^= subkeys[92];
-    r2 ^= subkeys[92 + 1];
-    r4 ^= subkeys[92 + 2];
-    r1 ^= subkeys[92 + 3];
-    r0 = r2;
-    r2 |= r4;
-    r2 ^= r1;
-    r0 ^= r4;
-    r4 ^= r2;
-    r1 |= r0;
-    r1 &= r3;
-    r0 ^= r4;
-    r1 ^= r2;
-    r2 |= r0;
-    r2 ^= r3;
-    r3 |= r0;
-    r3 ^= r4;
-    r2 ^= r0;
-    r4 ^= r2;
-    r2 &= r3;
-    r2 ^= r0;
-    r4 = !r4;
-    r4 |= r3;
-    r0 ^= r4;
-    r0 = r0.rotate_left(13);
-    r2 = r2.rotate_left(3);
-    r1 = r1 ^ r0 ^ r2;
-    r3 = r3 ^ r2 ^ (r0 << 3);
-    r1 = r1.rotate_left(1);
-    r3 = r3.rotate_left(7);
-    r0 = r0 ^ r1 ^ r3;
-    r2 = r2 ^ r3 ^ (r1 << 7);
-    r0 = r0.rotate_left(5);
-    r2 = r2.rotate_left(22);
-    r0 ^= subkeys[96];
-    r1 ^= subkeys[96 + 1];
-    r2 ^= subkeys[96 + 2];
-    r3 ^= subkeys[96 + 3];
-    lfsr[3] = r0;
-    lfsr[2] = r1;
-    lfsr[1] = r2;
-    lfsr[0] = r3;
+    let mut x = [0u32; 4];
+    read_u32v_le(&mut x, &nonce);
+    for (i, k) in subkeys[..24].iter().enumerate() {
+        x = serpent::add_round_key(x, k);
+        x = serpent::linear_transform(serpent::sbox(i, x));
+        if i == 11 {
+            lfsr[9] = x[0];
+            lfsr[8] = x[1];
+            lfsr[7] = x[2];
+            lfsr[6] = x[3];
+        } else if i == 17 {
+            fsm_r[0] = x[0];
+            lfsr[4] = x[1];
+            fsm_r[1] = x[2];
+            lfsr[5] = x[3];
+        }
+    }
+    x = serpent::add_round_key(x, &subkeys[24]);
+    lfsr[3] = x[0];
+    lfsr[2] = x[1];
+    lfsr[1] = x[2];
+    lfsr[0] = x[3];
 }
 
 
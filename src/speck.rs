@@ -0,0 +1,305 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+An implementation of the Speck family of lightweight block ciphers.
+
+Speck is an add-rotate-xor Feistel-like network operating on two n bit words. All of the standard
+sizes are supported:
+
+| Block size (bits) | Key sizes (bits) |
+|-------------------|------------------|
+| 32                | 64               |
+| 48                | 72, 96           |
+| 64                | 96, 128          |
+| 96                | 96, 144          |
+| 128               | 128, 192, 256    |
+
+Bytes are mapped to words as described in the Simon and Speck implementation guide: words are
+little endian and the words are stored least significant first. In the notation of the original
+paper, where a block is written as x || y and a key as l_{m-2} || ... || l_0 || k_0, this is the
+reverse of the byte order of the written value.
+*/
+
+use symmetriccipher::{BlockEncryptor, BlockDecryptor};
+
+const MAX_ROUNDS: usize = 34;
+
+// Read a little endian word from all of the given bytes
+fn read_word(input: &[u8]) -> u64 {
+    input.iter().rev().fold(0, |w, &b| (w << 8) | b as u64)
+}
+
+fn write_word(output: &mut [u8], mut w: u64) {
+    for b in output.iter_mut() {
+        *b = w as u8;
+        w >>= 8;
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Speck {
+    word_size: usize,
+    mask: u64,
+    alpha: usize,
+    beta: usize,
+    rounds: usize,
+    round_keys: [u64; MAX_ROUNDS]
+}
+
+impl Speck {
+    /// Create a Speck cipher with a block size of `block_size` bytes. The key size must be one
+    /// of those allowed for that block size.
+    pub fn new(block_size: usize, key: &[u8]) -> Speck {
+        let rounds = match (block_size, key.len()) {
+            (4, 8) => 22,
+            (6, 9) => 22,
+            (6, 12) => 23,
+            (8, 12) => 26,
+            (8, 16) => 27,
+            (12, 12) => 28,
+            (12, 18) => 29,
+            (16, 16) => 32,
+            (16, 24) => 33,
+            (16, 32) => 34,
+            _ => panic!("Invalid Speck block or key size")
+        };
+
+        let n = block_size * 4;
+        let word_bytes = block_size / 2;
+        let m = key.len() / word_bytes;
+
+        let mut speck = Speck {
+            word_size: n,
+            mask: if n == 64 { !0 } else { (1 << n) - 1 },
+            alpha: if n == 16 { 7 } else { 8 },
+            beta: if n == 16 { 2 } else { 3 },
+            rounds: rounds,
+            round_keys: [0; MAX_ROUNDS]
+        };
+
+        let mut l = [0u64; MAX_ROUNDS + 3];
+        for i in 0..m - 1 {
+            l[i] = read_word(&key[(i + 1) * word_bytes..(i + 2) * word_bytes]);
+        }
+        let mut k = read_word(&key[0..word_bytes]);
+        for i in 0..rounds {
+            speck.round_keys[i] = k;
+            if i + 1 < rounds {
+                l[i + m - 1] =
+                    (k.wrapping_add(speck.ror(l[i], speck.alpha)) & speck.mask) ^ i as u64;
+                k = speck.rol(k, speck.beta) ^ l[i + m - 1];
+            }
+        }
+
+        speck
+    }
+
+    fn rol(&self, x: u64, r: usize) -> u64 {
+        ((x << r) | (x >> (self.word_size - r))) & self.mask
+    }
+
+    fn ror(&self, x: u64, r: usize) -> u64 {
+        ((x >> r) | (x << (self.word_size - r))) & self.mask
+    }
+}
+
+impl BlockEncryptor for Speck {
+    fn block_size(&self) -> usize {
+        self.word_size / 4
+    }
+
+    fn encrypt_block(&self, input: &[u8], output: &mut [u8]) {
+        let word_bytes = self.word_size / 8;
+        assert!(input.len() == 2 * word_bytes);
+        assert!(output.len() == 2 * word_bytes);
+        let mut y = read_word(&input[0..word_bytes]);
+        let mut x = read_word(&input[word_bytes..]);
+        for k in self.round_keys[..self.rounds].iter() {
+            x = (self.ror(x, self.alpha).wrapping_add(y) & self.mask) ^ k;
+            y = self.rol(y, self.beta) ^ x;
+        }
+        write_word(&mut output[0..word_bytes], y);
+        write_word(&mut output[word_bytes..], x);
+    }
+}
+
+impl BlockDecryptor for Speck {
+    fn block_size(&self) -> usize {
+        self.word_size / 4
+    }
+
+    fn decrypt_block(&self, input: &[u8], output: &mut [u8]) {
+        let word_bytes = self.word_size / 8;
+        assert!(input.len() == 2 * word_bytes);
+        assert!(output.len() == 2 * word_bytes);
+        let mut y = read_word(&input[0..word_bytes]);
+        let mut x = read_word(&input[word_bytes..]);
+        for k in self.round_keys[..self.rounds].iter().rev() {
+            y = self.ror(y ^ x, self.beta);
+            x = self.rol((x ^ k).wrapping_sub(y) & self.mask, self.alpha);
+        }
+        write_word(&mut output[0..word_bytes], y);
+        write_word(&mut output[word_bytes..], x);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use speck::Speck;
+    use blockmodes::{CbcDecryptor, CbcEncryptor, PkcsPadding};
+    use buffer::{RefReadBuffer, RefWriteBuffer};
+    use symmetriccipher::{BlockEncryptor, BlockDecryptor, Decryptor, Encryptor};
+    use serialize::hex::FromHex;
+
+    struct Test {
+        key: &'static str,
+        plaintext: &'static str,
+        ciphertext: &'static str
+    }
+
+    // Vectors from "The SIMON and SPECK Families of Lightweight Block Ciphers", Appendix C. They
+    // are written as in the paper, so the bytes need to be reversed.
+    fn tests() -> Vec<Test> {
+        vec![
+            Test {
+                key: "1918111009080100",
+                plaintext: "6574694c",
+                ciphertext: "a86842f2"
+            },
+            Test {
+                key: "1211100a0908020100",
+                plaintext: "20796c6c6172",
+                ciphertext: "c049a5385adc"
+            },
+            Test {
+                key: "1a19181211100a0908020100",
+                plaintext: "6d2073696874",
+                ciphertext: "735e10b6445d"
+            },
+            Test {
+                key: "131211100b0a090803020100",
+                plaintext: "74614620736e6165",
+                ciphertext: "9f7952ec4175946c"
+            },
+            Test {
+                key: "1b1a1918131211100b0a090803020100",
+                plaintext: "3b7265747475432d",
+                ciphertext: "8c6fa548454e028b"
+            },
+            Test {
+                key: "0d0c0b0a0908050403020100",
+                plaintext: "65776f68202c656761737520",
+                ciphertext: "9e4d09ab717862bdde8f79aa"
+            },
+            Test {
+                key: "1514131211100d0c0b0a0908050403020100",
+                plaintext: "656d6974206e69202c726576",
+                ciphertext: "2bf31072228a7ae440252ee6"
+            },
+            Test {
+                key: "0f0e0d0c0b0a09080706050403020100",
+                plaintext: "6c617669757165207469206564616d20",
+                ciphertext: "a65d9851797832657860fedf5c570d18"
+            },
+            Test {
+                key: "17161514131211100f0e0d0c0b0a09080706050403020100",
+                plaintext: "726148206665696843206f7420746e65",
+                ciphertext: "1be4cf3a13135566f9bc185de03c1886"
+            },
+            Test {
+                key: "1f1e1d1c1b1a191817161514131211100f0e0d0c0b0a09080706050403020100",
+                plaintext: "65736f6874206e49202e72656e6f6f70",
+                ciphertext: "4109010405c0f53e4eeeb48d9c188f43"
+            }
+        ]
+    }
+
+    fn reversed(hex: &str) -> Vec<u8> {
+        let mut bytes = hex.from_hex().unwrap();
+        bytes.reverse();
+        bytes
+    }
+
+    #[test]
+    fn test_encrypt() {
+        for test in tests().iter() {
+            let plaintext = reversed(test.plaintext);
+            let state = Speck::new(plaintext.len(), &reversed(test.key));
+            let mut output = vec![0u8; plaintext.len()];
+            state.encrypt_block(&plaintext, &mut output);
+            assert_eq!(output, reversed(test.ciphertext));
+        }
+    }
+
+    #[test]
+    fn test_decrypt() {
+        for test in tests().iter() {
+            let ciphertext = reversed(test.ciphertext);
+            let state = Speck::new(ciphertext.len(), &reversed(test.key));
+            let mut output = vec![0u8; ciphertext.len()];
+            state.decrypt_block(&ciphertext, &mut output);
+            assert_eq!(output, reversed(test.plaintext));
+        }
+    }
+
+    #[test]
+    fn test_cbc_48_bit_blocks() {
+        let key = [7u8; 12];
+        let iv = vec![1u8; 6];
+        let plaintext: Vec<u8> = (0..50).collect();
+
+        let mut encryptor = CbcEncryptor::new(Speck::new(6, &key), PkcsPadding, iv.clone());
+        let mut ciphertext = [0u8; 54];
+        {
+            let mut read_buffer = RefReadBuffer::new(&plaintext);
+            let mut write_buffer = RefWriteBuffer::new(&mut ciphertext);
+            encryptor.encrypt(&mut read_buffer, &mut write_buffer, true).unwrap();
+        }
+
+        let mut decryptor = CbcDecryptor::new(Speck::new(6, &key), PkcsPadding, iv);
+        let mut decrypted = [0u8; 54];
+        {
+            let mut read_buffer = RefReadBuffer::new(&ciphertext);
+            let mut write_buffer = RefWriteBuffer::new(&mut decrypted);
+            decryptor.decrypt(&mut read_buffer, &mut write_buffer, true).unwrap();
+        }
+        assert_eq!(&decrypted[..50], &plaintext[..]);
+    }
+}
+
+#[cfg(all(test, feature = "with-bench"))]
+mod bench {
+    use speck::Speck;
+    use symmetriccipher::BlockEncryptor;
+    use test::Bencher;
+
+    #[bench]
+    fn speck64_128(bh: &mut Bencher) {
+        let key = [0u8; 16];
+        let plaintext = [1u8; 8];
+        let state = Speck::new(8, &key);
+        let mut ciphertext = [0u8; 8];
+
+        bh.iter(|| {
+            state.encrypt_block(&plaintext, &mut ciphertext);
+        });
+        bh.bytes = 8u64;
+    }
+
+    #[bench]
+    fn speck128_128(bh: &mut Bencher) {
+        let key = [0u8; 16];
+        let plaintext = [1u8; 16];
+        let state = Speck::new(16, &key);
+        let mut ciphertext = [0u8; 16];
+
+        bh.iter(|| {
+            state.encrypt_block(&plaintext, &mut ciphertext);
+        });
+        bh.bytes = 16u64;
+    }
+}
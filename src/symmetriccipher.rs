@@ -27,6 +27,17 @@ pub trait BlockDecryptorX8 {
     fn decrypt_block_x8(&self, input: &[u8], output: &mut [u8]);
 }
 
+// This allows the implementation picked by aes::block_encryptor to be used with generic code such
+// as CtrMode and Cmac
+impl BlockEncryptor for Box<BlockEncryptor + 'static> {
+    fn block_size(&self) -> usize {
+        (**self).block_size()
+    }
+    fn encrypt_block(&self, input: &[u8], output: &mut [u8]) {
+        (**self).encrypt_block(input, output);
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum SymmetricCipherError {
     InvalidLength,
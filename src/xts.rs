@@ -0,0 +1,544 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+An implementation of the XTS mode of operation, as specified in IEEE 1619 and NIST SP 800-38E.
+
+XTS is designed for encrypting storage. Each data unit, typically a disk sector, is encrypted
+independently using a tweak derived from its sector number, so that any sector can be read or
+written without touching the others and the ciphertext is the same size as the plaintext. Data
+units whose length is not a multiple of the block size are handled with ciphertext stealing, but
+every data unit must be at least one block long.
+
+An XTS key is two cipher keys of equal size: the first half keys the cipher that encrypts the
+data and the second half keys the cipher that encrypts the tweak. NIST SP 800-38E requires that
+the two halves differ.
+
+XTS provides confidentiality only. It does not detect modification of the ciphertext.
+
+# Usage
+
+```
+use crypto::aessafe::{AesSafe128Encryptor, AesSafe128Decryptor};
+use crypto::xts::{XtsEncryptor, XtsDecryptor};
+
+let key = [7u8; 32];
+let sector = [0u8; 512];
+
+let enc = XtsEncryptor::new(AesSafe128Encryptor::new(&key[0..16]),
+    AesSafe128Encryptor::new(&key[16..32]));
+let mut encrypted = [0u8; 512];
+enc.encrypt_sector(42, &sector, &mut encrypted);
+
+let dec = XtsDecryptor::new(AesSafe128Decryptor::new(&key[0..16]),
+    AesSafe128Encryptor::new(&key[16..32]));
+let mut decrypted = [0u8; 512];
+dec.decrypt_sector(42, &encrypted, &mut decrypted);
+assert!(sector[..] == decrypted[..]);
+```
+*/
+
+use std::cmp;
+use std::iter::repeat;
+
+use cryptoutil::{copy_memory, write_u64_le};
+use symmetriccipher::{BlockEncryptor, BlockEncryptorX8, BlockDecryptor, BlockDecryptorX8};
+
+const BLOCK_SIZE: usize = 16;
+
+// Multiply the tweak by x in GF(2^128), with the tweak interpreted as a little endian polynomial
+// modulo x^128 + x^7 + x^2 + x + 1
+fn mul_x(tweak: &mut [u8; BLOCK_SIZE]) {
+    let mut carry = 0u8;
+    for b in tweak.iter_mut() {
+        let next = *b >> 7;
+        *b = (*b << 1) | carry;
+        carry = next;
+    }
+    tweak[0] ^= 0x87 & 0u8.wrapping_sub(carry);
+}
+
+// Encrypt the sector number, as a 128 bit little endian value, to get the tweak for the first
+// block of a data unit
+fn initial_tweak<T: BlockEncryptor>(tweak_cipher: &T, sector: u64) -> [u8; BLOCK_SIZE] {
+    let mut input = [0u8; BLOCK_SIZE];
+    write_u64_le(&mut input[0..8], sector);
+    let mut tweak = [0u8; BLOCK_SIZE];
+    tweak_cipher.encrypt_block(&input, &mut tweak);
+    tweak
+}
+
+fn xor_block(block: &mut [u8], tweak: &[u8]) {
+    for (b, &t) in block.iter_mut().zip(tweak.iter()) {
+        *b ^= t;
+    }
+}
+
+// Encrypt or decrypt a data unit. The process function must encrypt or decrypt any whole number
+// of blocks up to max_blocks. When decrypting, the tweaks of the final two blocks are used in the
+// opposite order so that ciphertext stealing is reversed.
+fn process_data_unit<F>(
+        process: F,
+        max_blocks: usize,
+        mut tweak: [u8; BLOCK_SIZE],
+        decrypt: bool,
+        input: &[u8],
+        output: &mut [u8])
+        where F: Fn(&[u8], &mut [u8]) {
+    assert!(input.len() == output.len());
+    assert!(input.len() >= BLOCK_SIZE);
+
+    let remainder = input.len() % BLOCK_SIZE;
+    let full_blocks = input.len() / BLOCK_SIZE - if remainder == 0 { 0 } else { 1 };
+
+    let mut tweaks: Vec<u8> = repeat(0).take(max_blocks * BLOCK_SIZE).collect();
+    let mut buff: Vec<u8> = repeat(0).take(max_blocks * BLOCK_SIZE).collect();
+    let mut i = 0;
+    while i < full_blocks {
+        let len = cmp::min(max_blocks, full_blocks - i) * BLOCK_SIZE;
+        let start = i * BLOCK_SIZE;
+        for t in tweaks[..len].chunks_mut(BLOCK_SIZE) {
+            copy_memory(&tweak, t);
+            mul_x(&mut tweak);
+        }
+        copy_memory(&input[start..start + len], &mut buff[..len]);
+        xor_block(&mut buff[..len], &tweaks[..len]);
+        process(&buff[..len], &mut output[start..start + len]);
+        xor_block(&mut output[start..start + len], &tweaks[..len]);
+        i += len / BLOCK_SIZE;
+    }
+
+    if remainder != 0 {
+        let start = full_blocks * BLOCK_SIZE;
+        let mut next_tweak = tweak;
+        mul_x(&mut next_tweak);
+        let (first_tweak, second_tweak) = if decrypt {
+            (next_tweak, tweak)
+        } else {
+            (tweak, next_tweak)
+        };
+
+        // Process the last full block. Its leading bytes become the final partial block and the
+        // rest are stolen to pad the final partial input block.
+        let mut block = [0u8; BLOCK_SIZE];
+        let mut stolen = [0u8; BLOCK_SIZE];
+        copy_memory(&input[start..start + BLOCK_SIZE], &mut block);
+        xor_block(&mut block, &first_tweak);
+        process(&block, &mut stolen);
+        xor_block(&mut stolen, &first_tweak);
+        copy_memory(&stolen[..remainder], &mut output[start + BLOCK_SIZE..]);
+
+        copy_memory(&input[start + BLOCK_SIZE..], &mut block[..remainder]);
+        copy_memory(&stolen[remainder..], &mut block[remainder..]);
+        xor_block(&mut block, &second_tweak);
+        process(&block, &mut output[start..start + BLOCK_SIZE]);
+        xor_block(&mut output[start..start + BLOCK_SIZE], &second_tweak);
+    }
+}
+
+// Run an 8 block operation on fewer than 8 blocks by padding the input
+fn process_x8<F>(process: F, input: &[u8], output: &mut [u8]) where F: Fn(&[u8], &mut [u8]) {
+    if input.len() == 8 * BLOCK_SIZE {
+        process(input, output);
+    } else {
+        let mut in_buff = [0u8; 8 * BLOCK_SIZE];
+        let mut out_buff = [0u8; 8 * BLOCK_SIZE];
+        copy_memory(input, &mut in_buff[..input.len()]);
+        process(&in_buff, &mut out_buff);
+        copy_memory(&out_buff[..output.len()], output);
+    }
+}
+
+/// XTS encryption mode
+pub struct XtsEncryptor<C, T> {
+    cipher: C,
+    tweak_cipher: T
+}
+
+impl <C: BlockEncryptor, T: BlockEncryptor> XtsEncryptor<C, T> {
+    /// Create a new XTS encryptor from a cipher keyed with the first half of the XTS key and a
+    /// cipher keyed with the second half
+    pub fn new(cipher: C, tweak_cipher: T) -> XtsEncryptor<C, T> {
+        assert!(cipher.block_size() == BLOCK_SIZE);
+        assert!(tweak_cipher.block_size() == BLOCK_SIZE);
+        XtsEncryptor {
+            cipher: cipher,
+            tweak_cipher: tweak_cipher
+        }
+    }
+
+    /// Encrypt a data unit of at least 16 bytes
+    pub fn encrypt_sector(&self, sector: u64, input: &[u8], output: &mut [u8]) {
+        let cipher = &self.cipher;
+        process_data_unit(
+            |i: &[u8], o: &mut [u8]| {
+                for (ib, ob) in i.chunks(BLOCK_SIZE).zip(o.chunks_mut(BLOCK_SIZE)) {
+                    cipher.encrypt_block(ib, ob);
+                }
+            },
+            1,
+            initial_tweak(&self.tweak_cipher, sector),
+            false,
+            input,
+            output);
+    }
+}
+
+/// XTS decryption mode
+pub struct XtsDecryptor<C, T> {
+    cipher: C,
+    tweak_cipher: T
+}
+
+impl <C: BlockDecryptor, T: BlockEncryptor> XtsDecryptor<C, T> {
+    /// Create a new XTS decryptor from a cipher keyed with the first half of the XTS key and a
+    /// cipher keyed with the second half. The tweak is always encrypted, even when decrypting.
+    pub fn new(cipher: C, tweak_cipher: T) -> XtsDecryptor<C, T> {
+        assert!(cipher.block_size() == BLOCK_SIZE);
+        assert!(tweak_cipher.block_size() == BLOCK_SIZE);
+        XtsDecryptor {
+            cipher: cipher,
+            tweak_cipher: tweak_cipher
+        }
+    }
+
+    /// Decrypt a data unit of at least 16 bytes
+    pub fn decrypt_sector(&self, sector: u64, input: &[u8], output: &mut [u8]) {
+        let cipher = &self.cipher;
+        process_data_unit(
+            |i: &[u8], o: &mut [u8]| {
+                for (ib, ob) in i.chunks(BLOCK_SIZE).zip(o.chunks_mut(BLOCK_SIZE)) {
+                    cipher.decrypt_block(ib, ob);
+                }
+            },
+            1,
+            initial_tweak(&self.tweak_cipher, sector),
+            true,
+            input,
+            output);
+    }
+}
+
+/// XTS encryption mode that operates on 8 blocks at a time
+pub struct XtsEncryptorX8<C, T> {
+    cipher: C,
+    tweak_cipher: T
+}
+
+impl <C: BlockEncryptorX8, T: BlockEncryptor> XtsEncryptorX8<C, T> {
+    /// Create a new XTS encryptor that operates on 8 blocks at a time
+    pub fn new(cipher: C, tweak_cipher: T) -> XtsEncryptorX8<C, T> {
+        assert!(cipher.block_size() == BLOCK_SIZE);
+        assert!(tweak_cipher.block_size() == BLOCK_SIZE);
+        XtsEncryptorX8 {
+            cipher: cipher,
+            tweak_cipher: tweak_cipher
+        }
+    }
+
+    /// Encrypt a data unit of at least 16 bytes
+    pub fn encrypt_sector(&self, sector: u64, input: &[u8], output: &mut [u8]) {
+        let cipher = &self.cipher;
+        process_data_unit(
+            |i: &[u8], o: &mut [u8]| {
+                process_x8(|i8: &[u8], o8: &mut [u8]| cipher.encrypt_block_x8(i8, o8), i, o);
+            },
+            8,
+            initial_tweak(&self.tweak_cipher, sector),
+            false,
+            input,
+            output);
+    }
+}
+
+/// XTS decryption mode that operates on 8 blocks at a time
+pub struct XtsDecryptorX8<C, T> {
+    cipher: C,
+    tweak_cipher: T
+}
+
+impl <C: BlockDecryptorX8, T: BlockEncryptor> XtsDecryptorX8<C, T> {
+    /// Create a new XTS decryptor that operates on 8 blocks at a time
+    pub fn new(cipher: C, tweak_cipher: T) -> XtsDecryptorX8<C, T> {
+        assert!(cipher.block_size() == BLOCK_SIZE);
+        assert!(tweak_cipher.block_size() == BLOCK_SIZE);
+        XtsDecryptorX8 {
+            cipher: cipher,
+            tweak_cipher: tweak_cipher
+        }
+    }
+
+    /// Decrypt a data unit of at least 16 bytes
+    pub fn decrypt_sector(&self, sector: u64, input: &[u8], output: &mut [u8]) {
+        let cipher = &self.cipher;
+        process_data_unit(
+            |i: &[u8], o: &mut [u8]| {
+                process_x8(|i8: &[u8], o8: &mut [u8]| cipher.decrypt_block_x8(i8, o8), i, o);
+            },
+            8,
+            initial_tweak(&self.tweak_cipher, sector),
+            true,
+            input,
+            output);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use aessafe::{AesSafe128Encryptor, AesSafe128Decryptor, AesSafe128EncryptorX8,
+        AesSafe128DecryptorX8, AesSafe256Encryptor, AesSafe256Decryptor, AesSafe256EncryptorX8,
+        AesSafe256DecryptorX8};
+    use xts::{XtsEncryptor, XtsDecryptor, XtsEncryptorX8, XtsDecryptorX8};
+    use serialize::hex::FromHex;
+
+    struct Test {
+        key1: &'static str,
+        key2: &'static str,
+        sector: u64,
+        plaintext: &'static str,
+        ciphertext: &'static str
+    }
+
+    // Vectors from IEEE 1619, Annex B
+    fn tests() -> Vec<Test> {
+        vec![
+            // IEEE 1619 vector 1
+            Test {
+                key1: "00000000000000000000000000000000",
+                key2: "00000000000000000000000000000000",
+                sector: 0x0,
+                plaintext: "0000000000000000000000000000000000000000000000000000000000000000",
+                ciphertext: "917cf69ebd68b2ec9b9fe9a3eadda692cd43d2f59598ed858c02c2652fbf922e"
+            },
+            // Vector 2
+            Test {
+                key1: "11111111111111111111111111111111",
+                key2: "22222222222222222222222222222222",
+                sector: 0x3333333333,
+                plaintext: "4444444444444444444444444444444444444444444444444444444444444444",
+                ciphertext: "c454185e6a16936e39334038acef838bfb186fff7480adc4289382ecd6d394f0"
+            },
+            // Vector 4
+            Test {
+                key1: "27182818284590452353602874713526",
+                key2: "31415926535897932384626433832795",
+                sector: 0x0,
+                plaintext: "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f\
+                    202122232425262728292a2b2c2d2e2f303132333435363738393a3b3c3d3e3f\
+                    404142434445464748494a4b4c4d4e4f505152535455565758595a5b5c5d5e5f\
+                    606162636465666768696a6b6c6d6e6f707172737475767778797a7b7c7d7e7f\
+                    808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9f\
+                    a0a1a2a3a4a5a6a7a8a9aaabacadaeafb0b1b2b3b4b5b6b7b8b9babbbcbdbebf\
+                    c0c1c2c3c4c5c6c7c8c9cacbcccdcecfd0d1d2d3d4d5d6d7d8d9dadbdcdddedf\
+                    e0e1e2e3e4e5e6e7e8e9eaebecedeeeff0f1f2f3f4f5f6f7f8f9fafbfcfdfeff\
+                    000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f\
+                    202122232425262728292a2b2c2d2e2f303132333435363738393a3b3c3d3e3f\
+                    404142434445464748494a4b4c4d4e4f505152535455565758595a5b5c5d5e5f\
+                    606162636465666768696a6b6c6d6e6f707172737475767778797a7b7c7d7e7f\
+                    808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9f\
+                    a0a1a2a3a4a5a6a7a8a9aaabacadaeafb0b1b2b3b4b5b6b7b8b9babbbcbdbebf\
+                    c0c1c2c3c4c5c6c7c8c9cacbcccdcecfd0d1d2d3d4d5d6d7d8d9dadbdcdddedf\
+                    e0e1e2e3e4e5e6e7e8e9eaebecedeeeff0f1f2f3f4f5f6f7f8f9fafbfcfdfeff",
+                ciphertext: "27a7479befa1d476489f308cd4cfa6e2a96e4bbe3208ff25287dd3819616e89c\
+                    c78cf7f5e543445f8333d8fa7f56000005279fa5d8b5e4ad40e736ddb4d35412\
+                    328063fd2aab53e5ea1e0a9f332500a5df9487d07a5c92cc512c8866c7e860ce\
+                    93fdf166a24912b422976146ae20ce846bb7dc9ba94a767aaef20c0d61ad0265\
+                    5ea92dc4c4e41a8952c651d33174be51a10c421110e6d81588ede82103a252d8\
+                    a750e8768defffed9122810aaeb99f9172af82b604dc4b8e51bcb08235a6f434\
+                    1332e4ca60482a4ba1a03b3e65008fc5da76b70bf1690db4eae29c5f1badd03c\
+                    5ccf2a55d705ddcd86d449511ceb7ec30bf12b1fa35b913f9f747a8afd1b130e\
+                    94bff94effd01a91735ca1726acd0b197c4e5b03393697e126826fb6bbde8ecc\
+                    1e08298516e2c9ed03ff3c1b7860f6de76d4cecd94c8119855ef5297ca67e9f3\
+                    e7ff72b1e99785ca0a7e7720c5b36dc6d72cac9574c8cbbc2f801e23e56fd344\
+                    b07f22154beba0f08ce8891e643ed995c94d9a69c9f1b5f499027a78572aeebd\
+                    74d20cc39881c213ee770b1010e4bea718846977ae119f7a023ab58cca0ad752\
+                    afe656bb3c17256a9f6e9bf19fdd5a38fc82bbe872c5539edb609ef4f79c203e\
+                    bb140f2e583cb2ad15b4aa5b655016a8449277dbd477ef2c8d6c017db738b18d\
+                    eb4a427d1923ce3ff262735779a418f20a282df920147beabe421ee5319d0568"
+            },
+            // Vector 10
+            Test {
+                key1: "2718281828459045235360287471352662497757247093699959574966967627",
+                key2: "3141592653589793238462643383279502884197169399375105820974944592",
+                sector: 0xff,
+                plaintext: "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f\
+                    202122232425262728292a2b2c2d2e2f303132333435363738393a3b3c3d3e3f\
+                    404142434445464748494a4b4c4d4e4f505152535455565758595a5b5c5d5e5f\
+                    606162636465666768696a6b6c6d6e6f707172737475767778797a7b7c7d7e7f\
+                    808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9f\
+                    a0a1a2a3a4a5a6a7a8a9aaabacadaeafb0b1b2b3b4b5b6b7b8b9babbbcbdbebf\
+                    c0c1c2c3c4c5c6c7c8c9cacbcccdcecfd0d1d2d3d4d5d6d7d8d9dadbdcdddedf\
+                    e0e1e2e3e4e5e6e7e8e9eaebecedeeeff0f1f2f3f4f5f6f7f8f9fafbfcfdfeff\
+                    000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f\
+                    202122232425262728292a2b2c2d2e2f303132333435363738393a3b3c3d3e3f\
+                    404142434445464748494a4b4c4d4e4f505152535455565758595a5b5c5d5e5f\
+                    606162636465666768696a6b6c6d6e6f707172737475767778797a7b7c7d7e7f\
+                    808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9f\
+                    a0a1a2a3a4a5a6a7a8a9aaabacadaeafb0b1b2b3b4b5b6b7b8b9babbbcbdbebf\
+                    c0c1c2c3c4c5c6c7c8c9cacbcccdcecfd0d1d2d3d4d5d6d7d8d9dadbdcdddedf\
+                    e0e1e2e3e4e5e6e7e8e9eaebecedeeeff0f1f2f3f4f5f6f7f8f9fafbfcfdfeff",
+                ciphertext: "1c3b3a102f770386e4836c99e370cf9bea00803f5e482357a4ae12d414a3e63b\
+                    5d31e276f8fe4a8d66b317f9ac683f44680a86ac35adfc3345befecb4bb188fd\
+                    5776926c49a3095eb108fd1098baec70aaa66999a72a82f27d848b21d4a741b0\
+                    c5cd4d5fff9dac89aeba122961d03a757123e9870f8acf1000020887891429ca\
+                    2a3e7a7d7df7b10355165c8b9a6d0a7de8b062c4500dc4cd120c0f7418dae3d0\
+                    b5781c34803fa75421c790dfe1de1834f280d7667b327f6c8cd7557e12ac3a0f\
+                    93ec05c52e0493ef31a12d3d9260f79a289d6a379bc70c50841473d1a8cc81ec\
+                    583e9645e07b8d9670655ba5bbcfecc6dc3966380ad8fecb17b6ba02469a020a\
+                    84e18e8f84252070c13e9f1f289be54fbc481457778f616015e1327a02b140f1\
+                    505eb309326d68378f8374595c849d84f4c333ec4423885143cb47bd71c5edae\
+                    9be69a2ffeceb1bec9de244fbe15992b11b77c040f12bd8f6a975a44a0f90c29\
+                    a9abc3d4d893927284c58754cce294529f8614dcd2aba991925fedc4ae74ffac\
+                    6e333b93eb4aff0479da9a410e4450e0dd7ae4c6e2910900575da401fc07059f\
+                    645e8b7e9bfdef33943054ff84011493c27b3429eaedb4ed5376441a77ed4385\
+                    1ad77f16f541dfd269d50d6a5f14fb0aab1cbb4c1550be97f7ab4066193c4caa\
+                    773dad38014bd2092fa755c824bb5e54c4f36ffda9fcea70b9c6e693e148c151"
+            },
+            // Vector 15
+            Test {
+                key1: "fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0",
+                key2: "bfbebdbcbbbab9b8b7b6b5b4b3b2b1b0",
+                sector: 0x123456789a,
+                plaintext: "000102030405060708090a0b0c0d0e0f10",
+                ciphertext: "6c1625db4671522d3d7599601de7ca09ed"
+            },
+            // Vector 16
+            Test {
+                key1: "fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0",
+                key2: "bfbebdbcbbbab9b8b7b6b5b4b3b2b1b0",
+                sector: 0x123456789a,
+                plaintext: "000102030405060708090a0b0c0d0e0f1011",
+                ciphertext: "d069444b7a7e0cab09e24447d24deb1fedbf"
+            },
+            // Vector 17
+            Test {
+                key1: "fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0",
+                key2: "bfbebdbcbbbab9b8b7b6b5b4b3b2b1b0",
+                sector: 0x123456789a,
+                plaintext: "000102030405060708090a0b0c0d0e0f101112",
+                ciphertext: "e5df1351c0544ba1350b3363cd8ef4beedbf9d"
+            },
+            // Vector 18
+            Test {
+                key1: "fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0",
+                key2: "bfbebdbcbbbab9b8b7b6b5b4b3b2b1b0",
+                sector: 0x123456789a,
+                plaintext: "000102030405060708090a0b0c0d0e0f10111213",
+                ciphertext: "9d84c813f719aa2c7be3f66171c7c5c2edbf9dac"
+            }
+        ]
+    }
+
+    fn encrypt(key1: &[u8], key2: &[u8], sector: u64, input: &[u8], x8: bool) -> Vec<u8> {
+        let mut output = vec![0u8; input.len()];
+        match (key1.len(), x8) {
+            (16, false) => XtsEncryptor::new(AesSafe128Encryptor::new(key1),
+                AesSafe128Encryptor::new(key2)).encrypt_sector(sector, input, &mut output),
+            (16, true) => XtsEncryptorX8::new(AesSafe128EncryptorX8::new(key1),
+                AesSafe128Encryptor::new(key2)).encrypt_sector(sector, input, &mut output),
+            (_, false) => XtsEncryptor::new(AesSafe256Encryptor::new(key1),
+                AesSafe256Encryptor::new(key2)).encrypt_sector(sector, input, &mut output),
+            (_, true) => XtsEncryptorX8::new(AesSafe256EncryptorX8::new(key1),
+                AesSafe256Encryptor::new(key2)).encrypt_sector(sector, input, &mut output)
+        }
+        output
+    }
+
+    fn decrypt(key1: &[u8], key2: &[u8], sector: u64, input: &[u8], x8: bool) -> Vec<u8> {
+        let mut output = vec![0u8; input.len()];
+        match (key1.len(), x8) {
+            (16, false) => XtsDecryptor::new(AesSafe128Decryptor::new(key1),
+                AesSafe128Encryptor::new(key2)).decrypt_sector(sector, input, &mut output),
+            (16, true) => XtsDecryptorX8::new(AesSafe128DecryptorX8::new(key1),
+                AesSafe128Encryptor::new(key2)).decrypt_sector(sector, input, &mut output),
+            (_, false) => XtsDecryptor::new(AesSafe256Decryptor::new(key1),
+                AesSafe256Encryptor::new(key2)).decrypt_sector(sector, input, &mut output),
+            (_, true) => XtsDecryptorX8::new(AesSafe256DecryptorX8::new(key1),
+                AesSafe256Encryptor::new(key2)).decrypt_sector(sector, input, &mut output)
+        }
+        output
+    }
+
+    #[test]
+    fn test_encrypt() {
+        for test in tests().iter() {
+            let key1 = test.key1.from_hex().unwrap();
+            let key2 = test.key2.from_hex().unwrap();
+            let plaintext = test.plaintext.from_hex().unwrap();
+            let ciphertext = test.ciphertext.from_hex().unwrap();
+            for &x8 in [false, true].iter() {
+                assert_eq!(encrypt(&key1, &key2, test.sector, &plaintext, x8), ciphertext);
+            }
+        }
+    }
+
+    #[test]
+    fn test_decrypt() {
+        for test in tests().iter() {
+            let key1 = test.key1.from_hex().unwrap();
+            let key2 = test.key2.from_hex().unwrap();
+            let plaintext = test.plaintext.from_hex().unwrap();
+            let ciphertext = test.ciphertext.from_hex().unwrap();
+            for &x8 in [false, true].iter() {
+                assert_eq!(decrypt(&key1, &key2, test.sector, &ciphertext, x8), plaintext);
+            }
+        }
+    }
+
+    #[test]
+    fn test_x8_matches_single_block() {
+        let key1 = [1u8; 16];
+        let key2 = [2u8; 16];
+        let plaintext: Vec<u8> = (0..300).map(|i| i as u8).collect();
+        for len in 16..plaintext.len() {
+            let ciphertext = encrypt(&key1, &key2, len as u64, &plaintext[..len], false);
+            assert_eq!(encrypt(&key1, &key2, len as u64, &plaintext[..len], true), ciphertext);
+            assert_eq!(decrypt(&key1, &key2, len as u64, &ciphertext, false), &plaintext[..len]);
+            assert_eq!(decrypt(&key1, &key2, len as u64, &ciphertext, true), &plaintext[..len]);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_short_data_unit() {
+        encrypt(&[1u8; 16], &[2u8; 16], 0, &[0u8; 15], false);
+    }
+}
+
+#[cfg(all(test, feature = "with-bench"))]
+mod bench {
+    use aessafe::{AesSafe128Encryptor, AesSafe128EncryptorX8};
+    use xts::{XtsEncryptor, XtsEncryptorX8};
+    use test::Bencher;
+
+    #[bench]
+    pub fn aes_xts_bench(bh: &mut Bencher) {
+        let enc = XtsEncryptor::new(AesSafe128Encryptor::new(&[1u8; 16]),
+            AesSafe128Encryptor::new(&[2u8; 16]));
+        let plain = [3u8; 512];
+        let mut cipher = [0u8; 512];
+
+        bh.iter( || {
+            enc.encrypt_sector(0, &plain, &mut cipher);
+        });
+
+        bh.bytes = plain.len() as u64;
+    }
+
+    #[bench]
+    pub fn aes_xts_x8_bench(bh: &mut Bencher) {
+        let enc = XtsEncryptorX8::new(AesSafe128EncryptorX8::new(&[1u8; 16]),
+            AesSafe128Encryptor::new(&[2u8; 16]));
+        let plain = [3u8; 512];
+        let mut cipher = [0u8; 512];
+
+        bh.iter( || {
+            enc.encrypt_sector(0, &plain, &mut cipher);
+        });
+
+        bh.bytes = plain.len() as u64;
+    }
+}